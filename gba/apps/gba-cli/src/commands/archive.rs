@@ -0,0 +1,25 @@
+//! `gba archive` / `gba unarchive`: move features in and out of `.gba/archive`.
+
+use std::path::Path;
+
+use anyhow::Result;
+use gba_core::RealCommandRunner;
+
+use crate::ui::output::say;
+
+/// Archive a feature, removing its worktree
+pub fn run(repo: &Path, gba_path: &Path, feature: &str, force: bool) -> Result<()> {
+    let archived = gba_core::archive::archive(&RealCommandRunner, repo, gba_path, feature, force)?;
+    if let Some(worktree) = &archived.removed_worktree {
+        say(format_args!("Removed worktree {}", worktree.display()));
+    }
+    say(format_args!("Archived to {}", archived.path.display()));
+    Ok(())
+}
+
+/// Restore an archived feature
+pub fn run_unarchive(gba_path: &Path, feature: &str) -> Result<()> {
+    let path = gba_core::archive::unarchive(gba_path, feature)?;
+    say(format_args!("Restored {}", path.display()));
+    Ok(())
+}
@@ -0,0 +1,151 @@
+//! `gba auth`: store the API key in the keychain and show where it's read
+//! from.
+
+use std::fmt::Write as _;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use gba_core::auth::{self, DEFAULT_KEYCHAIN_SERVICE, KeySource};
+use gba_core::{AgentConfig, CommandRunner};
+
+use crate::ui::output::say;
+
+/// Keychain service used for `agent`
+fn service(agent: &AgentConfig) -> &str {
+    agent
+        .api_key_keychain
+        .as_deref()
+        .unwrap_or(DEFAULT_KEYCHAIN_SERVICE)
+}
+
+/// Read a key from the terminal (without echo) or stdin and store it in the
+/// keychain entry gba reads
+pub fn set(runner: &dyn CommandRunner, gba_path: &Path, agent: &AgentConfig) -> Result<()> {
+    let key = if std::io::stdin().is_terminal() {
+        read_hidden("API key: ")?
+    } else {
+        let mut line = String::new();
+        std::io::stdin().lock().read_line(&mut line)?;
+        line
+    };
+    let key = key.trim();
+    if key.is_empty() {
+        bail!("no API key given");
+    }
+    let service = service(agent);
+    auth::store_api_key(runner, service, key, gba_path)
+        .with_context(|| format!("Failed to store the API key in keychain entry `{service}`"))?;
+    say(format_args!("Stored API key in keychain entry `{service}`"));
+    Ok(())
+}
+
+/// Print where the API key comes from, failing if there is none
+pub fn status(
+    runner: &dyn CommandRunner,
+    gba_path: &Path,
+    agent: &AgentConfig,
+    explicit: Option<&str>,
+) -> Result<()> {
+    let resolved = auth::resolve(runner, agent, gba_path, explicit);
+    let home = std::env::var_os("HOME").map(std::path::PathBuf::from);
+    print!(
+        "{}",
+        render_status(
+            resolved.as_ref().map_err(ToString::to_string),
+            service(agent),
+            auth::keychain_has_key(runner, service(agent), gba_path),
+            auth::claude_logged_in(runner, home.as_deref(), gba_path),
+        )
+    );
+    resolved?;
+    Ok(())
+}
+
+fn render_status(
+    resolved: std::result::Result<&auth::ApiKey, String>,
+    service: &str,
+    in_keychain: bool,
+    claude_login: bool,
+) -> String {
+    let yes_no = |yes: bool| if yes { "yes" } else { "no" };
+    let mut out = String::new();
+    match resolved {
+        Ok(key) if key.source == KeySource::ClaudeLogin => {
+            let _ = writeln!(out, "API key:          none, using the Claude CLI login");
+        }
+        Ok(key) => {
+            let _ = writeln!(
+                out,
+                "API key:          {} (from {})",
+                auth::mask(&key.key),
+                key.source
+            );
+        }
+        Err(e) => {
+            let _ = writeln!(out, "API key:          none ({e})");
+        }
+    }
+    let _ = writeln!(out, "Keychain `{service}`: {}", yes_no(in_keychain));
+    let _ = writeln!(out, "Claude CLI login: {}", yes_no(claude_login));
+    out
+}
+
+/// Prompt for a line on the terminal without echoing it
+fn read_hidden(prompt: &str) -> Result<String> {
+    print!("{prompt}");
+    std::io::stdout().flush()?;
+    terminal::enable_raw_mode()?;
+    let mut line = String::new();
+    let read = loop {
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Enter => break Ok(()),
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    break Err(anyhow::anyhow!("aborted"));
+                }
+                KeyCode::Char(c) => line.push(c),
+                KeyCode::Backspace => {
+                    line.pop();
+                }
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(e) => break Err(e.into()),
+        }
+    };
+    terminal::disable_raw_mode()?;
+    println!();
+    read.map(|()| line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_masks_the_key() {
+        let key = auth::ApiKey {
+            key: "sk-ant-api03-abcdefgh".to_string(),
+            source: KeySource::Keychain("gba".to_string()),
+        };
+
+        let out = render_status(Ok(&key), "gba", true, false);
+
+        assert!(!out.contains("abcdefgh"));
+        assert!(out.contains("API key:          ****efgh (from keychain entry `gba`)"));
+        assert!(out.contains("Keychain `gba`: yes"));
+        assert!(out.contains("Claude CLI login: no"));
+
+        let login = auth::ApiKey {
+            key: String::new(),
+            source: KeySource::ClaudeLogin,
+        };
+        let out = render_status(Ok(&login), "gba", false, true);
+        assert!(out.starts_with("API key:          none, using the Claude CLI login"));
+        let out = render_status(Err("no API key found".to_string()), "gba", false, false);
+        assert!(out.starts_with("API key:          none (no API key found)"));
+    }
+}
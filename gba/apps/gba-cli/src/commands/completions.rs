@@ -0,0 +1,151 @@
+//! `gba completions`: shell completion scripts.
+//!
+//! The static part is generated by clap_complete. For bash, zsh and fish a
+//! small snippet is appended that completes feature arguments and `--skip-phase`
+//! values by calling the hidden `gba __complete-features` and
+//! `gba __complete-phases` helpers at completion time.
+
+use std::path::Path;
+
+use anyhow::Result;
+use clap_complete::Shell;
+use gba_core::{ConfigLoader, FEATURES_DIR};
+
+/// Subcommands whose first positional argument is a feature
+const FEATURE_COMMANDS: &[&str] = &[
+    "edit",
+    "run",
+    "retry",
+    "rollback",
+    "status",
+    "log",
+    "cost",
+    "archive",
+    "unarchive",
+    "delete",
+];
+
+/// Print the completion script for `shell`
+pub fn run(shell: Shell, cmd: clap::Command) -> Result<()> {
+    print!("{}", script(shell, cmd));
+    Ok(())
+}
+
+/// Completion script for `shell`, including dynamic feature completion
+pub fn script(shell: Shell, mut cmd: clap::Command) -> String {
+    let name = cmd.get_name().to_string();
+    let mut out = Vec::new();
+    clap_complete::generate(shell, &mut cmd, &name, &mut out);
+    let mut script = String::from_utf8_lossy(&out).into_owned();
+    script.push_str(&dynamic_snippet(shell, &name));
+    script
+}
+
+fn dynamic_snippet(shell: Shell, name: &str) -> String {
+    let commands = FEATURE_COMMANDS.join(" ");
+    match shell {
+        Shell::Bash => format!(
+            r#"
+_{name}_dynamic() {{
+    local cur="${{COMP_WORDS[COMP_CWORD]}}" prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    if [[ "$prev" == "--skip-phase" ]]; then
+        COMPREPLY=( $(compgen -W "$({name} __complete-phases 2>/dev/null)" -- "$cur") )
+        return 0
+    fi
+    if [[ $COMP_CWORD -eq 2 && "$cur" != -* && " {commands} " == *" ${{COMP_WORDS[1]}} "* ]]; then
+        COMPREPLY=( $(compgen -W "$({name} __complete-features 2>/dev/null)" -- "$cur") )
+        return 0
+    fi
+    _{name} "$@"
+}}
+complete -F _{name}_dynamic -o bashdefault -o default {name}
+"#
+        ),
+        Shell::Zsh => format!(
+            r#"
+_{name}_dynamic() {{
+    if [[ ${{words[CURRENT-1]}} == --skip-phase ]]; then
+        compadd -- ${{(f)"$({name} __complete-phases 2>/dev/null)"}}
+        return
+    fi
+    if (( CURRENT == 3 )) && [[ " {commands} " == *" ${{words[2]}} "* && ${{words[CURRENT]}} != -* ]]; then
+        compadd -- ${{(f)"$({name} __complete-features 2>/dev/null)"}}
+        return
+    fi
+    _{name} "$@"
+}}
+compdef _{name}_dynamic {name}
+"#
+        ),
+        Shell::Fish => format!(
+            r#"
+complete -c {name} -n "__fish_seen_subcommand_from {commands}" -f -a "({name} __complete-features 2>/dev/null)"
+complete -c {name} -l skip-phase -x -a "({name} __complete-phases 2>/dev/null)"
+"#
+        ),
+        _ => String::new(),
+    }
+}
+
+/// Feature directory names under `.gba/features`, sorted
+pub fn feature_names(gba_path: &Path) -> Vec<String> {
+    let mut names: Vec<String> = std::fs::read_dir(gba_path.join(FEATURES_DIR))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort();
+    names
+}
+
+/// Names of the configured phases
+pub fn phase_names(loader: &ConfigLoader) -> Vec<String> {
+    loader
+        .load()
+        .map(|loaded| loaded.config.phases.into_iter().map(|p| p.name).collect())
+        .unwrap_or_default()
+}
+
+/// One name per line, as printed by the hidden completion helpers
+pub fn lines(names: &[String]) -> String {
+    names.iter().map(|name| format!("{name}\n")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_feature_names_one_per_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let features = dir.path().join(FEATURES_DIR);
+        for name in ["0002-search", "0001-auth"] {
+            std::fs::create_dir_all(features.join(name)).unwrap();
+        }
+        std::fs::write(features.join("notes.txt"), "").unwrap();
+
+        assert_eq!(
+            lines(&feature_names(dir.path())),
+            "0001-auth\n0002-search\n"
+        );
+        assert!(feature_names(&dir.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn test_bash_script_completes_features_and_phases() {
+        let cmd = clap::Command::new("gba").subcommand(clap::Command::new("run"));
+
+        let script = script(Shell::Bash, cmd);
+
+        assert!(script.contains("complete -F _gba_dynamic"));
+        assert!(script.contains("gba __complete-features"));
+        assert!(script.contains("\"--skip-phase\""));
+    }
+
+    #[test]
+    fn test_phase_names_default_config() {
+        assert_eq!(phase_names(&ConfigLoader::default())[0], "observe");
+    }
+}
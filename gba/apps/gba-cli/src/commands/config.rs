@@ -0,0 +1,146 @@
+//! `gba config`: inspect and change the layered configuration.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use gba_core::{CONFIG_FILE, ConfigLoader, LoadedConfig};
+
+/// Print the effective configuration and where each value comes from
+pub fn show(loader: &ConfigLoader) -> Result<()> {
+    let loaded = loader.load()?;
+    print!("{}", render_files(loader));
+    print!("{}", render_show(&loaded));
+    println!("\n{}", render_rate_limit(&loaded));
+    Ok(())
+}
+
+/// Print the effective value of a dotted key
+pub fn get(loader: &ConfigLoader, key: &str) -> Result<()> {
+    let loaded = loader.load()?;
+    let value = loaded
+        .get(key)
+        .with_context(|| format!("Unknown configuration key `{key}`"))?;
+    println!("{value}");
+    Ok(())
+}
+
+/// Set a key in the repo config, or the global one with `global`
+pub fn set(gba_path: &Path, key: &str, value: &str, global: bool) -> Result<()> {
+    let path = if global {
+        gba_core::global_config_path()
+            .context("No global config location (set GBA_CONFIG or HOME)")?
+    } else {
+        gba_path.join(CONFIG_FILE)
+    };
+    gba_core::set_config_value(&path, key, value)?;
+    crate::ui::output::say(format_args!("Set {key} = {value} in {}", path.display()));
+    Ok(())
+}
+
+/// Check the config files for unknown keys and the merged configuration
+/// for invalid values
+pub fn validate(loader: &ConfigLoader) -> Result<()> {
+    print!("{}", render_files(loader));
+    loader.load()?.config.validate()?;
+    println!("Configuration is valid");
+    Ok(())
+}
+
+fn render_files(loader: &ConfigLoader) -> String {
+    let describe = |path: Option<&Path>| match path {
+        Some(path) if path.exists() => path.display().to_string(),
+        Some(path) => format!("{} (not found)", path.display()),
+        None => "none".to_string(),
+    };
+    format!(
+        "Global config: {}\nRepo config:   {}\n\n",
+        describe(loader.global_path()),
+        describe(loader.repo_path())
+    )
+}
+
+/// One `key = value (source)` line per configuration value
+pub fn render_show(loaded: &LoadedConfig) -> String {
+    let entries = loaded.entries();
+    let key_width = entries.iter().map(|(k, _, _)| k.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for (key, value, source) in entries {
+        let _ = writeln!(out, "{key:<key_width$} = {value}  ({source})");
+    }
+    out
+}
+
+/// The configured request limits of `loaded`.
+///
+/// Only the limits: the live bucket and running requests belong to the
+/// engine of a `gba run`, which this process doesn't share.
+fn render_rate_limit(loaded: &LoadedConfig) -> String {
+    let limits = &loaded.config.rate_limit;
+    let rate = limits
+        .requests_per_minute
+        .map_or_else(|| "unlimited".to_string(), |n| format!("{n}/min"));
+    let concurrent = limits
+        .max_concurrent_executions
+        .map_or_else(|| "unlimited".to_string(), |n| n.to_string());
+    format!(
+        "Configured rate limits: {rate} requests, {concurrent} concurrent, {} retries",
+        limits.max_retries
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_show_lists_sources() {
+        let loaded = ConfigLoader::default()
+            .with_override("agent.model", "claude-opus-4")
+            .load()
+            .unwrap();
+
+        let out = render_show(&loaded);
+
+        let model = out.lines().find(|l| l.starts_with("agent.model ")).unwrap();
+        assert!(model.contains("= claude-opus-4"));
+        assert!(model.ends_with("(command line)"));
+        assert!(out.contains("(default)"));
+        assert!(out.contains("phases[0].name"));
+    }
+
+    #[test]
+    fn test_render_rate_limit() {
+        let loaded = ConfigLoader::default()
+            .with_override("rateLimit.requestsPerMinute", 30)
+            .with_override("rateLimit.maxConcurrentExecutions", 2)
+            .load()
+            .unwrap();
+        assert_eq!(
+            render_rate_limit(&loaded),
+            "Configured rate limits: 30/min requests, 2 concurrent, 3 retries"
+        );
+
+        let loaded = ConfigLoader::default().load().unwrap();
+        assert_eq!(
+            render_rate_limit(&loaded),
+            "Configured rate limits: unlimited requests, unlimited concurrent, 3 retries"
+        );
+    }
+
+    #[test]
+    fn test_validate_names_unknown_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(CONFIG_FILE);
+        let loader = ConfigLoader::new(dir.path()).with_global(None);
+        std::fs::write(&path, "agent:\n  maxTurn: 40\n").unwrap();
+
+        let err = validate(&loader).unwrap_err().to_string();
+
+        assert!(err.contains("`agent.maxTurn` at line 2 (did you mean `maxTurns`?)"));
+        std::fs::write(&path, "phases:\n  - name: build\n  - name: build\n").unwrap();
+        assert!(validate(&loader).is_err());
+        std::fs::write(&path, "agent:\n  maxTurns: 40\n").unwrap();
+        assert!(validate(&loader).is_ok());
+    }
+}
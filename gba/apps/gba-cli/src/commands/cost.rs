@@ -0,0 +1,293 @@
+//! `gba cost`: spend per feature, per phase of a single feature, or per model.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use gba_core::{CostReport, CostSummary, ExecutionStats, FeatureState, ModelCost};
+
+use super::load_features;
+
+/// Output format of the cost command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Human readable table
+    Table,
+    /// Pretty-printed JSON
+    Json,
+    /// Comma separated values with a header row
+    Csv,
+}
+
+impl OutputFormat {
+    /// Pick the format from the `--json`/`--csv` flags
+    pub fn from_flags(json: bool, csv: bool) -> Self {
+        match (json, csv) {
+            (true, _) => Self::Json,
+            (_, true) => Self::Csv,
+            _ => Self::Table,
+        }
+    }
+}
+
+/// Print spend of all features, or the per-phase breakdown of one feature.
+///
+/// With `by_model` the selected phases are grouped by model instead.
+pub fn run(
+    gba_path: &Path,
+    feature: Option<&str>,
+    format: OutputFormat,
+    since: Option<&str>,
+    by_model: bool,
+) -> Result<()> {
+    let since = since.map(parse_since).transpose()?;
+
+    if let Some(feature) = feature {
+        let state = FeatureState::load(&FeatureState::find_dir(gba_path, feature)?)?;
+        if by_model {
+            return print_models(&FeatureState::cost_by_model([&state]), format);
+        }
+        let report = state.cost_report();
+        match format {
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&report)?),
+            _ => print!("{}", render_phases(&report, format)),
+        }
+        return Ok(());
+    }
+
+    let features = load_features(gba_path)?;
+    let selected = features
+        .iter()
+        .filter(|s| since.is_none_or(|since| s.feature.updated_at >= since));
+    if by_model {
+        return print_models(&FeatureState::cost_by_model(selected), format);
+    }
+    let summary = FeatureState::aggregate(selected);
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&summary)?),
+        _ => print!("{}", render_features(&summary, format)),
+    }
+    Ok(())
+}
+
+fn print_models(models: &[ModelCost], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(models)?),
+        _ => print!("{}", render_models(models, format)),
+    }
+    Ok(())
+}
+
+/// Parse a `--since` value: a date (midnight UTC) or an RFC 3339 timestamp
+pub fn parse_since(value: &str) -> Result<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .with_context(|| format!("invalid --since value `{value}`, expected YYYY-MM-DD"))
+}
+
+/// Render one row per feature plus a grand total
+pub fn render_features(summary: &CostSummary, format: OutputFormat) -> String {
+    let mut out = String::new();
+    if format == OutputFormat::Csv {
+        out.push_str("feature,status,turns,input_tokens,output_tokens,cost_usd\n");
+        for row in &summary.features {
+            csv_row(
+                &mut out,
+                &[&row.feature, &row.status.to_string()],
+                &row.stats,
+            );
+        }
+        csv_row(&mut out, &["TOTAL", ""], &summary.total);
+        return out;
+    }
+
+    let _ = writeln!(
+        out,
+        "{:<32} {:>7} {:>12} {:>12} {:>10}  STATUS",
+        "FEATURE", "TURNS", "INPUT", "OUTPUT", "COST"
+    );
+    for row in &summary.features {
+        table_row(&mut out, &row.feature, &row.stats);
+        let _ = writeln!(out, "  {}", row.status);
+    }
+    table_row(&mut out, "TOTAL", &summary.total);
+    out.push('\n');
+    out
+}
+
+/// Render the per-phase breakdown of a single feature
+pub fn render_phases(report: &CostReport, format: OutputFormat) -> String {
+    let mut out = String::new();
+    if format == OutputFormat::Csv {
+        out.push_str("phase,turns,input_tokens,output_tokens,cost_usd\n");
+        for phase in &report.phases {
+            csv_row(&mut out, &[&phase.phase], &phase.stats);
+        }
+        csv_row(&mut out, &["TOTAL"], &report.total);
+        return out;
+    }
+
+    let _ = writeln!(
+        out,
+        "{:<32} {:>7} {:>12} {:>12} {:>10}",
+        "PHASE", "TURNS", "INPUT", "OUTPUT", "COST"
+    );
+    for phase in &report.phases {
+        table_row(&mut out, &phase.phase, &phase.stats);
+        out.push('\n');
+    }
+    table_row(&mut out, "TOTAL", &report.total);
+    out.push('\n');
+    out
+}
+
+/// Render one row per model plus a grand total
+pub fn render_models(models: &[ModelCost], format: OutputFormat) -> String {
+    let mut total = ExecutionStats::default();
+    for row in models {
+        total.accumulate(&row.stats);
+    }
+    let phases: usize = models.iter().map(|m| m.phases).sum();
+
+    let mut out = String::new();
+    if format == OutputFormat::Csv {
+        out.push_str("model,phases,turns,input_tokens,output_tokens,cost_usd\n");
+        for row in models {
+            csv_row(&mut out, &[&row.model, &row.phases.to_string()], &row.stats);
+        }
+        csv_row(&mut out, &["TOTAL", &phases.to_string()], &total);
+        return out;
+    }
+
+    let _ = writeln!(
+        out,
+        "{:<32} {:>7} {:>12} {:>12} {:>10}  PHASES",
+        "MODEL", "TURNS", "INPUT", "OUTPUT", "COST"
+    );
+    for row in models {
+        table_row(&mut out, &row.model, &row.stats);
+        let _ = writeln!(out, "  {}", row.phases);
+    }
+    table_row(&mut out, "TOTAL", &total);
+    let _ = writeln!(out, "  {phases}");
+    out
+}
+
+fn table_row(out: &mut String, name: &str, stats: &ExecutionStats) {
+    let _ = write!(
+        out,
+        "{:<32} {:>7} {:>12} {:>12} {:>10}",
+        name,
+        stats.turns,
+        stats.input_tokens,
+        stats.output_tokens,
+        format!("${:.2}", stats.cost_usd)
+    );
+}
+
+fn csv_row(out: &mut String, labels: &[&str], stats: &ExecutionStats) {
+    for label in labels {
+        let _ = write!(out, "{},", csv_field(label));
+    }
+    let _ = writeln!(
+        out,
+        "{},{},{},{:.4}",
+        stats.turns, stats.input_tokens, stats.output_tokens, stats.cost_usd
+    );
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary() -> CostSummary {
+        let mut a = FeatureState::new("0001", "auth");
+        a.total_stats.turns = 12;
+        a.total_stats.cost_usd = 1.5;
+        let mut b = FeatureState::new("0002", "search");
+        b.total_stats.turns = 3;
+        b.total_stats.cost_usd = 0.25;
+        FeatureState::aggregate([&a, &b])
+    }
+
+    #[test]
+    fn test_render_features_csv() {
+        let out = render_features(&summary(), OutputFormat::Csv);
+        let lines: Vec<_> = out.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "feature,status,turns,input_tokens,output_tokens,cost_usd"
+        );
+        assert_eq!(lines[1], "0001_auth,planned,12,0,0,1.5000");
+        assert_eq!(lines[3], "TOTAL,,15,0,0,1.7500");
+    }
+
+    #[test]
+    fn test_render_phases_csv() {
+        let mut state = FeatureState::new("0001", "auth");
+        state.phase_mut("build").stats = Some(ExecutionStats {
+            turns: 4,
+            cost_usd: 0.5,
+            ..ExecutionStats::default()
+        });
+
+        let out = render_phases(&state.cost_report(), OutputFormat::Csv);
+
+        assert!(out.starts_with("phase,turns,input_tokens,output_tokens,cost_usd\n"));
+        assert!(out.contains("build,4,0,0,0.5000\n"));
+    }
+
+    #[test]
+    fn test_render_features_table_has_total() {
+        let out = render_features(&summary(), OutputFormat::Table);
+
+        assert!(out.contains("0002_search"));
+        assert!(out.contains("TOTAL"));
+        assert!(out.contains("$1.75"));
+    }
+
+    #[test]
+    fn test_render_models_csv() {
+        let mut state = FeatureState::new("0001", "auth");
+        for (name, model) in [("observe", "claude-haiku-4"), ("build", "claude-opus-4")] {
+            let phase = state.phase_mut(name);
+            phase.model = Some(model.to_string());
+            phase.stats = Some(ExecutionStats {
+                turns: 2,
+                cost_usd: 0.5,
+                ..ExecutionStats::default()
+            });
+        }
+
+        let out = render_models(&FeatureState::cost_by_model([&state]), OutputFormat::Csv);
+        let lines: Vec<_> = out.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "model,phases,turns,input_tokens,output_tokens,cost_usd"
+        );
+        assert_eq!(lines[1], "claude-haiku-4,1,2,0,0,0.5000");
+        assert_eq!(lines[3], "TOTAL,2,4,0,0,1.0000");
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let date = parse_since("2026-02-10").unwrap();
+        assert_eq!(date.to_rfc3339(), "2026-02-10T00:00:00+00:00");
+        assert!(parse_since("2026-02-10T12:30:00Z").is_ok());
+        assert!(parse_since("last week").is_err());
+    }
+}
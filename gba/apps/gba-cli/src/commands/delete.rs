@@ -0,0 +1,61 @@
+//! `gba delete`: abandon a feature and clean up after it.
+
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use gba_core::delete::DeletePlan;
+use gba_core::{CoreError, RealCommandRunner};
+
+use super::confirm;
+use crate::ui::output::say;
+
+/// Flags of `gba delete`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeleteOptions {
+    /// Skip the confirmation prompt
+    pub yes: bool,
+    /// Delete an in-progress feature
+    pub force: bool,
+    /// Also delete the feature branch
+    pub delete_branch: bool,
+}
+
+/// Delete a feature after confirmation
+pub fn run(repo: &Path, gba_path: &Path, feature: &str, options: DeleteOptions) -> Result<()> {
+    let runner = RealCommandRunner;
+    let plan = match DeletePlan::new(&runner, repo, gba_path, feature, options.delete_branch) {
+        Ok(plan) => plan,
+        Err(CoreError::FeatureNotFound(_)) => {
+            say(format_args!(
+                "Feature {feature} not found, nothing to delete"
+            ));
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if plan.in_progress() && !options.force {
+        bail!("feature {feature} is in progress, use --force to delete it anyway");
+    }
+
+    say("This will remove:");
+    if let Some(worktree) = &plan.worktree {
+        say(format_args!("  worktree {}", worktree.display()));
+    }
+    if let Some(branch) = &plan.branch {
+        say(format_args!("  branch {branch}"));
+    }
+    say(format_args!(
+        "  feature directory {}",
+        plan.feature_dir.display()
+    ));
+
+    if !options.yes && !confirm("Continue? [y/N] ")? {
+        say("Aborted");
+        return Ok(());
+    }
+
+    for item in plan.execute(&runner, repo, options.force)? {
+        say(format_args!("Removed {item}"));
+    }
+    Ok(())
+}
@@ -0,0 +1,203 @@
+//! `gba diff`: show what the agent changed in a feature or one phase.
+
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use gba_core::{CommandRunner, FeatureState, RealCommandRunner};
+
+/// Show the diff of `feature`, or of one `phase`, through git's pager
+pub fn run(
+    repo: &Path,
+    gba_path: &Path,
+    feature: &str,
+    phase: Option<&str>,
+    stat: bool,
+) -> Result<()> {
+    let state = FeatureState::load(&FeatureState::find_dir(gba_path, feature)?)?;
+    let args = diff_args(&state, phase, stat)?;
+    let tree = match &state.git {
+        Some(git) => repo.join(&git.worktree_path),
+        None => repo.to_path_buf(),
+    };
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let status = RealCommandRunner.run_interactive("git", &args, &tree)?;
+    if !status.success() {
+        bail!("git diff exited with {status}");
+    }
+    Ok(())
+}
+
+/// Arguments of `git` showing the changes of the feature or of `phase`.
+///
+/// A phase spans from the commit of the phase before it (or the feature's
+/// base commit) to its own commit. The whole feature spans from the base
+/// commit to the last recorded commit, or to the working tree if no phase
+/// recorded one.
+///
+/// # Errors
+///
+/// Returns an error if the feature has no base commit, the phase is
+/// unknown, or the phase recorded no commit.
+pub fn diff_args(state: &FeatureState, phase: Option<&str>, stat: bool) -> Result<Vec<String>> {
+    let Some(git) = &state.git else {
+        bail!(
+            "{} has no recorded base commit (it ran without a worktree; enable \
+             git.useWorktree to record one)",
+            state.dir_name()
+        );
+    };
+    let range = match phase {
+        Some(name) => {
+            let Some(index) = state.phases.iter().position(|p| p.name == name) else {
+                bail!("{} has no phase `{name}`", state.dir_name());
+            };
+            let Some(to) = &state.phases[index].commit_sha else {
+                bail!(
+                    "Phase {name} of {} has no recorded commit, so its changes can't be \
+                     told apart; run `gba diff {}` for the whole feature, and enable \
+                     git.autoCommit to record commits",
+                    state.dir_name(),
+                    state.feature.id
+                );
+            };
+            let from = state.phases[..index]
+                .iter()
+                .rev()
+                .find_map(|p| p.commit_sha.as_deref())
+                .unwrap_or(&git.base_commit);
+            format!("{from}..{to}")
+        }
+        None => match state
+            .phases
+            .iter()
+            .rev()
+            .find_map(|p| p.commit_sha.as_deref())
+        {
+            Some(to) => format!("{}..{to}", git.base_commit),
+            // Without commits the changes are still in the working tree.
+            None => git.base_commit.clone(),
+        },
+    };
+
+    let mut args = vec!["--paginate".to_string(), "diff".to_string()];
+    if stat {
+        args.push("--stat".to_string());
+    }
+    args.push(range);
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gba_core::GitInfo;
+
+    fn feature() -> FeatureState {
+        let mut state = FeatureState::new("0001", "auth");
+        state.git = Some(GitInfo {
+            worktree_path: ".trees/0001_auth".into(),
+            branch: "feature/0001-auth".to_string(),
+            base_branch: "main".to_string(),
+            base_commit: "base".to_string(),
+        });
+        for name in ["observe", "build", "test"] {
+            state.phase_mut(name);
+        }
+        state
+    }
+
+    #[test]
+    fn test_phase_and_feature_ranges() {
+        let mut state = feature();
+        assert_eq!(
+            diff_args(&state, None, false).unwrap(),
+            ["--paginate", "diff", "base"]
+        );
+
+        state.record_commit("build", "b2");
+        state.record_commit("test", "t3");
+
+        assert_eq!(
+            diff_args(&state, Some("build"), false).unwrap(),
+            ["--paginate", "diff", "base..b2"]
+        );
+        assert_eq!(
+            diff_args(&state, Some("test"), true).unwrap(),
+            ["--paginate", "diff", "--stat", "b2..t3"]
+        );
+        assert_eq!(
+            diff_args(&state, None, false).unwrap(),
+            ["--paginate", "diff", "base..t3"]
+        );
+    }
+
+    #[test]
+    fn test_diff_of_a_temp_repo() {
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(["-c", "user.name=gba", "-c", "user.email=gba@example.com"])
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {args:?} failed");
+            String::from_utf8(output.stdout).unwrap().trim().to_string()
+        };
+        git(&["init", "--quiet"]);
+        std::fs::write(dir.path().join("README.md"), "# App\n").unwrap();
+        git(&["add", "README.md"]);
+        git(&["commit", "--quiet", "-m", "base"]);
+        let base = git(&["rev-parse", "HEAD"]);
+        std::fs::write(dir.path().join("login.rs"), "fn login() {}\n").unwrap();
+        git(&["add", "login.rs"]);
+        git(&["commit", "--quiet", "-m", "build"]);
+        let build = git(&["rev-parse", "HEAD"]);
+        std::fs::write(
+            dir.path().join("login_test.rs"),
+            "#[test]\nfn logs_in() {}\n",
+        )
+        .unwrap();
+        git(&["add", "login_test.rs"]);
+        git(&["commit", "--quiet", "-m", "test"]);
+        let test = git(&["rev-parse", "HEAD"]);
+
+        let mut state = feature();
+        state.git.as_mut().unwrap().base_commit = base;
+        state.record_commit("build", build);
+        state.record_commit("test", test);
+        let diff = |phase: Option<&str>, stat: bool| {
+            let args = diff_args(&state, phase, stat).unwrap();
+            let args: Vec<&str> = args.iter().map(String::as_str).collect();
+            let output = RealCommandRunner.run("git", &args, dir.path()).unwrap();
+            assert!(output.status.success());
+            String::from_utf8(output.stdout).unwrap()
+        };
+
+        let build_diff = diff(Some("build"), false);
+        assert!(build_diff.contains("+++ b/login.rs"), "{build_diff}");
+        assert!(build_diff.contains("+fn login() {}"));
+        assert!(!build_diff.contains("login_test.rs"));
+        let test_diff = diff(Some("test"), false);
+        assert!(test_diff.contains("+++ b/login_test.rs"), "{test_diff}");
+        assert!(!test_diff.contains("+++ b/login.rs"));
+        let stat = diff(None, true);
+        assert!(stat.contains("login.rs"), "{stat}");
+        assert!(stat.contains("2 files changed"), "{stat}");
+    }
+
+    #[test]
+    fn test_missing_commits_are_explained() {
+        let state = feature();
+
+        let err = diff_args(&state, Some("build"), false).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Phase build of 0001_auth has no recorded commit")
+        );
+        assert!(diff_args(&state, Some("deploy"), false).is_err());
+
+        let err = diff_args(&FeatureState::new("0002", "search"), None, false).unwrap_err();
+        assert!(err.to_string().contains("no recorded base commit"));
+    }
+}
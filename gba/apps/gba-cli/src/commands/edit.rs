@@ -0,0 +1,189 @@
+//! `gba edit`: open a feature's spec files in the user's editor.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, bail};
+use gba_core::{CommandRunner, DESIGN_FILE, FeatureState, RealCommandRunner};
+
+/// Editor used when neither `$VISUAL` nor `$EDITOR` is set
+const DEFAULT_EDITOR: &str = if cfg!(windows) { "notepad" } else { "vi" };
+
+/// Open `file` (default: `design.md`) from the feature's `specs/` directory
+/// and warn about problems left in it once the editor exits
+pub fn run(gba_path: &Path, feature: &str, file: Option<&str>) -> Result<()> {
+    let var = |name: &str| std::env::var(name).ok().filter(|v| !v.trim().is_empty());
+    let editor = var("EDITOR")
+        .or_else(|| var("VISUAL"))
+        .unwrap_or_else(|| DEFAULT_EDITOR.to_string());
+    let path = open(&RealCommandRunner, &editor, gba_path, feature, file)?;
+    let content = std::fs::read_to_string(&path)?;
+    for warning in spec_warnings(&content) {
+        eprintln!("warning: {}: {warning}", path.display());
+    }
+    Ok(())
+}
+
+/// Launch `editor` on the spec file through `runner` and return its path.
+///
+/// `editor` may include arguments, e.g. `code --wait`.
+///
+/// # Errors
+///
+/// Returns an error if the feature or file doesn't exist or the editor
+/// can't be started or exits unsuccessfully.
+pub fn open(
+    runner: &dyn CommandRunner,
+    editor: &str,
+    gba_path: &Path,
+    feature: &str,
+    file: Option<&str>,
+) -> Result<PathBuf> {
+    let feature_path = FeatureState::find_dir(gba_path, feature)?;
+    let path = spec_path(&feature_path, file)?;
+    let mut words = editor.split_whitespace();
+    let Some(program) = words.next() else {
+        bail!("no editor configured");
+    };
+    let mut args: Vec<&str> = words.collect();
+    let path_arg = path.to_string_lossy();
+    args.push(&path_arg);
+
+    let status = runner.run_interactive(program, &args, &feature_path)?;
+    if !status.success() {
+        bail!("{editor} exited with {status}");
+    }
+    Ok(path)
+}
+
+/// Problems with an edited spec: empty, no heading, or scaffold TODOs left
+pub fn spec_warnings(content: &str) -> Vec<String> {
+    if content.trim().is_empty() {
+        return vec!["the file is empty".to_string()];
+    }
+    let mut warnings = Vec::new();
+    if !content.lines().any(|line| line.starts_with('#')) {
+        warnings.push("no Markdown heading found".to_string());
+    }
+    let todos = content.lines().filter(|line| line.contains("TODO")).count();
+    if todos > 0 {
+        warnings.push(format!("{todos} scaffold TODO marker(s) left to fill in"));
+    }
+    warnings
+}
+
+/// Path of a spec file, accepting `design`, `design.md` and
+/// `specs/design.md`
+pub fn spec_path(feature_path: &Path, file: Option<&str>) -> Result<PathBuf> {
+    let specs = feature_path.join("specs");
+    let path = match file {
+        Some(file) => {
+            let path = specs.join(file.strip_prefix("specs/").unwrap_or(file));
+            if path.extension().is_none() {
+                path.with_extension("md")
+            } else {
+                path
+            }
+        }
+        None => feature_path.join(DESIGN_FILE),
+    };
+    if !path.starts_with(&specs) || file.is_some_and(|f| f.contains("..")) {
+        bail!("{} is outside {}", path.display(), specs.display());
+    }
+    if !path.is_file() {
+        let mut available: Vec<String> = std::fs::read_dir(&specs)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.path().is_file())
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .collect();
+        available.sort();
+        bail!(
+            "{} does not exist (available: {})",
+            path.display(),
+            available.join(", ")
+        );
+    }
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::{ExitStatus, Output};
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::commands::plan;
+
+    /// Records invocations and reports success
+    #[derive(Debug, Default)]
+    struct RecordingRunner {
+        calls: Mutex<Vec<(String, Vec<String>)>>,
+    }
+
+    impl CommandRunner for RecordingRunner {
+        fn run(&self, program: &str, args: &[&str], _cwd: &Path) -> gba_core::Result<Output> {
+            self.calls.lock().unwrap().push((
+                program.to_string(),
+                args.iter().map(|a| a.to_string()).collect(),
+            ));
+            Ok(Output {
+                status: ExitStatus::from_raw(0),
+                stdout: Vec::new(),
+                stderr: Vec::new(),
+            })
+        }
+    }
+
+    #[test]
+    fn test_open_spec_in_editor() {
+        let dir = tempfile::tempdir().unwrap();
+        let feature_path = plan::create(dir.path(), "login", "# Design", "# Verification").unwrap();
+        let runner = RecordingRunner::default();
+
+        open(&runner, "code --wait", dir.path(), "login", None).unwrap();
+        open(&runner, "vi", dir.path(), "0001", Some("verification")).unwrap();
+
+        let calls = runner.calls.lock().unwrap().clone();
+        let design = feature_path
+            .join(DESIGN_FILE)
+            .to_string_lossy()
+            .into_owned();
+        let verification = feature_path.join("specs/verification.md");
+        assert_eq!(
+            calls[0],
+            ("code".to_string(), vec!["--wait".to_string(), design])
+        );
+        assert_eq!(
+            calls[1],
+            (
+                "vi".to_string(),
+                vec![verification.to_string_lossy().into_owned()]
+            )
+        );
+
+        let err = open(&runner, "vi", dir.path(), "login", Some("notes.md")).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("available: design.md, verification.md")
+        );
+        assert!(open(&runner, "vi", dir.path(), "signup", None).is_err());
+        assert_eq!(runner.calls.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_spec_warnings() {
+        let scaffold = plan::design_doc("login", &plan::PlanAnswers::default());
+        let warnings = spec_warnings(&scaffold);
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("TODO marker(s) left"));
+
+        assert_eq!(spec_warnings(" \n"), ["the file is empty"]);
+        assert_eq!(
+            spec_warnings("Just some notes.\n"),
+            ["no Markdown heading found"]
+        );
+        assert!(spec_warnings("# Design\n\nSessions use JWTs.\n").is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+//! `gba execute`: send a single prompt to the agent.
+
+use std::io::Write;
+
+use anyhow::Result;
+use gba_core::Engine;
+
+use crate::ui::output::say;
+
+/// Execute `prompt` with `engine`, writing the result to `output`.
+///
+/// # Errors
+///
+/// Returns an error if the execution or writing the result fails.
+pub async fn run(engine: &Engine, prompt: &str, output: &mut impl Write) -> Result<()> {
+    say(format_args!("Executing prompt: {prompt}"));
+    let result = engine.execute(prompt).await?;
+    writeln!(output, "Result: {result}")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::output;
+
+    #[test]
+    fn test_quiet_prints_only_the_result() {
+        let engine = Engine::new(gba_core::Config::default());
+        for (quiet, expected) in [(false, "Executing prompt: hi\n"), (true, "")] {
+            let mut result = Vec::new();
+            let status = output::capture(quiet, || {
+                futures::executor::block_on(run(&engine, "hi", &mut result)).unwrap();
+            });
+            assert_eq!(status, expected);
+            assert_eq!(
+                String::from_utf8(result).unwrap(),
+                "Result: Executing: hi\n"
+            );
+        }
+    }
+}
@@ -0,0 +1,88 @@
+//! `gba export` / `gba import`: bundle a feature into a single JSON file and
+//! recreate it from one.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use gba_core::export::ExportBundle;
+
+use crate::ui::output::say;
+
+/// Export `feature` to `out` (default: `<id>_<slug>.gba-export.json` in the
+/// current directory), returning the written path
+pub fn run(gba_path: &Path, feature: &str, out: Option<&Path>) -> Result<PathBuf> {
+    let bundle = gba_core::export::export(gba_path, feature)?;
+    let path = out.map_or_else(|| PathBuf::from(bundle.file_name()), Path::to_path_buf);
+    let json = serde_json::to_string_pretty(&bundle)?;
+    std::fs::write(&path, json + "\n")
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    say(format_args!(
+        "Exported {} ({} files) to {}",
+        bundle.feature,
+        bundle.files.len(),
+        path.display()
+    ));
+    Ok(path)
+}
+
+/// Recreate a feature from the bundle at `path`, returning its directory
+pub fn run_import(gba_path: &Path, path: &Path, force: bool) -> Result<PathBuf> {
+    let json = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let bundle: ExportBundle = serde_json::from_str(&json)
+        .with_context(|| format!("{} is not a gba export bundle", path.display()))?;
+    let imported = gba_core::export::import(gba_path, &bundle, force)?;
+    if let Some(original) = &imported.reassigned_from {
+        say(format_args!(
+            "Feature ID {original} is taken, imported as {}",
+            imported.path.display()
+        ));
+    } else {
+        say(format_args!("Imported {}", imported.path.display()));
+    }
+    Ok(imported.path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::plan;
+    use gba_core::FeatureState;
+
+    #[test]
+    fn test_export_writes_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        plan::create(dir.path(), "login", "# Design\n", "# Verification\n").unwrap();
+        let out = dir.path().join("login.json");
+
+        let path = run(dir.path(), "login", Some(&out)).unwrap();
+
+        let bundle: ExportBundle =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(bundle.feature, "0001_login");
+        assert!(bundle.files[gba_core::STATE_FILE].contains("slug: login"));
+        assert_eq!(bundle.files[gba_core::DESIGN_FILE], "# Design\n");
+    }
+
+    #[test]
+    fn test_import_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        plan::create(dir.path(), "login", "# Design\n", "# Verification\n").unwrap();
+        let out = dir.path().join("login.json");
+        run(dir.path(), "login", Some(&out)).unwrap();
+
+        let fresh = tempfile::tempdir().unwrap();
+        let path = run_import(fresh.path(), &out, false).unwrap();
+
+        let original = FeatureState::find_dir(dir.path(), "login").unwrap();
+        assert_eq!(
+            FeatureState::load(&path).unwrap(),
+            FeatureState::load(&original).unwrap()
+        );
+        assert_eq!(
+            std::fs::read_to_string(path.join(gba_core::DESIGN_FILE)).unwrap(),
+            "# Design\n"
+        );
+        assert!(run_import(fresh.path(), &out, false).is_err());
+    }
+}
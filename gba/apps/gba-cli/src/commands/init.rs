@@ -0,0 +1,304 @@
+//! `gba init`: set up `.gba/` with a default configuration and prompts.
+
+use std::path::Path;
+
+use anyhow::{Context, Result, bail};
+use gba_core::{
+    CONFIG_FILE, DEFAULT_CONFIG, PROMPTS_DIR, PhaseConfig, ProjectConfig, TaskConfig,
+    merge_default_config,
+};
+
+use crate::ui::output::say;
+
+/// Worktree directory created next to `.gba`
+pub const TREES_DIR: &str = ".trees";
+
+/// Flags of `gba init`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct InitOptions {
+    /// Initialize even if `.gba` already exists, keeping its configuration
+    pub force: bool,
+    /// Don't scaffold `prompts/`
+    pub no_templates: bool,
+}
+
+/// Create `.gba/` and `.trees/` in `repo`, keep `.trees/` out of git and
+/// scaffold the prompt templates.
+///
+/// With `force` an existing `config.yml` is kept and only gains the keys
+/// it is missing; existing templates are never overwritten.
+pub fn run(repo: &Path, gba_path: &Path, options: InitOptions) -> Result<()> {
+    if gba_path.exists() && !options.force {
+        bail!(
+            "{} already exists; use --force to reinitialize",
+            gba_path.display()
+        );
+    }
+
+    std::fs::create_dir_all(gba_path)
+        .with_context(|| format!("Failed to create {}", gba_path.display()))?;
+    say(format_args!("Created {}", gba_path.display()));
+    let trees = repo.join(TREES_DIR);
+    std::fs::create_dir_all(&trees)
+        .with_context(|| format!("Failed to create {}", trees.display()))?;
+    say(format_args!("Created {}", trees.display()));
+    if ignore_trees(repo)? {
+        say(format_args!("Added {TREES_DIR}/ to .git/info/exclude"));
+    }
+    let config_path = gba_path.join(CONFIG_FILE);
+    if config_path.exists() {
+        let added = merge_default_config(&config_path)?;
+        ProjectConfig::load(gba_path)?
+            .validate()
+            .with_context(|| format!("{} is invalid", config_path.display()))?;
+        if added.is_empty() {
+            say(format_args!("Kept {}", config_path.display()));
+        } else {
+            say(format_args!(
+                "Updated {} with new keys: {}",
+                config_path.display(),
+                added.join(", ")
+            ));
+        }
+    } else {
+        std::fs::write(&config_path, DEFAULT_CONFIG)?;
+        say(format_args!("Wrote {}", config_path.display()));
+    }
+
+    let prompts_dir = gba_path.join(PROMPTS_DIR);
+    if options.no_templates {
+        say(format_args!(
+            "Create prompt templates in {} before running a feature",
+            prompts_dir.display()
+        ));
+    } else {
+        let written = scaffold_templates(&prompts_dir, &ProjectConfig::default().phases)?;
+        say(format_args!(
+            "Scaffolded {written} prompt file(s) in {}",
+            prompts_dir.display()
+        ));
+    }
+    say("Done. Run `gba plan <slug>` to plan a feature");
+    Ok(())
+}
+
+/// Add `.trees/` to `.git/info/exclude`, so `git add -A` in the checkout
+/// doesn't pick up the feature worktrees. Unlike `.gitignore`, the exclude
+/// file isn't tracked, so this leaves no change behind.
+///
+/// Returns whether the exclude file changed.
+///
+/// # Errors
+///
+/// Returns an error if the exclude file can't be read or written.
+fn ignore_trees(repo: &Path) -> Result<bool> {
+    // Only a main checkout has a `.git` directory; worktrees have a file.
+    let git_dir = repo.join(".git");
+    if !git_dir.is_dir() {
+        return Ok(false);
+    }
+    let info = git_dir.join("info");
+    std::fs::create_dir_all(&info)
+        .with_context(|| format!("Failed to create {}", info.display()))?;
+    let path = info.join("exclude");
+    let mut content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+    if content
+        .lines()
+        .any(|line| line.trim().trim_matches('/') == TREES_DIR)
+    {
+        return Ok(false);
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(TREES_DIR);
+    content.push_str("/\n");
+    std::fs::write(&path, content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(true)
+}
+
+/// Write `system.md`, `user.md` and `config.yml` for every phase.
+///
+/// Existing files are kept, so customized templates survive a re-run.
+/// Returns the number of files written.
+///
+/// # Errors
+///
+/// Returns an error if a directory or file can't be written.
+pub fn scaffold_templates(prompts_dir: &Path, phases: &[PhaseConfig]) -> Result<usize> {
+    let mut written = 0;
+    for phase in phases {
+        let task_dir = prompts_dir.join(&phase.name);
+        std::fs::create_dir_all(&task_dir)
+            .with_context(|| format!("Failed to create {}", task_dir.display()))?;
+        let files = [
+            ("system.md", system_template(phase)),
+            ("user.md", user_template(phase)),
+            (TaskConfig::FILE_NAME, task_config(phase)),
+        ];
+        for (file, content) in files {
+            let path = task_dir.join(file);
+            if !path.exists() {
+                std::fs::write(&path, content)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                written += 1;
+            }
+        }
+    }
+    Ok(written)
+}
+
+fn system_template(phase: &PhaseConfig) -> String {
+    format!(
+        "You are a senior software engineer working on feature {{{{ feature_id }}}} \
+         ({{{{ feature_slug }}}}) in {{{{ repo_path }}}}.\n\n\
+         Current phase: {}. {}.\n\
+         Follow the conventions of the existing code.\n",
+        phase.name, phase.description
+    )
+}
+
+fn user_template(phase: &PhaseConfig) -> String {
+    format!(
+        "# {}: {{{{ feature_slug }}}}\n\n\
+         Feature {{{{ feature_id }}}} is described in \
+         `.gba/features/{{{{ feature_id }}}}_{{{{ feature_slug }}}}/specs/`.\n\n\
+         {}.\n",
+        phase.name, phase.description
+    )
+}
+
+fn task_config(phase: &PhaseConfig) -> String {
+    format!(
+        "# Task configuration for the {} phase\n\
+         preset: false\n\
+         tools: []\n\
+         disallowedTools: []\n",
+        phase.name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gba_pm::PromptManager;
+
+    #[test]
+    fn test_scaffolded_templates_are_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let gba_path = dir.path().join(".gba");
+
+        run(dir.path(), &gba_path, InitOptions::default()).unwrap();
+
+        let prompts_dir = gba_path.join(PROMPTS_DIR);
+        let phases = ProjectConfig::default().phases;
+        for phase in &phases {
+            for file in ["system.md", "user.md", TaskConfig::FILE_NAME] {
+                assert!(prompts_dir.join(&phase.name).join(file).is_file());
+            }
+            TaskConfig::load(&prompts_dir.join(&phase.name)).unwrap();
+        }
+        let mut pm = PromptManager::new();
+        pm.load_templates(&prompts_dir).unwrap();
+        assert!(pm.validate_all().is_empty());
+        let build = std::fs::read_to_string(prompts_dir.join("build/user.md")).unwrap();
+        assert!(build.contains("{{ feature_slug }}") && build.contains("{{ feature_id }}"));
+        assert!(dir.path().join(TREES_DIR).is_dir());
+
+        assert!(run(dir.path(), &gba_path, InitOptions::default()).is_err());
+        std::fs::write(prompts_dir.join("build/user.md"), "custom").unwrap();
+        assert_eq!(scaffold_templates(&prompts_dir, &phases).unwrap(), 0);
+        assert_eq!(
+            std::fs::read_to_string(prompts_dir.join("build/user.md")).unwrap(),
+            "custom"
+        );
+    }
+
+    #[test]
+    fn test_trees_are_ignored_in_git_repositories() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!ignore_trees(dir.path()).unwrap());
+        assert!(!dir.path().join(".git").exists());
+
+        let info = dir.path().join(".git/info");
+        std::fs::create_dir_all(&info).unwrap();
+        std::fs::write(
+            info.join("exclude"),
+            "# git ls-files --others --exclude-from=.git/info/exclude",
+        )
+        .unwrap();
+        assert!(ignore_trees(dir.path()).unwrap());
+        assert!(!ignore_trees(dir.path()).unwrap());
+
+        assert_eq!(
+            std::fs::read_to_string(info.join("exclude")).unwrap(),
+            "# git ls-files --others --exclude-from=.git/info/exclude\n.trees/\n"
+        );
+        assert!(!dir.path().join(".gitignore").exists());
+    }
+
+    #[test]
+    fn test_force_keeps_custom_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let gba_path = dir.path().join(".gba");
+        run(dir.path(), &gba_path, InitOptions::default()).unwrap();
+        let config_path = gba_path.join(CONFIG_FILE);
+        let custom = DEFAULT_CONFIG
+            .replace("claude-sonnet-4-5", "claude-opus-4")
+            .replace("  maxAttempts: 3\n", "");
+        std::fs::write(&config_path, custom).unwrap();
+
+        let force = InitOptions {
+            force: true,
+            ..InitOptions::default()
+        };
+        run(dir.path(), &gba_path, force).unwrap();
+
+        let config = ProjectConfig::load(&gba_path).unwrap();
+        assert_eq!(config.agent.model, "claude-opus-4");
+        let content = std::fs::read_to_string(&config_path).unwrap();
+        assert!(content.contains("maxAttempts: 3"));
+        assert!(content.starts_with("# GBA project configuration"));
+    }
+
+    #[test]
+    fn test_force_rejects_unknown_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let gba_path = dir.path().join(".gba");
+        std::fs::create_dir(&gba_path).unwrap();
+        let config_path = gba_path.join(CONFIG_FILE);
+        std::fs::write(&config_path, "agent:\n  timeoutSecond: 600\n").unwrap();
+        let force = InitOptions {
+            force: true,
+            ..InitOptions::default()
+        };
+
+        let err = run(dir.path(), &gba_path, force).unwrap_err().to_string();
+
+        assert!(err.contains("did you mean `timeoutSeconds`?"));
+        assert_eq!(
+            std::fs::read_to_string(&config_path).unwrap(),
+            "agent:\n  timeoutSecond: 600\n"
+        );
+    }
+
+    #[test]
+    fn test_init_without_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let gba_path = dir.path().join(".gba");
+        let options = InitOptions {
+            no_templates: true,
+            ..InitOptions::default()
+        };
+
+        run(dir.path(), &gba_path, options).unwrap();
+
+        assert!(gba_path.join(CONFIG_FILE).is_file());
+        assert!(!gba_path.join(PROMPTS_DIR).exists());
+    }
+}
@@ -0,0 +1,119 @@
+//! `gba list`: list features and their status.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use gba_core::{ConfigLoader, FeatureState, FeatureStatus};
+
+use super::log::format_duration;
+use super::{load_archived, load_features};
+use crate::ui::style::{feature_style, paint};
+
+/// Print all features; archived ones only with `all`
+pub fn run(gba_path: &Path, all: bool) -> Result<()> {
+    let active = load_features(gba_path)?;
+    let archived = if all {
+        load_archived(gba_path)?
+    } else {
+        Vec::new()
+    };
+    if active.is_empty() && archived.is_empty() {
+        println!("No features found");
+        return Ok(());
+    }
+    // A broken config is reported by the commands that need it.
+    let specs = ConfigLoader::new(gba_path)
+        .load()
+        .map(|loaded| loaded.config.specs)
+        .unwrap_or_default();
+    let drafts: Vec<String> = active
+        .iter()
+        .filter(|state| state.status == FeatureStatus::Planned)
+        .map(FeatureState::dir_name)
+        .filter(|name| {
+            let feature_path = gba_path.join(gba_core::FEATURES_DIR).join(name);
+            !FeatureState::draft_problems(&feature_path, &specs).is_empty()
+        })
+        .collect();
+    print!("{}", render(&active, &archived, &drafts));
+    Ok(())
+}
+
+/// Render the feature table, marking the features named in `drafts`
+pub fn render(active: &[FeatureState], archived: &[FeatureState], drafts: &[String]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:<6} {:<32} {:<12} {:<14} {:<10} UPDATED",
+        "ID", "SLUG", "STATUS", "PHASE", "ELAPSED"
+    );
+    let rows = active
+        .iter()
+        .map(|s| (s, false))
+        .chain(archived.iter().map(|s| (s, true)));
+    for (state, is_archived) in rows {
+        let phase = state
+            .phases
+            .get(state.current_phase)
+            .map_or("-", |p| p.name.as_str());
+        let elapsed = state
+            .duration()
+            .map_or_else(|| "-".to_string(), format_duration);
+        let _ = write!(
+            out,
+            "{:<6} {:<32} {} {:<14} {:<10} {}",
+            state.feature.id,
+            state.feature.slug,
+            paint(
+                &format!("{:<12}", state.status.to_string()),
+                feature_style(state.status)
+            ),
+            phase,
+            elapsed,
+            state.feature.updated_at.format("%Y-%m-%d %H:%M")
+        );
+        if is_archived {
+            out.push_str("  (archived)");
+        } else if drafts.contains(&state.dir_name()) {
+            out.push_str("  (draft)");
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeDelta, Utc};
+
+    #[test]
+    fn test_render_marks_archived() {
+        let active = [FeatureState::new("0002", "search")];
+        let archived = [FeatureState::new("0001", "auth")];
+
+        let out = render(&active, &archived, &["0002_search".to_string()]);
+        let lines: Vec<_> = out.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("0002   search"));
+        assert!(lines[1].ends_with("(draft)"));
+        assert!(lines[2].contains("auth") && lines[2].ends_with("(archived)"));
+    }
+
+    #[test]
+    fn test_render_elapsed() {
+        let mut running = FeatureState::new("0001", "auth");
+        running.phase_mut("observe").started_at = Some(Utc::now() - TimeDelta::seconds(90));
+        let mut done = FeatureState::new("0002", "search");
+        done.total_stats.wall_clock_seconds = 252;
+
+        let out = render(&[running, done], &[], &[]);
+        let lines: Vec<_> = out.lines().collect();
+
+        assert!(lines[0].contains("ELAPSED"));
+        assert!(lines[1].contains(" 1m3"));
+        assert!(lines[2].contains(" 4m12s "));
+    }
+}
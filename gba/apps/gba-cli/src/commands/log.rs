@@ -0,0 +1,130 @@
+//! `gba log`: per-phase history of a feature run.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use gba_core::{FeatureState, PhaseState};
+
+/// Print the phase timeline of a feature
+pub fn run(gba_path: &Path, feature: &str, json: bool) -> Result<()> {
+    let feature_path = FeatureState::find_dir(gba_path, feature)?;
+    let state = FeatureState::load(&feature_path)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&state.phases)?);
+    } else {
+        print!("{}", render(&state));
+    }
+    Ok(())
+}
+
+/// Render the phase timeline of a feature
+pub fn render(state: &FeatureState) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Feature {} [{}]", state.dir_name(), state.status);
+    if state.phases.is_empty() {
+        let _ = writeln!(out, "  (no phases executed yet)");
+    }
+    for phase in &state.phases {
+        render_phase(&mut out, phase);
+    }
+    out
+}
+
+fn render_phase(out: &mut String, phase: &PhaseState) {
+    let _ = writeln!(
+        out,
+        "{} {} [{}]",
+        phase.status.icon(),
+        phase.name,
+        phase.status
+    );
+    let _ = writeln!(
+        out,
+        "    started:   {}",
+        format_time(phase.started_at.as_ref())
+    );
+    let _ = writeln!(
+        out,
+        "    completed: {}",
+        format_time(phase.completed_at.as_ref())
+    );
+    if let Some(started) = phase.started_at {
+        let end = phase.completed_at.unwrap_or_else(Utc::now);
+        let _ = writeln!(out, "    duration:  {}", format_duration(end - started));
+    }
+    if let Some(stats) = &phase.stats {
+        let _ = writeln!(
+            out,
+            "    turns:     {}    cost: ${:.2}",
+            stats.turns, stats.cost_usd
+        );
+    }
+    if let Some(sha) = &phase.commit_sha {
+        let _ = writeln!(out, "    commit:    {sha}");
+    }
+    if let Some(summary) = &phase.output_summary {
+        let _ = writeln!(out, "    summary:   {summary}");
+    }
+}
+
+fn format_time(time: Option<&DateTime<Utc>>) -> String {
+    time.map_or_else(
+        || "-".to_string(),
+        |t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+    )
+}
+
+pub(crate) fn format_duration(duration: chrono::TimeDelta) -> String {
+    let secs = duration.num_seconds().max(0);
+    match (secs / 3600, secs % 3600 / 60, secs % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m{s:02}s"),
+        (h, m, s) => format!("{h}h{m:02}m{s:02}s"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeDelta;
+    use gba_core::{ExecutionStats, PhaseStatus};
+
+    fn completed_phase(state: &mut FeatureState, name: &str, minutes: i64, cost_usd: f64) {
+        let started = Utc::now() - TimeDelta::hours(1);
+        let phase = state.phase_mut(name);
+        phase.status = PhaseStatus::Completed;
+        phase.started_at = Some(started);
+        phase.completed_at = Some(started + TimeDelta::minutes(minutes));
+        phase.stats = Some(ExecutionStats {
+            turns: 8,
+            cost_usd,
+            ..ExecutionStats::default()
+        });
+        phase.commit_sha = Some("def5678".to_string());
+    }
+
+    #[test]
+    fn test_render_log_lists_phases_and_costs() {
+        let mut state = FeatureState::new("0001", "user-auth");
+        completed_phase(&mut state, "observe", 15, 0.42);
+        completed_phase(&mut state, "build", 45, 0.89);
+
+        let out = render(&state);
+
+        assert!(out.contains("✓ observe [completed]"));
+        assert!(out.contains("✓ build [completed]"));
+        assert!(out.contains("$0.42"));
+        assert!(out.contains("$0.89"));
+        assert!(out.contains("duration:  45m00s"));
+        assert!(out.contains("commit:    def5678"));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(TimeDelta::seconds(42)), "42s");
+        assert_eq!(format_duration(TimeDelta::seconds(252)), "4m12s");
+        assert_eq!(format_duration(TimeDelta::seconds(3723)), "1h02m03s");
+    }
+}
@@ -0,0 +1,99 @@
+//! Subcommand implementations.
+
+use std::fmt::Write as _;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::Path;
+
+use anyhow::Result;
+
+use gba_core::{CoreError, FeatureListing, FeatureState};
+
+pub mod archive;
+pub mod auth;
+pub mod completions;
+pub mod config;
+pub mod cost;
+pub mod delete;
+pub mod diff;
+pub mod edit;
+pub mod execute;
+pub mod export;
+pub mod init;
+pub mod list;
+pub mod log;
+pub mod notify;
+pub mod plan;
+pub mod report;
+pub mod retry;
+pub mod rollback;
+pub mod run;
+pub mod run_all;
+pub mod show;
+pub mod status;
+pub mod templates;
+pub mod validate;
+
+/// Load the state of every feature under `.gba/features`, sorted by ID.
+///
+/// Features whose state can't be loaded are reported on stderr.
+pub fn load_features(gba_path: &Path) -> Result<Vec<FeatureState>> {
+    Ok(loaded(FeatureState::list_all(gba_path)?))
+}
+
+/// Load the state of every feature under `.gba/archive`, sorted by ID
+pub fn load_archived(gba_path: &Path) -> Result<Vec<FeatureState>> {
+    Ok(loaded(FeatureState::list_archived(gba_path)?))
+}
+
+fn loaded(listing: FeatureListing) -> Vec<FeatureState> {
+    if !listing.failed.is_empty() {
+        eprint!("{}", render_unloadable(&listing.failed));
+    }
+    listing.loaded.into_iter().map(|(_, state)| state).collect()
+}
+
+/// Warning block listing features whose state failed to load
+pub fn render_unloadable(failed: &[(String, CoreError)]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "warning: {} feature(s) could not be loaded:",
+        failed.len()
+    );
+    for (name, e) in failed {
+        let _ = writeln!(out, "  {name}: {e}");
+    }
+    out
+}
+
+/// Ask a yes/no question on the terminal (default: no)
+pub fn confirm(prompt: &str) -> Result<bool> {
+    print!("{prompt}");
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+/// Whether stdin is an interactive terminal
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_unloadable() {
+        let dir = tempfile::tempdir().unwrap();
+        let broken = dir.path().join(gba_core::FEATURES_DIR).join("0002_broken");
+        std::fs::create_dir_all(&broken).unwrap();
+
+        let listing = FeatureState::list_all(dir.path()).unwrap();
+        let out = render_unloadable(&listing.failed);
+
+        assert!(out.starts_with("warning: 1 feature(s) could not be loaded:\n"));
+        assert!(out.contains("  0002_broken: "));
+    }
+}
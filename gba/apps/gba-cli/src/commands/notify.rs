@@ -0,0 +1,52 @@
+//! Notifications at the end of `gba run`, and `gba notify-test`.
+
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use chrono::{DateTime, Utc};
+use gba_core::notify::{self, RunNotification};
+use gba_core::{ConfigLoader, FeatureState, FeatureStatus, RealCommandRunner};
+
+use crate::ui::output::say;
+
+/// Notify about a run of `feature` that began at `started`.
+///
+/// Runs that didn't change the feature (aborted, nothing to do) aren't
+/// reported. Failing to notify only prints a warning.
+pub fn after_run(gba_path: &Path, feature: &str, started: DateTime<Utc>, error: Option<String>) {
+    let Ok(loaded) = ConfigLoader::new(gba_path).load() else {
+        return;
+    };
+    let config = loaded.config.notifications;
+    if !config.desktop && config.webhook_url.is_none() {
+        return;
+    }
+    let Ok(state) = FeatureState::find_dir(gba_path, feature).and_then(|p| FeatureState::load(&p))
+    else {
+        return;
+    };
+    if state.feature.updated_at < started {
+        return;
+    }
+    let notification = RunNotification::new(&state, error);
+    if let Err(e) = notify::send(&RealCommandRunner, &config, &notification) {
+        eprintln!("warning: {e}");
+    }
+}
+
+/// Send a sample notification through the configured channels
+pub fn test(gba_path: &Path) -> Result<()> {
+    let config = ConfigLoader::new(gba_path).load()?.config.notifications;
+    if !config.desktop && config.webhook_url.is_none() {
+        bail!("no notifications configured; set notifications.desktop or notifications.webhookUrl");
+    }
+    let mut state = FeatureState::new("0000", "notify-test");
+    state.status = FeatureStatus::Completed;
+    notify::send(
+        &RealCommandRunner,
+        &config,
+        &RunNotification::new(&state, None),
+    )?;
+    say("Notification sent");
+    Ok(())
+}
@@ -0,0 +1,234 @@
+//! `gba plan`: create a feature with its design and verification documents.
+
+use std::fmt::Write as _;
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, bail};
+use gba_core::{DESIGN_FILE, FEATURES_DIR, FeatureState, verification::VERIFICATION_FILE};
+
+use crate::ui::output::say;
+
+/// Flags of `gba plan`
+#[derive(Debug, Clone, Default)]
+pub struct PlanOptions {
+    /// Initial feature description for the boilerplate design
+    pub description: Option<String>,
+    /// Existing document to use as the design instead of the boilerplate
+    pub from_file: Option<PathBuf>,
+    /// Ask for the description, requirements and acceptance criteria
+    pub interactive: bool,
+}
+
+/// Answers that fill in the design and verification documents
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlanAnswers {
+    /// What the feature is about
+    pub description: Option<String>,
+    /// Requirements, one per item
+    pub requirements: Vec<String>,
+    /// Acceptance criteria, one per item
+    pub criteria: Vec<String>,
+}
+
+/// Plan feature `slug` and print how to run it
+pub fn run(gba_path: &Path, slug: &str, options: &PlanOptions) -> Result<()> {
+    let mut answers = PlanAnswers {
+        description: options.description.clone(),
+        ..PlanAnswers::default()
+    };
+    if options.interactive {
+        let stdin = std::io::stdin();
+        answers = ask(&mut stdin.lock(), &mut std::io::stdout(), answers)?;
+    }
+
+    let design = match &options.from_file {
+        Some(path) => {
+            if options.description.is_some() {
+                eprintln!(
+                    "warning: --description is ignored because --from-file {} is given",
+                    path.display()
+                );
+            }
+            std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+        }
+        None => design_doc(slug, &answers),
+    };
+
+    let feature_path = create(gba_path, slug, &design, &verification_doc(slug, &answers))?;
+    let name = feature_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    say(format_args!("Created feature {name}"));
+    say(format_args!(
+        "Plan finished. Run 'gba run {name}' to execute"
+    ));
+    Ok(())
+}
+
+/// Ask for whatever `answers` doesn't have yet.
+///
+/// Lists are read one item per line until an empty line.
+///
+/// # Errors
+///
+/// Returns an error if reading the input or writing the questions fails.
+pub fn ask(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    mut answers: PlanAnswers,
+) -> Result<PlanAnswers> {
+    if answers.description.is_none() {
+        write!(output, "Describe the feature: ")?;
+        output.flush()?;
+        let description = read_line(input)?;
+        answers.description = (!description.is_empty()).then_some(description);
+    }
+    writeln!(output, "Requirements (one per line, empty line to finish):")?;
+    answers.requirements = read_list(input)?;
+    writeln!(
+        output,
+        "Acceptance criteria (one per line, empty line to finish):"
+    )?;
+    answers.criteria = read_list(input)?;
+    Ok(answers)
+}
+
+fn read_line(input: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    input.read_line(&mut line)?;
+    Ok(line.trim().to_string())
+}
+
+fn read_list(input: &mut impl BufRead) -> Result<Vec<String>> {
+    let mut items = Vec::new();
+    loop {
+        let item = read_line(input)?;
+        if item.is_empty() {
+            return Ok(items);
+        }
+        items.push(item);
+    }
+}
+
+/// Create the feature directory with its design and verification documents.
+///
+/// # Errors
+///
+/// Returns an error if the slug is invalid or the files can't be written.
+pub fn create(gba_path: &Path, slug: &str, design: &str, verification: &str) -> Result<PathBuf> {
+    if slug.is_empty() || slug.contains(['/', '\\']) || slug.starts_with('.') {
+        bail!("invalid feature slug `{slug}`");
+    }
+
+    let state = FeatureState::new(FeatureState::next_id(gba_path)?, slug);
+    let feature_path = gba_path.join(FEATURES_DIR).join(state.dir_name());
+    std::fs::create_dir_all(feature_path.join("specs"))
+        .with_context(|| format!("Failed to create {}", feature_path.display()))?;
+    std::fs::write(feature_path.join(DESIGN_FILE), design)?;
+    std::fs::write(feature_path.join(VERIFICATION_FILE), verification)?;
+    state.save(&feature_path)?;
+    Ok(feature_path)
+}
+
+/// Design document; missing answers become TODO placeholders
+pub fn design_doc(slug: &str, answers: &PlanAnswers) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Design: {slug}\n");
+    let _ = writeln!(out, "## Overview\n");
+    let _ = writeln!(
+        out,
+        "{}\n",
+        answers
+            .description
+            .as_deref()
+            .unwrap_or("TODO: describe the feature.")
+    );
+    let _ = writeln!(out, "## Requirements\n");
+    if answers.requirements.is_empty() {
+        let _ = writeln!(out, "- TODO");
+    }
+    for requirement in &answers.requirements {
+        let _ = writeln!(out, "- {requirement}");
+    }
+    let _ = writeln!(out, "\n## Implementation\n");
+    let _ = writeln!(out, "TODO: components, data flow and key decisions.");
+    out
+}
+
+/// Acceptance criteria as a checklist; a TODO item if there are none
+pub fn verification_doc(slug: &str, answers: &PlanAnswers) -> String {
+    let mut out = format!("# Verification: {slug}\n\n## Acceptance Criteria\n\n");
+    if answers.criteria.is_empty() {
+        out.push_str("- [ ] TODO\n");
+    }
+    for criterion in &answers.criteria {
+        let _ = writeln!(out, "- [ ] {criterion}");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::output;
+
+    #[test]
+    fn test_plan_from_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let source = dir.path().join("issue.md");
+        std::fs::write(&source, "# Login\n\nUsers sign in with email.\n").unwrap();
+        let gba_path = dir.path().join(".gba");
+        let options = PlanOptions {
+            description: Some("ignored".to_string()),
+            from_file: Some(source.clone()),
+            ..PlanOptions::default()
+        };
+
+        let out = output::capture(true, || run(&gba_path, "login", &options).unwrap());
+
+        assert_eq!(out, "");
+        let feature_path = FeatureState::find_dir(&gba_path, "login").unwrap();
+        assert_eq!(
+            std::fs::read_to_string(feature_path.join(DESIGN_FILE)).unwrap(),
+            std::fs::read_to_string(&source).unwrap()
+        );
+        assert!(feature_path.join(VERIFICATION_FILE).is_file());
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.dir_name(), "0001_login");
+
+        let missing = PlanOptions {
+            from_file: Some(dir.path().join("missing.md")),
+            ..PlanOptions::default()
+        };
+        assert!(run(&gba_path, "signup", &missing).is_err());
+    }
+
+    #[test]
+    fn test_docs_from_canned_answers() {
+        let mut input =
+            "Email sign-in\nHash passwords\nRate limit\n\nLogin returns a JWT\n\n".as_bytes();
+        let mut output = Vec::new();
+
+        let answers = ask(&mut input, &mut output, PlanAnswers::default()).unwrap();
+        let design = design_doc("login", &answers);
+        let verification = verification_doc("login", &answers);
+
+        assert_eq!(answers.requirements, ["Hash passwords", "Rate limit"]);
+        assert!(design.starts_with("# Design: login\n"));
+        assert!(design.contains("## Overview\n\nEmail sign-in\n"));
+        assert!(design.contains("## Requirements\n\n- Hash passwords\n- Rate limit\n"));
+        assert!(verification.ends_with("## Acceptance Criteria\n\n- [ ] Login returns a JWT\n"));
+        assert!(
+            String::from_utf8(output)
+                .unwrap()
+                .contains("Describe the feature: ")
+        );
+
+        let defaults = PlanAnswers::default();
+        assert!(design_doc("login", &defaults).contains("TODO: describe the feature."));
+        assert!(verification_doc("login", &defaults).contains("- [ ] TODO\n"));
+    }
+}
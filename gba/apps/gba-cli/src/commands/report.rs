@@ -0,0 +1,51 @@
+//! `gba report`: spend aggregated across all features.
+
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use gba_core::{CostReport, FeatureState};
+
+use super::load_features;
+
+/// Print the cost report of every feature under `.gba/features`
+pub fn run(gba_path: &Path, json: bool) -> Result<()> {
+    let features = load_features(gba_path)?;
+
+    let reports: Vec<_> = features.iter().map(FeatureState::cost_report).collect();
+    let total = CostReport::aggregate(&reports);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&total)?);
+    } else {
+        print!("{}", render(&total, features.len()));
+    }
+    Ok(())
+}
+
+/// Render the aggregated report as a table
+pub fn render(report: &CostReport, feature_count: usize) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Cost report ({feature_count} features)");
+    let _ = writeln!(
+        out,
+        "{:<14} {:>7} {:>12} {:>12} {:>10}",
+        "PHASE", "TURNS", "INPUT", "OUTPUT", "COST"
+    );
+    let rows = report
+        .phases
+        .iter()
+        .map(|p| (p.phase.as_str(), &p.stats))
+        .chain(std::iter::once(("TOTAL", &report.total)));
+    for (name, stats) in rows {
+        let _ = writeln!(
+            out,
+            "{:<14} {:>7} {:>12} {:>12} {:>10}",
+            name,
+            stats.turns,
+            stats.input_tokens,
+            stats.output_tokens,
+            format!("${:.2}", stats.cost_usd)
+        );
+    }
+    out
+}
@@ -0,0 +1,146 @@
+//! `gba retry`: run the last failed phase of a feature again.
+
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use gba_core::{ConfigLoader, FeatureState, PhaseState};
+
+use super::run::{self, RunOptions};
+
+/// Retry the last failed phase of `feature`, up to `agent.maxAttempts`.
+///
+/// With `feedback` the previous failure is appended to the prompt.
+pub async fn run(
+    gba_path: &Path,
+    feature: &str,
+    config: gba_core::Config,
+    options: RunOptions,
+    feedback: bool,
+) -> Result<()> {
+    let feature_path = FeatureState::find_dir(gba_path, feature)?;
+    let mut state = FeatureState::load(&feature_path)?;
+    let Some(failed) = state.last_failed_phase() else {
+        bail!("{} has no failed phase to retry", state.dir_name());
+    };
+    let name = failed.name.clone();
+
+    let max_attempts = ConfigLoader::new(gba_path)
+        .load()?
+        .config
+        .agent
+        .max_attempts;
+    if failed.attempts >= max_attempts {
+        let error = format!(
+            "Phase {name} failed after {} attempts (agent.maxAttempts is {max_attempts})",
+            failed.attempts
+        );
+        state.fail_phase(&name, error.clone(), None);
+        state.save(&feature_path)?;
+        bail!(error);
+    }
+
+    let options = RunOptions {
+        feedback: feedback.then(|| failure_note(&state, failed)),
+        phase: Some(name),
+        ..options
+    };
+    run::run(gba_path, feature, config, options).await
+}
+
+/// Prompt note describing how the previous attempt of `phase` failed
+pub fn failure_note(state: &FeatureState, phase: &PhaseState) -> String {
+    let error = state.error.as_deref().unwrap_or("unknown error");
+    let mut note = format!("## Previous Attempt\n\nThe previous attempt failed with: {error}");
+    if let Some(summary) = &phase.output_summary {
+        note.push_str("\n\n");
+        note.push_str(summary);
+    }
+    note
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::run::tests::setup;
+    use gba_core::PhaseStatus;
+
+    fn options() -> RunOptions {
+        RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        }
+    }
+
+    fn fail_build(feature_path: &Path, attempts: u32) {
+        let mut state = FeatureState::load(feature_path).unwrap();
+        state.start_phase(0, "observe");
+        state.complete_phase("observe", &Default::default(), String::new());
+        state.start_phase(1, "build");
+        state.phase_mut("build").attempts = attempts;
+        state.fail_phase("build", "Agent timed out".to_string(), None);
+        state.save(feature_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_retry_reruns_failed_phase() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        fail_build(&feature_path, 1);
+
+        run(&gba_path, "auth", config, options(), true)
+            .await
+            .unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        let build = state.phase("build").unwrap();
+        assert_eq!(build.status, PhaseStatus::Completed);
+        assert_eq!(build.attempts, 2);
+        assert_eq!(state.phase("observe").unwrap().attempts, 1);
+        assert_eq!(state.status, gba_core::FeatureStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_retry_in_a_git_checkout() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        crate::commands::run::tests::commit_repo(dir.path());
+        // The failed run left its own state.yml modified.
+        fail_build(&feature_path, 1);
+
+        run(&gba_path, "auth", config, options(), false)
+            .await
+            .unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.phase("build").unwrap().status, PhaseStatus::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_attempts() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        fail_build(&feature_path, 3);
+
+        let err = run(&gba_path, "auth", config, options(), true)
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("failed after 3 attempts"));
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.error.as_deref(), Some(err.to_string().as_str()));
+        assert_eq!(state.phase("build").unwrap().attempts, 3);
+    }
+
+    #[test]
+    fn test_failure_note() {
+        let mut state = FeatureState::new("0001", "auth");
+        state.fail_phase("build", "Phase build failed".to_string(), None);
+        state.phase_mut("build").output_summary = Some("cargo test: 2 failures".to_string());
+
+        let note = failure_note(&state, state.phase("build").unwrap());
+
+        assert!(note.contains("The previous attempt failed with: Phase build failed"));
+        assert!(note.ends_with("\n\ncargo test: 2 failures"));
+    }
+}
@@ -0,0 +1,128 @@
+//! `gba rollback`: reset a feature to the commit of an earlier phase.
+
+use std::path::Path;
+
+use anyhow::Result;
+use gba_core::{ConfigLoader, FeatureState, RealCommandRunner, git};
+
+use super::confirm;
+use crate::ui::output::say;
+
+/// Reset the worktree of `feature` to the commit recorded for `phase` and
+/// forget the phases after it, so the next run continues from there
+pub fn run(repo: &Path, gba_path: &Path, feature: &str, phase: &str, yes: bool) -> Result<()> {
+    let feature_path = FeatureState::find_dir(gba_path, feature)?;
+    let mut state = FeatureState::load(&feature_path)?;
+    let project = ConfigLoader::new(gba_path).load()?.config;
+    let tree = match &state.git {
+        Some(git) => repo.join(&git.worktree_path),
+        None => repo.to_path_buf(),
+    };
+    let dropped: Vec<String> = state
+        .phases
+        .iter()
+        .skip_while(|p| p.name != phase)
+        .skip(1)
+        .map(|p| p.name.clone())
+        .collect();
+    let sha = state.rollback_to(phase)?;
+    let short = &sha[..sha.len().min(7)];
+
+    say(format_args!(
+        "This will reset {} to {short}, discarding uncommitted changes",
+        tree.display()
+    ));
+    if !dropped.is_empty() {
+        say(format_args!("  and forget phases {}", dropped.join(", ")));
+    }
+    if !yes && !confirm("Continue? [y/N] ")? {
+        say("Aborted");
+        return Ok(());
+    }
+
+    git::reset_hard(&RealCommandRunner, &tree, &sha)?;
+    state.resume.next_phase = project
+        .execution_order()?
+        .into_iter()
+        .map(|index| &project.phases[index].name)
+        .find(|name| state.phase(name).is_none_or(|s| !s.status.is_done()))
+        .cloned();
+    state.save(&feature_path)?;
+    match &state.resume.next_phase {
+        Some(next) => say(format_args!(
+            "Rolled back {} to {phase} ({short}); `gba run {}` continues with {next}",
+            state.dir_name(),
+            state.feature.id
+        )),
+        None => say(format_args!(
+            "Rolled back {} to {phase} ({short})",
+            state.dir_name()
+        )),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::run::tests::setup;
+    use gba_core::{FeatureStatus, PhaseStatus};
+
+    #[test]
+    fn test_rollback_resets_to_the_phase_commit() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, _) = setup(dir.path());
+        let git = |args: &[&str]| {
+            let output = std::process::Command::new("git")
+                .args(["-c", "user.name=gba", "-c", "user.email=gba@example.com"])
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap();
+            assert!(output.status.success(), "git {args:?} failed");
+            String::from_utf8(output.stdout).unwrap().trim().to_string()
+        };
+        let login = dir.path().join("login.rs");
+        git(&["init", "--quiet"]);
+        std::fs::write(&login, "fn login() {}\n").unwrap();
+        git(&["add", "login.rs"]);
+        git(&["commit", "--quiet", "-m", "observe"]);
+        let observe_sha = git(&["rev-parse", "HEAD"]);
+        std::fs::write(&login, "fn login() { todo!() }\n").unwrap();
+        git(&["commit", "--quiet", "-am", "build"]);
+        let build_sha = git(&["rev-parse", "HEAD"]);
+
+        let mut state = FeatureState::load(&feature_path).unwrap();
+        for (index, (name, sha)) in [("observe", &observe_sha), ("build", &build_sha)]
+            .into_iter()
+            .enumerate()
+        {
+            state.start_phase(index, name);
+            state.complete_phase(name, &Default::default(), format!("{name} done"));
+            state.record_commit(name, sha.clone());
+        }
+        state.start_phase(2, "test");
+        state.complete_phase("test", &Default::default(), String::new());
+        state.complete();
+        state.save(&feature_path).unwrap();
+
+        let err = run(dir.path(), &gba_path, "auth", "test", true).unwrap_err();
+        assert!(err.to_string().contains("no recorded commit"), "{err}");
+
+        run(dir.path(), &gba_path, "auth", "observe", true).unwrap();
+
+        assert_eq!(git(&["rev-parse", "HEAD"]), observe_sha);
+        assert_eq!(std::fs::read_to_string(&login).unwrap(), "fn login() {}\n");
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.phases.len(), 1);
+        assert_eq!(state.phases[0].status, PhaseStatus::Completed);
+        assert_eq!(state.current_phase, 1);
+        assert_eq!(state.status, FeatureStatus::InProgress);
+        assert!(state.resume.can_resume);
+        assert_eq!(
+            state.resume.last_completed_phase.as_deref(),
+            Some("observe")
+        );
+        assert_eq!(state.resume.next_phase.as_deref(), Some("build"));
+    }
+}
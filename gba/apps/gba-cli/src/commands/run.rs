@@ -0,0 +1,1939 @@
+//! `gba run`: execute the phases of a planned feature.
+
+use std::fmt::Write;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use gba_core::lock::FeatureLock;
+use gba_core::pr::{self, PullRequestDraft};
+use gba_core::transcript::{ReplayBackend, TranscriptRecorder};
+use gba_core::{
+    CommandRunner, ConfigLoader, CostEstimate, Engine, ExecutionResult, FeatureState,
+    FeatureStatus, GitInfo, HookContext, InterruptReason, PROMPTS_DIR, Phase, PhaseConfig,
+    PhaseKind, ProjectConfig, RealCommandRunner, RunEvent, RunEventSender, TaskConfig, gh, git,
+    mcp, observations, review, testing, verification,
+};
+use gba_pm::{PromptContext, PromptManager};
+
+use super::init::TREES_DIR;
+use super::{confirm, is_interactive, load_features, notify};
+use crate::progress::{self, PhaseProgress};
+use crate::ui::approval::TerminalApprover;
+use crate::ui::output;
+
+/// Maximum length of the output summary stored per phase
+const SUMMARY_CHARS: usize = 200;
+
+/// Maximum length of the hook output appended to the summary
+const HOOK_OUTPUT_CHARS: usize = 2000;
+
+/// Flags of `gba run` and `gba retry`
+#[derive(Debug, Clone, Default)]
+pub struct RunOptions {
+    /// Don't ask for confirmation
+    pub yes: bool,
+    /// Log plain status lines instead of drawing a spinner
+    pub no_progress: bool,
+    /// Stream the agent's text while it runs
+    pub verbose: bool,
+    /// Walk the phases with an offline engine, without hooks or saving state
+    pub dry_run: bool,
+    /// Run only this phase
+    pub phase: Option<String>,
+    /// Run `phase` on its own (`--only`): even if it is done or its
+    /// dependencies aren't, and without changing the feature's status
+    pub only: bool,
+    /// Appended to the user prompt, e.g. why the previous attempt failed
+    pub feedback: Option<String>,
+    /// Phases to mark as skipped instead of running
+    pub skip: Vec<String>,
+    /// Write NDJSON events instead of human output; implies `yes`
+    pub json: bool,
+    /// Send events here instead of printing human output; implies `yes`
+    pub events: Option<RunEventSender>,
+    /// Run even if the working tree has uncommitted changes
+    pub allow_dirty: bool,
+    /// Run a planned feature even if its specs are still drafts
+    pub force: bool,
+    /// Run the observe phase even if `docs/observations.md` exists
+    pub force_observe: bool,
+    /// Share this engine's rate limits instead of starting fresh ones
+    pub engine: Option<Engine>,
+    /// Record the agent's messages as transcripts in this directory
+    pub record: Option<PathBuf>,
+    /// Replay the transcripts of this directory instead of calling the API
+    pub replay: Option<PathBuf>,
+    /// Pause before each replayed message
+    pub replay_delay: Duration,
+}
+
+impl RunOptions {
+    /// Print a human status line, unless events are written or sent
+    fn say(&self, message: impl std::fmt::Display) {
+        if !self.json && self.events.is_none() {
+            output::say(message);
+        }
+    }
+
+    /// Write `event` as a JSON line with `json` and send it to `events`
+    fn emit(&self, event: RunEvent) {
+        if self.json {
+            println!("{}", event.to_json_line());
+        }
+        if let Some(events) = &self.events {
+            let _ = events.send(event);
+        }
+    }
+}
+
+/// Run every phase of `feature` that hasn't completed yet.
+///
+/// Ctrl-C stops the running phase and leaves the feature resumable. The
+/// configured notifications are sent once the run stops.
+pub async fn run(
+    gba_path: &Path,
+    feature: &str,
+    config: gba_core::Config,
+    options: RunOptions,
+) -> Result<()> {
+    let ctrl_c = async {
+        // Without a signal handler Ctrl-C simply kills the process.
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+    run_with_interrupt(gba_path, feature, config, options, ctrl_c).await
+}
+
+/// Like [`run`], but stopped when `interrupt` completes instead of on Ctrl-C
+pub async fn run_with_interrupt(
+    gba_path: &Path,
+    feature: &str,
+    config: gba_core::Config,
+    options: RunOptions,
+    interrupt: impl Future<Output = ()>,
+) -> Result<()> {
+    let started = chrono::Utc::now();
+    let dry_run = options.dry_run;
+    let result = run_until(gba_path, feature, config, options, interrupt).await;
+    if !dry_run {
+        let error = result.as_ref().err().map(ToString::to_string);
+        notify::after_run(gba_path, feature, started, error);
+    }
+    result
+}
+
+/// Like [`run`], but interrupted when `interrupt` completes
+async fn run_until(
+    gba_path: &Path,
+    feature: &str,
+    mut config: gba_core::Config,
+    options: RunOptions,
+    interrupt: impl Future<Output = ()>,
+) -> Result<()> {
+    let feature_path = FeatureState::find_dir(gba_path, feature)?;
+    // Held until the run returns, however it ends.
+    let _lock = FeatureLock::acquire(&feature_path)?;
+    let mut state = FeatureState::load(&feature_path)?;
+    let project = ConfigLoader::new(gba_path).load()?.config;
+    let Some(pending) = plan_phases(&project, &mut state, &options, &feature_path)? else {
+        options.say(format_args!(
+            "All phases of {} are already completed",
+            state.dir_name()
+        ));
+        options.emit(run_completed(&state));
+        return Ok(());
+    };
+
+    // Features that already started were checked (or forced) back then.
+    if state.status == FeatureStatus::Planned && !options.force {
+        FeatureState::validate_ready(&feature_path, &project.specs)?;
+    }
+    // The check applies to the tree the phases run in; a worktree created
+    // for this run holds nothing but the checked out commit.
+    let check_clean = !options.dry_run && !options.allow_dirty && !project.git.allow_dirty;
+    let new_worktree = !options.dry_run && project.git.use_worktree && state.git.is_none();
+    if check_clean && !new_worktree {
+        ensure_clean_tree(&state, &config.repo_path).await?;
+    }
+    if !confirm_run(gba_path, &state, &mut config, &pending, &options)? {
+        output::say("Aborted");
+        return Ok(());
+    }
+    if new_worktree {
+        add_worktree(&mut state, &config.repo_path).await?;
+        match &state.git {
+            Some(git) => {
+                state.save(&feature_path)?;
+                options.say(format_args!(
+                    "Created worktree {} on branch {}",
+                    git.worktree_path.display(),
+                    git.branch
+                ));
+            }
+            // Without a commit to start from, the phases run in the checkout.
+            None if check_clean => ensure_clean_tree(&state, &config.repo_path).await?,
+            None => {}
+        }
+    }
+    options.emit(RunEvent::RunStarted {
+        feature: state.dir_name(),
+        phases: pending.iter().map(|(_, p)| p.name.clone()).collect(),
+    });
+
+    if let Some(git) = &state.git {
+        config.repo_path = config.repo_path.join(&git.worktree_path);
+    }
+    let (engine, ask_approval) = build_engine(config, &options)?;
+    // Templates in `prompts/` replace the global ones, which replace the
+    // built-in ones.
+    let mut pm = super::templates::prompt_manager(gba_path)?;
+    super::templates::configure(&mut pm, &project);
+    let runner = PhaseRunner {
+        options: &options,
+        project: &project,
+        feature_path,
+        prompts_dir: gba_path.join(PROMPTS_DIR),
+        working_dir: engine.config().repo_path.to_string_lossy().into_owned(),
+        engine,
+        pm,
+        // The spinner would draw over approval questions.
+        spinner: !options.no_progress
+            && !ask_approval
+            && !output::is_quiet()
+            && progress::spinner_supported(),
+    };
+
+    tokio::pin!(interrupt);
+    let status_before = state.status;
+    for (index, phase_config) in pending {
+        runner
+            .run_phase(&mut state, index, phase_config, interrupt.as_mut())
+            .await?;
+    }
+    runner.finish(state, status_before)
+}
+
+/// Phases of the run in execution order, after marking the skipped ones in
+/// `state`; `None` if no phase is left to run or skip.
+///
+/// # Errors
+///
+/// Fails if `--skip-phase` or `--phase` name an unknown phase.
+fn plan_phases<'a>(
+    project: &'a ProjectConfig,
+    state: &mut FeatureState,
+    options: &RunOptions,
+    feature_path: &Path,
+) -> Result<Option<Vec<(usize, &'a PhaseConfig)>>> {
+    if let Some(unknown) = options
+        .skip
+        .iter()
+        .find(|name| !project.phases.iter().any(|p| &p.name == *name))
+    {
+        anyhow::bail!("Unknown phase `{unknown}` in --skip-phase");
+    }
+    if let Some(only) = &options.phase
+        && !project.phases.iter().any(|p| &p.name == only)
+    {
+        anyhow::bail!("Unknown phase `{only}`");
+    }
+    let rerun = |name: &String| options.only && options.phase.as_ref() == Some(name);
+
+    let (skipped, pending): (Vec<_>, Vec<_>) = project
+        .execution_order()?
+        .into_iter()
+        .map(|index| (index, &project.phases[index]))
+        .filter(|(_, p)| rerun(&p.name) || state.phase(&p.name).is_none_or(|s| !s.status.is_done()))
+        .filter(|(_, p)| options.phase.as_ref().is_none_or(|only| &p.name == only))
+        .partition(|(_, p)| skip_reason(p, options, feature_path).is_some());
+    for (_, phase_config) in &skipped {
+        let reason = skip_reason(phase_config, options, feature_path).unwrap_or_default();
+        options.say(format_args!(
+            "Skipping phase {} ({reason})",
+            phase_config.name
+        ));
+        options.emit(RunEvent::PhaseSkipped {
+            phase: phase_config.name.clone(),
+            reason: reason.to_string(),
+        });
+        state.skip_phase(&phase_config.name, reason.to_string());
+    }
+    if pending.is_empty() && skipped.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(pending))
+}
+
+/// Fail if the working tree of the feature has changes the agent's commits
+/// could pick up
+async fn ensure_clean_tree(state: &FeatureState, repo_path: &Path) -> Result<()> {
+    let tree = match &state.git {
+        Some(git) => repo_path.join(&git.worktree_path),
+        None => repo_path.to_path_buf(),
+    };
+    // Changes left behind by an interrupted or failed phase are ours.
+    let stopped = state.resume.can_resume || state.last_failed_phase().is_some();
+    let resume_base = state
+        .git
+        .as_ref()
+        .filter(|_| stopped)
+        .map(|git| git.base_commit.clone());
+    tokio::task::spawn_blocking(move || {
+        git::ensure_clean(&RealCommandRunner, &tree, resume_base.as_deref())
+    })
+    .await??;
+    Ok(())
+}
+
+/// Create the feature's worktree under `.trees/`, on a new branch started
+/// from the checked out commit, and record it in `state`. Outside a git
+/// repository, or before its first commit, the feature runs in `repo_path`.
+async fn add_worktree(state: &mut FeatureState, repo_path: &Path) -> Result<()> {
+    let repo = repo_path.to_path_buf();
+    let dir_name = state.dir_name();
+    let info = tokio::task::spawn_blocking(move || -> gba_core::Result<Option<GitInfo>> {
+        let runner = RealCommandRunner;
+        let Ok(base_commit) = git::head_commit(&runner, &repo) else {
+            return Ok(None);
+        };
+        let info = GitInfo {
+            worktree_path: Path::new(TREES_DIR).join(&dir_name),
+            branch: format!("feature/{}", dir_name.replacen('_', "-", 1)),
+            base_branch: git::current_branch(&runner, &repo)?,
+            base_commit,
+        };
+        git::worktree_add(
+            &runner,
+            &repo,
+            &info.worktree_path,
+            &info.branch,
+            &info.base_commit,
+        )?;
+        Ok(Some(info))
+    })
+    .await??;
+    state.git = info;
+    Ok(())
+}
+
+/// Print the pre-run summary and ask whether to proceed; a dry run makes
+/// `config` offline instead.
+///
+/// Returns `false` if the user declined.
+fn confirm_run(
+    gba_path: &Path,
+    state: &FeatureState,
+    config: &mut gba_core::Config,
+    pending: &[(usize, &PhaseConfig)],
+    options: &RunOptions,
+) -> Result<bool> {
+    let estimate = CostEstimate::new(
+        pending.iter().map(|(_, p)| (p.name.as_str(), p.kind)),
+        &load_features(gba_path)?,
+    );
+    options.say(render_summary(state, config, pending, &estimate).trim_end());
+    if options.dry_run {
+        options.say("Dry run: the agent, hooks and state changes are skipped");
+        config.offline = true;
+        return Ok(true);
+    }
+    let ask = !options.yes && !options.json && options.events.is_none() && is_interactive();
+    Ok(!ask || confirm("Proceed? [y/N] ")?)
+}
+
+/// Engine of the run, and whether it asks for tool approvals on the
+/// terminal
+fn build_engine(config: gba_core::Config, options: &RunOptions) -> Result<(Engine, bool)> {
+    let engine = match &options.engine {
+        Some(shared) => shared.with_config(config),
+        None => Engine::new(config),
+    };
+    // Tool uses needing approval are asked about on the terminal; without
+    // one (or with events instead of human output) they're denied.
+    let ask_approval = engine.config().permissions.is_enabled()
+        && !options.json
+        && options.events.is_none()
+        && !options.dry_run
+        && is_interactive();
+    let engine = if ask_approval {
+        engine.with_approver(Arc::new(TerminalApprover::default()))
+    } else {
+        engine
+    };
+    let engine = match &options.replay {
+        Some(dir) => {
+            let replay = ReplayBackend::load(dir)
+                .with_context(|| format!("Failed to load transcripts from {}", dir.display()))?;
+            engine.with_backend(Arc::new(replay.with_delay(options.replay_delay)))
+        }
+        None => engine,
+    };
+    let engine = match &options.record {
+        Some(dir) => engine.with_recorder(Arc::new(TranscriptRecorder::create(dir)?)),
+        None => engine,
+    };
+    Ok((engine, ask_approval))
+}
+
+/// How the run treats a phase beyond executing it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PhaseRole {
+    /// Nothing special
+    Plain,
+    /// Its output becomes the feature's observations
+    Observe,
+    /// Its output is a review, which may fail the phase
+    Review,
+    /// The test command runs after it, with fix iterations
+    Test,
+    /// Its output holds verdicts on the acceptance criteria, which may fail
+    /// the phase
+    Verification,
+    /// Its output drafts the pull request gba then opens
+    PullRequest,
+}
+
+impl PhaseRole {
+    /// Role of `phase`; a dry run treats every phase as plain
+    fn of(phase: &PhaseConfig, dry_run: bool) -> Self {
+        let is_agent = phase.kind == PhaseKind::Agent;
+        match phase.name.as_str() {
+            _ if dry_run => Self::Plain,
+            observations::OBSERVE_PHASE => Self::Observe,
+            review::REVIEW_PHASE if is_agent => Self::Review,
+            testing::TEST_PHASE if is_agent => Self::Test,
+            verification::VERIFICATION_PHASE if is_agent => Self::Verification,
+            pr::PR_PHASE if is_agent => Self::PullRequest,
+            _ => Self::Plain,
+        }
+    }
+}
+
+/// Runs the phases of one feature
+struct PhaseRunner<'a> {
+    options: &'a RunOptions,
+    project: &'a ProjectConfig,
+    feature_path: PathBuf,
+    prompts_dir: PathBuf,
+    /// Where the agent works: the repository or the feature's worktree
+    working_dir: String,
+    engine: Engine,
+    pm: PromptManager,
+    spinner: bool,
+}
+
+impl PhaseRunner<'_> {
+    fn save(&self, state: &FeatureState) -> Result<()> {
+        if !self.options.dry_run {
+            state.save(&self.feature_path)?;
+        }
+        Ok(())
+    }
+
+    /// Execute one phase and record its outcome in `state`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the phase fails or `interrupt` completes while it runs.
+    async fn run_phase(
+        &self,
+        state: &mut FeatureState,
+        index: usize,
+        phase_config: &PhaseConfig,
+        interrupt: Pin<&mut impl Future<Output = ()>>,
+    ) -> Result<()> {
+        let options = self.options;
+        let name = &phase_config.name;
+        if options.only {
+            let unfinished: Vec<&str> = phase_config
+                .depends_on
+                .iter()
+                .filter(|dep| state.phase(dep).is_none_or(|s| !s.status.is_done()))
+                .map(String::as_str)
+                .collect();
+            if !unfinished.is_empty() {
+                options.say(format_args!(
+                    "Warning: running {name} although {} hasn't completed",
+                    unfinished.join(", ")
+                ));
+            }
+        }
+        let total = self.project.phases.len();
+        options.say(format_args!("Phase {}/{}: {}", index + 1, total, name));
+        options.emit(RunEvent::PhaseStarted {
+            phase: name.clone(),
+        });
+
+        let role = PhaseRole::of(phase_config, options.dry_run);
+        let task = TaskConfig::load(&self.prompts_dir.join(name))?;
+        let phase = self.prepare(state, phase_config, &task, role)?;
+
+        state.start_phase(index, name);
+        state.phase_mut(name).model =
+            (phase_config.kind == PhaseKind::Agent).then(|| self.engine.config().model.clone());
+        self.save(state)?;
+
+        let progress = if let Some(events) = &options.events {
+            PhaseProgress::channel(name, events.clone())
+        } else if options.json {
+            PhaseProgress::json(name)
+        } else {
+            let spent = state.total_stats.cost_usd;
+            PhaseProgress::new(name, spent, self.spinner, options.verbose)
+        };
+        let hook_context = HookContext {
+            working_dir: self.engine.config().repo_path.clone(),
+            feature_id: state.feature.id.clone(),
+            feature_slug: state.feature.slug.clone(),
+            phase: name.clone(),
+        };
+        let Some(outcome) = execute(&self.engine, &phase, &hook_context, progress, interrupt).await
+        else {
+            state.mark_for_resume(InterruptReason::UserCancelled);
+            self.save(state)?;
+            options.emit(RunEvent::PhaseFailed {
+                phase: name.clone(),
+                error: InterruptReason::UserCancelled.to_string(),
+            });
+            anyhow::bail!(
+                "Interrupted during phase {name}; run `gba run {}` to resume",
+                state.dir_name()
+            );
+        };
+        if let Ok(result) = &outcome {
+            state.record_test_runs(name, &result.test_runs);
+        }
+        match outcome {
+            Ok(result) if result.success => self.succeeded(state, name, role, &task, result).await,
+            Ok(result) => {
+                let failing = result.test_runs.last().filter(|run| !run.passed());
+                let error = match failing {
+                    Some(run) => format!(
+                        "Tests still failing after {} fix iteration(s): {run}",
+                        result.stats.fix_iterations
+                    ),
+                    None => format!("Phase {name} failed"),
+                };
+                // The agent's spend still counts although the tests failed.
+                let spent = failing.map(|_| &result);
+                self.fail(state, name, &error, Some(phase_summary(&result)), spent)?;
+                anyhow::bail!(error)
+            }
+            Err(e) => {
+                let summary = e.partial_output().map(summarize);
+                self.fail(state, name, &e.to_string(), summary, None)?;
+                Err(e.into())
+            }
+        }
+    }
+
+    /// The phase to execute: its rendered prompts and the settings of its
+    /// task and role
+    fn prepare(
+        &self,
+        state: &FeatureState,
+        phase_config: &PhaseConfig,
+        task: &TaskConfig,
+        role: PhaseRole,
+    ) -> Result<Phase> {
+        let name = &phase_config.name;
+        let is_agent = phase_config.kind == PhaseKind::Agent;
+        // Command phases have no prompts.
+        let (system, user) = if is_agent {
+            let context =
+                prompt_context(&self.working_dir, &self.feature_path, state, phase_config)?;
+            self.pm
+                .load_phase_prompts(name, &context)
+                .with_context(|| format!("Failed to render prompts of phase {name}"))?
+        } else {
+            (None, String::new())
+        };
+        let system = if task.preset { None } else { system };
+        let user = match &self.options.feedback {
+            Some(feedback) => format!("{}\n\n{feedback}", user.trim_end()),
+            None => user,
+        };
+        let mut phase = Phase::from_config(phase_config, task).with_prompts(system, user);
+        phase.mcp_servers = mcp::merge(&self.project.mcp_servers, &task.mcp_servers);
+        if is_agent {
+            mcp::check_tools(&phase.tools, &phase.mcp_servers)
+                .with_context(|| format!("Invalid tools of phase {name}"))?;
+        }
+        if self.options.dry_run {
+            phase.hooks = Default::default();
+        }
+        match role {
+            PhaseRole::Review => {
+                phase.user_prompt = format!(
+                    "{}\n\n{}",
+                    phase.user_prompt.trim_end(),
+                    review::build_prompt()
+                );
+                phase.json_schema = Some(review::REVIEW_SCHEMA.to_string());
+            }
+            PhaseRole::Test => phase.test = Some(task.test.clone()),
+            PhaseRole::Verification => {
+                let criteria = verification::load_criteria(&self.feature_path)?;
+                phase.user_prompt = format!(
+                    "{}\n\n{}",
+                    phase.user_prompt.trim_end(),
+                    verification::build_prompt(&criteria)
+                );
+                phase.json_schema = Some(verification::VERIFICATION_SCHEMA.to_string());
+            }
+            PhaseRole::PullRequest => phase.json_schema = Some(pr::PR_SCHEMA.to_string()),
+            PhaseRole::Plain | PhaseRole::Observe => {}
+        }
+        Ok(phase)
+    }
+
+    /// Record a phase whose execution succeeded; judging its output, e.g. a
+    /// review, may still fail it
+    async fn succeeded(
+        &self,
+        state: &mut FeatureState,
+        name: &str,
+        role: PhaseRole,
+        task: &TaskConfig,
+        mut result: ExecutionResult,
+    ) -> Result<()> {
+        let summary = match role {
+            PhaseRole::Review => {
+                let outcome = review::record(
+                    &self.feature_path,
+                    &state.dir_name(),
+                    &result.output,
+                    &task.review,
+                );
+                let verdict = outcome.map(|outcome| {
+                    outcome.apply_to(state.phase_mut(name));
+                    (outcome.error(), outcome.output_summary())
+                });
+                self.judge(state, name, &result, verdict)?
+            }
+            PhaseRole::Verification => {
+                let outcome =
+                    verification::record(&self.feature_path, &result.output, &task.verification);
+                let verdict = outcome.map(|outcome| {
+                    outcome.apply_to(state.phase_mut(name));
+                    if outcome.summary.failed > 0 && outcome.error().is_none() {
+                        self.options.say(format_args!(
+                            "Warning: {} acceptance criteria not met (verification.warnOnly)",
+                            outcome.summary.failed
+                        ));
+                    }
+                    (outcome.error(), outcome.output_summary())
+                });
+                self.judge(state, name, &result, verdict)?
+            }
+            PhaseRole::PullRequest => {
+                let base = state.git.as_ref().map(|git| git.base_branch.clone());
+                let opened = open_pull_request(
+                    self.engine.command_runner().clone(),
+                    PathBuf::from(&self.working_dir),
+                    base,
+                    &result,
+                )
+                .await;
+                let verdict = opened.map(|(draft, url)| {
+                    self.options.say(format_args!("Opened pull request {url}"));
+                    state.record_pull_request(name, &url, &draft.title);
+                    (None, format!("{}: {url}", draft.title))
+                });
+                self.judge(state, name, &result, verdict)?
+            }
+            PhaseRole::Observe => {
+                observations::record(&self.feature_path, &result.output)?;
+                completed_summary(&self.engine, self.project, &mut result).await
+            }
+            PhaseRole::Plain | PhaseRole::Test => {
+                completed_summary(&self.engine, self.project, &mut result).await
+            }
+        };
+        let message = format!("{}: {name} phase\n\n{summary}", state.dir_name());
+        state.complete_phase(name, &result, summary);
+        self.checkpoint(state, name, message).await;
+        self.save(state)?;
+        self.options.emit(RunEvent::PhaseCompleted {
+            phase: name.to_string(),
+            stats: result.stats,
+        });
+        Ok(())
+    }
+
+    /// Commit the changes of the completed phase `name` with `message` and
+    /// record the commit it ends at; failing to commit only warns
+    async fn checkpoint(&self, state: &mut FeatureState, name: &str, message: String) {
+        if self.options.dry_run || !self.project.git.auto_commit {
+            return;
+        }
+        let runner = self.engine.command_runner().clone();
+        let cwd = PathBuf::from(&self.working_dir);
+        let committed =
+            tokio::task::spawn_blocking(move || git::checkpoint(runner.as_ref(), &cwd, &message))
+                .await
+                .map_err(|e| gba_core::CoreError::Io(std::io::Error::other(e)))
+                .and_then(|committed| committed);
+        match committed {
+            Ok(Some(sha)) => state.record_commit(name, sha),
+            Ok(None) => {}
+            Err(e) => self.options.say(format_args!(
+                "Warning: couldn't commit the changes of phase {name}: {e}"
+            )),
+        }
+    }
+
+    /// Summary of a phase whose output was judged; `verdict` holds why the
+    /// phase failed, if it did, and its summary.
+    ///
+    /// # Errors
+    ///
+    /// Fails the phase if the verdict says so or the output couldn't be
+    /// judged.
+    fn judge(
+        &self,
+        state: &mut FeatureState,
+        name: &str,
+        result: &ExecutionResult,
+        verdict: gba_core::Result<(Option<String>, String)>,
+    ) -> Result<String> {
+        let (error, summary) =
+            verdict.unwrap_or_else(|e| (Some(e.to_string()), phase_summary(result)));
+        if let Some(error) = error {
+            // The agent's spend still counts although the phase failed.
+            self.fail(state, name, &error, Some(summary), Some(result))?;
+            anyhow::bail!(error);
+        }
+        Ok(summary)
+    }
+
+    /// Record `name` as failed with `error`; the stats of `spent` count
+    /// towards the feature's total
+    fn fail(
+        &self,
+        state: &mut FeatureState,
+        name: &str,
+        error: &str,
+        summary: Option<String>,
+        spent: Option<&ExecutionResult>,
+    ) -> Result<()> {
+        if let Some(result) = spent {
+            state.total_stats.accumulate(&result.stats);
+            state.phase_mut(name).stats = Some(result.stats.clone());
+        }
+        state.fail_phase(name, error.to_string(), summary);
+        self.save(state)?;
+        self.options.emit(RunEvent::PhaseFailed {
+            phase: name.to_string(),
+            error: error.to_string(),
+        });
+        Ok(())
+    }
+
+    /// Complete the feature once all its phases are done, and report how
+    /// the run ended
+    fn finish(&self, mut state: FeatureState, status_before: FeatureStatus) -> Result<()> {
+        let options = self.options;
+        if options.only {
+            state.status = status_before;
+            self.save(&state)?;
+            options.say(format_args!(
+                "Ran only {} of {}; the feature stays {}",
+                options.phase.as_deref().unwrap_or_default(),
+                state.dir_name(),
+                state.status
+            ));
+            options.emit(run_completed(&state));
+            return Ok(());
+        }
+        let done = self
+            .project
+            .phases
+            .iter()
+            .all(|p| state.phase(&p.name).is_some_and(|s| s.status.is_done()));
+        if !done {
+            self.save(&state)?;
+            options.say(format_args!(
+                "Run `gba run {}` to continue with the remaining phases",
+                state.dir_name()
+            ));
+            options.emit(run_completed(&state));
+            return Ok(());
+        }
+        state.complete();
+        self.save(&state)?;
+        options.emit(run_completed(&state));
+        if options.dry_run {
+            options.say(format_args!("Dry run of {} finished", state.dir_name()));
+            return Ok(());
+        }
+        options.say(format_args!(
+            "Feature {} completed (${:.2})",
+            state.dir_name(),
+            state.total_stats.cost_usd
+        ));
+        Ok(())
+    }
+}
+
+fn run_completed(state: &FeatureState) -> RunEvent {
+    RunEvent::RunCompleted {
+        feature: state.dir_name(),
+        status: state.status,
+        stats: state.total_stats.clone(),
+    }
+}
+
+/// Why `phase` is skipped, if it is
+fn skip_reason(
+    phase: &PhaseConfig,
+    options: &RunOptions,
+    feature_path: &Path,
+) -> Option<&'static str> {
+    if options.skip.contains(&phase.name) {
+        Some("skipped with --skip-phase")
+    } else if !phase.enabled {
+        Some("disabled in config.yml")
+    } else if phase.name == observations::OBSERVE_PHASE
+        && !options.force_observe
+        && options.phase.is_none()
+        && observations::exists(feature_path)
+    {
+        Some("observations already in docs/observations.md")
+    } else {
+        None
+    }
+}
+
+/// Execute a phase while feeding its streamed events to `progress`.
+///
+/// Returns `None` if `interrupt` completes first; dropping the execution
+/// cancels the agent.
+async fn execute(
+    engine: &Engine,
+    phase: &Phase,
+    hook_context: &HookContext,
+    mut progress: PhaseProgress,
+    mut interrupt: Pin<&mut impl Future<Output = ()>>,
+) -> Option<gba_core::Result<ExecutionResult>> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let execution = engine.execute_phase(phase, hook_context, Some(tx));
+    tokio::pin!(execution);
+    let mut tick = tokio::time::interval(progress::TICK);
+
+    let outcome = loop {
+        tokio::select! {
+            biased;
+            _ = &mut interrupt => {
+                progress.abandon();
+                return None;
+            }
+            outcome = &mut execution => break outcome,
+            Some(event) = rx.recv() => progress.event(event),
+            _ = tick.tick() => progress.tick(),
+        }
+    };
+    while let Ok(event) = rx.try_recv() {
+        progress.event(event);
+    }
+
+    match &outcome {
+        Ok(result) if result.success => progress.finish(result),
+        _ => progress.abandon(),
+    }
+    Some(outcome)
+}
+
+/// Render the pre-run summary: phases, agent settings and cost estimate
+pub fn render_summary(
+    state: &FeatureState,
+    config: &gba_core::Config,
+    pending: &[(usize, &PhaseConfig)],
+    estimate: &CostEstimate,
+) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "Feature {}", state.dir_name());
+    let _ = writeln!(out, "  Model:           {}", config.model);
+    let _ = writeln!(out, "  Permission mode: {}", config.permission_mode);
+    let _ = writeln!(
+        out,
+        "  Timeout:         {}s per phase",
+        config.timeout_seconds
+    );
+    let max_turns = config
+        .max_turns
+        .map_or_else(|| "default".to_string(), |t| t.to_string());
+    let _ = writeln!(out, "  Max turns:       {max_turns}");
+    let _ = writeln!(out, "Phases:");
+    for ((index, phase), phase_estimate) in pending.iter().zip(&estimate.phases) {
+        let timeout = phase
+            .timeout_seconds
+            .map(|t| format!(", timeout {t}s"))
+            .unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "  {}. {:<14} ~${:.2} ({}{})",
+            index + 1,
+            phase.name,
+            phase_estimate.cost_usd,
+            phase_estimate.source,
+            timeout
+        );
+    }
+    let _ = writeln!(out, "Estimated cost: ~${:.2}", estimate.total_usd);
+    out
+}
+
+/// Summary of a completed phase, written by the summary model if
+/// `summaries.enabled`; its cost is added to the result's stats.
+///
+/// Falls back to [`phase_summary`] if summaries are disabled or fail.
+async fn completed_summary(
+    engine: &Engine,
+    project: &ProjectConfig,
+    result: &mut ExecutionResult,
+) -> String {
+    if project.summaries.enabled {
+        let model = &project.summaries.model;
+        match gba_core::summary::summarize(engine, &result.output, model).await {
+            Ok(summary) => {
+                result.stats.summary_cost_usd += summary.stats.cost_usd;
+                return with_notes(summary.output, result);
+            }
+            Err(e) => tracing::warn!("summarizing with {model} failed, truncating instead: {e}"),
+        }
+    }
+    phase_summary(result)
+}
+
+/// Open the pull request the agent drafted in `result` from `cwd`, against
+/// `base` or else the repository's default branch.
+///
+/// Returns the draft and the URL of the pull request.
+///
+/// # Errors
+///
+/// Fails if the draft can't be parsed or `gh` fails to open the pull
+/// request.
+async fn open_pull_request(
+    runner: Arc<dyn CommandRunner>,
+    cwd: PathBuf,
+    base: Option<String>,
+    result: &ExecutionResult,
+) -> gba_core::Result<(PullRequestDraft, String)> {
+    let draft = PullRequestDraft::from_result(result)?;
+    let (title, body) = (draft.title.clone(), draft.body.clone());
+    let url = tokio::task::spawn_blocking(move || {
+        gh::create_pull_request(runner.as_ref(), &cwd, &title, &body, base.as_deref())
+    })
+    .await
+    .map_err(|e| gba_core::CoreError::Io(std::io::Error::other(e)))??;
+    Ok((draft, url))
+}
+
+/// Summary stored in `state.yml`: the agent output followed by hook output
+fn phase_summary(result: &ExecutionResult) -> String {
+    with_notes(summarize(&result.output), result)
+}
+
+/// `summary` with a note on truncated output and the hook output
+fn with_notes(summary: String, result: &ExecutionResult) -> String {
+    let summary = if result.truncated {
+        format!(
+            "{summary} [output truncated, {} bytes dropped]",
+            result.dropped_bytes
+        )
+    } else {
+        summary
+    };
+    let summary = if result.denied_tools.is_empty() {
+        summary
+    } else {
+        let denied: Vec<String> = result
+            .denied_tools
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        format!("{summary} [denied tool uses: {}]", denied.join("; "))
+    };
+    let hooks = result.hook_output.trim_end();
+    if hooks.is_empty() {
+        return summary;
+    }
+    let hooks = gba_core::text::truncate_chars(hooks, HOOK_OUTPUT_CHARS, "...");
+    format!("{summary}\n{hooks}")
+}
+
+/// Summary of the agent output stored in `state.yml`
+fn summarize(output: &str) -> String {
+    gba_core::summary::summarize_output(output, SUMMARY_CHARS)
+}
+
+/// Variables the prompts of `phase` are rendered with: the feature branch,
+/// the observations, the outputs of earlier phases and the phase's `inputs`
+fn prompt_context(
+    working_dir: &str,
+    feature_path: &Path,
+    state: &FeatureState,
+    phase: &PhaseConfig,
+) -> Result<PromptContext> {
+    let mut context = PromptContext::new(working_dir, &state.feature.slug, &state.feature.id)
+        .with_phase(&phase.name);
+    if let Some(git) = &state.git {
+        context = context
+            .with_extra("branch", git.branch.clone())
+            .with_extra("base_branch", git.base_branch.clone());
+    }
+    if let Some(observations) =
+        observations::load(feature_path, observations::OBSERVATIONS_MAX_TOKENS)?
+    {
+        context = context.with_extra("observations", observations);
+    }
+
+    let previous = state.previous_outputs(&phase.name);
+    let mut rendered = String::new();
+    for output in &previous {
+        let _ = write!(
+            rendered,
+            "### {}\n\n{}\n\n",
+            output.phase,
+            output.summary.trim_end()
+        );
+    }
+    if let Some(last) = previous.last() {
+        context = context.with_extra("previous_output", last.summary.clone());
+    }
+    context = context
+        .with_extra("previous_outputs", rendered.trim_end())
+        .with_extra("previous", serde_json::to_value(&previous)?);
+
+    let mut inputs = Vec::with_capacity(phase.inputs.len());
+    for input in &phase.inputs {
+        let content = std::fs::read_to_string(feature_path.join(input)).with_context(|| {
+            format!(
+                "Failed to read input {} of phase {}",
+                input.display(),
+                phase.name
+            )
+        })?;
+        inputs.push(serde_json::json!({"path": input, "content": content}));
+    }
+    Ok(context.with_extra("inputs", inputs))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use gba_core::PhaseStatus;
+    use std::path::PathBuf;
+
+    /// `.gba` with two templated phases and a planned feature `0001_auth`
+    pub(crate) fn setup(dir: &Path) -> (PathBuf, PathBuf, gba_core::Config) {
+        let gba_path = dir.join(".gba");
+        for phase in ["observe", "build"] {
+            let task_dir = gba_path.join(PROMPTS_DIR).join(phase);
+            std::fs::create_dir_all(&task_dir).unwrap();
+            std::fs::write(
+                task_dir.join("user.md"),
+                format!("{phase} {{{{ feature_slug }}}}"),
+            )
+            .unwrap();
+        }
+        std::fs::write(
+            gba_path.join(gba_core::CONFIG_FILE),
+            "phases:\n  - name: observe\n  - name: build\n    dependsOn: [observe]\n",
+        )
+        .unwrap();
+        let state = FeatureState::new("0001", "auth");
+        let feature_path = gba_path.join(gba_core::FEATURES_DIR).join(state.dir_name());
+        std::fs::create_dir_all(feature_path.join("specs")).unwrap();
+        state.save(&feature_path).unwrap();
+        std::fs::write(
+            feature_path.join(gba_core::DESIGN_FILE),
+            "# Design\n\nUsers log in with an email and password.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            feature_path.join(gba_core::verification::VERIFICATION_FILE),
+            "# Verification\n\n- [ ] A wrong password is rejected with a 401\n",
+        )
+        .unwrap();
+        let config = gba_core::Config::builder()
+            .repo_path(dir)
+            .offline(true)
+            .build();
+        (gba_path, feature_path, config)
+    }
+
+    #[tokio::test]
+    async fn test_offline_run_completes_every_phase() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        };
+
+        run(&gba_path, "auth", config, options).await.unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.status, gba_core::FeatureStatus::Completed);
+        assert_eq!(state.phases.len(), 2);
+        assert!(
+            state
+                .phases
+                .iter()
+                .all(|p| p.status == PhaseStatus::Completed)
+        );
+        assert_eq!(
+            state.phase("build").unwrap().output_summary.as_deref(),
+            Some("build auth")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_only_runs_one_phase_and_keeps_the_feature_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let test_dir = gba_path.join(PROMPTS_DIR).join("test");
+        std::fs::create_dir_all(&test_dir).unwrap();
+        std::fs::write(test_dir.join("user.md"), "test {{ feature_slug }}").unwrap();
+        std::fs::write(
+            test_dir.join(TaskConfig::FILE_NAME),
+            "test:\n  command: \"true\"\n",
+        )
+        .unwrap();
+        std::fs::write(
+            gba_path.join(gba_core::CONFIG_FILE),
+            "phases:\n  - name: observe\n  - name: build\n    dependsOn: [observe]\n  - name: test\n    dependsOn: [build]\n",
+        )
+        .unwrap();
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            phase: Some("test".to_string()),
+            only: true,
+            ..RunOptions::default()
+        };
+
+        run(&gba_path, "auth", config.clone(), options.clone())
+            .await
+            .unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.status, gba_core::FeatureStatus::Planned);
+        assert_eq!(state.phases.len(), 1);
+        assert_eq!(state.phase("test").unwrap().status, PhaseStatus::Completed);
+
+        // A done phase runs again.
+        run(&gba_path, "auth", config.clone(), options.clone())
+            .await
+            .unwrap();
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.phase("test").unwrap().attempts, 2);
+
+        let options = RunOptions {
+            phase: Some("deploy".to_string()),
+            ..options
+        };
+        let err = run(&gba_path, "auth", config, options).await.unwrap_err();
+        assert_eq!(err.to_string(), "Unknown phase `deploy`");
+    }
+
+    #[tokio::test]
+    async fn test_locked_feature_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        };
+        let lock = FeatureLock::acquire(&feature_path).unwrap();
+
+        let err = run(&gba_path, "auth", config.clone(), options.clone())
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Feature 0001_auth is being run by another gba process")
+        );
+        assert_eq!(
+            FeatureState::load(&feature_path).unwrap().status,
+            FeatureStatus::Planned
+        );
+
+        drop(lock);
+        run(&gba_path, "auth", config, options).await.unwrap();
+        assert!(!feature_path.join(gba_core::lock::LOCK_FILE).exists());
+    }
+
+    #[tokio::test]
+    async fn test_later_phases_see_earlier_outputs() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        std::fs::write(
+            gba_path.join(PROMPTS_DIR).join("build/user.md"),
+            "build after {{ previous[0].phase }}: {{ previous_output }}",
+        )
+        .unwrap();
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        };
+
+        run(&gba_path, "auth", config, options).await.unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(
+            state.phase("build").unwrap().output_summary.as_deref(),
+            Some("build after observe: observe auth")
+        );
+
+        let build = PhaseConfig {
+            inputs: vec![PathBuf::from(gba_core::DESIGN_FILE)],
+            ..ProjectConfig::default().phases[1].clone()
+        };
+        let context = prompt_context("/repo", &feature_path, &state, &build).unwrap();
+        assert_eq!(
+            context.extra["previous_outputs"],
+            "### observe\n\nobserve auth"
+        );
+        assert_eq!(
+            context.extra["inputs"][0]["content"],
+            "# Design\n\nUsers log in with an email and password.\n"
+        );
+        let missing = PhaseConfig {
+            inputs: vec![PathBuf::from("notes.md")],
+            ..build
+        };
+        let err = prompt_context("/repo", &feature_path, &state, &missing).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Failed to read input notes.md of phase build"
+        );
+    }
+
+    /// Output of `git args` run in `cwd`, which must succeed
+    pub(crate) fn git(cwd: &Path, args: &[&str]) -> String {
+        let output = std::process::Command::new("git")
+            .args(args)
+            .current_dir(cwd)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8(output.stdout).unwrap().trim().to_string()
+    }
+
+    /// Make `dir` a git repository with everything in it committed
+    pub(crate) fn commit_repo(dir: &Path) {
+        git(dir, &["init", "--quiet"]);
+        git(dir, &["config", "user.name", "gba"]);
+        git(dir, &["config", "user.email", "gba@example.com"]);
+        git(dir, &["add", "-A"]);
+        git(dir, &["commit", "--quiet", "-m", "init"]);
+    }
+
+    /// Directory of transcripts in which the agent answers each `(file,
+    /// text)` request with `text` for $0.50
+    fn transcripts(dir: &Path, answers: &[(&str, &str)]) -> PathBuf {
+        let transcripts = dir.join("transcripts");
+        std::fs::create_dir_all(&transcripts).unwrap();
+        for (file, text) in answers {
+            let assistant = serde_json::json!({
+                "type": "assistant",
+                "message": {"content": [{"type": "text", "text": text}]}
+            });
+            let result = serde_json::json!({
+                "type": "result",
+                "subtype": "success",
+                "duration_ms": 1000,
+                "duration_api_ms": 900,
+                "is_error": false,
+                "num_turns": 1,
+                "session_id": "s",
+                "total_cost_usd": 0.5
+            });
+            std::fs::write(
+                transcripts.join(format!("{file}.jsonl")),
+                format!("{assistant}\n{result}\n"),
+            )
+            .unwrap();
+        }
+        transcripts
+    }
+
+    #[tokio::test]
+    async fn test_replayed_run_completes_every_phase() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let transcripts = transcripts(
+            dir.path(),
+            &[
+                ("0001-observe", "Found the login form"),
+                ("0002-build", "Built login"),
+            ],
+        );
+        let config = gba_core::Config {
+            offline: false,
+            ..config
+        };
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            replay: Some(transcripts),
+            ..RunOptions::default()
+        };
+
+        run(&gba_path, "auth", config, options).await.unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.status, gba_core::FeatureStatus::Completed);
+        let build = state.phase("build").unwrap();
+        assert_eq!(build.output_summary.as_deref(), Some("Built login"));
+        assert_eq!(build.stats.as_ref().unwrap().cost_usd, 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_failed_criterion_fails_verification_unless_warn_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let task_dir = gba_path.join(PROMPTS_DIR).join("verification");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::write(task_dir.join("user.md"), "verify {{ feature_slug }}").unwrap();
+        std::fs::write(
+            gba_path.join(gba_core::CONFIG_FILE),
+            "phases:\n  - name: verification\n",
+        )
+        .unwrap();
+        let checklist = feature_path.join(verification::VERIFICATION_FILE);
+        std::fs::write(
+            &checklist,
+            "# Verification\n\n- [ ] A wrong password is rejected with a 401\n- [ ] Passwords are hashed\n",
+        )
+        .unwrap();
+        let verdicts = r#"```json
+{"criteria": [
+  {"index": 1, "passed": false, "notes": "returns 500"},
+  {"index": 2, "passed": true, "notes": "argon2"}
+]}
+```"#;
+        let transcripts = transcripts(dir.path(), &[("0001-verification", verdicts)]);
+        let config = gba_core::Config {
+            offline: false,
+            ..config
+        };
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            replay: Some(transcripts),
+            ..RunOptions::default()
+        };
+
+        let err = run(&gba_path, "auth", config.clone(), options.clone())
+            .await
+            .unwrap_err();
+
+        assert!(
+            err.to_string()
+                .contains("Verification failed 1 of 2 criteria")
+        );
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.status, gba_core::FeatureStatus::Failed);
+        let phase = state.phase("verification").unwrap();
+        assert_eq!(phase.status, PhaseStatus::Failed);
+        assert_eq!(phase.verification.as_ref().unwrap().failed, 1);
+        assert!(
+            phase
+                .output_summary
+                .as_deref()
+                .unwrap()
+                .contains("returns 500")
+        );
+        let checked = std::fs::read_to_string(&checklist).unwrap();
+        assert!(checked.contains("- [ ] A wrong password is rejected"));
+        assert!(checked.contains("- [x] Passwords are hashed"));
+
+        std::fs::write(
+            task_dir.join(TaskConfig::FILE_NAME),
+            "verification:\n  warnOnly: true\n",
+        )
+        .unwrap();
+        run(&gba_path, "auth", config, options).await.unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.status, gba_core::FeatureStatus::Completed);
+        let phase = state.phase("verification").unwrap();
+        assert_eq!(phase.status, PhaseStatus::Completed);
+        assert_eq!(phase.verification.as_ref().unwrap().failed, 1);
+    }
+
+    /// Answers every command with `stdout` and records its arguments
+    #[derive(Debug)]
+    struct GhRunner {
+        stdout: &'static str,
+        calls: std::sync::Mutex<Vec<Vec<String>>>,
+    }
+
+    impl CommandRunner for GhRunner {
+        fn run(
+            &self,
+            _program: &str,
+            args: &[&str],
+            _cwd: &Path,
+        ) -> gba_core::Result<std::process::Output> {
+            use std::os::unix::process::ExitStatusExt;
+
+            self.calls
+                .lock()
+                .unwrap()
+                .push(args.iter().map(|a| a.to_string()).collect());
+            Ok(std::process::Output {
+                status: std::process::ExitStatus::from_raw(0),
+                stdout: self.stdout.as_bytes().to_vec(),
+                stderr: Vec::new(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_open_pull_request() {
+        let runner = Arc::new(GhRunner {
+            stdout: "Creating pull request...\nhttps://github.com/o/r/pull/7\n",
+            calls: Default::default(),
+        });
+        let result = ExecutionResult {
+            output: r#"Pushed. {"title": "Add JWT auth", "body": "Adds login"}"#.to_string(),
+            ..ExecutionResult::default()
+        };
+
+        let (draft, url) = open_pull_request(
+            runner.clone(),
+            PathBuf::from("/repo"),
+            Some("main".to_string()),
+            &result,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(draft.title, "Add JWT auth");
+        assert_eq!(url, "https://github.com/o/r/pull/7");
+        let calls = runner.calls.lock().unwrap();
+        assert_eq!(
+            calls[0],
+            [
+                "pr",
+                "create",
+                "--title",
+                "Add JWT auth",
+                "--body",
+                "Adds login",
+                "--base",
+                "main"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pr_phase_fails_without_a_draft() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let task_dir = gba_path.join(PROMPTS_DIR).join(pr::PR_PHASE);
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::write(task_dir.join("user.md"), "open a pr").unwrap();
+        std::fs::write(
+            gba_path.join(gba_core::CONFIG_FILE),
+            "phases:\n  - name: pr\n",
+        )
+        .unwrap();
+        let transcripts = transcripts(dir.path(), &[("0001-pr", "Pushed the branch.")]);
+        let config = gba_core::Config {
+            offline: false,
+            ..config
+        };
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            replay: Some(transcripts),
+            ..RunOptions::default()
+        };
+
+        let err = run(&gba_path, "auth", config, options).await.unwrap_err();
+
+        assert!(err.to_string().contains("Invalid agent output"), "{err}");
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.phase("pr").unwrap().status, PhaseStatus::Failed);
+        assert!(state.pull_request.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_command_phase_runs_its_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        std::fs::write(
+            gba_path.join(gba_core::CONFIG_FILE),
+            "phases:\n  - name: lint\n    kind: command\n    command: echo \"lint $GBA_FEATURE_SLUG\" | tee lint.txt\n",
+        )
+        .unwrap();
+        // Command phases don't need an agent, or prompts.
+        let config = gba_core::Config {
+            offline: false,
+            ..config
+        };
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        };
+
+        run(&gba_path, "auth", config.clone(), options.clone())
+            .await
+            .unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.status, gba_core::FeatureStatus::Completed);
+        let lint = state.phase("lint").unwrap();
+        assert_eq!(lint.output_summary.as_deref(), Some("lint auth"));
+        assert!(lint.model.is_none());
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("lint.txt")).unwrap(),
+            "lint auth\n"
+        );
+
+        std::fs::write(
+            gba_path.join(gba_core::CONFIG_FILE),
+            "phases:\n  - name: check\n    kind: command\n    command: \"echo 'error: unused import'; exit 1\"\n",
+        )
+        .unwrap();
+        let err = run(&gba_path, "auth", config, options).await.unwrap_err();
+        assert_eq!(err.to_string(), "Phase check failed");
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(
+            state.phase("check").unwrap().output_summary.as_deref(),
+            Some("error: unused import")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_draft_specs_need_force() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        std::fs::write(
+            feature_path.join(gba_core::DESIGN_FILE),
+            "# Design\n\nTODO: describe the feature.\n",
+        )
+        .unwrap();
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        };
+
+        let err = run(&gba_path, "auth", config.clone(), options.clone())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("0001_auth is not ready to run"));
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.status, gba_core::FeatureStatus::Planned);
+
+        let options = RunOptions {
+            force: true,
+            ..options
+        };
+        run(&gba_path, "auth", config, options).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_json_run_completes_without_prompting() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let options = RunOptions {
+            json: true,
+            ..RunOptions::default()
+        };
+
+        run(&gba_path, "auth", config, options).await.unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.status, gba_core::FeatureStatus::Completed);
+        assert_eq!(
+            run_completed(&state).to_json_line(),
+            format!(
+                r#"{{"event":"run_completed","feature":"0001_auth","status":"completed","stats":{}}}"#,
+                serde_json::to_string(&state.total_stats).unwrap()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_commits_each_phase_in_a_worktree() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        std::fs::write(
+            gba_path.join(gba_core::CONFIG_FILE),
+            "phases:\n  - name: lint\n    kind: command\n    command: echo lint > lint.txt\n  - name: check\n    kind: command\n    command: echo ok\n",
+        )
+        .unwrap();
+        commit_repo(dir.path());
+        let base = git(dir.path(), &["rev-parse", "HEAD"]);
+        let config = gba_core::Config {
+            offline: false,
+            ..config
+        };
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        };
+
+        run(&gba_path, "auth", config, options).await.unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        let info = state.git.as_ref().unwrap();
+        assert_eq!(info.worktree_path, Path::new(".trees/0001_auth"));
+        assert_eq!(info.branch, "feature/0001-auth");
+        assert_eq!(info.base_commit, base);
+        let worktree = dir.path().join(&info.worktree_path);
+        assert!(worktree.join("lint.txt").is_file());
+        assert!(!dir.path().join("lint.txt").exists());
+        assert_eq!(git(&worktree, &["status", "--porcelain"]), "");
+        let pr = ProjectConfig::default().phase("pr").unwrap().clone();
+        let context = prompt_context("/repo", &feature_path, &state, &pr).unwrap();
+        assert_eq!(context.extra["branch"], "feature/0001-auth");
+
+        let lint = state.phase("lint").unwrap().commit_sha.clone().unwrap();
+        assert_ne!(lint, base);
+        assert_eq!(
+            git(&worktree, &["log", "-1", "--format=%s"]),
+            "0001_auth: lint phase"
+        );
+        // Nothing changed during check, so it ends where lint did.
+        let check = state.phase("check").unwrap().commit_sha.clone().unwrap();
+        assert_eq!(check, lint);
+        assert_eq!(
+            crate::commands::diff::diff_args(&state, Some("lint"), false).unwrap(),
+            ["--paginate", "diff", &format!("{base}..{lint}")]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_checkpoint_in_the_checkout_leaves_out_gba_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        std::fs::write(
+            gba_path.join(gba_core::CONFIG_FILE),
+            "git:\n  useWorktree: false\nphases:\n  - name: lint\n    kind: command\n    command: echo lint > lint.txt\n",
+        )
+        .unwrap();
+        commit_repo(dir.path());
+        let config = gba_core::Config {
+            offline: false,
+            ..config
+        };
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        };
+
+        run(&gba_path, "auth", config, options).await.unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert!(state.git.is_none());
+        let sha = state.phase("lint").unwrap().commit_sha.clone().unwrap();
+        assert_eq!(
+            git(dir.path(), &["show", "--name-only", "--format=", &sha]),
+            "lint.txt"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dirty_tree_is_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+        git(&["init", "--quiet"]);
+        std::fs::write(dir.path().join("notes.md"), "work in progress").unwrap();
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        };
+
+        let err = run(&gba_path, "auth", config.clone(), options.clone())
+            .await
+            .unwrap_err()
+            .to_string();
+
+        // gba's own files under .gba/ aren't the user's changes.
+        assert!(err.contains("uncommitted changes: notes.md ("), "{err}");
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.status, gba_core::FeatureStatus::Planned);
+
+        let options = RunOptions {
+            allow_dirty: true,
+            ..options
+        };
+        run(&gba_path, "auth", config, options).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_events_feed_run_view() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, _, config) = setup(dir.path());
+        let (events, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let options = RunOptions {
+            events: Some(events),
+            ..RunOptions::default()
+        };
+
+        run(&gba_path, "auth", config, options).await.unwrap();
+
+        let mut view = crate::ui::run_view::RunView::default();
+        while let Some(event) = receiver.recv().await {
+            view.apply(event);
+        }
+        view.close();
+        assert_eq!(view.feature.as_deref(), Some("0001_auth"));
+        assert_eq!(view.status, Some(gba_core::FeatureStatus::Completed));
+        assert!(
+            view.phases
+                .iter()
+                .all(|row| row.status == PhaseStatus::Completed)
+        );
+        assert_eq!(view.phases.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_interrupted_run_is_resumable() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        };
+
+        let err = run_until(&gba_path, "auth", config, options, std::future::ready(()))
+            .await
+            .unwrap_err();
+
+        assert!(err.to_string().contains("gba run 0001_auth"));
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.status, gba_core::FeatureStatus::InProgress);
+        assert!(state.resume.can_resume);
+        assert_eq!(
+            state.resume.interrupt_reason,
+            Some(InterruptReason::UserCancelled)
+        );
+        assert_eq!(state.resume.next_phase.as_deref(), Some("observe"));
+        assert_eq!(state.phase("observe").unwrap().status, PhaseStatus::Pending);
+    }
+
+    #[tokio::test]
+    async fn test_observations_are_recorded_and_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        };
+
+        run(&gba_path, "auth", config.clone(), options.clone())
+            .await
+            .unwrap();
+        assert!(observations::exists(&feature_path));
+
+        // A fresh run with existing observations skips observe.
+        FeatureState::new("0001", "auth")
+            .save(&feature_path)
+            .unwrap();
+        run(&gba_path, "auth", config.clone(), options.clone())
+            .await
+            .unwrap();
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.phase("observe").unwrap().status, PhaseStatus::Skipped);
+        assert_eq!(state.phase("build").unwrap().status, PhaseStatus::Completed);
+
+        FeatureState::new("0001", "auth")
+            .save(&feature_path)
+            .unwrap();
+        let options = RunOptions {
+            force_observe: true,
+            ..options
+        };
+        run(&gba_path, "auth", config, options).await.unwrap();
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(
+            state.phase("observe").unwrap().status,
+            PhaseStatus::Completed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_failing_tests_fail_the_test_phase() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let task_dir = gba_path.join(PROMPTS_DIR).join("test");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::write(task_dir.join("user.md"), "test").unwrap();
+        std::fs::write(
+            task_dir.join(TaskConfig::FILE_NAME),
+            "test:\n  command: exit 1\n  maxFixIterations: 1\n",
+        )
+        .unwrap();
+        std::fs::write(
+            gba_path.join(gba_core::CONFIG_FILE),
+            "phases:\n  - name: observe\n  - name: test\n",
+        )
+        .unwrap();
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            ..RunOptions::default()
+        };
+
+        let err = run(&gba_path, "auth", config, options).await.unwrap_err();
+
+        assert!(err.to_string().starts_with(
+            "Tests still failing after 1 fix iteration(s): `exit 1` exited with code 1"
+        ));
+        let state = FeatureState::load(&feature_path).unwrap();
+        let test = state.phase("test").unwrap();
+        assert_eq!(test.status, PhaseStatus::Failed);
+        assert_eq!(
+            test.tests,
+            Some(gba_core::testing::TestSummary {
+                passed: false,
+                runs: 2
+            })
+        );
+        assert_eq!(test.stats.as_ref().unwrap().fix_iterations, 1);
+    }
+
+    #[tokio::test]
+    async fn test_skipped_phase_counts_as_done() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, feature_path, config) = setup(dir.path());
+        let options = RunOptions {
+            yes: true,
+            no_progress: true,
+            skip: vec!["build".to_string()],
+            ..RunOptions::default()
+        };
+
+        run(&gba_path, "auth", config.clone(), options.clone())
+            .await
+            .unwrap();
+
+        let state = FeatureState::load(&feature_path).unwrap();
+        assert_eq!(state.status, gba_core::FeatureStatus::Completed);
+        let build = state.phase("build").unwrap();
+        assert_eq!(build.status, PhaseStatus::Skipped);
+        assert_eq!(
+            build.output_summary.as_deref(),
+            Some("skipped with --skip-phase")
+        );
+        assert_eq!(build.attempts, 0);
+
+        let unknown = RunOptions {
+            skip: vec!["deploy".to_string()],
+            ..options
+        };
+        let err = run(&gba_path, "auth", config, unknown).await.unwrap_err();
+        assert!(err.to_string().contains("Unknown phase `deploy`"));
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let state = FeatureState::new("0001", "auth");
+        let config = gba_core::Config::builder().max_turns(Some(30)).build();
+        let project = ProjectConfig::default();
+        let pending: Vec<_> = project.phases.iter().enumerate().take(2).collect();
+        let estimate = CostEstimate::new(
+            [("observe", PhaseKind::Agent), ("build", PhaseKind::Agent)],
+            &[],
+        );
+
+        let out = render_summary(&state, &config, &pending, &estimate);
+
+        assert!(out.contains("Permission mode: bypassPermissions"));
+        assert!(out.contains("Max turns:       30"));
+        assert!(out.contains("2. build"));
+        assert!(out.contains("Estimated cost: ~$1.80"));
+    }
+
+    #[test]
+    fn test_phase_summary_includes_hook_output() {
+        let result = ExecutionResult {
+            output: "Implemented login.".to_string(),
+            hook_output: "[post hook] $ cargo fmt (ok, 120ms)\n".to_string(),
+            ..ExecutionResult::default()
+        };
+
+        assert_eq!(
+            phase_summary(&result),
+            "Implemented login.\n[post hook] $ cargo fmt (ok, 120ms)"
+        );
+
+        let truncated = ExecutionResult {
+            truncated: true,
+            dropped_bytes: 2048,
+            ..result
+        };
+        assert!(
+            phase_summary(&truncated).starts_with(
+                "Implemented login. [output truncated, 2048 bytes dropped]\n[post hook]"
+            )
+        );
+
+        let denied = ExecutionResult {
+            denied_tools: vec![gba_core::permissions::ToolDenial {
+                tool: "Bash".to_string(),
+                reason: "denied by the user".to_string(),
+            }],
+            hook_output: String::new(),
+            ..truncated
+        };
+        assert_eq!(
+            phase_summary(&denied),
+            "Implemented login. [output truncated, 2048 bytes dropped] \
+             [denied tool uses: Bash: denied by the user]"
+        );
+    }
+
+    #[test]
+    fn test_summaries_cut_multibyte_output_safely() {
+        // Byte 200 of the output and byte 2000 of the hook output both fall
+        // inside a character.
+        let result = ExecutionResult {
+            output: format!("a{}", "→".repeat(SUMMARY_CHARS)),
+            hook_output: format!("a{}", "🦀".repeat(HOOK_OUTPUT_CHARS)),
+            ..ExecutionResult::default()
+        };
+
+        let summary = phase_summary(&result);
+
+        let (output, hooks) = summary.split_once('\n').unwrap();
+        assert_eq!(output.chars().count(), SUMMARY_CHARS + 3);
+        assert!(output.ends_with("→..."));
+        assert_eq!(hooks.chars().count(), HOOK_OUTPUT_CHARS + 3);
+        assert!(hooks.ends_with("🦀..."));
+    }
+}
@@ -0,0 +1,463 @@
+//! `gba run-all`: run several features one after another.
+
+use std::cell::{Cell, RefCell};
+use std::fmt::Write;
+use std::path::Path;
+
+use anyhow::{Result, bail};
+use chrono::{TimeDelta, Utc};
+use futures::StreamExt;
+use gba_core::{Engine, FeatureState, FeatureStatus, RunEvent};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use super::log::format_duration;
+use super::run::RunOptions;
+use super::{confirm, is_interactive, load_features};
+use crate::ui::output;
+
+/// Flags of `gba run-all`
+#[derive(Debug, Clone, Default)]
+pub struct RunAllOptions {
+    /// Only run features with this status
+    pub status: FeatureStatus,
+    /// Run at most this many features
+    pub max: Option<usize>,
+    /// Keep going after a feature fails
+    pub continue_on_error: bool,
+    /// Start no further feature once the batch has spent this much (USD)
+    pub budget: Option<f64>,
+    /// Run up to this many features at once (0 and 1 run them in turn)
+    pub parallel: usize,
+    /// Options of every feature's run
+    pub run: RunOptions,
+}
+
+/// How a feature of the batch ended
+#[derive(Debug, Clone, PartialEq)]
+pub enum BatchOutcome {
+    /// Every phase ran
+    Completed,
+    /// The run failed with this error
+    Failed(String),
+    /// Not started, for this reason
+    Skipped(String),
+}
+
+/// One feature of the batch
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchEntry {
+    /// Feature directory name
+    pub feature: String,
+    /// How its run ended
+    pub outcome: BatchOutcome,
+    /// Cost of this run in USD
+    pub cost_usd: f64,
+    /// Duration of this run
+    pub duration: TimeDelta,
+}
+
+impl BatchEntry {
+    /// Short result, e.g. `completed` or `failed`
+    pub fn result(&self) -> &'static str {
+        match self.outcome {
+            BatchOutcome::Completed => "completed",
+            BatchOutcome::Failed(_) => "failed",
+            BatchOutcome::Skipped(_) => "skipped",
+        }
+    }
+}
+
+/// Parse a `--status` value
+///
+/// # Errors
+///
+/// Returns an error for anything but a feature status.
+pub fn parse_status(value: &str) -> Result<FeatureStatus> {
+    Ok(match value {
+        "planned" => FeatureStatus::Planned,
+        "in_progress" => FeatureStatus::InProgress,
+        "failed" => FeatureStatus::Failed,
+        "completed" => FeatureStatus::Completed,
+        other => bail!("Unknown feature status `{other}`"),
+    })
+}
+
+/// Run every feature with the selected status in ID order.
+///
+/// Each feature goes through the normal `gba run` flow, up to `parallel` of
+/// them at once; all share one engine and so its rate limits. A failure
+/// stops the queue unless `continue_on_error` is set (features already
+/// running finish). The budget is checked before each feature starts, so
+/// features that cross it still finish.
+pub async fn run(gba_path: &Path, config: gba_core::Config, options: RunAllOptions) -> Result<()> {
+    let queue: Vec<String> = load_features(gba_path)?
+        .iter()
+        .filter(|state| state.status == options.status)
+        .take(options.max.unwrap_or(usize::MAX))
+        .map(FeatureState::dir_name)
+        .collect();
+    if queue.is_empty() {
+        output::say(format_args!("No {} features to run", options.status));
+        return Ok(());
+    }
+
+    output::say(format_args!(
+        "Running {} feature(s): {}",
+        queue.len(),
+        queue.join(", ")
+    ));
+    if !options.run.yes && !options.run.dry_run && is_interactive() && !confirm("Proceed? [y/N] ")?
+    {
+        output::say("Aborted");
+        return Ok(());
+    }
+
+    let engine = Engine::new(config);
+    let batch = Batch::default();
+    // The stream hands out every queued feature exactly once.
+    let mut entries: Vec<BatchEntry> = futures::stream::iter(queue)
+        .map(|feature| run_feature(gba_path, feature, &options, &engine, &batch))
+        .buffer_unordered(options.parallel.max(1))
+        .inspect(|entry| {
+            if options.parallel > 1 {
+                output::say(format_args!("{}: {}", entry.feature, entry.result()));
+            }
+        })
+        .collect()
+        .await;
+    entries.sort_by(|a, b| a.feature.cmp(&b.feature));
+
+    print!("{}", render(&entries));
+    let failed = entries
+        .iter()
+        .filter(|e| matches!(e.outcome, BatchOutcome::Failed(_)))
+        .count();
+    if failed > 0 {
+        bail!("{failed} of {} feature(s) failed", entries.len());
+    }
+    Ok(())
+}
+
+/// State shared by the features of a batch
+#[derive(Debug, Default)]
+struct Batch {
+    /// Cost of the finished features in USD
+    spent: Cell<f64>,
+    /// Why no further feature starts
+    stop: RefCell<Option<String>>,
+}
+
+/// Run one feature of the batch, unless the batch was stopped
+async fn run_feature(
+    gba_path: &Path,
+    feature: String,
+    options: &RunAllOptions,
+    engine: &Engine,
+    batch: &Batch,
+) -> BatchEntry {
+    if batch.stop.borrow().is_none()
+        && let Some(budget) = options.budget
+        && batch.spent.get() >= budget
+    {
+        *batch.stop.borrow_mut() = Some(format!("budget of ${budget:.2} spent"));
+    }
+    if let Some(reason) = batch.stop.borrow().clone() {
+        return BatchEntry {
+            feature,
+            outcome: BatchOutcome::Skipped(reason),
+            cost_usd: 0.0,
+            duration: TimeDelta::zero(),
+        };
+    }
+
+    let cost_before = feature_cost(gba_path, &feature);
+    let started = Utc::now();
+    // The batch was confirmed as a whole.
+    let run_options = RunOptions {
+        yes: true,
+        engine: Some(engine.clone()),
+        ..options.run.clone()
+    };
+    let config = engine.config().clone();
+    let result = if options.parallel > 1 {
+        // Interleaved output of several runs, one prefixed line per event.
+        let (events, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let run_options = RunOptions {
+            events: Some(events),
+            ..run_options
+        };
+        let prefix = feature.split('_').next().unwrap_or(&feature).to_string();
+        let run = super::run::run(gba_path, &feature, config, run_options);
+        let (result, ()) = tokio::join!(run, print_events(receiver, &prefix));
+        result
+    } else {
+        super::run::run(gba_path, &feature, config, run_options).await
+    };
+
+    let outcome = match result {
+        Ok(()) => BatchOutcome::Completed,
+        Err(e) => {
+            output::say(format_args!("{feature} failed: {e:#}"));
+            if !options.continue_on_error {
+                batch
+                    .stop
+                    .borrow_mut()
+                    .get_or_insert_with(|| format!("{feature} failed"));
+            }
+            BatchOutcome::Failed(format!("{e:#}"))
+        }
+    };
+    let cost_usd = (feature_cost(gba_path, &feature) - cost_before).max(0.0);
+    batch.spent.set(batch.spent.get() + cost_usd);
+    BatchEntry {
+        feature,
+        outcome,
+        cost_usd,
+        duration: Utc::now() - started,
+    }
+}
+
+/// Print the events of one run as lines starting with `[prefix]`
+async fn print_events(mut receiver: UnboundedReceiver<RunEvent>, prefix: &str) {
+    while let Some(event) = receiver.recv().await {
+        if let Some(line) = event_line(&event) {
+            output::say(format_args!("[{prefix}] {line}"));
+        }
+    }
+}
+
+/// One-line description of `event`; None for streamed agent text
+pub fn event_line(event: &RunEvent) -> Option<String> {
+    Some(match event {
+        RunEvent::RunStarted { phases, .. } => format!("running {}", phases.join(", ")),
+        RunEvent::PhaseStarted { phase } => format!("phase {phase} started"),
+        RunEvent::PhaseSkipped { phase, reason } => format!("phase {phase} skipped ({reason})"),
+        RunEvent::AssistantText { .. } => return None,
+        RunEvent::PhaseCompleted { phase, stats } => {
+            format!("phase {phase} completed (${:.2})", stats.cost_usd)
+        }
+        RunEvent::PhaseFailed { phase, error } => {
+            let error = error.lines().next().unwrap_or_default();
+            format!("phase {phase} failed: {error}")
+        }
+        RunEvent::RunCompleted { status, .. } => format!("run finished ({status})"),
+    })
+}
+
+/// Total cost recorded for `feature`, 0 if its state can't be read
+fn feature_cost(gba_path: &Path, feature: &str) -> f64 {
+    FeatureState::find_dir(gba_path, feature)
+        .and_then(|path| FeatureState::load(&path))
+        .map_or(0.0, |state| state.total_stats.cost_usd)
+}
+
+/// Render the summary table of a batch
+pub fn render(entries: &[BatchEntry]) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{:<32} {:<10} {:>8} {:>10}",
+        "FEATURE", "RESULT", "COST", "DURATION"
+    );
+    for entry in entries {
+        let note = match &entry.outcome {
+            BatchOutcome::Completed => None,
+            BatchOutcome::Failed(note) | BatchOutcome::Skipped(note) => Some(note),
+        };
+        let _ = write!(
+            out,
+            "{:<32} {:<10} {:>8} {:>10}",
+            entry.feature,
+            entry.result(),
+            format!("${:.2}", entry.cost_usd),
+            format_duration(entry.duration)
+        );
+        if let Some(note) = note {
+            let first_line = note.lines().next().unwrap_or_default();
+            let _ = write!(out, "  {first_line}");
+        }
+        out.push('\n');
+    }
+    let total_cost: f64 = entries.iter().map(|e| e.cost_usd).sum();
+    let total_duration = entries
+        .iter()
+        .map(|e| e.duration)
+        .fold(TimeDelta::zero(), |a, b| a + b);
+    let _ = writeln!(
+        out,
+        "{:<32} {:<10} {:>8} {:>10}",
+        "TOTAL",
+        "",
+        format!("${total_cost:.2}"),
+        format_duration(total_duration)
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Planned feature `0002_billing` with the specs of `0001_auth`
+    fn add_billing(gba_path: &Path, auth_path: &Path) -> std::path::PathBuf {
+        let billing = FeatureState::new("0002", "billing");
+        let billing_path = gba_path
+            .join(gba_core::FEATURES_DIR)
+            .join(billing.dir_name());
+        std::fs::create_dir_all(billing_path.join("specs")).unwrap();
+        billing.save(&billing_path).unwrap();
+        for file in [
+            gba_core::DESIGN_FILE,
+            gba_core::verification::VERIFICATION_FILE,
+        ] {
+            std::fs::copy(auth_path.join(file), billing_path.join(file)).unwrap();
+        }
+        billing_path
+    }
+
+    #[tokio::test]
+    async fn test_runs_planned_features_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, auth_path, config) = super::super::run::tests::setup(dir.path());
+        let billing_path = add_billing(&gba_path, &auth_path);
+        let options = RunAllOptions {
+            max: Some(1),
+            run: RunOptions {
+                no_progress: true,
+                ..RunOptions::default()
+            },
+            ..RunAllOptions::default()
+        };
+
+        run(&gba_path, config.clone(), options.clone())
+            .await
+            .unwrap();
+        assert_eq!(
+            FeatureState::load(&auth_path).unwrap().status,
+            FeatureStatus::Completed
+        );
+        assert_eq!(
+            FeatureState::load(&billing_path).unwrap().status,
+            FeatureStatus::Planned
+        );
+
+        let options = RunAllOptions {
+            max: None,
+            ..options
+        };
+        run(&gba_path, config, options).await.unwrap();
+        assert_eq!(
+            FeatureState::load(&billing_path).unwrap().status,
+            FeatureStatus::Completed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_state_written_by_earlier_features_is_not_dirt() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, auth_path, config) = super::super::run::tests::setup(dir.path());
+        let billing_path = add_billing(&gba_path, &auth_path);
+        super::super::run::tests::commit_repo(dir.path());
+        let options = RunAllOptions {
+            run: RunOptions {
+                no_progress: true,
+                ..RunOptions::default()
+            },
+            ..RunAllOptions::default()
+        };
+
+        run(&gba_path, config, options).await.unwrap();
+
+        for path in [auth_path, billing_path] {
+            let state = FeatureState::load(&path).unwrap();
+            assert_eq!(state.status, FeatureStatus::Completed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parallel_run_completes_every_feature() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, auth_path, config) = super::super::run::tests::setup(dir.path());
+        let billing_path = add_billing(&gba_path, &auth_path);
+        let options = RunAllOptions {
+            parallel: 2,
+            budget: Some(10.0),
+            ..RunAllOptions::default()
+        };
+
+        run(&gba_path, config, options).await.unwrap();
+
+        for path in [auth_path, billing_path] {
+            let state = FeatureState::load(&path).unwrap();
+            assert_eq!(state.status, FeatureStatus::Completed);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failure_stops_the_queue() {
+        let dir = tempfile::tempdir().unwrap();
+        let (gba_path, auth_path, config) = super::super::run::tests::setup(dir.path());
+        // A draft fails the readiness check.
+        std::fs::write(
+            auth_path.join(gba_core::DESIGN_FILE),
+            "# Design\n\nTODO: describe the feature.\n",
+        )
+        .unwrap();
+        let billing = FeatureState::new("0002", "billing");
+        let billing_path = gba_path
+            .join(gba_core::FEATURES_DIR)
+            .join(billing.dir_name());
+        std::fs::create_dir_all(&billing_path).unwrap();
+        billing.save(&billing_path).unwrap();
+        let options = RunAllOptions {
+            run: RunOptions {
+                no_progress: true,
+                ..RunOptions::default()
+            },
+            ..RunAllOptions::default()
+        };
+
+        let err = run(&gba_path, config, options).await.unwrap_err();
+
+        assert_eq!(err.to_string(), "1 of 2 feature(s) failed");
+        assert_eq!(
+            FeatureState::load(&billing_path).unwrap().status,
+            FeatureStatus::Planned
+        );
+    }
+
+    #[test]
+    fn test_render_summary_table() {
+        let entries = vec![
+            BatchEntry {
+                feature: "0001_auth".to_string(),
+                outcome: BatchOutcome::Completed,
+                cost_usd: 1.25,
+                duration: TimeDelta::seconds(754),
+            },
+            BatchEntry {
+                feature: "0002_billing".to_string(),
+                outcome: BatchOutcome::Skipped("budget of $1.00 spent".to_string()),
+                cost_usd: 0.0,
+                duration: TimeDelta::zero(),
+            },
+        ];
+
+        let table = render(&entries);
+
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[1].starts_with("0001_auth"));
+        assert!(lines[1].contains("completed"));
+        assert!(lines[1].contains("$1.25"));
+        assert!(lines[1].contains("12m34s"));
+        assert!(lines[2].ends_with("skipped       $0.00         0s  budget of $1.00 spent"));
+        assert!(lines[3].starts_with("TOTAL"));
+        assert!(lines[3].contains("$1.25"));
+        assert_eq!(
+            parse_status("in_progress").unwrap(),
+            FeatureStatus::InProgress
+        );
+        assert!(parse_status("done").is_err());
+    }
+}
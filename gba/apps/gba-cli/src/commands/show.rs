@@ -0,0 +1,57 @@
+//! `gba show`: print a feature's spec documents or state.
+
+use std::path::Path;
+
+use anyhow::Result;
+use gba_core::FeatureState;
+
+use super::{edit, status};
+
+/// Documents `gba show` can print
+pub const DOCUMENTS: [&str; 3] = ["design", "verification", "state"];
+
+/// Print `document` of `feature` (default: the design); `state` is rendered
+/// as `gba status` shows it
+pub fn run(gba_path: &Path, feature: &str, document: Option<&str>) -> Result<()> {
+    print!("{}", render(gba_path, feature, document)?);
+    Ok(())
+}
+
+/// Text printed by `gba show`.
+///
+/// # Errors
+///
+/// Returns an error if the feature or document doesn't exist or can't be
+/// read.
+pub fn render(gba_path: &Path, feature: &str, document: Option<&str>) -> Result<String> {
+    let feature_path = FeatureState::find_dir(gba_path, feature)?;
+    if document == Some("state") {
+        return Ok(status::render(&FeatureState::load(&feature_path)?));
+    }
+    let path = edit::spec_path(&feature_path, document)?;
+    Ok(std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::plan;
+
+    #[test]
+    fn test_show_documents() {
+        let dir = tempfile::tempdir().unwrap();
+        plan::create(dir.path(), "login", "# Design\n", "# Verification\n").unwrap();
+
+        assert_eq!(render(dir.path(), "login", None).unwrap(), "# Design\n");
+        assert_eq!(
+            render(dir.path(), "0001", Some("verification")).unwrap(),
+            "# Verification\n"
+        );
+        assert!(
+            render(dir.path(), "login", Some("state"))
+                .unwrap()
+                .contains("login")
+        );
+        assert!(render(dir.path(), "signup", None).is_err());
+    }
+}
@@ -0,0 +1,321 @@
+//! `gba status`: show the execution state of a feature.
+
+use std::fmt::Write;
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use anyhow::Result;
+use gba_core::{FeatureState, FeatureStatus, PhaseStatus, STATE_FILE};
+
+use super::log::format_duration;
+use crate::ui::style::{Style, feature_style, paint, paint_if, phase_style};
+
+/// Print the status of a feature, or its event log with `events`
+pub fn run(gba_path: &Path, feature: &str, events: bool) -> Result<()> {
+    let feature_path = FeatureState::find_dir(gba_path, feature)?;
+    let state = FeatureState::load(&feature_path)?;
+    if events {
+        print!("{}", render_events(&state));
+    } else {
+        print!("{}", render(&state));
+    }
+    Ok(())
+}
+
+/// Flags of `gba status --watch`
+#[derive(Debug, Clone, Copy)]
+pub struct WatchOptions {
+    /// How often `state.yml` is checked and the view redrawn
+    pub interval: Duration,
+    /// Stop once the feature is completed or failed
+    pub until_done: bool,
+}
+
+/// Clear the terminal and move the cursor home
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// Redraw the status of `feature` until Ctrl-C, reloading it when
+/// `state.yml` changes
+pub async fn watch(gba_path: &Path, feature: &str, options: WatchOptions) -> Result<()> {
+    let mut watcher = StateWatcher::new(FeatureState::find_dir(gba_path, feature)?)?;
+    let mut tick = tokio::time::interval(options.interval);
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => return Ok(()),
+            _ = tick.tick() => {}
+        }
+        watcher.refresh();
+        print!("{CLEAR_SCREEN}{}", render_watch(&watcher, options.interval));
+        let _ = std::io::stdout().flush();
+        let finished = matches!(
+            watcher.state.status,
+            FeatureStatus::Completed | FeatureStatus::Failed
+        );
+        if options.until_done && finished {
+            return Ok(());
+        }
+    }
+}
+
+/// Last successfully read state of a feature
+struct StateWatcher {
+    feature_path: PathBuf,
+    state: FeatureState,
+    /// Modification time and size of `state.yml` when it was last read
+    version: Option<(SystemTime, u64)>,
+    /// Whether the latest read failed, e.g. because the file was mid-rewrite
+    stale: bool,
+}
+
+impl StateWatcher {
+    fn new(feature_path: PathBuf) -> Result<Self> {
+        let version = file_version(&feature_path);
+        let state = FeatureState::load(&feature_path)?;
+        Ok(Self {
+            feature_path,
+            state,
+            version,
+            stale: false,
+        })
+    }
+
+    /// Reload the state if the file changed; keep the last one if it can't
+    /// be parsed so the next refresh retries
+    fn refresh(&mut self) {
+        let version = file_version(&self.feature_path);
+        if version == self.version {
+            return;
+        }
+        match FeatureState::load(&self.feature_path) {
+            Ok(state) => {
+                self.state = state;
+                self.version = version;
+                self.stale = false;
+            }
+            Err(_) => self.stale = true,
+        }
+    }
+}
+
+fn file_version(feature_path: &Path) -> Option<(SystemTime, u64)> {
+    let metadata = std::fs::metadata(feature_path.join(STATE_FILE)).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Watch view: a header and the status with the running phase's name in
+/// bold
+fn render_watch(watcher: &StateWatcher, interval: Duration) -> String {
+    let mut out = format!(
+        "Every {}s: gba status {}    {}\n",
+        interval.as_secs_f64(),
+        watcher.state.dir_name(),
+        chrono::Local::now().format("%H:%M:%S")
+    );
+    if watcher.stale {
+        out.push_str("(state.yml could not be read, showing the last good state)\n");
+    }
+    out.push('\n');
+    out.push_str(&render_with(&watcher.state, true));
+    out
+}
+
+/// Render the status view of a feature
+pub fn render(state: &FeatureState) -> String {
+    render_with(state, false)
+}
+
+fn render_with(state: &FeatureState, highlight: bool) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "Feature {} [{}]",
+        state.dir_name(),
+        paint(&state.status.to_string(), feature_style(state.status))
+    );
+
+    for phase in &state.phases {
+        let running = highlight && phase.status == PhaseStatus::InProgress;
+        let style = phase_style(phase.status);
+        let name = format!("{:<14}", phase.name);
+        let name = if running {
+            paint_if(true, &name, Style::new().bold())
+        } else {
+            name
+        };
+        let _ = write!(
+            out,
+            "  {} {name} {}",
+            paint(phase.status.icon(), style),
+            paint(&phase.status.to_string(), style)
+        );
+        let mut details: Vec<String> = phase.model.iter().cloned().collect();
+        details.extend(phase.duration().map(format_duration));
+        if let Some(stats) = &phase.stats {
+            details.push(format!("{} turns", stats.turns));
+            details.push(format!("${:.2}", stats.cost_usd));
+        }
+        if !details.is_empty() {
+            let _ = write!(out, " ({})", details.join(", "));
+        }
+        out.push('\n');
+        if let Some(summary) = &phase.output_summary {
+            let _ = writeln!(out, "      {summary}");
+        }
+        if let Some(verification) = &phase.verification {
+            for failure in verification.failures() {
+                let _ = write!(
+                    out,
+                    "      {} {}",
+                    paint(PhaseStatus::Failed.icon(), phase_style(PhaseStatus::Failed)),
+                    failure.criterion
+                );
+                if !failure.notes.is_empty() {
+                    let _ = write!(out, " ({})", failure.notes);
+                }
+                out.push('\n');
+            }
+        }
+    }
+
+    let stats = &state.total_stats;
+    let _ = write!(out, "Total: ");
+    if let Some(duration) = state.duration() {
+        let _ = write!(out, "{}, ", format_duration(duration));
+    }
+    let _ = writeln!(
+        out,
+        "{} turns, {} input / {} output tokens, ${:.2}",
+        stats.turns, stats.input_tokens, stats.output_tokens, stats.cost_usd
+    );
+    if let Some(error) = &state.error {
+        let _ = writeln!(out, "Error: {error}");
+    }
+    out
+}
+
+/// Render the event log of a feature, oldest first
+pub fn render_events(state: &FeatureState) -> String {
+    if state.events.is_empty() {
+        return format!("No events recorded for {}\n", state.dir_name());
+    }
+    let mut out = String::new();
+    for event in &state.events {
+        let line = format!(
+            "{}  {:<16} {:<14} {}",
+            event.at.format("%Y-%m-%d %H:%M:%S"),
+            event.kind.to_string(),
+            event.phase.as_deref().unwrap_or("-"),
+            event.message
+        );
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gba_core::verification::{CriterionResult, VerificationSummary};
+
+    #[test]
+    fn test_render_lists_failing_criteria() {
+        let mut state = FeatureState::new("0001", "user-auth");
+        state.phase_mut("build").status = PhaseStatus::Completed;
+        state.phase_mut("build").model = Some("claude-opus-4".to_string());
+        state.phase_mut("observe").status = PhaseStatus::Completed;
+        state.phase_mut("observe").stats = Some(gba_core::ExecutionStats {
+            turns: 3,
+            cost_usd: 0.42,
+            ..Default::default()
+        });
+        let phase = state.phase_mut("verification");
+        phase.status = PhaseStatus::Failed;
+        phase.verification = Some(VerificationSummary::new(vec![
+            CriterionResult {
+                index: 1,
+                criterion: "Login returns a JWT".to_string(),
+                passed: true,
+                notes: String::new(),
+            },
+            CriterionResult {
+                index: 2,
+                criterion: "Logout clears the session".to_string(),
+                passed: false,
+                notes: "cookie kept".to_string(),
+            },
+        ]));
+
+        let out = render(&state);
+
+        assert!(out.contains("✓ build"));
+        assert!(out.contains("completed (claude-opus-4)"));
+        assert!(out.contains("completed (3 turns, $0.42)"));
+        assert!(out.contains("✗ verification"));
+        assert!(out.contains("✗ Logout clears the session (cookie kept)"));
+        assert!(!out.contains("Login returns a JWT"));
+    }
+
+    #[test]
+    fn test_watcher_keeps_last_state_while_rewriting() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = FeatureState::new("0001", "user-auth");
+        state.start_phase(1, "build");
+        state.save(dir.path()).unwrap();
+        let mut watcher = StateWatcher::new(dir.path().to_path_buf()).unwrap();
+
+        std::fs::write(dir.path().join(STATE_FILE), "version: \"0.1.0\"\nfeature:").unwrap();
+        watcher.refresh();
+        assert!(watcher.stale);
+        assert_eq!(watcher.state.status, FeatureStatus::InProgress);
+        let out = render_watch(&watcher, Duration::from_secs(2));
+        assert!(out.starts_with("Every 2s: gba status 0001_user-auth"));
+        assert!(out.contains("could not be read"));
+        assert!(out.contains("◐ \x1b[1mbuild"));
+
+        state.complete_phase("build", &Default::default(), String::new());
+        state.complete();
+        state.save(dir.path()).unwrap();
+        watcher.refresh();
+        assert!(!watcher.stale);
+        assert_eq!(watcher.state.status, FeatureStatus::Completed);
+        assert!(!render_watch(&watcher, Duration::from_secs(2)).contains("\x1b[1m"));
+    }
+
+    #[test]
+    fn test_render_durations() {
+        let mut state = FeatureState::new("0001", "user-auth");
+        let started = chrono::Utc::now() - chrono::TimeDelta::hours(1);
+        let build = state.phase_mut("build");
+        build.status = PhaseStatus::Completed;
+        build.started_at = Some(started);
+        build.completed_at = Some(started + chrono::TimeDelta::seconds(252));
+
+        let out = render(&state);
+        assert!(out.contains("completed (4m12s)"));
+        assert!(out.contains("Total: 4m12s, 0 turns"));
+
+        state.total_stats.wall_clock_seconds = 600;
+        assert!(render(&state).contains("Total: 10m00s, 0 turns"));
+    }
+
+    #[test]
+    fn test_render_events() {
+        let mut state = FeatureState::new("0001", "user-auth");
+        assert_eq!(
+            render_events(&state),
+            "No events recorded for 0001_user-auth\n"
+        );
+
+        state.start_phase(0, "build");
+        state.fail_phase("build", "Agent timed out".to_string(), None);
+        let out = render_events(&state);
+        let lines: Vec<_> = out.lines().collect();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("phase_started    build          attempt 1"));
+        assert!(lines[1].contains("phase_failed     build          Agent timed out"));
+    }
+}
@@ -0,0 +1,219 @@
+//! `gba templates`: list the prompt templates of each phase, or render
+//! those of one.
+
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use gba_core::{
+    AgentConfig, ConfigLoader, FeatureState, PROMPTS_DIR, ProjectConfig, global_prompts_dir,
+};
+use gba_pm::{OversizedPrompt, PromptContext, PromptManager, TemplateInfo};
+
+/// Print the phases with the template files each provides, where they
+/// come from and their description, built-in templates included
+pub fn run(gba_path: &Path) -> Result<()> {
+    let pm = prompt_manager(gba_path)?;
+    print!(
+        "{}",
+        render(&pm.list_templates_detailed()?, &gba_path.join(PROMPTS_DIR))
+    );
+    Ok(())
+}
+
+/// Template roots in order of precedence: the repo's `prompts/`, then the
+/// global ones of `$GBA_PROMPTS_DIR` or `~/.gba/prompts`
+pub(crate) fn search_path(gba_path: &Path) -> Vec<PathBuf> {
+    let mut roots = vec![gba_path.join(PROMPTS_DIR)];
+    roots.extend(global_prompts_dir());
+    roots
+}
+
+/// Prompt manager resolving templates through [`search_path`], falling
+/// back to the built-in ones
+pub(crate) fn prompt_manager(gba_path: &Path) -> Result<PromptManager> {
+    PromptManager::with_search_path(search_path(gba_path))
+}
+
+/// Print the prompts of `phase` rendered for `feature` (or an example
+/// feature), with their estimated size; `strict` fails on missing variables
+pub fn render_phase(
+    repo: &Path,
+    gba_path: &Path,
+    phase: &str,
+    feature: Option<&str>,
+    strict: bool,
+) -> Result<()> {
+    let project = ConfigLoader::new(gba_path).load()?.config;
+    let mut pm = prompt_manager(gba_path)?;
+    configure(&mut pm, &project);
+    pm.set_strict(strict);
+    let (slug, id) = match feature {
+        Some(feature) => {
+            let state = FeatureState::load(&FeatureState::find_dir(gba_path, feature)?)?;
+            (state.feature.slug, state.feature.id)
+        }
+        None => ("example".to_string(), "0000".to_string()),
+    };
+    let context = PromptContext::new(repo.to_string_lossy(), &slug, &id).with_phase(phase);
+    let (system, user) = pm.load_phase_prompts(phase, &context)?;
+
+    let mut out = String::new();
+    if let Some(system) = &system {
+        let _ = writeln!(out, "--- system.md ---\n{}\n", system.trim_end());
+    }
+    let _ = writeln!(out, "--- user.md ---\n{}\n", user.trim_end());
+    let estimated =
+        system.as_deref().map_or(0, |s| pm.estimate_tokens(s)) + pm.estimate_tokens(&user);
+    out.push_str(&size_line(estimated, max_prompt_tokens(&project.agent)));
+    print!("{out}");
+    Ok(())
+}
+
+/// Apply the project's prompt settings to `pm`
+pub(crate) fn configure(pm: &mut PromptManager, project: &ProjectConfig) {
+    limit_prompts(pm, &project.agent);
+    pm.set_env_allowlist(project.template_env_allowlist.iter().cloned());
+}
+
+/// `agent.maxPromptTokens`, None if unlimited
+pub(crate) fn max_prompt_tokens(agent: &AgentConfig) -> Option<usize> {
+    (agent.max_prompt_tokens > 0).then_some(agent.max_prompt_tokens)
+}
+
+/// Apply `agent.maxPromptTokens` and `agent.warnOnLargePrompt` to `pm`
+pub(crate) fn limit_prompts(pm: &mut PromptManager, agent: &AgentConfig) {
+    let oversized = if agent.warn_on_large_prompt {
+        OversizedPrompt::Warn
+    } else {
+        OversizedPrompt::Error
+    };
+    pm.set_max_prompt_tokens(max_prompt_tokens(agent), oversized);
+}
+
+fn size_line(estimated: usize, limit: Option<usize>) -> String {
+    match limit {
+        Some(limit) if estimated > limit => {
+            format!("~{estimated} tokens, over the limit of {limit} (agent.maxPromptTokens)\n")
+        }
+        Some(limit) => format!("~{estimated} tokens (limit {limit})\n"),
+        None => format!("~{estimated} tokens\n"),
+    }
+}
+
+fn render(templates: &[TemplateInfo], repo_prompts: &Path) -> String {
+    let mark = |present: bool| if present { "yes" } else { "-" };
+    let source = |t: &TemplateInfo| match &t.source {
+        Some(root) if root == repo_prompts => "repo".to_string(),
+        Some(root) => root.display().to_string(),
+        None => "built-in".to_string(),
+    };
+    let source_width = templates
+        .iter()
+        .map(|t| source(t).len())
+        .max()
+        .unwrap_or(0)
+        .max("SOURCE".len());
+    let width = templates
+        .iter()
+        .map(|t| t.name.len())
+        .max()
+        .unwrap_or(0)
+        .max("PHASE".len());
+    let mut out = format!(
+        "{:<width$}  SYSTEM  USER  CONFIG  {:<source_width$}  DESCRIPTION\n",
+        "PHASE", "SOURCE"
+    );
+    for t in templates {
+        let line = format!(
+            "{:<width$}  {:<6}  {:<4}  {:<6}  {:<source_width$}  {}",
+            t.name,
+            mark(t.has_system),
+            mark(t.has_user),
+            mark(t.has_config),
+            source(t),
+            t.description.as_deref().unwrap_or("-")
+        );
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_line() {
+        assert_eq!(size_line(120, None), "~120 tokens\n");
+        assert_eq!(size_line(120, Some(150)), "~120 tokens (limit 150)\n");
+        assert_eq!(
+            size_line(180, Some(150)),
+            "~180 tokens, over the limit of 150 (agent.maxPromptTokens)\n"
+        );
+    }
+
+    #[test]
+    fn test_limit_prompts() {
+        let mut pm = PromptManager::new();
+        let context = PromptContext::new("/repo", "auth", "0001");
+        let mut agent = AgentConfig {
+            max_prompt_tokens: 2,
+            ..AgentConfig::default()
+        };
+        limit_prompts(&mut pm, &agent);
+        assert!(pm.render_string("twelve chars", &context).is_err());
+        agent.warn_on_large_prompt = true;
+        limit_prompts(&mut pm, &agent);
+        assert!(pm.render_string("twelve chars", &context).is_ok());
+        agent.max_prompt_tokens = 0;
+        agent.warn_on_large_prompt = false;
+        limit_prompts(&mut pm, &agent);
+        assert!(pm.render_string("twelve chars", &context).is_ok());
+    }
+
+    #[test]
+    fn test_render_marks_missing_files() {
+        let repo = Path::new("/repo/.gba/prompts");
+        let templates = [
+            TemplateInfo {
+                name: "build".to_string(),
+                has_system: true,
+                has_user: true,
+                has_config: true,
+                source: Some(repo.to_path_buf()),
+                description: Some("Implement the feature".to_string()),
+                requires: vec!["specs".to_string()],
+                version: Some("2".to_string()),
+            },
+            TemplateInfo {
+                name: "plan".to_string(),
+                has_system: true,
+                has_user: true,
+                has_config: false,
+                source: None,
+                description: None,
+                requires: Vec::new(),
+                version: None,
+            },
+            TemplateInfo {
+                name: "review".to_string(),
+                has_system: true,
+                has_user: false,
+                has_config: false,
+                source: Some(PathBuf::from("/home/me/.gba/prompts")),
+                description: Some("Review the changes".to_string()),
+                requires: Vec::new(),
+                version: None,
+            },
+        ];
+
+        assert_eq!(
+            render(&templates, repo),
+            "PHASE   SYSTEM  USER  CONFIG  SOURCE                 DESCRIPTION\n\
+             build   yes     yes   yes     repo                   Implement the feature\n\
+             plan    yes     yes   -       built-in               -\n\
+             review  yes     -     -       /home/me/.gba/prompts  Review the changes\n"
+        );
+    }
+}
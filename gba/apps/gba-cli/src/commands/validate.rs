@@ -0,0 +1,188 @@
+//! `gba validate`: preflight check of config, templates and feature state.
+
+use std::path::Path;
+
+use std::time::Duration;
+
+use anyhow::{Result, bail};
+use gba_core::{
+    CONFIG_FILE, FEATURES_DIR, FeatureState, PROMPTS_DIR, PhaseKind, ProjectConfig, TaskConfig, mcp,
+};
+use gba_pm::PromptManager;
+
+/// How long a stdio MCP server must keep running to pass `--mcp`
+const MCP_PROBE_GRACE: Duration = Duration::from_secs(2);
+
+/// Validate the `.gba` directory, failing if any problem is found.
+///
+/// With `probe_mcp`, every configured stdio MCP server is also started
+/// briefly from `repo_path`.
+pub async fn run(gba_path: &Path, repo_path: &Path, probe_mcp: bool) -> Result<()> {
+    let mut problems = check(gba_path);
+    if probe_mcp {
+        problems.extend(probe_servers(gba_path, repo_path).await);
+    }
+    if problems.is_empty() {
+        println!("No problems found");
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("✗ {problem}");
+    }
+    bail!("{} problem(s) found", problems.len());
+}
+
+/// Collect every problem in the `.gba` directory
+pub fn check(gba_path: &Path) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    let config_path = gba_path.join(CONFIG_FILE);
+    let prompts_dir = gba_path.join(PROMPTS_DIR);
+    if config_path.exists() {
+        match ProjectConfig::load(gba_path).and_then(|c| c.validate().map(|()| c)) {
+            Ok(config) => problems.extend(check_mcp_tools(&config, &prompts_dir)),
+            Err(e) => problems.push(format!("{}: {e}", config_path.display())),
+        }
+    } else {
+        problems.push(format!("{} not found", config_path.display()));
+    }
+
+    if prompts_dir.is_dir() {
+        let mut pm = PromptManager::new();
+        match pm.load_templates(&prompts_dir) {
+            Ok(()) => problems.extend(pm.validate_all().iter().map(|i| format!("template {i}"))),
+            Err(e) => problems.push(format!("{e:#}")),
+        }
+    } else {
+        problems.push(format!("{} not found", prompts_dir.display()));
+    }
+
+    match FeatureState::list_all(gba_path) {
+        Ok(listing) => {
+            let mut errors: Vec<(String, String)> = listing
+                .failed
+                .into_iter()
+                .map(|(name, e)| (name, e.to_string()))
+                .collect();
+            for (name, state) in listing.loaded {
+                if let Err(e) = state.validate() {
+                    errors.push((name, e.to_string()));
+                }
+            }
+            errors.sort();
+            let features = gba_path.join(FEATURES_DIR);
+            for (name, e) in errors {
+                problems.push(format!("{}: {e}", features.join(name).display()));
+            }
+        }
+        Err(e) => problems.push(format!("{}: {e}", gba_path.join(FEATURES_DIR).display())),
+    }
+
+    problems
+}
+
+/// Problems with the MCP tools allowed by the agent phases' task configs
+fn check_mcp_tools(config: &ProjectConfig, prompts_dir: &Path) -> Vec<String> {
+    let mut problems = Vec::new();
+    for phase in config.phases.iter().filter(|p| p.kind == PhaseKind::Agent) {
+        let task_dir = prompts_dir.join(&phase.name);
+        let task_config = task_dir.join(TaskConfig::FILE_NAME);
+        let result = TaskConfig::load(&task_dir).and_then(|task| {
+            let servers = mcp::merge(&config.mcp_servers, &task.mcp_servers);
+            mcp::check_tools(&task.tools, &servers)
+        });
+        if let Err(e) = result {
+            problems.push(format!("{}: {e}", task_config.display()));
+        }
+    }
+    problems
+}
+
+/// Start every stdio MCP server of the project and its tasks briefly
+async fn probe_servers(gba_path: &Path, repo_path: &Path) -> Vec<String> {
+    let Ok(config) = ProjectConfig::load(gba_path) else {
+        // Already reported by `check`.
+        return Vec::new();
+    };
+    let prompts_dir = gba_path.join(PROMPTS_DIR);
+    let mut servers = config.mcp_servers.clone();
+    for phase in &config.phases {
+        if let Ok(task) = TaskConfig::load(&prompts_dir.join(&phase.name)) {
+            servers = mcp::merge(&servers, &task.mcp_servers);
+        }
+    }
+
+    let mut problems = Vec::new();
+    for (name, server) in &servers {
+        match mcp::probe(server, repo_path, MCP_PROBE_GRACE).await {
+            Ok(()) => println!("✓ MCP server {name}"),
+            Err(e) => problems.push(format!("MCP server {name}: {e}")),
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let gba = dir.path();
+        std::fs::write(
+            gba.join(CONFIG_FILE),
+            "agent:\n  model: claude-sonnet-4-5\nphases:\n  - name: build\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(gba.join(PROMPTS_DIR).join("build")).unwrap();
+        std::fs::write(
+            gba.join(PROMPTS_DIR).join("build/user.md"),
+            "Build {{ feature_slug }}",
+        )
+        .unwrap();
+        let feature = gba.join(FEATURES_DIR).join("0001_auth");
+        std::fs::create_dir_all(&feature).unwrap();
+        FeatureState::new("0001", "auth").save(&feature).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_clean_repo_passes() {
+        let dir = setup();
+        assert_eq!(check(dir.path()), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_broken_template_is_named() {
+        let dir = setup();
+        std::fs::write(
+            dir.path().join(PROMPTS_DIR).join("build/system.md"),
+            "{% for x in items %}never closed",
+        )
+        .unwrap();
+
+        let problems = check(dir.path());
+
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("build/system.md"));
+    }
+
+    #[test]
+    fn test_unknown_mcp_server_in_tools() {
+        let dir = setup();
+        let task_config = dir.path().join(PROMPTS_DIR).join("build/config.yml");
+        std::fs::write(&task_config, "tools: [Read, mcp__docs__search]\n").unwrap();
+
+        let problems = check(dir.path());
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("build/config.yml"));
+        assert!(problems[0].contains("unknown MCP server `docs`"));
+
+        std::fs::write(
+            &task_config,
+            "tools: [Read, mcp__docs__search]\nmcpServers:\n  docs:\n    command: docs-mcp\n",
+        )
+        .unwrap();
+        assert_eq!(check(dir.path()), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,113 @@
+//! Process exit codes and error output.
+//!
+//! | Code | Meaning                                      |
+//! |------|----------------------------------------------|
+//! | 0    | Success                                      |
+//! | 1    | Any other failure                            |
+//! | 2    | Invalid configuration or command line        |
+//! | 3    | Feature not found                            |
+//! | 4    | The agent timed out                          |
+
+use anyhow::Error;
+use gba_core::CoreError;
+
+/// Any failure without a more specific code
+pub const FAILURE: u8 = 1;
+
+/// Invalid configuration (config files, overrides, model ID)
+pub const CONFIG: u8 = 2;
+
+/// No feature matches the given ID or slug
+pub const NOT_FOUND: u8 = 3;
+
+/// The agent or a command didn't finish within its timeout
+pub const TIMEOUT: u8 = 4;
+
+/// Core error behind `error`, looking through added context
+fn core_error(error: &Error) -> Option<&CoreError> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<CoreError>())
+}
+
+/// Exit code for `error`
+pub fn code(error: &Error) -> u8 {
+    match core_error(error) {
+        Some(CoreError::ConfigError(_) | CoreError::Yaml(_)) => CONFIG,
+        Some(CoreError::FeatureNotFound(_)) => NOT_FOUND,
+        Some(CoreError::AgentTimeout { .. } | CoreError::CommandTimedOut { .. }) => TIMEOUT,
+        _ => FAILURE,
+    }
+}
+
+/// Short machine-readable name of the kind of `error`
+pub fn kind(error: &Error) -> &'static str {
+    match core_error(error) {
+        Some(CoreError::AgentExecutionFailed(_)) => "agent_failed",
+        Some(CoreError::AgentTimeout { .. }) => "agent_timeout",
+        Some(CoreError::ConfigError(_) | CoreError::Yaml(_)) => "config",
+        Some(CoreError::CommandFailed { .. }) => "command_failed",
+        Some(CoreError::CommandTimedOut { .. }) => "command_timed_out",
+        Some(CoreError::FeatureNotFound(_)) => "feature_not_found",
+        Some(CoreError::AmbiguousFeature { .. }) => "ambiguous_feature",
+        Some(CoreError::FeatureInProgress(_)) => "feature_in_progress",
+        Some(CoreError::FeatureLocked { .. }) => "feature_locked",
+        Some(CoreError::OutputLimitExceeded { .. }) => "output_limit_exceeded",
+        Some(CoreError::InvalidAgentOutput(_)) => "invalid_agent_output",
+        Some(CoreError::DirtyWorkingTree { .. }) => "dirty_working_tree",
+        Some(CoreError::FeatureNotReady { .. }) => "feature_not_ready",
+        Some(CoreError::NotificationFailed(_)) => "notification_failed",
+        Some(CoreError::Io(_)) => "io",
+        None => "other",
+    }
+}
+
+/// `error` as one JSON line:
+/// `{"error": {"kind", "message", "causes"}, "code": N}`
+pub fn render_json(error: &Error) -> String {
+    let causes: Vec<String> = error.chain().skip(1).map(ToString::to_string).collect();
+    serde_json::json!({
+        "error": {
+            "kind": kind(error),
+            "message": error.to_string(),
+            "causes": causes,
+        },
+        "code": code(error),
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_missing_feature_exits_with_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = crate::commands::status::run(dir.path(), "0042", false).unwrap_err();
+
+        assert_eq!(code(&err), NOT_FOUND);
+        assert_eq!(kind(&err), "feature_not_found");
+    }
+
+    #[test]
+    fn test_codes_look_through_context() {
+        let err = Err::<(), _>(CoreError::ConfigError("agent.model is empty".to_string()))
+            .context("Failed to load .gba/config.yml")
+            .unwrap_err();
+        assert_eq!(code(&err), CONFIG);
+
+        let json: serde_json::Value = serde_json::from_str(&render_json(&err)).unwrap();
+        assert_eq!(json["code"], 2);
+        assert_eq!(json["error"]["kind"], "config");
+        assert_eq!(json["error"]["message"], "Failed to load .gba/config.yml");
+        assert_eq!(
+            json["error"]["causes"][0],
+            "Invalid configuration: agent.model is empty"
+        );
+
+        assert_eq!(code(&anyhow::anyhow!("git diff exited with 1")), FAILURE);
+    }
+}
@@ -1,24 +1,46 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
-use std::path::PathBuf;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use gba_core::ConfigPermissionMode;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
+mod commands;
+mod exit;
+mod progress;
+mod repo;
 mod ui;
 
+/// Directory holding GBA configuration and feature state
+const GBA_DIR: &str = ".gba";
+
 #[derive(Parser)]
 #[command(name = "gba")]
 #[command(author, version, about = "Geektime Bootcamp Agent - A CLI tool for Claude Agent SDK", long_about = None)]
 struct Cli {
-    /// Repository path to work with
-    #[arg(short, long, default_value = ".")]
-    repo: PathBuf,
+    /// Repository path to work with (default: the nearest parent directory
+    /// holding .gba or .git)
+    #[arg(short, long)]
+    repo: Option<PathBuf>,
 
-    /// Claude API key (or set ANTHROPIC_API_KEY env var)
+    /// Claude API key (or set ANTHROPIC_API_KEY, agent.apiKeyFile or agent.apiKeyKeychain)
     #[arg(short, long, env)]
     api_key: Option<String>,
 
-    /// Model to use
-    #[arg(short, long, default_value = "claude-sonnet-4-5-20250929")]
-    model: String,
+    /// Model to use (overrides agent.model from the config files)
+    #[arg(short, long, global = true)]
+    model: Option<String>,
+
+    /// Only print results and errors, no status chatter
+    #[arg(short, long, global = true)]
+    quiet: bool,
+
+    /// How errors are printed: text on stderr, or one JSON line on stdout
+    #[arg(long, global = true, default_value = "text", value_parser = ["text", "json"])]
+    format: String,
+
+    /// Diagnostic log level on stderr (error, warn, info, debug, trace)
+    #[arg(long, global = true, default_value = "warn", value_name = "LEVEL")]
+    log_level: tracing_subscriber::filter::LevelFilter,
 
     #[command(subcommand)]
     command: Commands,
@@ -32,48 +54,777 @@ enum Commands {
         prompt: String,
     },
     /// Interactive TUI mode
-    Tui,
-    /// List available prompt templates
-    Templates,
+    Tui {
+        #[command(flatten)]
+        agent: AgentOverrides,
+        /// Show a live table of feature states instead of the chat
+        #[arg(short, long)]
+        watch: bool,
+        /// Seconds between reloads of the feature states with --watch
+        #[arg(long, default_value_t = 1, value_name = "SECS", requires = "watch")]
+        interval: u64,
+    },
+    /// List the prompt templates of each phase
+    Templates {
+        #[command(subcommand)]
+        command: Option<TemplatesCommand>,
+    },
+    /// Initialize GBA: .gba/ with a default config.yml and prompt templates
+    Init {
+        /// Reinitialize an existing .gba: keep config.yml, add new default keys
+        #[arg(short, long)]
+        force: bool,
+        /// Don't scaffold prompt templates in .gba/prompts
+        #[arg(long)]
+        no_templates: bool,
+    },
+    /// Plan a new feature: design and verification documents plus state
+    Plan {
+        /// Feature slug (e.g. user-auth)
+        slug: String,
+        /// Initial feature description
+        #[arg(short, long)]
+        description: Option<String>,
+        /// Use an existing document as the design instead of the boilerplate
+        #[arg(long, value_name = "PATH")]
+        from_file: Option<PathBuf>,
+        /// Ask for the description, requirements and acceptance criteria
+        #[arg(short, long)]
+        interactive: bool,
+    },
+    /// Print a feature's design, verification spec or state
+    Show {
+        /// Feature ID, slug or directory name
+        feature: String,
+        /// Document to print (default: design)
+        #[arg(value_parser = commands::show::DOCUMENTS)]
+        document: Option<String>,
+    },
+    /// Open a feature's spec files in $EDITOR or $VISUAL
+    Edit {
+        /// Feature ID, slug or directory name
+        feature: String,
+        /// Spec to open: design or verification (default: design)
+        #[arg(value_parser = ["design", "verification"], conflicts_with = "file")]
+        document: Option<String>,
+        /// Spec file to open by name, e.g. notes.md
+        #[arg(long)]
+        file: Option<String>,
+    },
+    /// Show the changes the agent made to a feature, through git's pager
+    Diff {
+        /// Feature ID, slug or directory name
+        feature: String,
+        /// Only the changes of this phase
+        phase: Option<String>,
+        /// Same as the PHASE argument
+        #[arg(
+            long = "phase",
+            value_name = "NAME",
+            conflicts_with = "phase",
+            hide = true
+        )]
+        phase_flag: Option<String>,
+        /// Summarize changed files instead of the full diff
+        #[arg(long)]
+        stat: bool,
+    },
+    /// Execute the phases of a planned feature
+    Run {
+        /// Feature ID, slug or directory name
+        feature: String,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Log periodic status lines instead of a spinner
+        #[arg(long)]
+        no_progress: bool,
+        /// Stream the agent's output while phases run
+        #[arg(short, long)]
+        verbose: bool,
+        /// Render prompts and walk the phases without calling the agent or saving state
+        #[arg(long)]
+        dry_run: bool,
+        /// Mark a phase as skipped instead of running it (repeatable)
+        #[arg(long = "skip-phase", visible_alias = "skip", value_name = "NAME")]
+        skip_phase: Vec<String>,
+        /// Run just this phase, even if it already ran, keeping the feature's status
+        #[arg(long, value_name = "PHASE", conflicts_with = "skip_phase")]
+        only: Option<String>,
+        /// Write one JSON event per line instead of human output (implies --yes)
+        #[arg(long)]
+        json: bool,
+        /// Follow the run in a full-screen view (implies --yes)
+        #[arg(long, conflicts_with = "json")]
+        tui: bool,
+        /// Run even if the working tree has uncommitted changes
+        #[arg(long)]
+        allow_dirty: bool,
+        /// Run even if the feature's specs are still drafts
+        #[arg(long)]
+        force: bool,
+        /// Run the observe phase even if docs/observations.md already exists
+        #[arg(long)]
+        force_observe: bool,
+        /// Save the agent's messages of every request as JSON lines in DIR
+        #[arg(long, value_name = "DIR", conflicts_with = "dry_run")]
+        record: Option<PathBuf>,
+        /// Replay the transcripts recorded in DIR instead of calling the API
+        #[arg(long, value_name = "DIR", conflicts_with_all = ["dry_run", "record"])]
+        replay: Option<PathBuf>,
+        /// Wait this long before each replayed message
+        #[arg(long, value_name = "MS", default_value_t = 0, requires = "replay")]
+        replay_delay: u64,
+        #[command(flatten)]
+        agent: AgentOverrides,
+    },
+    /// Run several features one after another, in ID order
+    RunAll {
+        /// Run the features with this status
+        #[arg(long, default_value = "planned", value_parser = ["planned", "in_progress", "failed", "completed"])]
+        status: String,
+        /// Run at most this many features
+        #[arg(long, value_name = "N")]
+        max: Option<usize>,
+        /// Keep going after a feature fails
+        #[arg(long)]
+        continue_on_error: bool,
+        /// Start no further feature once the batch has cost this much (USD)
+        #[arg(long, value_name = "USD")]
+        budget: Option<f64>,
+        /// Run up to N features at once, each in its own worktree
+        #[arg(long, value_name = "N", default_value_t = 1)]
+        parallel: usize,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Log periodic status lines instead of a spinner
+        #[arg(long)]
+        no_progress: bool,
+        /// Stream the agent's output while phases run
+        #[arg(short, long)]
+        verbose: bool,
+        /// Run even if the working tree has uncommitted changes
+        #[arg(long)]
+        allow_dirty: bool,
+        /// Run features even if their specs are still drafts
+        #[arg(long)]
+        force: bool,
+        #[command(flatten)]
+        agent: AgentOverrides,
+    },
+    /// Run the last failed phase of a feature again
+    Retry {
+        /// Feature ID, slug or directory name
+        feature: String,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Log periodic status lines instead of a spinner
+        #[arg(long)]
+        no_progress: bool,
+        /// Stream the agent's output while the phase runs
+        #[arg(short, long)]
+        verbose: bool,
+        /// Don't tell the agent why the previous attempt failed
+        #[arg(long)]
+        no_feedback: bool,
+        #[command(flatten)]
+        agent: AgentOverrides,
+    },
+    /// Reset a feature to the commit of a phase and continue from there
+    Rollback {
+        /// Feature ID, slug or directory name
+        feature: String,
+        /// Last phase to keep
+        phase: String,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// List features
+    List {
+        /// Include archived features
+        #[arg(long)]
+        all: bool,
+    },
+    /// Move a feature to .gba/archive and remove its worktree
+    Archive {
+        /// Feature ID, slug or directory name
+        feature: String,
+        /// Archive even if the feature is in progress
+        #[arg(long)]
+        force: bool,
+    },
+    /// Bundle a feature's state, specs and docs into one JSON file
+    Export {
+        /// Feature ID, slug or directory name
+        feature: String,
+        /// Output file (default: <id>_<slug>.gba-export.json)
+        #[arg(long)]
+        out: Option<PathBuf>,
+    },
+    /// Recreate a feature from a bundle written by `gba export`
+    Import {
+        /// Bundle file
+        path: PathBuf,
+        /// Replace an existing feature with the same slug
+        #[arg(long)]
+        force: bool,
+    },
+    /// Restore an archived feature
+    Unarchive {
+        /// Feature ID, slug or directory name
+        feature: String,
+    },
+    /// Delete a feature, its worktree and optionally its branch
+    Delete {
+        /// Feature ID, slug or directory name
+        feature: String,
+        /// Don't ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+        /// Delete even if the feature is in progress
+        #[arg(long)]
+        force: bool,
+        /// Also delete the feature branch
+        #[arg(long)]
+        delete_branch: bool,
+    },
+    /// Check config, templates and feature state for problems
+    Validate {
+        /// Also start every configured stdio MCP server briefly
+        #[arg(long)]
+        mcp: bool,
+    },
+    /// Show the execution status of a feature
+    Status {
+        /// Feature ID, slug or directory name (e.g. 0001, user-auth)
+        feature: String,
+        /// Print the chronological event log instead
+        #[arg(long, conflicts_with = "watch")]
+        events: bool,
+        /// Redraw the status whenever state.yml changes, until Ctrl-C
+        #[arg(short, long)]
+        watch: bool,
+        /// Seconds between checks with --watch
+        #[arg(long, default_value_t = 2, value_name = "SECS", requires = "watch")]
+        interval: u64,
+        /// Stop watching once the feature is completed or failed
+        #[arg(long, requires = "watch")]
+        until_done: bool,
+    },
+    /// Show the per-phase history of a feature run
+    Log {
+        /// Feature ID, slug or directory name (e.g. 0001, user-auth)
+        feature: String,
+        /// Print the phases as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show spend per feature, or per phase of one feature
+    Cost {
+        /// Feature ID, slug or directory name; all features if omitted
+        feature: Option<String>,
+        /// Print as JSON
+        #[arg(long, conflicts_with = "csv")]
+        json: bool,
+        /// Print as CSV
+        #[arg(long)]
+        csv: bool,
+        /// Only include features updated on or after this date (YYYY-MM-DD or RFC 3339)
+        #[arg(long)]
+        since: Option<String>,
+        /// Group spend by the model each phase ran with
+        #[arg(long)]
+        by_model: bool,
+    },
+    /// Report spend aggregated across all features
+    Report {
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Inspect the configuration
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+    /// Manage the API key stored in the system keychain
+    Auth {
+        #[command(subcommand)]
+        command: AuthCommand,
+    },
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate completions for
+        shell: clap_complete::Shell,
+    },
+    /// List feature names for shell completion
+    #[command(name = "__complete-features", hide = true)]
+    CompleteFeatures,
+    /// Send a sample notification to check the notifications settings
+    #[command(name = "notify-test", hide = true)]
+    NotifyTest,
+    /// List phase names for shell completion
+    #[command(name = "__complete-phases", hide = true)]
+    CompletePhases,
+}
+
+/// Agent settings overriding the config files for one invocation
+#[derive(Args, Debug, Default)]
+struct AgentOverrides {
+    /// Maximum agent turns per phase
+    #[arg(long)]
+    max_turns: Option<u32>,
+    /// Response timeout per phase, in seconds
+    #[arg(long, value_name = "SECONDS")]
+    timeout: Option<u64>,
+    /// Tool permission mode: plan, default, acceptEdits or bypassPermissions
+    #[arg(long, value_parser = parse_permission_mode)]
+    permission_mode: Option<ConfigPermissionMode>,
+}
+
+fn parse_permission_mode(s: &str) -> Result<ConfigPermissionMode, String> {
+    s.parse().map_err(|e: gba_core::CoreError| e.to_string())
+}
+
+#[derive(Subcommand)]
+enum TemplatesCommand {
+    /// Print the rendered prompts of a phase and their estimated tokens
+    Render {
+        /// Phase whose templates to render
+        phase: String,
+        /// Feature to render them for (default: an example feature)
+        #[arg(long)]
+        feature: Option<String>,
+        /// Fail on variables the templates require or use but aren't set
+        #[arg(long)]
+        strict: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Show the effective configuration and where each value comes from
+    Show,
+    /// Print the effective value of a key (e.g. agent.model)
+    Get {
+        /// Dotted configuration key
+        key: String,
+    },
+    /// Set a key in .gba/config.yml (e.g. agent.maxTurns 80)
+    Set {
+        /// Dotted configuration key
+        key: String,
+        /// New value
+        value: String,
+        /// Write the global config file instead
+        #[arg(long)]
+        global: bool,
+    },
+    /// Check the config files for unknown keys and invalid values
+    Validate,
+}
+
+#[derive(Subcommand)]
+enum AuthCommand {
+    /// Store an API key (prompted for, or read from stdin) in the keychain
+    Set,
+    /// Show where the API key is read from
+    Status,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() -> ExitCode {
     let cli = Cli::parse();
+    let json_errors = cli.format == "json";
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            if json_errors {
+                println!("{}", exit::render_json(&e));
+            } else {
+                eprintln!("Error: {e:?}");
+            }
+            ExitCode::from(exit::code(&e))
+        }
+    }
+}
 
-    // Get API key from args or environment
-    let api_key = cli.api_key.unwrap_or_else(|| {
-        std::env::var("ANTHROPIC_API_KEY")
-            .expect("ANTHROPIC_API_KEY must be set either via --api-key or environment variable")
-    });
-
-    // Create core engine config
-    let config = gba_core::Config {
-        repo_path: cli.repo,
-        api_key,
-        model: cli.model,
-    };
-
-    let engine = gba_core::Engine::new(config);
+async fn run(cli: Cli) -> Result<()> {
+    ui::output::set_quiet(cli.quiet);
+    ui::style::set_color(ui::style::color_wanted(
+        std::env::var("NO_COLOR").ok().as_deref(),
+        std::io::IsTerminal::is_terminal(&std::io::stdout()),
+    ));
+    tracing_subscriber::fmt()
+        .with_max_level(cli.log_level)
+        .with_writer(std::io::stderr)
+        .init();
+    let creates = matches!(cli.command, Commands::Init { .. } | Commands::Plan { .. });
+    let repo = repo::resolve(cli.repo, creates)?;
+    let gba_path = repo.join(GBA_DIR);
 
     match cli.command {
         Commands::Execute { prompt } => {
-            println!("Executing prompt: {}", prompt);
-            let result = engine.execute(&prompt).await?;
-            println!("Result: {}", result);
+            let agent = AgentOverrides::default();
+            let config = engine_config(repo.clone(), &gba_path, cli.api_key, cli.model, &agent)?;
+            let engine = gba_core::Engine::new(config);
+            commands::execute::run(&engine, &prompt, &mut std::io::stdout()).await?;
         }
-        Commands::Tui => {
-            println!("Starting TUI mode...");
+        Commands::Tui {
+            watch: true,
+            interval,
+            ..
+        } => ui::dashboard::run(&gba_path, std::time::Duration::from_secs(interval.max(1)))?,
+        Commands::Tui { agent, .. } => {
+            let config = engine_config(repo.clone(), &gba_path, cli.api_key, cli.model, &agent)?;
+            let engine = gba_core::Engine::new(config);
+            ui::output::say("Starting TUI mode...");
             ui::run_tui(engine).await?;
         }
-        Commands::Templates => {
-            let pm = gba_pm::PromptManager::new();
-            println!("Available templates:");
-            for template in pm.list_templates() {
-                println!("  - {}", template);
+        Commands::Templates { command } => match command {
+            None => commands::templates::run(&gba_path)?,
+            Some(TemplatesCommand::Render {
+                phase,
+                feature,
+                strict,
+            }) => commands::templates::render_phase(
+                &repo,
+                &gba_path,
+                &phase,
+                feature.as_deref(),
+                strict,
+            )?,
+        },
+        Commands::Init {
+            force,
+            no_templates,
+        } => {
+            let options = commands::init::InitOptions {
+                force,
+                no_templates,
+            };
+            commands::init::run(&repo, &gba_path, options)?;
+        }
+        Commands::Plan {
+            slug,
+            description,
+            from_file,
+            interactive,
+        } => {
+            let options = commands::plan::PlanOptions {
+                description,
+                from_file,
+                interactive,
+            };
+            commands::plan::run(&gba_path, &slug, &options)?;
+        }
+        Commands::Show { feature, document } => {
+            commands::show::run(&gba_path, &feature, document.as_deref())?;
+        }
+        Commands::Edit {
+            feature,
+            document,
+            file,
+        } => {
+            commands::edit::run(&gba_path, &feature, document.or(file).as_deref())?;
+        }
+        Commands::Diff {
+            feature,
+            phase,
+            phase_flag,
+            stat,
+        } => {
+            let phase = phase.or(phase_flag);
+            commands::diff::run(&repo, &gba_path, &feature, phase.as_deref(), stat)?;
+        }
+        Commands::Run {
+            feature,
+            yes,
+            no_progress,
+            verbose,
+            dry_run,
+            skip_phase,
+            only,
+            json,
+            tui,
+            allow_dirty,
+            force,
+            force_observe,
+            record,
+            replay,
+            replay_delay,
+            agent,
+        } => {
+            // Dry runs and replays never reach the API, so they need no key.
+            let offline = dry_run || replay.is_some();
+            let api_key = cli.api_key.or_else(|| offline.then(String::new));
+            let config = engine_config(repo.clone(), &gba_path, api_key, cli.model, &agent)?;
+            let options = commands::run::RunOptions {
+                yes,
+                no_progress,
+                verbose,
+                dry_run,
+                skip: skip_phase,
+                only: only.is_some(),
+                phase: only,
+                json,
+                allow_dirty,
+                force,
+                force_observe,
+                record,
+                replay,
+                replay_delay: std::time::Duration::from_millis(replay_delay),
+                ..Default::default()
+            };
+            if tui {
+                let (events, receiver) = tokio::sync::mpsc::unbounded_channel();
+                let (interrupt, interrupted) = tokio::sync::oneshot::channel();
+                let options = commands::run::RunOptions {
+                    events: Some(events),
+                    ..options
+                };
+                let run = commands::run::run_with_interrupt(
+                    &gba_path,
+                    &feature,
+                    config,
+                    options,
+                    async {
+                        let _ = interrupted.await;
+                    },
+                );
+                ui::run_view::run(receiver, interrupt, run).await?;
+            } else {
+                commands::run::run(&gba_path, &feature, config, options).await?;
             }
         }
+        Commands::RunAll {
+            status,
+            max,
+            continue_on_error,
+            budget,
+            parallel,
+            yes,
+            no_progress,
+            verbose,
+            allow_dirty,
+            force,
+            agent,
+        } => {
+            let config = engine_config(repo.clone(), &gba_path, cli.api_key, cli.model, &agent)?;
+            let options = commands::run_all::RunAllOptions {
+                status: commands::run_all::parse_status(&status)?,
+                max,
+                continue_on_error,
+                budget,
+                parallel,
+                run: commands::run::RunOptions {
+                    yes,
+                    no_progress,
+                    verbose,
+                    allow_dirty,
+                    force,
+                    ..Default::default()
+                },
+            };
+            commands::run_all::run(&gba_path, config, options).await?;
+        }
+        Commands::Retry {
+            feature,
+            yes,
+            no_progress,
+            verbose,
+            no_feedback,
+            agent,
+        } => {
+            let config = engine_config(repo.clone(), &gba_path, cli.api_key, cli.model, &agent)?;
+            let options = commands::run::RunOptions {
+                yes,
+                no_progress,
+                verbose,
+                ..Default::default()
+            };
+            commands::retry::run(&gba_path, &feature, config, options, !no_feedback).await?;
+        }
+        Commands::Rollback {
+            feature,
+            phase,
+            yes,
+        } => commands::rollback::run(&repo, &gba_path, &feature, &phase, yes)?,
+        Commands::List { all } => commands::list::run(&gba_path, all)?,
+        Commands::Archive { feature, force } => {
+            commands::archive::run(&repo, &gba_path, &feature, force)?;
+        }
+        Commands::Export { feature, out } => {
+            commands::export::run(&gba_path, &feature, out.as_deref())?;
+        }
+        Commands::Import { path, force } => {
+            commands::export::run_import(&gba_path, &path, force)?;
+        }
+        Commands::Unarchive { feature } => commands::archive::run_unarchive(&gba_path, &feature)?,
+        Commands::Delete {
+            feature,
+            yes,
+            force,
+            delete_branch,
+        } => {
+            let options = commands::delete::DeleteOptions {
+                yes,
+                force,
+                delete_branch,
+            };
+            commands::delete::run(&repo, &gba_path, &feature, options)?;
+        }
+        Commands::Validate { mcp } => commands::validate::run(&gba_path, &repo, mcp).await?,
+        Commands::Status {
+            feature,
+            events,
+            watch,
+            interval,
+            until_done,
+        } => {
+            if watch {
+                let options = commands::status::WatchOptions {
+                    interval: std::time::Duration::from_secs(interval.max(1)),
+                    until_done,
+                };
+                commands::status::watch(&gba_path, &feature, options).await?;
+            } else {
+                commands::status::run(&gba_path, &feature, events)?;
+            }
+        }
+        Commands::Log { feature, json } => commands::log::run(&gba_path, &feature, json)?,
+        Commands::Report { json } => commands::report::run(&gba_path, json)?,
+        Commands::Config { command } => match command {
+            ConfigCommand::Show => commands::config::show(&config_loader(
+                &gba_path,
+                cli.model,
+                &AgentOverrides::default(),
+            ))?,
+            ConfigCommand::Get { key } => {
+                commands::config::get(
+                    &config_loader(&gba_path, cli.model, &AgentOverrides::default()),
+                    &key,
+                )?;
+            }
+            ConfigCommand::Set { key, value, global } => {
+                commands::config::set(&gba_path, &key, &value, global)?;
+            }
+            ConfigCommand::Validate => commands::config::validate(&config_loader(
+                &gba_path,
+                None,
+                &AgentOverrides::default(),
+            ))?,
+        },
+        Commands::Auth { command } => {
+            let agent = config_loader(&gba_path, None, &AgentOverrides::default())
+                .load()?
+                .config
+                .agent;
+            match command {
+                AuthCommand::Set => {
+                    commands::auth::set(&gba_core::RealCommandRunner, &gba_path, &agent)?;
+                }
+                AuthCommand::Status => commands::auth::status(
+                    &gba_core::RealCommandRunner,
+                    &gba_path,
+                    &agent,
+                    cli.api_key.as_deref(),
+                )?,
+            }
+        }
+        Commands::Completions { shell } => commands::completions::run(shell, Cli::command())?,
+        Commands::NotifyTest => commands::notify::test(&gba_path)?,
+        Commands::CompleteFeatures => {
+            let names = commands::completions::feature_names(&gba_path);
+            print!("{}", commands::completions::lines(&names));
+        }
+        Commands::CompletePhases => {
+            let names = commands::completions::phase_names(&config_loader(
+                &gba_path,
+                None,
+                &AgentOverrides::default(),
+            ));
+            print!("{}", commands::completions::lines(&names));
+        }
+        Commands::Cost {
+            feature,
+            json,
+            csv,
+            since,
+            by_model,
+        } => {
+            let format = commands::cost::OutputFormat::from_flags(json, csv);
+            commands::cost::run(
+                &gba_path,
+                feature.as_deref(),
+                format,
+                since.as_deref(),
+                by_model,
+            )?;
+        }
     }
 
     Ok(())
 }
+
+/// Configuration layers: global file, `.gba/config.yml`, then CLI flags
+fn config_loader(
+    gba_path: &Path,
+    model: Option<String>,
+    agent: &AgentOverrides,
+) -> gba_core::ConfigLoader {
+    let mut loader = gba_core::ConfigLoader::new(gba_path);
+    if let Some(model) = model {
+        loader = loader.with_override("agent.model", model);
+    }
+    if let Some(max_turns) = agent.max_turns {
+        loader = loader.with_override("agent.maxTurns", max_turns);
+    }
+    if let Some(timeout) = agent.timeout {
+        loader = loader.with_override("agent.timeoutSeconds", timeout);
+    }
+    if let Some(mode) = agent.permission_mode {
+        loader = loader.with_override("agent.permissionMode", mode.to_string());
+    }
+    loader
+}
+
+/// Engine configuration for commands that talk to the agent
+fn engine_config(
+    repo_path: PathBuf,
+    gba_path: &Path,
+    api_key: Option<String>,
+    model: Option<String>,
+    agent: &AgentOverrides,
+) -> Result<gba_core::Config> {
+    let project = config_loader(gba_path, model, agent).load()?.config;
+    let api_key = match api_key {
+        // An empty key is how a dry run says it needs none.
+        Some(key) if key.is_empty() => key,
+        explicit => gba_core::auth::resolve_api_key(
+            &gba_core::RealCommandRunner,
+            &project.agent,
+            gba_path,
+            explicit.as_deref(),
+        )?,
+    };
+
+    gba_core::validate_model(&project.agent.model)?;
+    Ok(gba_core::Config::builder()
+        .repo_path(repo_path)
+        .api_key(api_key)
+        .model(project.agent.model)
+        .permission_mode(project.agent.permission_mode)
+        .max_turns(project.agent.max_turns)
+        .timeout_seconds(project.agent.timeout_seconds)
+        .max_output_bytes(Some(project.agent.max_output_bytes))
+        .stop_on_output_limit(project.agent.stop_on_output_limit)
+        .rate_limit(project.rate_limit)
+        .permissions(project.permissions)
+        .env(project.agent.env)
+        .env_deny(project.agent.env_deny)
+        .build())
+}
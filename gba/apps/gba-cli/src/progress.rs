@@ -0,0 +1,234 @@
+//! Live progress display for `gba run`.
+//!
+//! On a terminal each phase gets a spinner line showing elapsed time, turns
+//! and cost; otherwise (or with `--no-progress`) a plain status line is logged
+//! periodically. Verbose agent text is printed above the spinner line by line
+//! so the two never interleave. With `--json` the agent text is written as
+//! `assistant_text` events instead and nothing else is printed; with
+//! `--tui` those events are sent to the full-screen view.
+
+use std::io::IsTerminal;
+use std::time::{Duration, Instant};
+
+use chrono::TimeDelta;
+use gba_core::{ExecutionResult, PhaseStatus, ProgressEvent, RunEvent, RunEventSender};
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::commands::log::format_duration;
+use crate::ui::output::say;
+
+/// How often the display is refreshed
+pub const TICK: Duration = Duration::from_secs(1);
+
+/// Interval between status lines in plain mode
+const PLAIN_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Whether the spinner can be drawn (stdout is a terminal)
+pub fn spinner_supported() -> bool {
+    std::io::stdout().is_terminal()
+}
+
+enum Display {
+    Spinner(ProgressBar),
+    Plain { last_log: Instant },
+    Json,
+    Channel(RunEventSender),
+}
+
+/// Progress of a single running phase
+pub struct PhaseProgress {
+    name: String,
+    started: Instant,
+    turns: u32,
+    /// Cost of the feature before this phase started
+    cost_usd: f64,
+    verbose: bool,
+    /// Verbose text not yet terminated by a newline
+    line: String,
+    display: Display,
+}
+
+impl PhaseProgress {
+    /// Start displaying progress for phase `name`
+    pub fn new(name: &str, cost_usd: f64, spinner: bool, verbose: bool) -> Self {
+        let started = Instant::now();
+        let display = if spinner {
+            let bar = ProgressBar::new_spinner();
+            bar.set_style(
+                ProgressStyle::with_template("  {spinner} {msg}").expect("valid progress template"),
+            );
+            bar.enable_steady_tick(Duration::from_millis(100));
+            Display::Spinner(bar)
+        } else {
+            Display::Plain { last_log: started }
+        };
+        let progress = Self {
+            name: name.to_string(),
+            started,
+            turns: 0,
+            cost_usd,
+            verbose,
+            line: String::new(),
+            display,
+        };
+        progress.refresh();
+        progress
+    }
+
+    /// Report progress of phase `name` as JSON events on stdout
+    pub fn json(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            started: Instant::now(),
+            turns: 0,
+            cost_usd: 0.0,
+            verbose: false,
+            line: String::new(),
+            display: Display::Json,
+        }
+    }
+
+    /// Send the progress of phase `name` as events through `events`
+    pub fn channel(name: &str, events: RunEventSender) -> Self {
+        Self {
+            display: Display::Channel(events),
+            ..Self::json(name)
+        }
+    }
+
+    /// Apply an event streamed from the engine
+    pub fn event(&mut self, event: ProgressEvent) {
+        match event {
+            ProgressEvent::Text(text)
+                if matches!(self.display, Display::Json | Display::Channel(_)) =>
+            {
+                let event = RunEvent::AssistantText {
+                    phase: self.name.clone(),
+                    text,
+                };
+                match &self.display {
+                    Display::Channel(events) => {
+                        let _ = events.send(event);
+                    }
+                    _ => println!("{}", event.to_json_line()),
+                }
+            }
+            ProgressEvent::Turn(turns) => {
+                self.turns = turns;
+                self.refresh();
+            }
+            ProgressEvent::Text(text) if self.verbose => {
+                self.line.push_str(&text);
+                while let Some(pos) = self.line.find('\n') {
+                    let line: String = self.line.drain(..=pos).collect();
+                    self.print(&gba_core::sanitize(line.trim_end_matches('\n')));
+                }
+            }
+            ProgressEvent::Text(_) => {}
+            ProgressEvent::ToolUse { name, target } if self.verbose => {
+                let line = match target {
+                    Some(target) => format!("→ {name} {target}"),
+                    None => format!("→ {name}"),
+                };
+                self.flush();
+                self.print(&gba_core::sanitize(&line));
+            }
+            ProgressEvent::ToolUse { .. } => {}
+        }
+    }
+
+    /// Periodic refresh; logs a status line in plain mode every so often
+    pub fn tick(&mut self) {
+        match &mut self.display {
+            Display::Spinner(_) => self.refresh(),
+            Display::Plain { last_log } if last_log.elapsed() >= PLAIN_INTERVAL => {
+                *last_log = Instant::now();
+                say(format_args!("  {}", self.status()));
+            }
+            Display::Plain { .. } | Display::Json | Display::Channel(_) => {}
+        }
+    }
+
+    /// Replace the progress line with the final result of the phase
+    pub fn finish(mut self, result: &ExecutionResult) {
+        self.flush();
+        match &self.display {
+            Display::Spinner(bar) => bar.finish_and_clear(),
+            Display::Plain { .. } => {}
+            Display::Json | Display::Channel(_) => return,
+        }
+        say(format_args!(
+            "  {} {} ({}, {} turns, ${:.2})",
+            PhaseStatus::Completed.icon(),
+            self.name,
+            format_duration(TimeDelta::from_std(result.duration).unwrap_or_default()),
+            result.stats.turns,
+            result.stats.cost_usd
+        ));
+    }
+
+    /// Remove the progress line without a final message
+    pub fn abandon(mut self) {
+        self.flush();
+        if let Display::Spinner(bar) = &self.display {
+            bar.finish_and_clear();
+        }
+    }
+
+    fn status(&self) -> String {
+        status_line(
+            &self.name,
+            self.started.elapsed(),
+            self.turns,
+            self.cost_usd,
+        )
+    }
+
+    fn refresh(&self) {
+        if let Display::Spinner(bar) = &self.display {
+            bar.set_message(self.status());
+        }
+    }
+
+    fn print(&self, line: &str) {
+        match &self.display {
+            Display::Spinner(bar) => bar.println(line),
+            Display::Plain { .. } => say(line),
+            Display::Json | Display::Channel(_) => {}
+        }
+    }
+
+    fn flush(&mut self) {
+        if !self.line.is_empty() {
+            let line = std::mem::take(&mut self.line);
+            self.print(&line);
+        }
+    }
+}
+
+/// Status text shown while a phase runs
+pub fn status_line(name: &str, elapsed: Duration, turns: u32, cost_usd: f64) -> String {
+    let elapsed = format_duration(TimeDelta::from_std(elapsed).unwrap_or_default());
+    let turns = match turns {
+        1 => "1 turn".to_string(),
+        n => format!("{n} turns"),
+    };
+    format!("{name}: {elapsed}, {turns}, ${cost_usd:.2} so far")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_line() {
+        assert_eq!(
+            status_line("build", Duration::from_secs(95), 3, 1.234),
+            "build: 1m35s, 3 turns, $1.23 so far"
+        );
+        assert_eq!(
+            status_line("pr", Duration::from_millis(400), 1, 0.0),
+            "pr: 0s, 1 turn, $0.00 so far"
+        );
+    }
+}
@@ -0,0 +1,107 @@
+//! Locating the repository to work on when `--repo` isn't given.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use gba_core::{CONFIG_FILE, FEATURES_DIR};
+
+use crate::GBA_DIR;
+use crate::commands::{confirm, is_interactive};
+
+/// Nearest directory at or above `start` holding a project `.gba`, else the
+/// nearest holding `.git`.
+///
+/// `.gba` wins over a closer `.git` so that running inside a worktree under
+/// `.trees/` still finds the repository the features belong to. Only a
+/// `.gba` with a config file or features counts: `~/.gba`, which holds
+/// global prompts, doesn't make the home directory a project.
+pub fn discover_root(start: &Path) -> Option<PathBuf> {
+    let is_project = |dir: &Path| {
+        let gba = dir.join(GBA_DIR);
+        gba.join(CONFIG_FILE).is_file() || gba.join(FEATURES_DIR).is_dir()
+    };
+    start
+        .ancestors()
+        .find(|dir| is_project(dir))
+        .or_else(|| start.ancestors().find(|dir| dir.join(".git").exists()))
+        .map(Path::to_path_buf)
+}
+
+/// Repository path: `explicit` if given, otherwise the root discovered from
+/// the current directory.
+///
+/// Commands that `create` files (init, plan) stay in the current directory
+/// unless the user confirms the discovered root.
+pub fn resolve(explicit: Option<PathBuf>, create: bool) -> Result<PathBuf> {
+    if let Some(repo) = explicit {
+        return Ok(repo);
+    }
+    let cwd = std::env::current_dir()?;
+    let Some(root) = discover_root(&cwd) else {
+        return Ok(PathBuf::from("."));
+    };
+    tracing::debug!(root = %root.display(), "discovered repository root");
+    if root == cwd {
+        return Ok(PathBuf::from("."));
+    }
+    if create {
+        let prompt = format!(
+            "Use repository root {} instead of the current directory? [y/N] ",
+            root.display()
+        );
+        if !is_interactive() || !confirm(&prompt)? {
+            return Ok(PathBuf::from("."));
+        }
+    }
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_discover_root_walks_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        let nested = repo.join("src").join("deep");
+        std::fs::create_dir_all(&nested).unwrap();
+
+        std::fs::create_dir(repo.join(".git")).unwrap();
+        assert_eq!(discover_root(&nested), Some(repo.clone()));
+        assert_eq!(discover_root(&repo), Some(repo.clone()));
+
+        // A worktree's `.git` file is closer, but `.gba` decides.
+        let worktree = repo.join(".trees").join("0001_auth");
+        std::fs::create_dir_all(worktree.join("src")).unwrap();
+        std::fs::write(
+            worktree.join(".git"),
+            "gitdir: ../../.git/worktrees/0001_auth",
+        )
+        .unwrap();
+        assert_eq!(discover_root(&worktree.join("src")), Some(worktree.clone()));
+        std::fs::create_dir_all(repo.join(GBA_DIR).join(FEATURES_DIR)).unwrap();
+        assert_eq!(discover_root(&worktree.join("src")), Some(repo.clone()));
+    }
+
+    #[test]
+    fn test_global_gba_dir_is_not_a_project() {
+        let home = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(home.path().join(GBA_DIR).join("prompts")).unwrap();
+        let repo = home.path().join("code").join("app");
+        std::fs::create_dir_all(repo.join(".git")).unwrap();
+        std::fs::create_dir(repo.join("src")).unwrap();
+
+        assert_eq!(discover_root(&repo.join("src")), Some(repo.clone()));
+
+        std::fs::create_dir(repo.join(GBA_DIR)).unwrap();
+        std::fs::write(repo.join(GBA_DIR).join(CONFIG_FILE), "").unwrap();
+        assert_eq!(discover_root(&repo.join("src")), Some(repo));
+    }
+
+    #[test]
+    fn test_explicit_repo_wins() {
+        let repo = resolve(Some(PathBuf::from("/tmp/elsewhere")), true).unwrap();
+        assert_eq!(repo, PathBuf::from("/tmp/elsewhere"));
+    }
+}
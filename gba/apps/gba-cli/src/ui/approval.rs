@@ -0,0 +1,85 @@
+//! Asking the user on the terminal about tool uses the `permissions`
+//! policy doesn't allow by itself.
+
+use std::fmt::Write as _;
+use std::io::{BufRead, Write};
+
+use futures::future::BoxFuture;
+use gba_core::permissions::{ToolApprover, ToolUse};
+use tokio::sync::Mutex;
+
+/// Most lines of tool input shown in a question
+const INPUT_LINES: usize = 20;
+
+/// Asks about each tool use on stdin/stdout, one question at a time
+#[derive(Debug, Default)]
+pub struct TerminalApprover {
+    asking: Mutex<()>,
+}
+
+impl ToolApprover for TerminalApprover {
+    fn approve<'a>(&'a self, tool_use: &'a ToolUse) -> BoxFuture<'a, bool> {
+        Box::pin(async move {
+            let _asking = self.asking.lock().await;
+            let question = describe(tool_use);
+            let answer = tokio::task::spawn_blocking(move || -> std::io::Result<String> {
+                print!("{question}");
+                std::io::stdout().flush()?;
+                let mut answer = String::new();
+                std::io::stdin().lock().read_line(&mut answer)?;
+                Ok(answer)
+            })
+            .await;
+            matches!(answer, Ok(Ok(answer)) if matches!(answer.trim(), "y" | "Y" | "yes"))
+        })
+    }
+}
+
+/// The question asked about `tool_use`, showing its input
+fn describe(tool_use: &ToolUse) -> String {
+    let input = match tool_use.input.get("command").and_then(|c| c.as_str()) {
+        Some(command) => command.to_string(),
+        None => serde_json::to_string_pretty(&tool_use.input).unwrap_or_default(),
+    };
+    let mut out = format!("\nThe agent wants to use {}:\n", tool_use.tool);
+    let lines: Vec<&str> = input.lines().collect();
+    for line in lines.iter().take(INPUT_LINES) {
+        let _ = writeln!(out, "  {line}");
+    }
+    if lines.len() > INPUT_LINES {
+        let _ = writeln!(out, "  ... ({} more lines)", lines.len() - INPUT_LINES);
+    }
+    out.push_str("Allow? [y/N] ");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_question_shows_tool_input() {
+        let bash = ToolUse {
+            tool: "Bash".to_string(),
+            input: serde_json::json!({"command": "rm -rf target", "description": "clean"}),
+        };
+        assert_eq!(
+            describe(&bash),
+            "\nThe agent wants to use Bash:\n  rm -rf target\nAllow? [y/N] "
+        );
+
+        let write = ToolUse {
+            tool: "Write".to_string(),
+            input: serde_json::json!({"file_path": "a.txt", "content": "x\n".repeat(30)}),
+        };
+        let question = describe(&write);
+        assert!(question.contains("  \"file_path\": \"a.txt\""));
+        assert!(question.ends_with("Allow? [y/N] "));
+
+        let script = ToolUse {
+            tool: "Bash".to_string(),
+            input: serde_json::json!({"command": "echo\n".repeat(25)}),
+        };
+        assert!(describe(&script).contains("  echo\n  ... (5 more lines)\nAllow?"));
+    }
+}
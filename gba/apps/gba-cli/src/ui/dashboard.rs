@@ -0,0 +1,342 @@
+//! Live feature dashboard (`gba tui --watch`).
+//!
+//! Every feature under `.gba/features` is listed in a table that is reloaded
+//! from `state.yml` on a timer, so it follows a `gba run` in another
+//! terminal. The selected feature's phases are shown below the table.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEventKind},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use gba_core::{FeatureState, FeatureStatus, PhaseStatus};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState},
+};
+
+use crate::commands::log::format_duration;
+
+/// A feature directory and its last successfully read state
+#[derive(Debug, Clone)]
+pub struct FeatureRow {
+    /// Directory name under `.gba/features`
+    pub name: String,
+    /// Last state read; `None` if it has never been readable
+    pub state: Option<FeatureState>,
+    /// Why the latest read failed, e.g. because the file was mid-rewrite
+    pub error: Option<String>,
+}
+
+/// Features shown by the dashboard and the selected one
+#[derive(Debug)]
+pub struct Dashboard {
+    gba_path: PathBuf,
+    /// Features sorted by ID
+    pub rows: Vec<FeatureRow>,
+    /// Index of the selected row
+    pub selected: usize,
+}
+
+impl Dashboard {
+    /// Dashboard of the features in `gba_path`, loaded once
+    pub fn new(gba_path: &Path) -> Self {
+        let mut dashboard = Self {
+            gba_path: gba_path.to_path_buf(),
+            rows: Vec::new(),
+            selected: 0,
+        };
+        dashboard.refresh();
+        dashboard
+    }
+
+    /// Reload every feature.
+    ///
+    /// A feature that can't be read keeps its previous state (marked with
+    /// the error) so a file caught mid-rewrite doesn't blank its row. The
+    /// selection follows the selected feature as rows come and go.
+    pub fn refresh(&mut self) {
+        let selected = self.selected_row().map(|row| row.name.clone());
+        let listing = match FeatureState::list_all(&self.gba_path) {
+            Ok(listing) => listing,
+            Err(e) => {
+                for row in &mut self.rows {
+                    row.error = Some(e.to_string());
+                }
+                return;
+            }
+        };
+
+        let mut rows: Vec<FeatureRow> = listing
+            .loaded
+            .into_iter()
+            .map(|(name, state)| FeatureRow {
+                name,
+                state: Some(state),
+                error: None,
+            })
+            .collect();
+        for (name, e) in listing.failed {
+            let previous = self.rows.iter().find(|row| row.name == name);
+            rows.push(FeatureRow {
+                state: previous.and_then(|row| row.state.clone()),
+                name,
+                error: Some(e.to_string()),
+            });
+        }
+        rows.sort_by(|a, b| a.name.cmp(&b.name));
+
+        self.rows = rows;
+        self.selected = selected
+            .and_then(|name| self.rows.iter().position(|row| row.name == name))
+            .unwrap_or(self.selected)
+            .min(self.rows.len().saturating_sub(1));
+    }
+
+    /// Currently selected feature, if there is any
+    pub fn selected_row(&self) -> Option<&FeatureRow> {
+        self.rows.get(self.selected)
+    }
+
+    /// Move the selection down, stopping at the last row
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.rows.len() {
+            self.selected += 1;
+        }
+    }
+
+    /// Move the selection up, stopping at the first row
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+}
+
+/// Run the dashboard until `q` or Esc, reloading every `interval`
+pub fn run(gba_path: &Path, interval: Duration) -> Result<()> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    let res = run_loop(&mut terminal, gba_path, interval);
+
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    res
+}
+
+fn run_loop<B: ratatui::backend::Backend>(
+    terminal: &mut Terminal<B>,
+    gba_path: &Path,
+    interval: Duration,
+) -> Result<()> {
+    let mut dashboard = Dashboard::new(gba_path);
+    let mut last_refresh = Instant::now();
+    loop {
+        terminal.draw(|f| draw(f, &dashboard))?;
+
+        let timeout = interval.saturating_sub(last_refresh.elapsed());
+        if event::poll(timeout)?
+            && let Event::Key(key) = event::read()?
+            && key.kind == KeyEventKind::Press
+        {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => dashboard.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => dashboard.select_previous(),
+                _ => {}
+            }
+        }
+        if last_refresh.elapsed() >= interval {
+            dashboard.refresh();
+            last_refresh = Instant::now();
+        }
+    }
+}
+
+fn draw(f: &mut Frame, dashboard: &Dashboard) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(50), Constraint::Min(5)])
+        .split(f.area());
+
+    let rows = dashboard.rows.iter().map(|row| match &row.state {
+        Some(state) => {
+            let phase = state
+                .phases
+                .get(state.current_phase)
+                .map_or("-", |p| p.name.as_str());
+            let status = match row.error {
+                Some(_) => format!("{} (stale)", state.status),
+                None => state.status.to_string(),
+            };
+            Row::new(vec![
+                Line::from(state.feature.id.clone()),
+                Line::from(state.feature.slug.clone()),
+                Line::from(Span::styled(status, feature_color(state.status))),
+                Line::from(phase.to_string()),
+                Line::from(format!("${:.2}", state.total_stats.cost_usd)),
+            ])
+        }
+        None => Row::new(vec![
+            Line::from("?"),
+            Line::from(row.name.clone()),
+            Line::from(Span::styled("unreadable", Style::default().fg(Color::Red))),
+            Line::from("-"),
+            Line::from("-"),
+        ]),
+    });
+    let widths = [
+        Constraint::Length(6),
+        Constraint::Min(20),
+        Constraint::Length(20),
+        Constraint::Length(14),
+        Constraint::Length(9),
+    ];
+    let table = Table::new(rows, widths)
+        .header(
+            Row::new(["ID", "SLUG", "STATUS", "PHASE", "COST"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Features (↑/↓ select, q to quit)"),
+        );
+    let mut table_state = TableState::default().with_selected(Some(dashboard.selected));
+    f.render_stateful_widget(table, chunks[0], &mut table_state);
+
+    let (title, lines) = match dashboard.selected_row() {
+        Some(row) => (row.name.clone(), detail_lines(row)),
+        None => (
+            "No features".to_string(),
+            vec![Line::from("Run `gba plan <slug>` to plan a feature")],
+        ),
+    };
+    let detail = Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title));
+    f.render_widget(detail, chunks[1]);
+}
+
+/// Phase lines of the detail pane
+fn detail_lines(row: &FeatureRow) -> Vec<Line<'static>> {
+    let mut lines = Vec::new();
+    if let Some(e) = &row.error {
+        lines.push(Line::from(Span::styled(
+            e.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    let Some(state) = &row.state else {
+        return lines;
+    };
+    for phase in &state.phases {
+        let style = phase_color(phase.status);
+        let mut details = Vec::new();
+        details.extend(phase.duration().map(format_duration));
+        if let Some(stats) = phase.stats.as_ref().filter(|s| s.cost_usd > 0.0) {
+            details.push(format!("${:.2}", stats.cost_usd));
+        }
+        lines.push(Line::from(vec![
+            Span::styled(format!("{} ", phase.status.icon()), style),
+            Span::raw(format!("{:<14} ", phase.name)),
+            Span::styled(format!("{:<12}", phase.status.to_string()), style),
+            Span::raw(details.join(", ")),
+        ]));
+    }
+    if let Some(error) = &state.error {
+        lines.push(Line::from(Span::styled(
+            format!("Error: {error}"),
+            Style::default().fg(Color::Red),
+        )));
+    }
+    lines
+}
+
+fn feature_color(status: FeatureStatus) -> Style {
+    match status {
+        FeatureStatus::Completed => Style::default().fg(Color::Green),
+        FeatureStatus::InProgress => Style::default().fg(Color::Yellow),
+        FeatureStatus::Failed => Style::default().fg(Color::Red),
+        FeatureStatus::Planned => Style::default(),
+    }
+}
+
+fn phase_color(status: PhaseStatus) -> Style {
+    match status {
+        PhaseStatus::Completed => Style::default().fg(Color::Green),
+        PhaseStatus::InProgress => Style::default().fg(Color::Yellow),
+        PhaseStatus::Failed => Style::default().fg(Color::Red),
+        PhaseStatus::Pending | PhaseStatus::Skipped => Style::default().add_modifier(Modifier::DIM),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gba_core::{FEATURES_DIR, STATE_FILE};
+
+    fn save(gba_path: &Path, state: &FeatureState) -> PathBuf {
+        let path = gba_path.join(FEATURES_DIR).join(state.dir_name());
+        std::fs::create_dir_all(&path).unwrap();
+        state.save(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_refresh_follows_external_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut auth = FeatureState::new("0001", "auth");
+        let auth_path = save(dir.path(), &auth);
+        save(dir.path(), &FeatureState::new("0002", "search"));
+
+        let mut dashboard = Dashboard::new(dir.path());
+        assert_eq!(dashboard.rows.len(), 2);
+        dashboard.select_next();
+        dashboard.select_next();
+        assert_eq!(dashboard.selected_row().unwrap().name, "0002_search");
+
+        auth.start_phase(0, "observe");
+        auth.save(&auth_path).unwrap();
+        save(dir.path(), &FeatureState::new("0000", "setup"));
+        dashboard.refresh();
+
+        let names: Vec<&str> = dashboard.rows.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["0000_setup", "0001_auth", "0002_search"]);
+        assert_eq!(dashboard.selected_row().unwrap().name, "0002_search");
+        let auth = dashboard.rows[1].state.as_ref().unwrap();
+        assert_eq!(auth.status, FeatureStatus::InProgress);
+        assert_eq!(auth.phases[0].status, PhaseStatus::InProgress);
+    }
+
+    #[test]
+    fn test_unreadable_state_keeps_last_one() {
+        let dir = tempfile::tempdir().unwrap();
+        let auth_path = save(dir.path(), &FeatureState::new("0001", "auth"));
+        let mut dashboard = Dashboard::new(dir.path());
+
+        std::fs::write(auth_path.join(STATE_FILE), "status: [unterminated").unwrap();
+        dashboard.refresh();
+
+        let row = &dashboard.rows[0];
+        assert!(row.error.is_some());
+        assert_eq!(row.state.as_ref().unwrap().feature.slug, "auth");
+
+        std::fs::remove_dir_all(&auth_path).unwrap();
+        dashboard.refresh();
+        assert!(dashboard.rows.is_empty());
+        assert!(dashboard.selected_row().is_none());
+        dashboard.select_next();
+        assert_eq!(dashboard.selected, 0);
+    }
+}
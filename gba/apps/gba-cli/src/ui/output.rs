@@ -0,0 +1,70 @@
+//! User-facing status output that `--quiet` silences.
+//!
+//! Progress chatter ("Created ...", "Phase 2/6: build") goes through
+//! [`say`]; results a command was asked for (tables, JSON) and errors are
+//! printed directly and are never suppressed.
+
+use std::cell::RefCell;
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Quiet flag and buffer of [`capture`], replacing the global state
+    static CAPTURED: RefCell<Option<(bool, String)>> = const { RefCell::new(None) };
+}
+
+/// Silence (or restore) status output for the rest of the process
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Whether status output is silenced
+pub fn is_quiet() -> bool {
+    CAPTURED.with_borrow(|captured| {
+        captured
+            .as_ref()
+            .map_or_else(|| QUIET.load(Ordering::Relaxed), |(quiet, _)| *quiet)
+    })
+}
+
+/// Print a status line unless `--quiet` is set
+pub fn say(message: impl Display) {
+    if is_quiet() {
+        return;
+    }
+    let line = format!("{message}\n");
+    let printed = CAPTURED.with_borrow_mut(|captured| match captured {
+        Some((_, buffer)) => {
+            buffer.push_str(&line);
+            true
+        }
+        None => false,
+    });
+    if !printed {
+        print!("{line}");
+    }
+}
+
+/// Run `f` with status output going to a buffer instead of stdout
+#[cfg(test)]
+pub fn capture(quiet: bool, f: impl FnOnce()) -> String {
+    CAPTURED.set(Some((quiet, String::new())));
+    f();
+    CAPTURED
+        .take()
+        .map(|(_, buffer)| buffer)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_suppresses_status_lines() {
+        assert_eq!(capture(false, || say("Created .gba")), "Created .gba\n");
+        assert_eq!(capture(true, || say("Created .gba")), "");
+    }
+}
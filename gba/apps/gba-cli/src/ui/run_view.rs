@@ -0,0 +1,410 @@
+//! Full-screen view of a running feature (`gba run --tui`).
+//!
+//! The run sends its [`RunEvent`]s through a channel; the view lists the
+//! phases with a spinner on the active one and streams the agent's text in
+//! a scrollable pane. Once the channel closes the run is over and the view
+//! stays up until the user quits.
+
+use std::future::Future;
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use anyhow::Result;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
+};
+use gba_core::{FeatureStatus, PhaseStatus, RunEvent};
+use ratatui::{
+    Frame, Terminal,
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+};
+use tokio::sync::{mpsc, oneshot};
+
+/// Spinner frames of the active phase
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// How often the spinner advances
+const FRAME: Duration = Duration::from_millis(100);
+
+/// A phase of the run and how it went
+#[derive(Debug, Clone, PartialEq)]
+pub struct PhaseRow {
+    /// Phase name
+    pub name: String,
+    /// Latest known status
+    pub status: PhaseStatus,
+    /// Cost once the phase completed
+    pub cost_usd: Option<f64>,
+    /// Why the phase failed or was skipped
+    pub note: Option<String>,
+}
+
+/// State of the view, built from the run's events
+#[derive(Debug, Default)]
+pub struct RunView {
+    /// Feature directory name, once the run started
+    pub feature: Option<String>,
+    /// Phases in execution order
+    pub phases: Vec<PhaseRow>,
+    /// Streamed agent text
+    pub text: String,
+    /// Final feature status reported by the run
+    pub status: Option<FeatureStatus>,
+    /// Whether the event channel closed
+    pub finished: bool,
+    /// Lines scrolled back from the end of the text (0 = follow)
+    pub scroll_back: usize,
+    frame: usize,
+}
+
+impl RunView {
+    /// Apply an event of the run
+    pub fn apply(&mut self, event: RunEvent) {
+        match event {
+            RunEvent::RunStarted { feature, phases } => {
+                self.feature = Some(feature);
+                for name in phases {
+                    self.row(&name);
+                }
+            }
+            RunEvent::PhaseStarted { phase } => {
+                self.row(&phase).status = PhaseStatus::InProgress;
+                if !self.text.is_empty() && !self.text.ends_with('\n') {
+                    self.text.push('\n');
+                }
+                self.text.push_str(&format!("── {phase} ──\n"));
+            }
+            RunEvent::PhaseSkipped { phase, reason } => {
+                let row = self.row(&phase);
+                row.status = PhaseStatus::Skipped;
+                row.note = Some(reason);
+            }
+            RunEvent::AssistantText { text, .. } => {
+                self.text.push_str(&gba_core::sanitize(&text));
+            }
+            RunEvent::PhaseCompleted { phase, stats } => {
+                let row = self.row(&phase);
+                row.status = PhaseStatus::Completed;
+                row.cost_usd = Some(stats.cost_usd);
+            }
+            RunEvent::PhaseFailed { phase, error } => {
+                let row = self.row(&phase);
+                row.status = PhaseStatus::Failed;
+                row.note = Some(error);
+            }
+            RunEvent::RunCompleted {
+                feature, status, ..
+            } => {
+                self.feature = Some(feature);
+                self.status = Some(status);
+            }
+        }
+    }
+
+    /// The run is over: no phase is shown as running any more.
+    ///
+    /// A phase that started without reporting an outcome was stopped, so it
+    /// is marked failed; phases that never started stay pending.
+    pub fn close(&mut self) {
+        self.finished = true;
+        for row in &mut self.phases {
+            if row.status == PhaseStatus::InProgress {
+                row.status = PhaseStatus::Failed;
+                row.note.get_or_insert_with(|| "stopped".to_string());
+            }
+        }
+    }
+
+    /// Advance the spinner
+    pub fn tick(&mut self) {
+        self.frame = self.frame.wrapping_add(1);
+    }
+
+    /// Scroll the text pane back by `lines` (negative: towards the end)
+    pub fn scroll(&mut self, lines: isize) {
+        self.scroll_back = self.scroll_back.saturating_add_signed(lines);
+    }
+
+    /// One line per phase: icon (spinner while running), name, status
+    pub fn phase_lines(&self) -> Vec<String> {
+        self.phases
+            .iter()
+            .map(|row| {
+                let icon = match row.status {
+                    PhaseStatus::InProgress => SPINNER[self.frame % SPINNER.len()],
+                    status => status.icon(),
+                };
+                let mut line = format!("{icon} {:<14} {}", row.name, row.status);
+                if let Some(cost) = row.cost_usd {
+                    line.push_str(&format!(" ${cost:.2}"));
+                }
+                if let Some(note) = &row.note {
+                    line.push_str(&format!(" ({note})"));
+                }
+                line
+            })
+            .collect()
+    }
+
+    fn row(&mut self, name: &str) -> &mut PhaseRow {
+        let index = match self.phases.iter().position(|row| row.name == name) {
+            Some(index) => index,
+            None => {
+                self.phases.push(PhaseRow {
+                    name: name.to_string(),
+                    status: PhaseStatus::Pending,
+                    cost_usd: None,
+                    note: None,
+                });
+                self.phases.len() - 1
+            }
+        };
+        &mut self.phases[index]
+    }
+}
+
+/// Show the progress of `run` until it finished and the user quits.
+///
+/// `run` must send its events through the sender of `events` and stop when
+/// `interrupt` fires, which happens on Ctrl-C. Returns the result of `run`.
+pub async fn run(
+    mut events: mpsc::UnboundedReceiver<RunEvent>,
+    interrupt: oneshot::Sender<()>,
+    run: impl Future<Output = Result<()>>,
+) -> Result<()> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+    // crossterm's reads block, so they get their own thread.
+    let (keys_tx, mut keys) = mpsc::unbounded_channel();
+    let stop = Arc::new(AtomicBool::new(false));
+    let reader = {
+        let stop = stop.clone();
+        tokio::task::spawn_blocking(move || {
+            while !stop.load(Ordering::Relaxed) {
+                if let Ok(true) = event::poll(FRAME)
+                    && let Ok(Event::Key(key)) = event::read()
+                    && key.kind == KeyEventKind::Press
+                    && keys_tx.send(key).is_err()
+                {
+                    break;
+                }
+            }
+        })
+    };
+
+    let mut view = RunView::default();
+    let mut interrupt = Some(interrupt);
+    let mut result = None;
+    let mut tick = tokio::time::interval(FRAME);
+    tokio::pin!(run);
+    let res: Result<()> = async {
+        loop {
+            terminal.draw(|f| draw(f, &view))?;
+            tokio::select! {
+                outcome = &mut run, if result.is_none() => result = Some(outcome),
+                event = events.recv(), if !view.finished => match event {
+                    Some(event) => view.apply(event),
+                    None => view.close(),
+                },
+                Some(key) = keys.recv() => {
+                    if handle_key(&mut view, key, &mut interrupt) {
+                        return Ok(());
+                    }
+                }
+                _ = tick.tick() => view.tick(),
+            }
+        }
+    }
+    .await;
+
+    stop.store(true, Ordering::Relaxed);
+    let _ = reader.await;
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+    res?;
+    match result {
+        Some(result) => result,
+        // The events ended before the run's result was polled.
+        None => run.await,
+    }
+}
+
+/// Apply a key press; returns whether to quit.
+///
+/// Ctrl-C stops the run; once it is over Ctrl-C, `q` or Esc quit.
+fn handle_key(
+    view: &mut RunView,
+    key: KeyEvent,
+    interrupt: &mut Option<oneshot::Sender<()>>,
+) -> bool {
+    let ctrl_c = key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c');
+    let quit = view.finished && matches!(key.code, KeyCode::Char('q') | KeyCode::Esc);
+    if ctrl_c || quit {
+        if let Some(interrupt) = interrupt.take() {
+            let _ = interrupt.send(());
+        }
+        return view.finished;
+    }
+    match key.code {
+        KeyCode::Up | KeyCode::Char('k') => view.scroll(1),
+        KeyCode::Down | KeyCode::Char('j') => view.scroll(-1),
+        KeyCode::PageUp => view.scroll(10),
+        KeyCode::PageDown => view.scroll(-10),
+        KeyCode::End => view.scroll_back = 0,
+        _ => {}
+    }
+    false
+}
+
+fn draw(f: &mut Frame, view: &RunView) {
+    let height = u16::try_from(view.phases.len())
+        .unwrap_or(u16::MAX)
+        .saturating_add(2);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(height), Constraint::Min(3)])
+        .split(f.area());
+
+    let title = match (&view.feature, view.finished) {
+        (Some(feature), false) => format!("Running {feature} (Ctrl-C to stop)"),
+        (Some(feature), true) => format!(
+            "{feature} {} (q to quit)",
+            view.status
+                .map_or_else(|| "stopped".to_string(), |s| s.to_string())
+        ),
+        (None, _) => "Starting...".to_string(),
+    };
+    let phases: Vec<Line> = view
+        .phases
+        .iter()
+        .zip(view.phase_lines())
+        .map(|(row, line)| Line::styled(line, phase_color(row.status)))
+        .collect();
+    f.render_widget(
+        Paragraph::new(phases).block(Block::default().borders(Borders::ALL).title(title)),
+        chunks[0],
+    );
+
+    let pane = chunks[1];
+    let width = usize::from(pane.width.saturating_sub(2)).max(1);
+    let visible = usize::from(pane.height.saturating_sub(2));
+    let lines = wrap(&view.text, width);
+    let end = lines
+        .len()
+        .saturating_sub(view.scroll_back.min(lines.len()));
+    let start = end.saturating_sub(visible);
+    let text: Vec<Line> = lines[start..end]
+        .iter()
+        .map(|l| Line::raw(l.clone()))
+        .collect();
+    let title = if view.scroll_back > 0 {
+        "Agent output (↑/↓ scroll, End to follow)"
+    } else {
+        "Agent output (↑/↓ scroll)"
+    };
+    f.render_widget(
+        Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(title)),
+        pane,
+    );
+}
+
+/// Split `text` into lines of at most `width` characters
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in text.lines() {
+        let chars: Vec<char> = line.chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        lines.extend(chars.chunks(width).map(|chunk| chunk.iter().collect()));
+    }
+    lines
+}
+
+fn phase_color(status: PhaseStatus) -> Style {
+    match status {
+        PhaseStatus::Completed => Style::default().fg(Color::Green),
+        PhaseStatus::InProgress => Style::default().fg(Color::Yellow),
+        PhaseStatus::Failed => Style::default().fg(Color::Red),
+        PhaseStatus::Pending | PhaseStatus::Skipped => Style::default().add_modifier(Modifier::DIM),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gba_core::ExecutionStats;
+
+    #[test]
+    fn test_event_stream_drives_phase_list() {
+        let stats = ExecutionStats {
+            cost_usd: 0.5,
+            ..ExecutionStats::default()
+        };
+        let events = [
+            RunEvent::PhaseSkipped {
+                phase: "observe".into(),
+                reason: "skipped with --skip-phase".into(),
+            },
+            RunEvent::RunStarted {
+                feature: "0001_auth".into(),
+                phases: vec!["build".into(), "test".into(), "review".into()],
+            },
+            RunEvent::PhaseStarted {
+                phase: "build".into(),
+            },
+            RunEvent::AssistantText {
+                phase: "build".into(),
+                text: "Implementing ".into(),
+            },
+            RunEvent::AssistantText {
+                phase: "build".into(),
+                text: "login\n".into(),
+            },
+            RunEvent::PhaseCompleted {
+                phase: "build".into(),
+                stats,
+            },
+            RunEvent::PhaseStarted {
+                phase: "test".into(),
+            },
+        ];
+        let mut view = RunView::default();
+        for event in events {
+            view.apply(event);
+        }
+        assert!(view.phase_lines()[2].starts_with("⠋ test"));
+
+        view.close();
+
+        assert_eq!(
+            view.phase_lines(),
+            [
+                "⊘ observe        skipped (skipped with --skip-phase)",
+                "✓ build          completed $0.50",
+                "✗ test           failed (stopped)",
+                "○ review         pending",
+            ]
+        );
+        assert_eq!(view.text, "── build ──\nImplementing login\n── test ──\n");
+        assert!(view.finished);
+        assert_eq!(view.status, None);
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("abcdef\n\nxy", 4), ["abcd", "ef", "", "xy"]);
+    }
+}
@@ -0,0 +1,78 @@
+//! Colors for feature and phase statuses on a terminal.
+//!
+//! Color is enabled once at startup when stdout is a terminal and
+//! `NO_COLOR` isn't set; until then (and in tests) text stays plain.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+pub use anstyle::Style;
+
+use anstyle::AnsiColor;
+use gba_core::{FeatureStatus, PhaseStatus};
+
+static COLOR: AtomicBool = AtomicBool::new(false);
+
+/// Whether to color output, following <https://no-color.org>
+pub fn color_wanted(no_color: Option<&str>, is_terminal: bool) -> bool {
+    is_terminal && no_color.is_none_or(str::is_empty)
+}
+
+/// Enable (or disable) color for the rest of the process
+pub fn set_color(enabled: bool) {
+    COLOR.store(enabled, Ordering::Relaxed);
+}
+
+/// Style of a phase status
+pub fn phase_style(status: PhaseStatus) -> Style {
+    match status {
+        PhaseStatus::Completed => AnsiColor::Green.on_default(),
+        PhaseStatus::InProgress => AnsiColor::Yellow.on_default(),
+        PhaseStatus::Failed => AnsiColor::Red.on_default(),
+        PhaseStatus::Pending | PhaseStatus::Skipped => Style::new().dimmed(),
+    }
+}
+
+/// Style of a feature status
+pub fn feature_style(status: FeatureStatus) -> Style {
+    match status {
+        FeatureStatus::Completed => AnsiColor::Green.on_default(),
+        FeatureStatus::InProgress => AnsiColor::Yellow.on_default(),
+        FeatureStatus::Failed => AnsiColor::Red.on_default(),
+        FeatureStatus::Planned => Style::new(),
+    }
+}
+
+/// `text` in `style` if color is enabled, otherwise unchanged
+pub fn paint(text: &str, style: Style) -> String {
+    paint_if(COLOR.load(Ordering::Relaxed), text, style)
+}
+
+/// `text` in `style` if `color` is set
+pub fn paint_if(color: bool, text: &str, style: Style) -> String {
+    if color && style != Style::new() {
+        format!("{}{text}{}", style.render(), style.render_reset())
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_color_yields_plain_text() {
+        let style = phase_style(PhaseStatus::Failed);
+
+        let color = color_wanted(Some("1"), true);
+        assert_eq!(paint_if(color, "failed", style), "failed");
+        assert!(!color_wanted(None, false));
+
+        let color = color_wanted(Some(""), true);
+        assert_eq!(paint_if(color, "failed", style), "\x1b[31mfailed\x1b[0m");
+        assert_eq!(
+            paint_if(true, "planned", feature_style(FeatureStatus::Planned)),
+            "planned"
+        );
+    }
+}
@@ -0,0 +1,717 @@
+//! Claude Agent SDK plumbing: client options and response collection.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use claude_agent_sdk_rs::{
+    ClaudeAgentOptions, ClaudeError, ContentBlock, Message, PermissionMode, ResultMessage,
+    SystemPrompt, SystemPromptPreset, ToolResultBlock, UserMessage,
+};
+use futures::{Stream, StreamExt};
+
+use crate::Config;
+use crate::config::ConfigPermissionMode;
+use crate::environment;
+use crate::error::{CoreError, Result};
+use crate::execution::ExecutionRequest;
+use crate::progress::{ProgressEvent, ProgressSender};
+use crate::state::ExecutionStats;
+
+/// Final status and statistics of an agent response
+#[derive(Debug, Clone, Default)]
+pub(crate) struct AgentResponse {
+    /// Whether the Result message reported success
+    pub success: bool,
+    /// Turns, tokens and cost reported by the Result message
+    pub stats: ExecutionStats,
+    /// Model reported by the assistant messages, if any
+    pub model: Option<String>,
+    /// Bytes of assistant text dropped after the output limit was reached
+    pub dropped_bytes: usize,
+}
+
+/// Build the SDK options for a request
+pub(crate) fn build_options(config: &Config, request: &ExecutionRequest) -> ClaudeAgentOptions {
+    let system_prompt = match (&request.system_prompt, &request.append) {
+        (Some(text), _) => SystemPrompt::Text(text.clone()),
+        (None, Some(append)) => {
+            SystemPrompt::Preset(SystemPromptPreset::with_append("claude_code", append))
+        }
+        (None, None) => SystemPrompt::Preset(SystemPromptPreset::new("claude_code")),
+    };
+    let mut options = ClaudeAgentOptions::builder()
+        .system_prompt(system_prompt)
+        .model(
+            request
+                .model
+                .clone()
+                .unwrap_or_else(|| config.model.clone()),
+        )
+        .cwd(
+            request
+                .working_dir
+                .clone()
+                .unwrap_or_else(|| config.repo_path.clone()),
+        )
+        .permission_mode(permission_mode(config.permission_mode))
+        .disallowed_tools(request.disallowed_tools.clone())
+        .build();
+    options.max_turns = request.max_turns.or(config.max_turns);
+    if !request.tools.is_empty() {
+        options.allowed_tools = request.tools.clone();
+    }
+    options.mcp_servers = crate::mcp::to_sdk(&request.mcp_servers);
+    options.env = agent_env(config, request, &std::env::vars().collect());
+    if !config.api_key.is_empty() {
+        options
+            .env
+            .insert("ANTHROPIC_API_KEY".to_string(), config.api_key.clone());
+    }
+    options
+}
+
+/// Script starting the agent CLI without the variables of `parent` denied
+/// to `request`, or `None` if none is set.
+///
+/// Variables `options` sets, such as the configured API key, are kept.
+///
+/// # Errors
+///
+/// Returns an error if a variable must be removed but the agent CLI cannot
+/// be found, rather than letting it through, or the script cannot be written
+pub(crate) fn launcher(
+    config: &Config,
+    request: &ExecutionRequest,
+    options: &ClaudeAgentOptions,
+    parent: &HashMap<String, String>,
+) -> Result<Option<PathBuf>> {
+    let deny = [config.env_deny.as_slice(), request.env_deny.as_slice()].concat();
+    let unset = environment::denied(&deny, parent, &options.env);
+    if unset.is_empty() {
+        return Ok(None);
+    }
+    let cli = options
+        .cli_path
+        .clone()
+        .or_else(|| environment::find_cli(parent))
+        .ok_or_else(|| {
+            CoreError::ConfigError(format!(
+                "cannot remove {} from the agent's environment: the claude CLI was not found \
+                 (set CLAUDE_CLI_PATH)",
+                unset.join(", ")
+            ))
+        })?;
+    Ok(Some(environment::launcher(&cli, &unset)?))
+}
+
+/// Values to redact from the recorded transcript of `request`: the API
+/// key, the injected variables and the denied ones of gba's environment
+pub(crate) fn secrets(
+    config: &Config,
+    request: &ExecutionRequest,
+    parent: &HashMap<String, String>,
+) -> Vec<String> {
+    let deny = [config.env_deny.as_slice(), request.env_deny.as_slice()].concat();
+    let mut secrets: Vec<String> = parent
+        .iter()
+        .filter(|(name, _)| {
+            deny.iter()
+                .any(|pattern| environment::matches(pattern, name))
+        })
+        .map(|(_, value)| value.clone())
+        .chain(agent_env(config, request, parent).into_values())
+        .filter(|value| !value.is_empty())
+        .collect();
+    if !config.api_key.is_empty() {
+        secrets.push(config.api_key.clone());
+    }
+    secrets
+}
+
+/// Variables set for the agent of `request`, given gba's environment
+fn agent_env(
+    config: &Config,
+    request: &ExecutionRequest,
+    parent: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut env = config.env.clone();
+    env.extend(request.env.clone());
+    environment::agent_env(&env, parent)
+}
+
+fn permission_mode(mode: ConfigPermissionMode) -> PermissionMode {
+    match mode {
+        ConfigPermissionMode::Default => PermissionMode::Default,
+        ConfigPermissionMode::AcceptEdits => PermissionMode::AcceptEdits,
+        ConfigPermissionMode::Plan => PermissionMode::Plan,
+        ConfigPermissionMode::BypassPermissions => PermissionMode::BypassPermissions,
+    }
+}
+
+/// Cap on the assistant text kept from a response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OutputLimit {
+    /// Bytes of text kept
+    pub bytes: usize,
+    /// Stop reading the response once the cap is hit
+    pub stop: bool,
+}
+
+/// Collect a response stream, giving up after `timeout`.
+///
+/// Assistant text is appended to `output` as it arrives and, like each new
+/// turn, reported to `progress` if given. Once `output` holds `limit` bytes
+/// further text is dropped, but the stream is still drained for its Result
+/// message unless the limit says to stop.
+///
+/// # Errors
+///
+/// Returns `CoreError::AgentTimeout` carrying the text received so far if
+/// the stream doesn't finish in time, `CoreError::OutputLimitExceeded` if
+/// the limit stopped it, or `CoreError::AgentExecutionFailed` if the stream
+/// yields an error.
+pub(crate) async fn collect_with_timeout<S>(
+    stream: S,
+    timeout: Duration,
+    output: &mut String,
+    limit: Option<OutputLimit>,
+    progress: Option<&ProgressSender>,
+) -> Result<AgentResponse>
+where
+    S: Stream<Item = Result<Message, ClaudeError>>,
+{
+    match tokio::time::timeout(timeout, collect(stream, output, limit, progress)).await {
+        Ok(response) => response,
+        Err(_) => Err(CoreError::AgentTimeout {
+            duration: timeout,
+            partial: std::mem::take(output),
+        }),
+    }
+}
+
+async fn collect<S>(
+    stream: S,
+    output: &mut String,
+    limit: Option<OutputLimit>,
+    progress: Option<&ProgressSender>,
+) -> Result<AgentResponse>
+where
+    S: Stream<Item = Result<Message, ClaudeError>>,
+{
+    // A closed receiver only means nobody is watching, so send errors are ignored.
+    let emit = |event| {
+        if let Some(progress) = progress {
+            let _ = progress.send(event);
+        }
+    };
+    let mut turns = 0;
+    let mut model = None;
+    let mut dropped_bytes = 0;
+    let mut stream = std::pin::pin!(stream);
+    while let Some(message) = stream.next().await {
+        match message.map_err(|e| CoreError::AgentExecutionFailed(e.to_string()))? {
+            Message::Assistant(message) => {
+                turns += 1;
+                emit(ProgressEvent::Turn(turns));
+                if message.message.model.is_some() {
+                    model = message.message.model;
+                }
+                for block in message.message.content {
+                    match block {
+                        ContentBlock::Text(text) => {
+                            if dropped_bytes > 0 {
+                                dropped_bytes += text.text.len();
+                            } else {
+                                dropped_bytes =
+                                    push_capped(output, &text.text, limit.map(|l| l.bytes));
+                            }
+                            emit(ProgressEvent::Text(text.text));
+                            if let Some(limit) = limit.filter(|l| l.stop && dropped_bytes > 0) {
+                                return Err(CoreError::OutputLimitExceeded {
+                                    limit: limit.bytes,
+                                    partial: std::mem::take(output),
+                                });
+                            }
+                        }
+                        ContentBlock::ToolUse(tool) => {
+                            let target = tool_target(&tool.input);
+                            tracing::debug!(
+                                tool = %tool.name,
+                                id = %tool.id,
+                                target = target.as_deref().unwrap_or_default(),
+                                "agent used a tool"
+                            );
+                            emit(ProgressEvent::ToolUse {
+                                name: tool.name,
+                                target,
+                            });
+                        }
+                        ContentBlock::ToolResult(result) => log_tool_result(&result),
+                        ContentBlock::Thinking(_) | ContentBlock::Image(_) => {}
+                    }
+                }
+            }
+            Message::User(message) => {
+                for result in tool_results(&message) {
+                    log_tool_result(&result);
+                }
+            }
+            Message::System(message) => {
+                tracing::debug!(
+                    subtype = %message.subtype,
+                    model = message.model.as_deref().unwrap_or_default(),
+                    "agent system message"
+                );
+            }
+            Message::Result(result) => {
+                return Ok(AgentResponse {
+                    model,
+                    dropped_bytes,
+                    ..response(&result)
+                });
+            }
+            Message::StreamEvent(event) => {
+                tracing::trace!(?event, "agent stream event");
+            }
+            // Internal to the SDK's control protocol
+            Message::ControlCancelRequest(_) => {}
+        }
+    }
+    Err(CoreError::AgentExecutionFailed(
+        "response stream ended without a result".to_string(),
+    ))
+}
+
+/// Input keys naming what a tool acts on, most specific first
+const TOOL_TARGET_KEYS: &[&str] = &[
+    "file_path",
+    "notebook_path",
+    "path",
+    "command",
+    "pattern",
+    "url",
+];
+
+/// What a tool with `input` acts on, e.g. the file of an `Edit`; only the
+/// first line of a multi-line command
+fn tool_target(input: &serde_json::Value) -> Option<String> {
+    TOOL_TARGET_KEYS
+        .iter()
+        .find_map(|key| input.get(key)?.as_str())
+        .and_then(|target| target.lines().next())
+        .map(str::to_string)
+}
+
+/// Tool results carried by a user message: its content blocks, or those of
+/// the wrapped API message the CLI streams
+fn tool_results(message: &UserMessage) -> Vec<ToolResultBlock> {
+    let blocks = match &message.content {
+        Some(blocks) => blocks.clone(),
+        None => message
+            .extra
+            .get("message")
+            .and_then(|m| m.get("content"))
+            .and_then(|content| serde_json::from_value(content.clone()).ok())
+            .unwrap_or_default(),
+    };
+    blocks
+        .into_iter()
+        .filter_map(|block| match block {
+            ContentBlock::ToolResult(result) => Some(result),
+            _ => None,
+        })
+        .collect()
+}
+
+fn log_tool_result(result: &ToolResultBlock) {
+    tracing::debug!(
+        id = %result.tool_use_id,
+        is_error = result.is_error.unwrap_or_default(),
+        "tool result"
+    );
+}
+
+/// Append as much of `text` as fits in `limit` bytes without splitting a
+/// character, returning the number of bytes left out
+fn push_capped(output: &mut String, text: &str, limit: Option<usize>) -> usize {
+    let room = limit.map_or(text.len(), |limit| limit.saturating_sub(output.len()));
+    let mut end = room.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    output.push_str(&text[..end]);
+    text.len() - end
+}
+
+fn response(result: &ResultMessage) -> AgentResponse {
+    let usage = |key: &str| {
+        result
+            .usage
+            .as_ref()
+            .and_then(|u| u.get(key))
+            .and_then(|v| v.as_u64())
+            .unwrap_or_default()
+    };
+    AgentResponse {
+        success: !result.is_error,
+        stats: ExecutionStats {
+            turns: result.num_turns,
+            input_tokens: usage("input_tokens"),
+            output_tokens: usage("output_tokens"),
+            cost_usd: result.total_cost_usd.unwrap_or_default(),
+            ..ExecutionStats::default()
+        },
+        model: None,
+        dropped_bytes: 0,
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod stub {
+    //! Message builders for stub response streams.
+
+    use claude_agent_sdk_rs::Message;
+    use serde_json::json;
+
+    /// Assistant message with a single text block
+    pub fn text(text: &str) -> Message {
+        serde_json::from_value(json!({
+            "type": "assistant",
+            "message": {
+                "content": [{"type": "text", "text": text}],
+                "model": "claude-sonnet-4-5"
+            }
+        }))
+        .unwrap()
+    }
+
+    /// Assistant message invoking the tool `name` with `input`
+    pub fn tool_use(name: &str, input: serde_json::Value) -> Message {
+        serde_json::from_value(json!({
+            "type": "assistant",
+            "message": {
+                "content": [{"type": "tool_use", "id": "toolu_1", "name": name, "input": input}],
+                "model": "claude-sonnet-4-5"
+            }
+        }))
+        .unwrap()
+    }
+
+    /// User message carrying the result of a tool, as streamed by the CLI
+    pub fn tool_result(output: &str) -> Message {
+        serde_json::from_value(json!({
+            "type": "user",
+            "message": {
+                "role": "user",
+                "content": [{"type": "tool_result", "tool_use_id": "toolu_1", "content": output}]
+            }
+        }))
+        .unwrap()
+    }
+
+    /// Successful Result message
+    pub fn result(turns: u32, cost_usd: f64) -> Message {
+        serde_json::from_value(json!({
+            "type": "result",
+            "subtype": "success",
+            "duration_ms": 10,
+            "duration_api_ms": 8,
+            "is_error": false,
+            "num_turns": turns,
+            "session_id": "test",
+            "total_cost_usd": cost_usd,
+            "usage": {"input_tokens": 120, "output_tokens": 45}
+        }))
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use futures::stream;
+
+    use super::*;
+
+    #[test]
+    fn test_agent_environment() {
+        let config = Config {
+            api_key: "sk-ant-test".to_string(),
+            env: BTreeMap::from([("RUST_LOG".to_string(), "debug".to_string())]),
+            env_deny: vec!["PATH".to_string(), "ANTHROPIC_*".to_string()],
+            ..Config::default()
+        };
+        let request = ExecutionRequest {
+            env: BTreeMap::from([
+                ("RUST_LOG".to_string(), "trace".to_string()),
+                ("SEARCH_PATH".to_string(), "${PATH}".to_string()),
+            ]),
+            env_deny: vec!["HOME".to_string()],
+            ..ExecutionRequest::new("build it")
+        };
+        let parent: HashMap<String, String> = [("PATH", "/usr/bin"), ("HOME", "/root")]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+
+        let env = agent_env(&config, &request, &parent);
+        assert_eq!(env["RUST_LOG"], "trace");
+        assert_eq!(env["SEARCH_PATH"], "/usr/bin");
+        assert!(!env.contains_key("PATH"));
+        assert!(!env.contains_key("HOME"));
+
+        // The configured key is passed whatever the deny patterns say.
+        let mut options = build_options(&config, &request);
+        assert!(!options.env.contains_key("PATH"));
+        assert_eq!(options.env["RUST_LOG"], "trace");
+        assert_eq!(options.env["ANTHROPIC_API_KEY"], "sk-ant-test");
+
+        // Denied variables are removed by a launcher script, which needs
+        // the CLI to wrap.
+        let parent: HashMap<String, String> = [
+            ("PATH", "/nonexistent"),
+            ("HOME", "/root"),
+            ("ANTHROPIC_API_KEY", "sk-ant-parent"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+        let err = launcher(&config, &request, &options, &parent).unwrap_err();
+        assert!(
+            err.to_string().contains("cannot remove HOME, PATH from"),
+            "{err}"
+        );
+        options.cli_path = Some(PathBuf::from("/opt/claude"));
+        let script = launcher(&config, &request, &options, &parent)
+            .unwrap()
+            .unwrap();
+        let script = std::fs::read_to_string(script).unwrap();
+        assert!(
+            script.contains("env -u 'HOME' -u 'PATH' '/opt/claude'"),
+            "{script}"
+        );
+        assert!(
+            launcher(
+                &Config::default(),
+                &ExecutionRequest::new("x"),
+                &options,
+                &parent
+            )
+            .unwrap()
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn test_preset_with_append() {
+        let request = ExecutionRequest {
+            append: Some("Always run cargo fmt.".to_string()),
+            ..ExecutionRequest::new("build it")
+        };
+
+        let options = build_options(&Config::default(), &request);
+
+        match options.system_prompt {
+            Some(SystemPrompt::Preset(preset)) => {
+                assert_eq!(preset.preset, "claude_code");
+                assert_eq!(preset.append.as_deref(), Some("Always run cargo fmt."));
+            }
+            other => panic!("unexpected system prompt: {other:?}"),
+        }
+
+        let custom = ExecutionRequest {
+            system_prompt: Some("You are a reviewer.".to_string()),
+            ..request
+        };
+        let options = build_options(&Config::default(), &custom);
+        assert!(
+            matches!(options.system_prompt, Some(SystemPrompt::Text(t)) if t == "You are a reviewer.")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_collect_text_and_stats() {
+        let messages = vec![
+            Ok(stub::text("Hello, ")),
+            Ok(stub::text("world")),
+            Ok(stub::result(3, 0.02)),
+        ];
+        let mut output = String::new();
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let response = collect_with_timeout(
+            stream::iter(messages),
+            Duration::from_secs(5),
+            &mut output,
+            None,
+            Some(&tx),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(output, "Hello, world");
+        assert_eq!(rx.recv().await, Some(ProgressEvent::Turn(1)));
+        assert_eq!(rx.recv().await, Some(ProgressEvent::Text("Hello, ".into())));
+        assert_eq!(rx.recv().await, Some(ProgressEvent::Turn(2)));
+        assert!(response.success);
+        assert_eq!(response.stats.turns, 3);
+        assert_eq!(response.stats.input_tokens, 120);
+        assert_eq!(response.stats.output_tokens, 45);
+        assert_eq!(response.model.as_deref(), Some("claude-sonnet-4-5"));
+    }
+
+    #[tokio::test]
+    async fn test_collect_observes_tool_use() {
+        let system: Message = serde_json::from_value(serde_json::json!({
+            "type": "system",
+            "subtype": "init",
+            "model": "claude-sonnet-4-5"
+        }))
+        .unwrap();
+        let messages = vec![
+            Ok(system),
+            Ok(stub::tool_use(
+                "Edit",
+                serde_json::json!({"file_path": "src/main.rs", "old_string": "a"}),
+            )),
+            Ok(stub::tool_result("ok")),
+            Ok(stub::tool_use(
+                "Bash",
+                serde_json::json!({"command": "cargo test\ncargo fmt"}),
+            )),
+            Ok(stub::tool_use(
+                "TodoWrite",
+                serde_json::json!({"todos": []}),
+            )),
+            Ok(stub::text("Done")),
+            Ok(stub::result(5, 0.02)),
+        ];
+        let mut output = String::new();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+
+        let response = collect_with_timeout(
+            stream::iter(messages),
+            Duration::from_secs(5),
+            &mut output,
+            None,
+            Some(&tx),
+        )
+        .await
+        .unwrap();
+        drop(tx);
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+
+        let tool = |name: &str, target: Option<&str>| ProgressEvent::ToolUse {
+            name: name.to_string(),
+            target: target.map(str::to_string),
+        };
+        assert_eq!(
+            events,
+            vec![
+                ProgressEvent::Turn(1),
+                tool("Edit", Some("src/main.rs")),
+                ProgressEvent::Turn(2),
+                tool("Bash", Some("cargo test")),
+                ProgressEvent::Turn(3),
+                tool("TodoWrite", None),
+                ProgressEvent::Turn(4),
+                ProgressEvent::Text("Done".into()),
+            ]
+        );
+        assert_eq!(output, "Done");
+        assert!(response.success);
+        let user = match stub::tool_result("ok") {
+            Message::User(user) => user,
+            other => panic!("unexpected message: {other:?}"),
+        };
+        let results = tool_results(&user);
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].tool_use_id, "toolu_1");
+    }
+
+    #[tokio::test]
+    async fn test_output_over_limit_is_truncated() {
+        let messages = vec![
+            Ok(stub::text("0123456789")),
+            Ok(stub::text("héllo")),
+            Ok(stub::text("more")),
+            Ok(stub::result(3, 0.02)),
+        ];
+        let mut output = String::new();
+
+        let response = collect_with_timeout(
+            stream::iter(messages),
+            Duration::from_secs(5),
+            &mut output,
+            Some(OutputLimit {
+                bytes: 12,
+                stop: false,
+            }),
+            None,
+        )
+        .await
+        .unwrap();
+
+        // "é" would straddle the limit, so only "h" of the second block fits.
+        assert_eq!(output, "0123456789h");
+        assert_eq!(response.dropped_bytes, 5 + 4);
+        assert!(response.success);
+        assert_eq!(response.stats.turns, 3);
+    }
+
+    #[tokio::test]
+    async fn test_output_limit_can_stop_the_stream() {
+        let runaway = stream::iter(vec![Ok(stub::text("0123456789")), Ok(stub::text("abc"))])
+            .chain(stream::repeat_with(|| Ok(stub::text(&"x".repeat(1 << 20)))));
+        let mut output = String::new();
+        let limit = OutputLimit {
+            bytes: 12,
+            stop: true,
+        };
+
+        let err = collect_with_timeout(
+            runaway,
+            Duration::from_secs(5),
+            &mut output,
+            Some(limit),
+            None,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(err.partial_output(), Some("0123456789ab"));
+        assert!(matches!(
+            err,
+            CoreError::OutputLimitExceeded { limit: 12, .. }
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stalled_stream_times_out_with_partial_output() {
+        let stalled = stream::iter(vec![
+            Ok(stub::text("Reading src/lib.rs. ")),
+            Ok(stub::text("Found the bug in")),
+        ])
+        .chain(stream::pending());
+        let mut output = String::new();
+
+        let err = collect_with_timeout(stalled, Duration::from_millis(50), &mut output, None, None)
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            err.partial_output(),
+            Some("Reading src/lib.rs. Found the bug in")
+        );
+        assert!(matches!(
+            err,
+            CoreError::AgentTimeout { duration, .. } if duration == Duration::from_millis(50)
+        ));
+    }
+}
@@ -0,0 +1,146 @@
+//! Archiving features out of `.gba/features` into `.gba/archive`.
+//!
+//! Archived features keep their state but are hidden from `gba list` and
+//! still reserve their ID (see [`FeatureState::next_id`]).
+
+use std::path::{Path, PathBuf};
+
+use crate::command::CommandRunner;
+use crate::error::{CoreError, Result};
+use crate::git;
+use crate::state::{ARCHIVE_DIR, FEATURES_DIR, FeatureState, FeatureStatus};
+
+/// What `archive` did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Archived {
+    /// New location of the feature directory
+    pub path: PathBuf,
+    /// Worktree that was removed, if any
+    pub removed_worktree: Option<PathBuf>,
+}
+
+/// Move a feature into `.gba/archive`, removing its worktree.
+///
+/// # Errors
+///
+/// Returns `CoreError::FeatureInProgress` for a running feature unless
+/// `force` is set, or an error if the feature can't be found or moved.
+pub fn archive(
+    runner: &dyn CommandRunner,
+    repo: &Path,
+    gba_path: &Path,
+    feature: &str,
+    force: bool,
+) -> Result<Archived> {
+    let source = FeatureState::find_dir(gba_path, feature)?;
+    let state = FeatureState::load(&source)?;
+    if state.status == FeatureStatus::InProgress && !force {
+        return Err(CoreError::FeatureInProgress(state.dir_name()));
+    }
+
+    let mut removed_worktree = None;
+    if let Some(info) = &state.git {
+        let worktree = repo.join(&info.worktree_path);
+        if worktree.exists() {
+            git::worktree_remove(runner, repo, &info.worktree_path, force)?;
+            removed_worktree = Some(info.worktree_path.clone());
+        }
+    }
+
+    let path = move_dir(&source, &gba_path.join(ARCHIVE_DIR))?;
+    Ok(Archived {
+        path,
+        removed_worktree,
+    })
+}
+
+/// Move an archived feature back into `.gba/features`.
+///
+/// # Errors
+///
+/// Returns an error if the feature isn't archived or can't be moved.
+pub fn unarchive(gba_path: &Path, feature: &str) -> Result<PathBuf> {
+    let source = FeatureState::find_archived_dir(gba_path, feature)?;
+    move_dir(&source, &gba_path.join(FEATURES_DIR))
+}
+
+fn move_dir(source: &Path, target_dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(target_dir)?;
+    let target = target_dir.join(source.file_name().unwrap_or_default());
+    if target.exists() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists", target.display()),
+        )
+        .into());
+    }
+    std::fs::rename(source, &target)?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::fake::FakeCommandRunner;
+    use crate::state::GitInfo;
+
+    fn setup(status: FeatureStatus) -> (tempfile::TempDir, PathBuf) {
+        let repo = tempfile::tempdir().unwrap();
+        let gba = repo.path().join(".gba");
+        let mut state = FeatureState::new("0001", "auth");
+        state.status = status;
+        state.git = Some(GitInfo {
+            worktree_path: PathBuf::from(".trees/0001_auth"),
+            branch: "feature/0001-auth".to_string(),
+            base_branch: "main".to_string(),
+            base_commit: "abc1234".to_string(),
+        });
+        let feature = gba.join(FEATURES_DIR).join(state.dir_name());
+        std::fs::create_dir_all(&feature).unwrap();
+        std::fs::create_dir_all(repo.path().join(".trees/0001_auth")).unwrap();
+        state.save(&feature).unwrap();
+        (repo, gba)
+    }
+
+    #[test]
+    fn test_archive_and_unarchive() {
+        let (repo, gba) = setup(FeatureStatus::Completed);
+        let runner = FakeCommandRunner::default();
+
+        let archived = archive(&runner, repo.path(), &gba, "auth", false).unwrap();
+
+        assert_eq!(archived.path, gba.join(ARCHIVE_DIR).join("0001_auth"));
+        assert_eq!(
+            archived.removed_worktree,
+            Some(PathBuf::from(".trees/0001_auth"))
+        );
+        assert_eq!(
+            runner.calls()[0].1,
+            ["worktree", "remove", ".trees/0001_auth"]
+        );
+        assert!(FeatureState::find_dir(&gba, "auth").is_err());
+        assert_eq!(FeatureState::next_id(&gba).unwrap(), "0002");
+
+        let restored = unarchive(&gba, "0001").unwrap();
+        assert_eq!(restored, gba.join(FEATURES_DIR).join("0001_auth"));
+        assert!(FeatureState::load(&restored).is_ok());
+    }
+
+    #[test]
+    fn test_archive_in_progress_requires_force() {
+        let (repo, gba) = setup(FeatureStatus::InProgress);
+        let runner = FakeCommandRunner::default();
+
+        assert!(matches!(
+            archive(&runner, repo.path(), &gba, "auth", false),
+            Err(CoreError::FeatureInProgress(_))
+        ));
+        assert!(runner.calls().is_empty());
+
+        archive(&runner, repo.path(), &gba, "auth", true).unwrap();
+        assert_eq!(
+            runner.calls()[0].1,
+            ["worktree", "remove", "--force", ".trees/0001_auth"]
+        );
+    }
+}
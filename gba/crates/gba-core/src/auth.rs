@@ -0,0 +1,400 @@
+//! Resolving the Anthropic API key.
+//!
+//! Sources are tried in order: a key given explicitly (`--api-key` or its
+//! environment variable), `agent.apiKeyFile`, the variable named by
+//! `agent.apiKeyEnv`, and the keychain entry named by `agent.apiKeyKeychain`
+//! (default: [`DEFAULT_KEYCHAIN_SERVICE`], as stored by `gba auth set`).
+//! Without a key gba still runs if the Claude CLI is logged in, leaving
+//! authentication to it. The keychain is accessed with `security` on macOS
+//! and `secret-tool` elsewhere, through a [`CommandRunner`].
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::command::{CommandRunner, run_checked};
+use crate::config::AgentConfig;
+use crate::error::{CoreError, Result};
+
+/// Keychain service read when `agent.apiKeyKeychain` isn't set
+pub const DEFAULT_KEYCHAIN_SERVICE: &str = "gba";
+
+/// Keychain service of the Claude CLI's login on macOS
+const CLAUDE_KEYCHAIN_SERVICE: &str = "Claude Code-credentials";
+
+/// Where the API key came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeySource {
+    /// `--api-key` or its environment variable
+    Explicit,
+    /// `agent.apiKeyFile`
+    File(PathBuf),
+    /// The variable named by `agent.apiKeyEnv`
+    Env(String),
+    /// A keychain entry of this service
+    Keychain(String),
+    /// No key; the logged-in Claude CLI authenticates
+    ClaudeLogin,
+}
+
+impl fmt::Display for KeySource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Explicit => write!(f, "--api-key"),
+            Self::File(path) => write!(f, "file {}", path.display()),
+            Self::Env(name) => write!(f, "environment variable {name}"),
+            Self::Keychain(service) => write!(f, "keychain entry `{service}`"),
+            Self::ClaudeLogin => write!(f, "Claude CLI login"),
+        }
+    }
+}
+
+/// A resolved API key
+#[derive(Clone, PartialEq, Eq)]
+pub struct ApiKey {
+    /// The key; empty with [`KeySource::ClaudeLogin`]
+    pub key: String,
+    /// Where it came from
+    pub source: KeySource,
+}
+
+impl fmt::Debug for ApiKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ApiKey")
+            .field("key", &mask(&self.key))
+            .field("source", &self.source)
+            .finish()
+    }
+}
+
+/// `key` reduced to its last 4 characters, for display
+pub fn mask(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() <= 8 {
+        return "*".repeat(chars.len());
+    }
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("****{tail}")
+}
+
+/// Find the API key, trying each configured source in order.
+///
+/// Returns an empty key if none is found but the Claude CLI is logged in.
+///
+/// # Errors
+///
+/// Same as [`resolve`].
+pub fn resolve_api_key(
+    runner: &dyn CommandRunner,
+    agent: &AgentConfig,
+    gba_path: &Path,
+    explicit: Option<&str>,
+) -> Result<String> {
+    Ok(resolve(runner, agent, gba_path, explicit)?.key)
+}
+
+/// Find the API key and its source, trying each configured source in order.
+///
+/// A relative `apiKeyFile` is relative to the repository (the parent of
+/// `gba_path`); `~/` expands to the home directory.
+///
+/// # Errors
+///
+/// Returns `CoreError::ConfigError` if a configured file or keychain entry
+/// can't be read or is empty, or if no source yields a key and the Claude
+/// CLI isn't logged in.
+pub fn resolve(
+    runner: &dyn CommandRunner,
+    agent: &AgentConfig,
+    gba_path: &Path,
+    explicit: Option<&str>,
+) -> Result<ApiKey> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    resolve_with_home(runner, agent, gba_path, explicit, home.as_deref())
+}
+
+fn resolve_with_home(
+    runner: &dyn CommandRunner,
+    agent: &AgentConfig,
+    gba_path: &Path,
+    explicit: Option<&str>,
+    home: Option<&Path>,
+) -> Result<ApiKey> {
+    let found = |key: String, source: KeySource| Ok(ApiKey { key, source });
+    if let Some(key) = explicit.map(str::trim).filter(|k| !k.is_empty()) {
+        return found(key.to_string(), KeySource::Explicit);
+    }
+    if let Some(file) = &agent.api_key_file {
+        let path = key_file_path(file, gba_path);
+        let key = std::fs::read_to_string(&path).map_err(|e| {
+            CoreError::ConfigError(format!(
+                "cannot read agent.apiKeyFile {}: {e}",
+                path.display()
+            ))
+        })?;
+        let key = non_empty(key.trim(), || {
+            format!("agent.apiKeyFile {}", path.display())
+        })?;
+        return found(key, KeySource::File(path));
+    }
+    if let Ok(key) = std::env::var(&agent.api_key_env)
+        && !key.trim().is_empty()
+    {
+        return found(
+            key.trim().to_string(),
+            KeySource::Env(agent.api_key_env.clone()),
+        );
+    }
+    match &agent.api_key_keychain {
+        Some(service) => {
+            let key = keychain_lookup(runner, service, gba_path).map_err(|e| {
+                CoreError::ConfigError(format!("cannot read keychain entry `{service}`: {e}"))
+            })?;
+            let key = non_empty(&key, || format!("keychain entry `{service}`"))?;
+            return found(key, KeySource::Keychain(service.clone()));
+        }
+        // The default entry is optional.
+        None => {
+            if let Ok(key) = keychain_lookup(runner, DEFAULT_KEYCHAIN_SERVICE, gba_path)
+                && !key.is_empty()
+            {
+                return found(key, KeySource::Keychain(DEFAULT_KEYCHAIN_SERVICE.into()));
+            }
+        }
+    }
+    if claude_logged_in(runner, home, gba_path) {
+        return found(String::new(), KeySource::ClaudeLogin);
+    }
+    Err(CoreError::ConfigError(format!(
+        "no API key found: pass --api-key, set {}, store one with `gba auth set`, \
+         configure agent.apiKeyFile, or log in with `claude`",
+        agent.api_key_env
+    )))
+}
+
+/// Store `key` in the keychain under `service`.
+///
+/// # Errors
+///
+/// Returns an error if the keychain tool fails.
+pub fn store_api_key(
+    runner: &dyn CommandRunner,
+    service: &str,
+    key: &str,
+    cwd: &Path,
+) -> Result<()> {
+    let cwd = if cwd.is_dir() { cwd } else { Path::new(".") };
+    let (program, args, input) = store_command(cfg!(target_os = "macos"), service, key);
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let output = runner.run_with_input(program, &args, &input, cwd)?;
+    if !output.status.success() {
+        return Err(CoreError::CommandFailed {
+            command: format!("store keychain entry `{service}`"),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(())
+}
+
+/// Program, arguments and stdin storing `key` under `service`.
+///
+/// The key only ever travels on stdin: `secret-tool` reads the secret from
+/// it, and `security -i` reads its whole command line from it.
+fn store_command(macos: bool, service: &str, key: &str) -> (&'static str, Vec<String>, String) {
+    if macos {
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let input = format!(
+            "add-generic-password -U -a gba -s {} -w {}\n",
+            quote(service),
+            quote(key)
+        );
+        ("security", vec!["-i".to_string()], input)
+    } else {
+        let args = ["store", "--label", "gba API key", "service", service];
+        (
+            "secret-tool",
+            args.iter().map(|a| a.to_string()).collect(),
+            key.to_string(),
+        )
+    }
+}
+
+/// Whether the keychain holds a non-empty entry for `service`
+pub fn keychain_has_key(runner: &dyn CommandRunner, service: &str, cwd: &Path) -> bool {
+    keychain_lookup(runner, service, cwd).is_ok_and(|key| !key.is_empty())
+}
+
+/// Whether the Claude CLI can authenticate by itself: an OAuth token in the
+/// environment, or a login stored in `~/.claude` or the macOS keychain
+pub fn claude_logged_in(runner: &dyn CommandRunner, home: Option<&Path>, cwd: &Path) -> bool {
+    if std::env::var("CLAUDE_CODE_OAUTH_TOKEN").is_ok_and(|token| !token.trim().is_empty()) {
+        return true;
+    }
+    if home.is_some_and(|home| home.join(".claude/.credentials.json").is_file()) {
+        return true;
+    }
+    cfg!(target_os = "macos") && keychain_has_key(runner, CLAUDE_KEYCHAIN_SERVICE, cwd)
+}
+
+fn key_file_path(file: &Path, gba_path: &Path) -> PathBuf {
+    if let Ok(rest) = file.strip_prefix("~")
+        && let Some(home) = std::env::var_os("HOME")
+    {
+        return PathBuf::from(home).join(rest);
+    }
+    match gba_path.parent() {
+        Some(repo) if file.is_relative() => repo.join(file),
+        _ => file.to_path_buf(),
+    }
+}
+
+fn keychain_lookup(runner: &dyn CommandRunner, service: &str, cwd: &Path) -> Result<String> {
+    let cwd = if cwd.is_dir() { cwd } else { Path::new(".") };
+    if cfg!(target_os = "macos") {
+        run_checked(
+            runner,
+            "security",
+            &["find-generic-password", "-s", service, "-w"],
+            cwd,
+        )
+    } else {
+        run_checked(runner, "secret-tool", &["lookup", "service", service], cwd)
+    }
+}
+
+fn non_empty(key: &str, source: impl FnOnce() -> String) -> Result<String> {
+    if key.is_empty() {
+        return Err(CoreError::ConfigError(format!("{} is empty", source())));
+    }
+    Ok(key.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::fake::FakeCommandRunner;
+
+    fn agent() -> AgentConfig {
+        AgentConfig {
+            api_key_env: "GBA_TEST_UNSET_API_KEY".to_string(),
+            ..AgentConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_key_file_is_read_and_trimmed() {
+        let dir = tempfile::tempdir().unwrap();
+        let gba_path = dir.path().join(".gba");
+        std::fs::write(dir.path().join("api-key"), "sk-ant-file\n").unwrap();
+        let runner = FakeCommandRunner::default();
+        let agent = AgentConfig {
+            api_key_file: Some(PathBuf::from("api-key")),
+            ..agent()
+        };
+
+        let key = resolve(&runner, &agent, &gba_path, None).unwrap();
+        assert_eq!(key.key, "sk-ant-file");
+        assert_eq!(key.source, KeySource::File(dir.path().join("api-key")));
+
+        let key = resolve_api_key(&runner, &agent, &gba_path, Some("sk-ant-env")).unwrap();
+        assert_eq!(key, "sk-ant-env");
+
+        std::fs::write(dir.path().join("api-key"), " \n").unwrap();
+        let err = resolve_api_key(&runner, &agent, &gba_path, None).unwrap_err();
+        assert!(err.to_string().contains("api-key is empty"));
+        assert!(runner.calls().is_empty());
+    }
+
+    #[test]
+    fn test_missing_key_file_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let agent = AgentConfig {
+            api_key_file: Some(dir.path().join("missing")),
+            api_key_keychain: Some("gba".to_string()),
+            ..agent()
+        };
+
+        let err =
+            resolve_api_key(&FakeCommandRunner::default(), &agent, dir.path(), None).unwrap_err();
+
+        assert!(matches!(
+            err,
+            CoreError::ConfigError(ref msg) if msg.contains("cannot read agent.apiKeyFile")
+        ));
+    }
+
+    #[test]
+    fn test_keychain_and_no_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = Some(dir.path());
+        let runner = FakeCommandRunner::default();
+        runner.respond(0, "sk-ant-keychain\n", "");
+        let agent = AgentConfig {
+            api_key_keychain: Some("work".to_string()),
+            ..agent()
+        };
+
+        let key = resolve_with_home(&runner, &agent, dir.path(), None, home).unwrap();
+
+        assert_eq!(key.key, "sk-ant-keychain");
+        assert_eq!(key.source, KeySource::Keychain("work".to_string()));
+        assert!(runner.calls()[0].1.contains(&"work".to_string()));
+
+        // The default entry is read unless another is configured, and may
+        // be missing.
+        runner.respond(0, "sk-ant-default\n", "");
+        let key = resolve_with_home(&runner, &self::agent(), dir.path(), None, home).unwrap();
+        assert_eq!(key.source, KeySource::Keychain("gba".to_string()));
+        runner.respond(1, "", "No such secret");
+        let err = resolve_with_home(&runner, &self::agent(), dir.path(), None, home).unwrap_err();
+        assert!(err.to_string().contains("set GBA_TEST_UNSET_API_KEY"));
+        assert!(err.to_string().contains("gba auth set"));
+    }
+
+    #[test]
+    fn test_claude_login_needs_no_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let home = Some(dir.path());
+        let runner = FakeCommandRunner::default();
+        std::fs::create_dir(dir.path().join(".claude")).unwrap();
+        std::fs::write(dir.path().join(".claude/.credentials.json"), "{}").unwrap();
+
+        let key = resolve_with_home(&runner, &agent(), dir.path(), None, home).unwrap();
+
+        assert_eq!(key.key, "");
+        assert_eq!(key.source, KeySource::ClaudeLogin);
+        assert_eq!(key.source.to_string(), "Claude CLI login");
+    }
+
+    #[test]
+    fn test_store_and_mask_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = FakeCommandRunner::default();
+
+        store_api_key(&runner, "gba", "sk-ant-secret-1234", dir.path()).unwrap();
+        let calls = runner.calls();
+        assert!(!calls[0].1.iter().any(|arg| arg.contains("secret")));
+        for macos in [true, false] {
+            // The key goes through stdin, never the arguments.
+            let (_, args, input) = store_command(macos, "gba", "sk-ant-secret-1234");
+            assert!(!args.iter().any(|arg| arg.contains("secret")));
+            assert!(input.contains("sk-ant-secret-1234"));
+        }
+        let (program, _, input) = store_command(true, "g\"ba", "k\\ey");
+        assert_eq!(program, "security");
+        assert_eq!(
+            input,
+            "add-generic-password -U -a gba -s \"g\\\"ba\" -w \"k\\\\ey\"\n"
+        );
+        runner.respond(1, "", "no keyring daemon");
+        let err = store_api_key(&runner, "gba", "sk-ant-secret-1234", dir.path()).unwrap_err();
+        assert!(err.to_string().contains("no keyring daemon"));
+
+        assert_eq!(mask("sk-ant-secret-1234"), "****1234");
+        assert_eq!(mask("short"), "*****");
+        let key = ApiKey {
+            key: "sk-ant-secret-1234".to_string(),
+            source: KeySource::Explicit,
+        };
+        assert!(!format!("{key:?}").contains("secret"));
+    }
+}
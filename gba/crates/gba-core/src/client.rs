@@ -0,0 +1,141 @@
+//! The agent session behind a trait, so the engine can be driven without
+//! the Claude CLI.
+//!
+//! The engine creates one [`AgentClient`] per request through the
+//! [`AgentClientFactory`] of [`crate::Engine::with_client_factory`]; by
+//! default a [`ClaudeClient`]. Unlike a [`crate::transcript::AgentBackend`],
+//! a factory receives the SDK options, permission hooks included.
+
+use std::fmt;
+
+use claude_agent_sdk_rs::{ClaudeAgentOptions, ClaudeClient, ClaudeError};
+use futures::future::BoxFuture;
+
+use crate::transcript::MessageStream;
+
+/// Result of an agent client call
+pub type ClientResult<T> = std::result::Result<T, ClaudeError>;
+
+/// A session with the agent, as [`ClaudeClient`] provides it
+pub trait AgentClient: Send {
+    /// Open the session
+    fn connect(&mut self) -> BoxFuture<'_, ClientResult<()>>;
+
+    /// Send a prompt
+    fn query(&mut self, prompt: String) -> BoxFuture<'_, ClientResult<()>>;
+
+    /// Messages answering the last prompt, up to its result
+    fn receive_response(&mut self) -> MessageStream<'_>;
+
+    /// Close the session
+    fn disconnect(&mut self) -> BoxFuture<'_, ClientResult<()>>;
+}
+
+impl AgentClient for ClaudeClient {
+    fn connect(&mut self) -> BoxFuture<'_, ClientResult<()>> {
+        Box::pin(ClaudeClient::connect(self))
+    }
+
+    fn query(&mut self, prompt: String) -> BoxFuture<'_, ClientResult<()>> {
+        Box::pin(ClaudeClient::query(self, prompt))
+    }
+
+    fn receive_response(&mut self) -> MessageStream<'_> {
+        ClaudeClient::receive_response(self)
+    }
+
+    fn disconnect(&mut self) -> BoxFuture<'_, ClientResult<()>> {
+        Box::pin(ClaudeClient::disconnect(self))
+    }
+}
+
+/// Creates the client of each request
+pub trait AgentClientFactory: Send + Sync + fmt::Debug {
+    /// A client configured with `options`
+    fn create(&self, options: ClaudeAgentOptions) -> ClientResult<Box<dyn AgentClient>>;
+}
+
+/// Creates [`ClaudeClient`]s; the engine's default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClaudeClientFactory;
+
+impl AgentClientFactory for ClaudeClientFactory {
+    fn create(&self, options: ClaudeAgentOptions) -> ClientResult<Box<dyn AgentClient>> {
+        Ok(Box::new(ClaudeClient::try_new(options)?))
+    }
+}
+
+/// A client answering with scripted messages, for tests
+#[cfg(test)]
+pub(crate) mod scripted {
+    use std::collections::VecDeque;
+    use std::sync::Arc;
+
+    use claude_agent_sdk_rs::Message;
+    use futures::stream::{self, StreamExt};
+    use parking_lot::Mutex;
+
+    use super::*;
+
+    /// Calls a [`ScriptedClient`] received, in order
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub(crate) enum Call {
+        Connect,
+        Query(String),
+        Disconnect,
+    }
+
+    /// Creates clients that answer each request with the next queued
+    /// response, recording their calls and the models they were created for
+    #[derive(Debug, Default)]
+    pub(crate) struct ScriptedFactory {
+        responses: Mutex<VecDeque<Vec<Message>>>,
+        pub(crate) calls: Arc<Mutex<Vec<Call>>>,
+        pub(crate) models: Mutex<Vec<Option<String>>>,
+    }
+
+    impl ScriptedFactory {
+        /// Queue the messages of the next response
+        pub(crate) fn respond(&self, messages: serde_json::Value) -> &Self {
+            let messages = serde_json::from_value(messages).expect("invalid scripted messages");
+            self.responses.lock().push_back(messages);
+            self
+        }
+    }
+
+    impl AgentClientFactory for ScriptedFactory {
+        fn create(&self, options: ClaudeAgentOptions) -> ClientResult<Box<dyn AgentClient>> {
+            self.models.lock().push(options.model.clone());
+            Ok(Box::new(ScriptedClient {
+                messages: self.responses.lock().pop_front().unwrap_or_default(),
+                calls: self.calls.clone(),
+            }))
+        }
+    }
+
+    struct ScriptedClient {
+        messages: Vec<Message>,
+        calls: Arc<Mutex<Vec<Call>>>,
+    }
+
+    impl AgentClient for ScriptedClient {
+        fn connect(&mut self) -> BoxFuture<'_, ClientResult<()>> {
+            self.calls.lock().push(Call::Connect);
+            Box::pin(async { Ok(()) })
+        }
+
+        fn query(&mut self, prompt: String) -> BoxFuture<'_, ClientResult<()>> {
+            self.calls.lock().push(Call::Query(prompt));
+            Box::pin(async { Ok(()) })
+        }
+
+        fn receive_response(&mut self) -> MessageStream<'_> {
+            stream::iter(std::mem::take(&mut self.messages).into_iter().map(Ok)).boxed()
+        }
+
+        fn disconnect(&mut self) -> BoxFuture<'_, ClientResult<()>> {
+            self.calls.lock().push(Call::Disconnect);
+            Box::pin(async { Ok(()) })
+        }
+    }
+}
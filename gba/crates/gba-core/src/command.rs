@@ -0,0 +1,310 @@
+//! Injectable process runner used by the git and gh helpers.
+//!
+//! The helpers take `&dyn CommandRunner` so tests can substitute a fake that
+//! records invocations and returns canned output instead of touching a real
+//! repository or the network.
+
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::{Command, ExitStatus, Output, Stdio};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use crate::error::{CoreError, Result};
+
+/// How often a command with a timeout is checked for having exited
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runs external programs
+pub trait CommandRunner: Send + Sync + fmt::Debug {
+    /// Run `program` with `args` in `cwd` and capture its output.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the program cannot be spawned. A nonzero exit
+    /// status is not an error at this level.
+    fn run(&self, program: &str, args: &[&str], cwd: &Path) -> Result<Output>;
+
+    /// Run `program` attached to the terminal (e.g. an editor) and wait for
+    /// it to exit.
+    ///
+    /// Defaults to [`CommandRunner::run`], which suits fakes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the program cannot be spawned.
+    fn run_interactive(&self, program: &str, args: &[&str], cwd: &Path) -> Result<ExitStatus> {
+        Ok(self.run(program, args, cwd)?.status)
+    }
+
+    /// Run `program` with `input` on its stdin and capture its output.
+    ///
+    /// Defaults to [`CommandRunner::run`], ignoring `input`, which suits
+    /// fakes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the program cannot be spawned or fed.
+    fn run_with_input(
+        &self,
+        program: &str,
+        args: &[&str],
+        input: &str,
+        cwd: &Path,
+    ) -> Result<Output> {
+        let _ = input;
+        self.run(program, args, cwd)
+    }
+
+    /// Run `program` with `env` added to its environment and capture its
+    /// output, killing it once `timeout` elapsed.
+    ///
+    /// Defaults to [`CommandRunner::run`], ignoring `env` and `timeout`,
+    /// which suits fakes.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::CommandTimedOut` if the program outlives
+    /// `timeout`, or an error if it cannot be spawned.
+    fn run_with_timeout(
+        &self,
+        program: &str,
+        args: &[&str],
+        cwd: &Path,
+        env: &[(&str, &str)],
+        timeout: Duration,
+    ) -> Result<Output> {
+        let _ = (env, timeout);
+        self.run(program, args, cwd)
+    }
+}
+
+/// `CommandRunner` backed by `std::process::Command`.
+///
+/// Arguments may carry secrets, so only their count is logged.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealCommandRunner;
+
+impl CommandRunner for RealCommandRunner {
+    fn run(&self, program: &str, args: &[&str], cwd: &Path) -> Result<Output> {
+        tracing::debug!(program, args = args.len(), cwd = %cwd.display(), "running command");
+        Ok(Command::new(program).args(args).current_dir(cwd).output()?)
+    }
+
+    fn run_interactive(&self, program: &str, args: &[&str], cwd: &Path) -> Result<ExitStatus> {
+        tracing::debug!(program, args = args.len(), cwd = %cwd.display(), "running interactive command");
+        Ok(Command::new(program).args(args).current_dir(cwd).status()?)
+    }
+
+    fn run_with_input(
+        &self,
+        program: &str,
+        args: &[&str],
+        input: &str,
+        cwd: &Path,
+    ) -> Result<Output> {
+        tracing::debug!(program, args = args.len(), cwd = %cwd.display(), "running command with input");
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(cwd)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input.as_bytes())?;
+        }
+        Ok(child.wait_with_output()?)
+    }
+
+    fn run_with_timeout(
+        &self,
+        program: &str,
+        args: &[&str],
+        cwd: &Path,
+        env: &[(&str, &str)],
+        timeout: Duration,
+    ) -> Result<Output> {
+        tracing::debug!(program, args = args.len(), cwd = %cwd.display(), ?timeout, "running command with timeout");
+        let mut child = Command::new(program)
+            .args(args)
+            .current_dir(cwd)
+            .envs(env.iter().copied())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        let stdout = read_pipe(child.stdout.take());
+        let stderr = read_pipe(child.stderr.take());
+        let deadline = Instant::now() + timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break status;
+            }
+            if Instant::now() >= deadline {
+                // Processes the command started may keep the pipes open, so
+                // their readers are left to finish on their own.
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(CoreError::CommandTimedOut {
+                    command: program.to_string(),
+                    timeout,
+                });
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        };
+        Ok(Output {
+            status,
+            stdout: stdout.join().unwrap_or_default(),
+            stderr: stderr.join().unwrap_or_default(),
+        })
+    }
+}
+
+/// Read `pipe` to its end on a thread of its own
+fn read_pipe(pipe: Option<impl Read + Send + 'static>) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            let _ = pipe.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Run a command and return its trimmed stdout, failing on nonzero exit.
+pub(crate) fn run_checked(
+    runner: &dyn CommandRunner,
+    program: &str,
+    args: &[&str],
+    cwd: &Path,
+) -> Result<String> {
+    let output = runner.run(program, args, cwd)?;
+    if !output.status.success() {
+        return Err(CoreError::CommandFailed {
+            command: format!("{program} {}", args.join(" ")),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+pub(crate) mod fake {
+    use std::os::unix::process::ExitStatusExt;
+    use std::path::PathBuf;
+
+    use parking_lot::Mutex;
+
+    use super::*;
+
+    /// A recorded invocation: program, args and working directory
+    pub(crate) type Invocation = (String, Vec<String>, PathBuf);
+
+    /// Fake runner returning queued responses (default: success, empty
+    /// output); a `None` response times out
+    #[derive(Debug, Default)]
+    pub(crate) struct FakeCommandRunner {
+        pub(crate) calls: Mutex<Vec<Invocation>>,
+        envs: Mutex<Vec<Vec<(String, String)>>>,
+        responses: Mutex<Vec<Option<(i32, String, String)>>>,
+    }
+
+    impl FakeCommandRunner {
+        /// Queue a response for the next unanswered invocation
+        pub(crate) fn respond(&self, code: i32, stdout: &str, stderr: &str) {
+            self.responses
+                .lock()
+                .push(Some((code, stdout.to_string(), stderr.to_string())));
+        }
+
+        /// Let the next unanswered invocation time out
+        pub(crate) fn time_out(&self) {
+            self.responses.lock().push(None);
+        }
+
+        pub(crate) fn calls(&self) -> Vec<Invocation> {
+            self.calls.lock().clone()
+        }
+
+        /// Environments passed to `run_with_timeout`, in call order
+        pub(crate) fn envs(&self) -> Vec<Vec<(String, String)>> {
+            self.envs.lock().clone()
+        }
+    }
+
+    impl CommandRunner for FakeCommandRunner {
+        fn run(&self, program: &str, args: &[&str], cwd: &Path) -> Result<Output> {
+            self.calls.lock().push((
+                program.to_string(),
+                args.iter().map(|a| a.to_string()).collect(),
+                cwd.to_path_buf(),
+            ));
+            let mut responses = self.responses.lock();
+            let response = if responses.is_empty() {
+                Some((0, String::new(), String::new()))
+            } else {
+                responses.remove(0)
+            };
+            let Some((code, stdout, stderr)) = response else {
+                return Err(CoreError::CommandTimedOut {
+                    command: program.to_string(),
+                    timeout: Duration::ZERO,
+                });
+            };
+            Ok(Output {
+                status: ExitStatus::from_raw(code << 8),
+                stdout: stdout.into_bytes(),
+                stderr: stderr.into_bytes(),
+            })
+        }
+
+        fn run_with_timeout(
+            &self,
+            program: &str,
+            args: &[&str],
+            cwd: &Path,
+            env: &[(&str, &str)],
+            _timeout: Duration,
+        ) -> Result<Output> {
+            self.envs.lock().push(
+                env.iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            );
+            self.run(program, args, cwd)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_with_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = RealCommandRunner;
+
+        let output = runner
+            .run_with_timeout(
+                "sh",
+                &["-c", "echo $GBA_PHASE; pwd >&2"],
+                dir.path(),
+                &[("GBA_PHASE", "build")],
+                Duration::from_secs(10),
+            )
+            .unwrap();
+        assert!(output.status.success());
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "build\n");
+        assert!(!output.stderr.is_empty());
+
+        let start = Instant::now();
+        let err = runner
+            .run_with_timeout("sleep", &["5"], dir.path(), &[], Duration::from_millis(100))
+            .unwrap_err();
+        assert!(matches!(err, CoreError::CommandTimedOut { command, .. } if command == "sleep"));
+        assert!(start.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,756 @@
+//! Project configuration loaded from `.gba/config.yml`.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CoreError, Result};
+use crate::executor::PhaseKind;
+use crate::hooks::PhaseHooks;
+use crate::mcp::McpServerMap;
+use crate::model::validate_model;
+use crate::permissions::PermissionsConfig;
+use crate::phase::dependency_order;
+
+/// Project configuration file name inside `.gba`
+pub const CONFIG_FILE: &str = "config.yml";
+
+/// Prompt templates directory inside `.gba`
+pub const PROMPTS_DIR: &str = "prompts";
+
+/// Default per-phase response timeout
+pub const DEFAULT_TIMEOUT_SECONDS: u64 = 300;
+
+/// Agent output kept per request unless `agent.maxOutputBytes` says otherwise
+pub const DEFAULT_MAX_OUTPUT_BYTES: usize = 4 * 1024 * 1024;
+
+/// Estimated prompt tokens allowed unless `agent.maxPromptTokens` says
+/// otherwise, leaving room in a 200k context for the agent's work
+pub const DEFAULT_MAX_PROMPT_TOKENS: usize = 150_000;
+
+/// Attempts per phase unless `agent.maxAttempts` says otherwise
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+
+/// `config.yml` written by `gba init`; parses to [`ProjectConfig::default`]
+pub const DEFAULT_CONFIG: &str = r#"# GBA project configuration
+version: "0.1.0"
+
+agent:
+  apiKeyEnv: ANTHROPIC_API_KEY
+  # Read the key from a file instead; without the file or the variable the
+  # keychain entry stored by `gba auth set` is used, then the Claude CLI login
+  # apiKeyFile: ~/.config/gba/api-key
+  # apiKeyKeychain: gba
+  model: claude-sonnet-4-5
+  permissionMode: bypassPermissions
+  timeoutSeconds: 300
+  maxAttempts: 3
+  # Agent text kept per phase; with stopOnOutputLimit the agent is stopped
+  # once it writes more
+  # maxOutputBytes: 4194304
+  # stopOnOutputLimit: false
+  # Estimated tokens a rendered prompt may have (0 = no limit); larger ones
+  # fail the phase, or only log a warning with warnOnLargePrompt
+  # maxPromptTokens: 150000
+  # warnOnLargePrompt: false
+  # Variables set for the agent (${VAR} expands gba's environment), and
+  # inherited ones removed for it; phases may add their own env/envDeny
+  # env:
+  #   DATABASE_URL: postgres://localhost/${DB_NAME}
+  # envDeny: ["AWS_*"]
+
+# Each phase runs prompts/{name}/system.md and user.md, which see the
+# summaries of earlier phases as `previous`; `inputs: [design.md]` adds
+# files of the feature directory
+phases:
+  - name: observe
+    description: Observe codebase and understand context
+  - name: build
+    description: Build implementation
+  - name: test
+    description: Write and run tests
+  - name: verification
+    description: Verify implementation against requirements
+  - name: review
+    description: Code review and refinement
+  - name: pr
+    description: Create pull request
+
+# Notify when a run completes, fails or is interrupted
+notifications:
+  desktop: false
+  # webhookUrl: https://example.com/hooks/gba
+
+# Refuse to run phases on a working tree with uncommitted changes; useWorktree
+# runs each feature in its own worktree under .trees/, autoCommit commits after
+# every phase so `gba diff` and `gba rollback` can tell the phases apart
+git:
+  allowDirty: false
+  useWorktree: true
+  autoCommit: true
+
+# Summarize each phase's output with a cheap model instead of keeping its
+# first line; the summaries' cost is tracked as summaryCostUsd
+summaries:
+  enabled: false
+  model: claude-haiku-4-5
+
+# A feature whose specs are shorter than minLength characters (headings
+# aside) or still hold a scaffold line is a draft and won't run without
+# --force
+specs:
+  minLength: 40
+  draftMarkers:
+    - "TODO: describe the feature."
+    - "TODO: components, data flow and key decisions."
+    - "- TODO"
+    - "- [ ] TODO"
+
+# Pace agent requests of one gba process; requests rejected as rate-limited
+# are retried with backoff up to maxRetries times
+# rateLimit:
+#   requestsPerMinute: 50
+#   maxConcurrentExecutions: 2
+#   maxRetries: 3
+
+# MCP servers available to the agent; tasks refer to their tools as
+# mcp__<server>__<tool> and may add servers in their own config.yml
+# mcpServers:
+#   docs:
+#     command: docs-mcp
+#     args: [--stdio]
+#     env:
+#       DOCS_TOKEN: secret
+#   search:
+#     url: https://mcp.example.com/search
+#     type: http
+
+# Check every tool use: autoAllow tools run, others are asked about on the
+# terminal (askFor always), or denied when nobody can be asked
+# permissions:
+#   autoAllow: [Read, Grep, Glob]
+#   askFor: [Bash, Write]
+
+# Environment variables templates may read with env("NAME"); reading any
+# other is an error, so secrets can't end up in a prompt by accident
+# templateEnvAllowlist: [CI_JOB_URL]
+"#;
+
+/// Project configuration (`.gba/config.yml`)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct ProjectConfig {
+    /// Configuration format version
+    pub version: String,
+    /// Agent settings
+    pub agent: AgentConfig,
+    /// Phase execution order
+    pub phases: Vec<PhaseConfig>,
+    /// Where to report finished runs
+    pub notifications: NotificationConfig,
+    /// Git safety and worktree settings
+    pub git: GitConfig,
+    /// When a feature's specs count as a draft
+    pub specs: SpecsConfig,
+    /// Phase output summaries
+    pub summaries: SummariesConfig,
+    /// Pacing of agent requests
+    pub rate_limit: RateLimitConfig,
+    /// MCP servers available to the agent
+    #[serde(skip_serializing_if = "McpServerMap::is_empty")]
+    pub mcp_servers: McpServerMap,
+    /// Approval of the agent's tool uses
+    pub permissions: PermissionsConfig,
+    /// Environment variables templates may read with `env(name)`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub template_env_allowlist: Vec<String>,
+}
+
+/// Git settings (`git:` section)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct GitConfig {
+    /// Run phases even if the working tree has uncommitted changes
+    pub allow_dirty: bool,
+    /// Run each feature in its own worktree under `.trees/`, on a branch of
+    /// its own
+    pub use_worktree: bool,
+    /// Commit the changes of every completed phase and record the commit in
+    /// the phase's state
+    pub auto_commit: bool,
+}
+
+impl Default for GitConfig {
+    fn default() -> Self {
+        Self {
+            allow_dirty: false,
+            use_worktree: true,
+            auto_commit: true,
+        }
+    }
+}
+
+/// Output summary settings (`summaries:` section)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct SummariesConfig {
+    /// Ask `model` for a summary after every completed phase
+    pub enabled: bool,
+    /// Model writing the summaries
+    pub model: String,
+}
+
+impl Default for SummariesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            model: "claude-haiku-4-5".to_string(),
+        }
+    }
+}
+
+/// Agent request pacing (`rateLimit:` section)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct RateLimitConfig {
+    /// Requests started per minute (None = unlimited)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requests_per_minute: Option<u32>,
+    /// Requests running at once (None = unlimited)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_executions: Option<usize>,
+    /// Retries of a request rejected as rate-limited
+    pub max_retries: u32,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            requests_per_minute: None,
+            max_concurrent_executions: None,
+            max_retries: 3,
+        }
+    }
+}
+
+/// Spec readiness settings (`specs:` section)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct SpecsConfig {
+    /// Fewest characters a spec needs besides headings and marker lines
+    pub min_length: usize,
+    /// Lines (compared trimmed) left by the scaffold until the spec is edited
+    pub draft_markers: Vec<String>,
+}
+
+impl Default for SpecsConfig {
+    fn default() -> Self {
+        Self {
+            min_length: 40,
+            draft_markers: [
+                "TODO: describe the feature.",
+                "TODO: components, data flow and key decisions.",
+                "- TODO",
+                "- [ ] TODO",
+            ]
+            .map(String::from)
+            .to_vec(),
+        }
+    }
+}
+
+/// Notification settings (`notifications:` section)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct NotificationConfig {
+    /// Show a desktop notification (`notify-send` or `osascript`)
+    pub desktop: bool,
+    /// URL that receives a JSON POST for every finished run
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+}
+
+/// Agent settings (`agent:` section)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct AgentConfig {
+    /// Environment variable holding the API key
+    pub api_key_env: String,
+    /// File holding the API key; tried before `apiKeyEnv` and the keychain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_file: Option<PathBuf>,
+    /// Keychain service holding the API key (None = `gba`); tried after `apiKeyEnv`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_key_keychain: Option<String>,
+    /// Default Claude model
+    pub model: String,
+    /// How the agent may use tools
+    pub permission_mode: ConfigPermissionMode,
+    /// Maximum agent turns per phase (None = SDK default)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_turns: Option<u32>,
+    /// Response timeout applied to phases without their own
+    pub timeout_seconds: u64,
+    /// How often a phase may be attempted before `gba retry` gives up
+    pub max_attempts: u32,
+    /// Bytes of agent text kept per phase; the rest is dropped
+    pub max_output_bytes: usize,
+    /// Stop the agent once it exceeds `max_output_bytes`
+    pub stop_on_output_limit: bool,
+    /// Estimated tokens a rendered prompt may have (0 = no limit)
+    pub max_prompt_tokens: usize,
+    /// Only warn about prompts over `max_prompt_tokens` instead of failing
+    pub warn_on_large_prompt: bool,
+    /// Variables set in the agent's environment, `${VAR}` expanded
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Inherited variables (names or `*` globs) removed for the agent
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub env_deny: Vec<String>,
+}
+
+/// Tool permission mode of the agent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigPermissionMode {
+    /// Ask before using tools
+    Default,
+    /// Accept file edits automatically
+    AcceptEdits,
+    /// Plan only, don't execute tools
+    Plan,
+    /// Allow all tools without asking (required for unattended runs)
+    #[default]
+    BypassPermissions,
+}
+
+impl ConfigPermissionMode {
+    /// All modes, in order of increasing autonomy
+    pub const ALL: [Self; 4] = [
+        Self::Plan,
+        Self::Default,
+        Self::AcceptEdits,
+        Self::BypassPermissions,
+    ];
+}
+
+impl std::str::FromStr for ConfigPermissionMode {
+    type Err = CoreError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.to_string() == s)
+            .ok_or_else(|| {
+                let allowed: Vec<String> = Self::ALL.iter().map(ToString::to_string).collect();
+                CoreError::ConfigError(format!(
+                    "unknown permission mode `{s}` (expected one of: {})",
+                    allowed.join(", ")
+                ))
+            })
+    }
+}
+
+impl std::fmt::Display for ConfigPermissionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Default => "default",
+            Self::AcceptEdits => "acceptEdits",
+            Self::Plan => "plan",
+            Self::BypassPermissions => "bypassPermissions",
+        };
+        f.write_str(s)
+    }
+}
+
+/// A phase entry of the `phases:` list
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PhaseConfig {
+    /// Phase name, also the task directory under `prompts/`
+    pub name: String,
+    /// Human readable description
+    #[serde(default)]
+    pub description: String,
+    /// Whether the agent or `command` executes the phase
+    #[serde(default, skip_serializing_if = "is_agent")]
+    pub kind: PhaseKind,
+    /// Shell command run by a `kind: command` phase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Response timeout overriding `agent.timeoutSeconds`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_seconds: Option<u64>,
+    /// Phases that must complete first (empty = only list order applies)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// Hooks replacing those of the task's `config.yml`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<PhaseHooks>,
+    /// Whether the phase runs; disabled phases are recorded as skipped
+    #[serde(default = "enabled", skip_serializing_if = "is_enabled")]
+    pub enabled: bool,
+    /// Variables set for the agent on top of `agent.env`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Variables removed for the agent on top of `agent.envDeny`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_deny: Vec<String>,
+    /// Files of the feature directory (e.g. `design.md`) given to the
+    /// prompts as `inputs`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub inputs: Vec<PathBuf>,
+}
+
+fn enabled() -> bool {
+    true
+}
+
+fn is_enabled(enabled: &bool) -> bool {
+    *enabled
+}
+
+fn is_agent(kind: &PhaseKind) -> bool {
+    *kind == PhaseKind::Agent
+}
+
+impl Default for ProjectConfig {
+    fn default() -> Self {
+        let phase = |name: &str, description: &str| PhaseConfig {
+            name: name.to_string(),
+            description: description.to_string(),
+            kind: PhaseKind::Agent,
+            command: None,
+            timeout_seconds: None,
+            depends_on: Vec::new(),
+            hooks: None,
+            enabled: true,
+            env: BTreeMap::new(),
+            env_deny: Vec::new(),
+            inputs: Vec::new(),
+        };
+        Self {
+            version: "0.1.0".to_string(),
+            agent: AgentConfig::default(),
+            phases: vec![
+                phase("observe", "Observe codebase and understand context"),
+                phase("build", "Build implementation"),
+                phase("test", "Write and run tests"),
+                phase("verification", "Verify implementation against requirements"),
+                phase("review", "Code review and refinement"),
+                phase("pr", "Create pull request"),
+            ],
+            notifications: NotificationConfig::default(),
+            git: GitConfig::default(),
+            specs: SpecsConfig::default(),
+            summaries: SummariesConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            mcp_servers: McpServerMap::new(),
+            permissions: PermissionsConfig::default(),
+            template_env_allowlist: Vec::new(),
+        }
+    }
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            api_key_env: "ANTHROPIC_API_KEY".to_string(),
+            api_key_file: None,
+            api_key_keychain: None,
+            model: "claude-sonnet-4-5".to_string(),
+            permission_mode: ConfigPermissionMode::default(),
+            max_turns: None,
+            timeout_seconds: DEFAULT_TIMEOUT_SECONDS,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+            stop_on_output_limit: false,
+            max_prompt_tokens: DEFAULT_MAX_PROMPT_TOKENS,
+            warn_on_large_prompt: false,
+            env: BTreeMap::new(),
+            env_deny: Vec::new(),
+        }
+    }
+}
+
+impl ProjectConfig {
+    /// Load `config.yml` from the `.gba` directory.
+    ///
+    /// A missing file yields the default configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load(gba_path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(gba_path.join(CONFIG_FILE)) {
+            Ok(content) => Self::from_yaml(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Parse a project configuration from YAML text.
+    ///
+    /// Unknown keys are rejected rather than ignored, so a typo doesn't
+    /// silently fall back to a default.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the YAML is malformed, or `CoreError::ConfigError`
+    /// naming an unknown key, its line and the closest valid key.
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        let config: Option<Self> = serde_yaml::from_str(content).map_err(parse_error)?;
+        Ok(config.unwrap_or_default())
+    }
+
+    /// Check that required fields are set and phase names are unique.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::ConfigError` listing every problem found.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+        if self.agent.model.trim().is_empty() {
+            problems.push("agent.model is empty".to_string());
+        } else if let Err(CoreError::ConfigError(e)) = validate_model(&self.agent.model) {
+            problems.push(format!("agent.model: {e}"));
+        }
+        if self.summaries.enabled
+            && let Err(CoreError::ConfigError(e)) = validate_model(&self.summaries.model)
+        {
+            problems.push(format!("summaries.model: {e}"));
+        }
+        if self.agent.timeout_seconds == 0 {
+            problems.push("agent.timeoutSeconds must be greater than 0".to_string());
+        }
+        if self.agent.max_attempts == 0 {
+            problems.push("agent.maxAttempts must be greater than 0".to_string());
+        }
+        if self.phases.is_empty() {
+            problems.push("no phases configured".to_string());
+        }
+        for (idx, phase) in self.phases.iter().enumerate() {
+            if phase.name.trim().is_empty() {
+                problems.push(format!("phases[{idx}] has no name"));
+            } else if self.phases[..idx].iter().any(|p| p.name == phase.name) {
+                problems.push(format!("phase `{}` is listed twice", phase.name));
+            }
+            match (phase.kind, &phase.command) {
+                (PhaseKind::Command, None) => problems.push(format!(
+                    "phase `{}` is a command phase without a command",
+                    phase.name
+                )),
+                (PhaseKind::Agent, Some(_)) => problems.push(format!(
+                    "phase `{}` has a command but isn't `kind: command`",
+                    phase.name
+                )),
+                _ => {}
+            }
+            if phase.timeout_seconds == Some(0) {
+                problems.push(format!(
+                    "phase `{}` timeoutSeconds must be greater than 0",
+                    phase.name
+                ));
+            }
+        }
+
+        if let Err(CoreError::ConfigError(e)) = self.execution_order() {
+            problems.push(e);
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(CoreError::ConfigError(problems.join("; ")))
+        }
+    }
+
+    /// Indices of `phases` in dependency order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::ConfigError` on unknown or cyclic dependencies.
+    pub fn execution_order(&self) -> Result<Vec<usize>> {
+        dependency_order(
+            self.phases
+                .iter()
+                .map(|p| (p.name.as_str(), p.depends_on.as_slice())),
+        )
+    }
+
+    /// Look up a phase entry by name
+    pub fn phase(&self, name: &str) -> Option<&PhaseConfig> {
+        self.phases.iter().find(|p| p.name == name)
+    }
+}
+
+/// Describe an unknown key by its dotted path and line, suggesting the
+/// closest valid key; other errors are passed through
+fn parse_error(e: serde_yaml::Error) -> CoreError {
+    // serde reports `agent: unknown field `maxTurn`, expected one of ...`
+    let message = e.to_string();
+    let Some((context, rest)) = message.split_once("unknown field `") else {
+        return e.into();
+    };
+    let Some((key, rest)) = rest.split_once('`') else {
+        return e.into();
+    };
+    let rest = rest.split(" at line ").next().unwrap_or(rest);
+    let valid: Vec<&str> = rest.split('`').skip(1).step_by(2).collect();
+
+    let mut problem = match context.strip_suffix(": ") {
+        Some(path) => format!("unknown key `{path}.{key}`"),
+        None => format!("unknown key `{key}`"),
+    };
+    if let Some(location) = e.location() {
+        problem.push_str(&format!(" at line {}", location.line()));
+    }
+    match closest_key(key, &valid) {
+        Some(suggestion) => problem.push_str(&format!(" (did you mean `{suggestion}`?)")),
+        None => problem.push_str(&format!(" (valid keys: {})", valid.join(", "))),
+    }
+    CoreError::ConfigError(problem)
+}
+
+/// Valid key closest to `key` by edit distance, if it is close enough to be
+/// a likely typo
+pub(crate) fn closest_key<'a>(key: &str, valid: &[&'a str]) -> Option<&'a str> {
+    let key = key.to_lowercase();
+    valid
+        .iter()
+        .map(|candidate| {
+            (
+                strsim::levenshtein(&key, &candidate.to_lowercase()),
+                *candidate,
+            )
+        })
+        .filter(|(distance, _)| *distance <= (key.len() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phase_timeout_override() {
+        let yaml = r#"
+agent:
+  timeoutSeconds: 120
+phases:
+  - name: observe
+    description: Observe codebase
+    timeoutSeconds: 60
+  - name: build
+    description: Build implementation
+    timeoutSeconds: 1800
+  - name: pr
+    enabled: false
+"#;
+        let config = ProjectConfig::from_yaml(yaml).unwrap();
+
+        assert_eq!(config.agent.timeout_seconds, 120);
+        assert_eq!(config.agent.model, "claude-sonnet-4-5");
+        assert_eq!(config.phase("build").unwrap().timeout_seconds, Some(1800));
+        assert_eq!(config.phase("pr").unwrap().timeout_seconds, None);
+        assert!(config.phase("build").unwrap().enabled);
+        assert!(!config.phase("pr").unwrap().enabled);
+        assert_eq!(config.phases.len(), 3);
+    }
+
+    #[test]
+    fn test_validate_reports_problems() {
+        assert!(ProjectConfig::default().validate().is_ok());
+
+        let yaml = "agent:\n  model: ''\nphases:\n  - name: build\n  - name: build\n";
+        let err = ProjectConfig::from_yaml(yaml)
+            .unwrap()
+            .validate()
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("agent.model is empty"));
+        assert!(err.contains("phase `build` is listed twice"));
+
+        let yaml =
+            "phases:\n  - name: lint\n    kind: command\n  - name: build\n    command: make\n";
+        let err = ProjectConfig::from_yaml(yaml)
+            .unwrap()
+            .validate()
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("phase `lint` is a command phase without a command"));
+        assert!(err.contains("phase `build` has a command but isn't `kind: command`"));
+    }
+
+    #[test]
+    fn test_parse_permission_mode() {
+        assert_eq!(
+            "acceptEdits".parse::<ConfigPermissionMode>().unwrap(),
+            ConfigPermissionMode::AcceptEdits
+        );
+        let err = "bypass".parse::<ConfigPermissionMode>().unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("plan, default, acceptEdits, bypassPermissions")
+        );
+    }
+
+    #[test]
+    fn test_default_config_file_matches_default() {
+        assert_eq!(
+            ProjectConfig::from_yaml(DEFAULT_CONFIG).unwrap(),
+            ProjectConfig::default()
+        );
+    }
+
+    #[test]
+    fn test_missing_config_is_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ProjectConfig::load(dir.path()).unwrap();
+        assert_eq!(config.agent.timeout_seconds, DEFAULT_TIMEOUT_SECONDS);
+        assert_eq!(config.phases[0].name, "observe");
+    }
+
+    #[test]
+    fn test_unknown_keys_are_reported() {
+        let cases = [
+            (
+                "agent:\n  model: claude-opus-4\n  maxTurn: 40\n",
+                "unknown key `agent.maxTurn` at line 3 (did you mean `maxTurns`?)",
+            ),
+            (
+                "version: \"0.1.0\"\npermisionMode: plan\n",
+                "unknown key `permisionMode` at line 2 (valid keys: version, agent, phases, notifications, git, specs, summaries, rateLimit, mcpServers, permissions, templateEnvAllowlist)",
+            ),
+            (
+                "phases:\n  - name: review\n    dependOn: [build]\n",
+                "unknown key `phases[0].dependOn` at line 3 (did you mean `dependsOn`?)",
+            ),
+            (
+                "phases:\n  - name: build\n    hooks:\n      preCommands: [cargo fmt]\n",
+                "unknown key `phases[0].hooks.preCommands` at line 4 (did you mean `preCommand`?)",
+            ),
+            (
+                "notifications:\n  slack: https://example.com\n",
+                "unknown key `notifications.slack` at line 2 (valid keys: desktop, webhookUrl)",
+            ),
+        ];
+        for (yaml, expected) in cases {
+            match ProjectConfig::from_yaml(yaml) {
+                Err(CoreError::ConfigError(msg)) => assert_eq!(msg, expected),
+                other => panic!("expected a config error for {yaml:?}, got {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_closest_key() {
+        let valid = ["apiKeyEnv", "model", "permissionMode", "maxTurns"];
+        assert_eq!(closest_key("permisionMode", &valid), Some("permissionMode"));
+        assert_eq!(closest_key("MODEL", &valid), Some("model"));
+        assert_eq!(closest_key("retries", &valid), None);
+    }
+}
@@ -0,0 +1,239 @@
+//! Cost reporting over feature state.
+
+use serde::{Deserialize, Serialize};
+
+use crate::state::{ExecutionStats, FeatureState, FeatureStatus};
+
+/// Spend of a feature (or a set of features) with a per-phase breakdown
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostReport {
+    /// Totals across all phases
+    pub total: ExecutionStats,
+    /// Per-phase totals, in first-seen order
+    pub phases: Vec<PhaseCost>,
+}
+
+/// Spend of a single phase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseCost {
+    /// Phase name
+    pub phase: String,
+    /// Accumulated statistics
+    pub stats: ExecutionStats,
+}
+
+/// Spend of each feature plus a grand total
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostSummary {
+    /// One row per feature, in the order given
+    pub features: Vec<FeatureCost>,
+    /// Sum over all rows
+    pub total: ExecutionStats,
+}
+
+/// Spend of a single feature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureCost {
+    /// Feature directory name (`{id}_{slug}`)
+    pub feature: String,
+    /// Feature status
+    pub status: FeatureStatus,
+    /// Accumulated statistics of the feature
+    pub stats: ExecutionStats,
+}
+
+/// Spend of all phases run with one model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelCost {
+    /// Model name, or `unknown` for phases recorded without one
+    pub model: String,
+    /// Number of phases run with the model
+    pub phases: usize,
+    /// Accumulated statistics
+    pub stats: ExecutionStats,
+}
+
+impl CostReport {
+    /// Sum several reports, merging phases with the same name
+    pub fn aggregate<'a>(reports: impl IntoIterator<Item = &'a CostReport>) -> Self {
+        let mut total = Self::default();
+        for report in reports {
+            for phase in &report.phases {
+                total.add_phase(&phase.phase, &phase.stats);
+            }
+        }
+        total
+    }
+
+    fn add_phase(&mut self, name: &str, stats: &ExecutionStats) {
+        self.total.accumulate(stats);
+        match self.phases.iter_mut().find(|p| p.phase == name) {
+            Some(phase) => phase.stats.accumulate(stats),
+            None => self.phases.push(PhaseCost {
+                phase: name.to_string(),
+                stats: stats.clone(),
+            }),
+        }
+    }
+}
+
+impl FeatureState {
+    /// Summarize `total_stats` of several features with a grand total
+    pub fn aggregate<'a>(states: impl IntoIterator<Item = &'a FeatureState>) -> CostSummary {
+        let mut summary = CostSummary::default();
+        for state in states {
+            summary.total.accumulate(&state.total_stats);
+            summary.features.push(FeatureCost {
+                feature: state.dir_name(),
+                status: state.status,
+                stats: state.total_stats.clone(),
+            });
+        }
+        summary
+    }
+
+    /// Cost report of this feature; phases without stats count as zero
+    pub fn cost_report(&self) -> CostReport {
+        let mut report = CostReport::default();
+        for phase in &self.phases {
+            report.add_phase(&phase.name, &phase.stats.clone().unwrap_or_default());
+        }
+        report
+    }
+
+    /// Spend of several features grouped by the model each phase ran with.
+    ///
+    /// Phases without stats are skipped. Models are in first-seen order.
+    pub fn cost_by_model<'a>(states: impl IntoIterator<Item = &'a FeatureState>) -> Vec<ModelCost> {
+        let mut models: Vec<ModelCost> = Vec::new();
+        let phases = states.into_iter().flat_map(|state| &state.phases);
+        for phase in phases {
+            let Some(stats) = &phase.stats else {
+                continue;
+            };
+            let model = phase.model.as_deref().unwrap_or("unknown");
+            match models.iter_mut().find(|m| m.model == model) {
+                Some(row) => {
+                    row.phases += 1;
+                    row.stats.accumulate(stats);
+                }
+                None => models.push(ModelCost {
+                    model: model.to_string(),
+                    phases: 1,
+                    stats: stats.clone(),
+                }),
+            }
+        }
+        models
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(turns: u32, input: u64, output: u64, cost_usd: f64) -> ExecutionStats {
+        ExecutionStats {
+            turns,
+            input_tokens: input,
+            output_tokens: output,
+            cost_usd,
+            ..ExecutionStats::default()
+        }
+    }
+
+    fn feature(id: &str, phases: &[(&str, Option<ExecutionStats>)]) -> FeatureState {
+        let mut state = FeatureState::new(id, "demo");
+        for (name, stats) in phases {
+            state.phase_mut(name).stats = stats.clone();
+        }
+        state
+    }
+
+    #[test]
+    fn test_cost_report_multi_phase() {
+        let state = feature(
+            "0001",
+            &[
+                ("observe", Some(stats(8, 12_500, 8_300, 0.42))),
+                ("build", Some(stats(15, 45_000, 32_000, 0.89))),
+                ("test", None),
+            ],
+        );
+
+        let report = state.cost_report();
+
+        assert_eq!(report.total.turns, 23);
+        assert_eq!(report.total.input_tokens, 57_500);
+        assert_eq!(report.total.output_tokens, 40_300);
+        assert!((report.total.cost_usd - 1.31).abs() < 1e-9);
+        assert_eq!(report.phases.len(), 3);
+        assert_eq!(report.phases[2].stats, ExecutionStats::default());
+    }
+
+    #[test]
+    fn test_feature_state_aggregate() {
+        let mut a = FeatureState::new("0001", "auth");
+        a.total_stats = stats(45, 100_500, 69_300, 2.35);
+        a.status = FeatureStatus::Completed;
+        let mut b = FeatureState::new("0002", "search");
+        b.total_stats = stats(5, 1_000, 500, 0.15);
+
+        let summary = FeatureState::aggregate([&a, &b]);
+
+        assert_eq!(summary.features.len(), 2);
+        assert_eq!(summary.features[0].feature, "0001_auth");
+        assert_eq!(summary.features[0].status, FeatureStatus::Completed);
+        assert_eq!(summary.total.turns, 50);
+        assert_eq!(summary.total.input_tokens, 101_500);
+        assert!((summary.total.cost_usd - 2.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_aggregate_two_features() {
+        let a = feature("0001", &[("build", Some(stats(10, 100, 50, 1.0)))]);
+        let b = feature(
+            "0002",
+            &[
+                ("observe", Some(stats(2, 10, 5, 0.25))),
+                ("build", Some(stats(5, 40, 20, 0.5))),
+            ],
+        );
+
+        let report = CostReport::aggregate(&[a.cost_report(), b.cost_report()]);
+
+        assert_eq!(report.total.turns, 17);
+        assert!((report.total.cost_usd - 1.75).abs() < 1e-9);
+        let phases: Vec<_> = report.phases.iter().map(|p| p.phase.as_str()).collect();
+        assert_eq!(phases, ["build", "observe"]);
+        assert_eq!(report.phases[0].stats.turns, 15);
+    }
+
+    #[test]
+    fn test_cost_by_model() {
+        let mut a = feature(
+            "0001",
+            &[
+                ("observe", Some(stats(2, 10, 5, 0.25))),
+                ("build", Some(stats(10, 100, 50, 1.0))),
+                ("test", None),
+            ],
+        );
+        a.phase_mut("build").model = Some("claude-opus-4".to_string());
+        let mut b = feature("0002", &[("build", Some(stats(5, 40, 20, 0.5)))]);
+        b.phase_mut("build").model = Some("claude-opus-4".to_string());
+
+        let models = FeatureState::cost_by_model([&a, &b]);
+
+        let names: Vec<_> = models.iter().map(|m| m.model.as_str()).collect();
+        assert_eq!(names, ["unknown", "claude-opus-4"]);
+        assert_eq!(models[1].phases, 2);
+        assert_eq!(models[1].stats.turns, 15);
+        assert!((models[1].stats.cost_usd - 1.5).abs() < 1e-9);
+    }
+}
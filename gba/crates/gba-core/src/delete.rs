@@ -0,0 +1,186 @@
+//! Deleting an abandoned feature together with its worktree and branch.
+//!
+//! Every step skips pieces that are already gone, so deleting a half-removed
+//! feature finishes the job instead of failing.
+
+use std::path::{Path, PathBuf};
+
+use crate::command::CommandRunner;
+use crate::error::{CoreError, Result};
+use crate::git;
+use crate::state::{FeatureState, FeatureStatus};
+
+/// Everything `gba delete` would remove
+#[derive(Debug, Clone)]
+pub struct DeletePlan {
+    /// Feature directory under `.gba/features`
+    pub feature_dir: PathBuf,
+    /// Feature state (None if `state.yml` is missing or unreadable)
+    pub state: Option<FeatureState>,
+    /// Worktree directory (relative to the repository) if it still exists
+    pub worktree: Option<PathBuf>,
+    /// Feature branch if it should be and still can be deleted
+    pub branch: Option<String>,
+}
+
+impl DeletePlan {
+    /// Work out what deleting `feature` involves.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::FeatureNotFound` if the feature directory is gone.
+    pub fn new(
+        runner: &dyn CommandRunner,
+        repo: &Path,
+        gba_path: &Path,
+        feature: &str,
+        delete_branch: bool,
+    ) -> Result<Self> {
+        let feature_dir = FeatureState::find_dir(gba_path, feature)?;
+        let state = FeatureState::load(&feature_dir).ok();
+        let git_info = state.as_ref().and_then(|s| s.git.as_ref());
+
+        let worktree = git_info
+            .map(|g| g.worktree_path.clone())
+            .filter(|path| repo.join(path).exists());
+        let branch = match git_info {
+            Some(g) if delete_branch && git::branch_exists(runner, repo, &g.branch)? => {
+                Some(g.branch.clone())
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            feature_dir,
+            state,
+            worktree,
+            branch,
+        })
+    }
+
+    /// Whether the feature is still running
+    pub fn in_progress(&self) -> bool {
+        self.state
+            .as_ref()
+            .is_some_and(|s| s.status == FeatureStatus::InProgress)
+    }
+
+    /// Remove the worktree, branch and feature directory.
+    ///
+    /// Returns a description of each removed item.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::FeatureInProgress` for a running feature unless
+    /// `force` is set, or the first git/filesystem error.
+    pub fn execute(
+        &self,
+        runner: &dyn CommandRunner,
+        repo: &Path,
+        force: bool,
+    ) -> Result<Vec<String>> {
+        if self.in_progress() && !force {
+            let name = self.feature_dir.file_name().unwrap_or_default();
+            return Err(CoreError::FeatureInProgress(
+                name.to_string_lossy().into_owned(),
+            ));
+        }
+
+        let mut removed = Vec::new();
+        if let Some(worktree) = &self.worktree
+            && repo.join(worktree).exists()
+        {
+            git::worktree_remove(runner, repo, worktree, true)?;
+            removed.push(format!("worktree {}", worktree.display()));
+        }
+        if let Some(branch) = &self.branch {
+            git::branch_delete(runner, repo, branch)?;
+            removed.push(format!("branch {branch}"));
+        }
+        if self.feature_dir.exists() {
+            std::fs::remove_dir_all(&self.feature_dir)?;
+            removed.push(format!("feature directory {}", self.feature_dir.display()));
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::fake::FakeCommandRunner;
+    use crate::state::{FEATURES_DIR, GitInfo};
+
+    fn setup(status: FeatureStatus, with_worktree: bool) -> (tempfile::TempDir, PathBuf) {
+        let repo = tempfile::tempdir().unwrap();
+        let gba = repo.path().join(".gba");
+        let mut state = FeatureState::new("0001", "auth");
+        state.status = status;
+        state.git = Some(GitInfo {
+            worktree_path: PathBuf::from(".trees/0001_auth"),
+            branch: "feature/0001-auth".to_string(),
+            base_branch: "main".to_string(),
+            base_commit: "abc1234".to_string(),
+        });
+        let feature = gba.join(FEATURES_DIR).join(state.dir_name());
+        std::fs::create_dir_all(&feature).unwrap();
+        state.save(&feature).unwrap();
+        if with_worktree {
+            std::fs::create_dir_all(repo.path().join(".trees/0001_auth")).unwrap();
+        }
+        (repo, gba)
+    }
+
+    #[test]
+    fn test_delete_removes_worktree_branch_and_dir() {
+        let (repo, gba) = setup(FeatureStatus::Failed, true);
+        let runner = FakeCommandRunner::default();
+
+        let plan = DeletePlan::new(&runner, repo.path(), &gba, "0001", true).unwrap();
+        let removed = plan.execute(&runner, repo.path(), false).unwrap();
+
+        assert_eq!(removed.len(), 3);
+        assert!(removed[0].starts_with("worktree"));
+        assert_eq!(removed[1], "branch feature/0001-auth");
+        let calls = runner.calls();
+        assert_eq!(
+            calls[1].1,
+            ["worktree", "remove", "--force", ".trees/0001_auth"]
+        );
+        assert_eq!(calls[2].1, ["branch", "-D", "feature/0001-auth"]);
+        assert!(!plan.feature_dir.exists());
+    }
+
+    #[test]
+    fn test_delete_skips_missing_pieces() {
+        let (repo, gba) = setup(FeatureStatus::Failed, false);
+        let runner = FakeCommandRunner::default();
+        // The branch lookup fails: it was already deleted.
+        runner.respond(1, "", "");
+
+        let plan = DeletePlan::new(&runner, repo.path(), &gba, "auth", true).unwrap();
+        let removed = plan.execute(&runner, repo.path(), false).unwrap();
+
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].starts_with("feature directory"));
+        assert!(matches!(
+            DeletePlan::new(&runner, repo.path(), &gba, "auth", true),
+            Err(CoreError::FeatureNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_delete_in_progress_requires_force() {
+        let (repo, gba) = setup(FeatureStatus::InProgress, false);
+        let runner = FakeCommandRunner::default();
+        let plan = DeletePlan::new(&runner, repo.path(), &gba, "auth", false).unwrap();
+
+        assert!(matches!(
+            plan.execute(&runner, repo.path(), false),
+            Err(CoreError::FeatureInProgress(_))
+        ));
+        assert!(plan.feature_dir.exists());
+        plan.execute(&runner, repo.path(), true).unwrap();
+        assert!(!plan.feature_dir.exists());
+    }
+}
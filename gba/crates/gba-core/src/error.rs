@@ -0,0 +1,148 @@
+//! Error types for the GBA core engine.
+
+use thiserror::Error;
+
+/// Errors produced by the GBA core engine
+#[derive(Debug, Error)]
+pub enum CoreError {
+    /// Agent (or a step wrapped around it, such as a hook) failed
+    #[error("Agent execution failed: {0}")]
+    AgentExecutionFailed(String),
+
+    /// Agent didn't finish responding within the timeout
+    #[error("Agent timed out after {duration:?}")]
+    AgentTimeout {
+        /// Timeout that elapsed
+        duration: std::time::Duration,
+        /// Text received before the timeout
+        partial: String,
+    },
+
+    /// Configuration could not be parsed or is invalid
+    #[error("Invalid configuration: {0}")]
+    ConfigError(String),
+
+    /// External command (git, gh, ...) exited unsuccessfully
+    #[error("Command `{command}` failed: {stderr}")]
+    CommandFailed {
+        /// Command line that was run
+        command: String,
+        /// Captured standard error
+        stderr: String,
+    },
+
+    /// External command didn't exit within its timeout and was killed
+    #[error("Command `{command}` timed out after {timeout:?}")]
+    CommandTimedOut {
+        /// Program that was run
+        command: String,
+        /// Timeout that elapsed
+        timeout: std::time::Duration,
+    },
+
+    /// No feature directory matches the given ID or slug
+    #[error("Feature not found: {0}")]
+    FeatureNotFound(String),
+
+    /// A slug names several feature directories
+    #[error("Feature {feature} is ambiguous: {} (use the ID)", matches.join(", "))]
+    AmbiguousFeature {
+        /// Slug that was given
+        feature: String,
+        /// Directory names of the matching features
+        matches: Vec<String>,
+    },
+
+    /// Operation refused because the feature is still running
+    #[error("Feature {0} is in progress (use --force to override)")]
+    FeatureInProgress(String),
+
+    /// Another process is running the feature
+    #[error(
+        "Feature {feature} is being run by another gba process (remove {} if none is)",
+        path.display()
+    )]
+    FeatureLocked {
+        /// Directory name of the feature
+        feature: String,
+        /// Lock file held by the other process
+        path: std::path::PathBuf,
+    },
+
+    /// Agent output hit `max_output_bytes` with `stop_on_output_limit` set
+    #[error("Agent output exceeded {limit} bytes and was stopped")]
+    OutputLimitExceeded {
+        /// Configured `max_output_bytes`
+        limit: usize,
+        /// Output received before the limit was hit
+        partial: String,
+    },
+
+    /// The agent's response didn't have the expected structure
+    #[error("Invalid agent output: {0}")]
+    InvalidAgentOutput(String),
+
+    /// The working tree has changes the agent's commits could pick up
+    #[error(
+        "{} has uncommitted changes: {} (commit or stash them, or use --allow-dirty)",
+        path.display(),
+        list_paths(paths)
+    )]
+    DirtyWorkingTree {
+        /// Working tree that was checked
+        path: std::path::PathBuf,
+        /// Paths with changes, as reported by `git status`
+        paths: Vec<String>,
+    },
+
+    /// A feature's specs are still drafts
+    #[error(
+        "{feature} is not ready to run: {} (edit the specs with `gba edit`, or use --force)",
+        problems.join("; ")
+    )]
+    FeatureNotReady {
+        /// Directory name of the feature
+        feature: String,
+        /// What is wrong with the specs
+        problems: Vec<String>,
+    },
+
+    /// A desktop or webhook notification couldn't be delivered
+    #[error("Notification failed: {0}")]
+    NotificationFailed(String),
+
+    /// Underlying I/O failure
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    /// YAML (de)serialization failure
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+}
+
+impl CoreError {
+    /// Agent output received before the failure, if any was preserved
+    pub fn partial_output(&self) -> Option<&str> {
+        match self {
+            Self::AgentTimeout { partial, .. } | Self::OutputLimitExceeded { partial, .. }
+                if !partial.is_empty() =>
+            {
+                Some(partial)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Paths of a dirty working tree, cut short after the first few
+fn list_paths(paths: &[String]) -> String {
+    const SHOWN: usize = 9;
+    let mut list = paths[..paths.len().min(SHOWN)].join(", ");
+    if paths.len() > SHOWN {
+        list.push_str(&format!(" and {} more", paths.len() - SHOWN));
+    }
+    list
+}
+
+/// Result type alias for gba-core operations
+pub type Result<T, E = CoreError> = std::result::Result<T, E>;
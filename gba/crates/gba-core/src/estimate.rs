@@ -0,0 +1,172 @@
+//! Rough cost estimate shown before a run starts.
+//!
+//! Each phase is estimated from the average cost of that phase across
+//! previously completed features, falling back to a static per-phase
+//! heuristic when there is no history yet. `kind: command` phases don't
+//! call the agent and cost nothing.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::executor::PhaseKind;
+use crate::state::{FeatureState, PhaseStatus};
+
+/// Fallback estimate for phases without history or a built-in heuristic
+pub const DEFAULT_PHASE_COST_USD: f64 = 0.50;
+
+/// Estimated cost of a run
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CostEstimate {
+    /// Per-phase estimates, in execution order
+    pub phases: Vec<PhaseEstimate>,
+    /// Sum of the phase estimates
+    pub total_usd: f64,
+}
+
+/// Estimated cost of a single phase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseEstimate {
+    /// Phase name
+    pub phase: String,
+    /// Estimated cost in USD
+    pub cost_usd: f64,
+    /// Where the estimate comes from
+    pub source: EstimateSource,
+}
+
+/// Basis of a phase estimate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum EstimateSource {
+    /// Average over completed runs of the phase
+    History {
+        /// Number of completed runs averaged
+        samples: usize,
+    },
+    /// Static heuristic
+    Heuristic,
+    /// Shell command, run without the agent
+    Command,
+}
+
+impl fmt::Display for EstimateSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::History { samples } => write!(f, "avg of {samples} run(s)"),
+            Self::Heuristic => f.write_str("heuristic"),
+            Self::Command => f.write_str("command"),
+        }
+    }
+}
+
+impl CostEstimate {
+    /// Estimate the cost of running `phases` (names and kinds), using
+    /// `history` where possible
+    pub fn new<'a>(
+        phases: impl IntoIterator<Item = (&'a str, PhaseKind)>,
+        history: &[FeatureState],
+    ) -> Self {
+        let phases: Vec<PhaseEstimate> = phases
+            .into_iter()
+            .map(|(phase, kind)| match kind {
+                PhaseKind::Agent => estimate_phase(phase, history),
+                PhaseKind::Command => PhaseEstimate {
+                    phase: phase.to_string(),
+                    cost_usd: 0.0,
+                    source: EstimateSource::Command,
+                },
+            })
+            .collect();
+        let total_usd = phases.iter().map(|p| p.cost_usd).sum();
+        Self { phases, total_usd }
+    }
+}
+
+fn estimate_phase(phase: &str, history: &[FeatureState]) -> PhaseEstimate {
+    let costs: Vec<f64> = history
+        .iter()
+        .filter_map(|state| state.phase(phase))
+        .filter(|p| p.status == PhaseStatus::Completed)
+        .filter_map(|p| p.stats.as_ref().map(|s| s.cost_usd))
+        .collect();
+
+    if costs.is_empty() {
+        PhaseEstimate {
+            phase: phase.to_string(),
+            cost_usd: heuristic(phase),
+            source: EstimateSource::Heuristic,
+        }
+    } else {
+        PhaseEstimate {
+            phase: phase.to_string(),
+            cost_usd: costs.iter().sum::<f64>() / costs.len() as f64,
+            source: EstimateSource::History {
+                samples: costs.len(),
+            },
+        }
+    }
+}
+
+fn heuristic(phase: &str) -> f64 {
+    match phase {
+        "observe" => 0.30,
+        "build" => 1.50,
+        "test" => 0.80,
+        "verification" => 0.40,
+        "review" => 0.50,
+        "pr" => 0.10,
+        _ => DEFAULT_PHASE_COST_USD,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::ExecutionStats;
+
+    fn completed(state: &mut FeatureState, phase: &str, cost_usd: f64) {
+        let phase = state.phase_mut(phase);
+        phase.status = PhaseStatus::Completed;
+        phase.stats = Some(ExecutionStats {
+            cost_usd,
+            ..ExecutionStats::default()
+        });
+    }
+
+    #[test]
+    fn test_estimate_uses_history_average() {
+        let mut a = FeatureState::new("0001", "auth");
+        completed(&mut a, "build", 2.0);
+        let mut b = FeatureState::new("0002", "search");
+        completed(&mut b, "build", 1.0);
+        b.phase_mut("test").status = PhaseStatus::Failed;
+
+        completed(&mut b, "fmt", 1.0);
+
+        let estimate = CostEstimate::new(
+            [
+                ("build", PhaseKind::Agent),
+                ("test", PhaseKind::Agent),
+                ("lint", PhaseKind::Agent),
+                ("fmt", PhaseKind::Command),
+            ],
+            &[a, b],
+        );
+
+        assert_eq!(
+            estimate.phases[0].source,
+            EstimateSource::History { samples: 2 }
+        );
+        assert!((estimate.phases[0].cost_usd - 1.5).abs() < 1e-9);
+        // The failed test run doesn't count as history.
+        assert_eq!(estimate.phases[1].source, EstimateSource::Heuristic);
+        assert!((estimate.phases[2].cost_usd - DEFAULT_PHASE_COST_USD).abs() < 1e-9);
+        // Commands never call the agent, whatever their history says.
+        assert_eq!(estimate.phases[3].source, EstimateSource::Command);
+        assert_eq!(estimate.phases[3].cost_usd, 0.0);
+        assert!((estimate.total_usd - 2.8).abs() < 1e-9);
+    }
+}
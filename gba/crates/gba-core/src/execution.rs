@@ -0,0 +1,351 @@
+//! Execution request/result types and structured output parsing.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CoreError, Result};
+use crate::hooks::HookRecord;
+use crate::mcp::McpServerMap;
+use crate::permissions::ToolDenial;
+use crate::state::ExecutionStats;
+use crate::testing::TestRun;
+use crate::text::{estimate_tokens, truncate_chars};
+
+/// Maximum length of the snippet quoted in JSON parse errors
+const ERROR_SNIPPET_CHARS: usize = 200;
+
+/// Most tokens of history and prompt sent in one request, leaving room for
+/// the system prompt, tools and the response in a 200k-token context
+pub const HISTORY_BUDGET_TOKENS: usize = 150_000;
+
+/// Speaker of a turn of the conversation history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// What gba asked
+    User,
+    /// What the agent answered
+    Assistant,
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::User => "User",
+            Self::Assistant => "Assistant",
+        })
+    }
+}
+
+/// A single agent execution
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionRequest {
+    /// Custom system prompt (None = use the `claude_code` preset)
+    pub system_prompt: Option<String>,
+    /// Text appended to the `claude_code` preset (ignored with a custom
+    /// `system_prompt`)
+    pub append: Option<String>,
+    /// User prompt
+    pub user_prompt: String,
+    /// Earlier turns of the conversation, oldest first, sent before the
+    /// user prompt
+    pub history: Vec<(Role, String)>,
+    /// Allowed tools (empty = all tools)
+    pub tools: Vec<String>,
+    /// Tools to explicitly disallow
+    pub disallowed_tools: Vec<String>,
+    /// MCP servers available to the agent
+    pub mcp_servers: McpServerMap,
+    /// Variables set for the agent on top of `Config::env`
+    pub env: BTreeMap<String, String>,
+    /// Variables removed for the agent on top of `Config::env_deny`
+    pub env_deny: Vec<String>,
+    /// Response timeout (None = `Config::timeout_seconds`)
+    pub timeout: Option<Duration>,
+    /// If set, the agent is asked to end its response with a JSON block
+    /// matching this schema (see [`ExecutionResult::extract_json`])
+    pub json_schema: Option<String>,
+    /// Working directory of the agent (None = `Config::repo_path`)
+    pub working_dir: Option<PathBuf>,
+    /// Model to use (None = `Config::model`)
+    pub model: Option<String>,
+    /// Maximum agent turns (None = `Config::max_turns`)
+    pub max_turns: Option<u32>,
+    /// What the request is for, e.g. the phase; names its recorded
+    /// transcript
+    pub label: Option<String>,
+}
+
+impl ExecutionRequest {
+    /// Create a request with the given user prompt
+    pub fn new(user_prompt: impl Into<String>) -> Self {
+        Self {
+            user_prompt: user_prompt.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Ask the agent to respond with JSON matching `schema`
+    pub fn with_json_schema(mut self, schema: impl Into<String>) -> Self {
+        self.json_schema = Some(schema.into());
+        self
+    }
+
+    /// User prompt including the conversation history and the structured
+    /// response instruction, if any
+    pub fn prompt(&self) -> String {
+        let prompt = match &self.json_schema {
+            Some(schema) => format!(
+                "{}\n\n## Response Format\n\nEnd your response with a single fenced ```json block \
+                 containing an object that matches this schema:\n\n```json\n{}\n```\n",
+                self.user_prompt.trim_end(),
+                schema.trim()
+            ),
+            None => self.user_prompt.clone(),
+        };
+        if self.history.is_empty() {
+            return prompt;
+        }
+        let mut out = String::from("## Conversation So Far\n\n");
+        for (role, text) in &self.history {
+            out.push_str(&format!("### {role}\n\n{}\n\n", text.trim_end()));
+        }
+        out.push_str("## Current Request\n\n");
+        out.push_str(&prompt);
+        out
+    }
+
+    /// Drop the oldest history turns until the prompt is estimated to fit
+    /// in `max_tokens`, returning how many were dropped
+    pub fn trim_history(&mut self, max_tokens: usize) -> u32 {
+        let mut dropped = 0;
+        while !self.history.is_empty() && estimate_tokens(&self.prompt()) > max_tokens {
+            self.history.remove(0);
+            dropped += 1;
+        }
+        dropped
+    }
+
+    /// The conversation after the agent answered this request with `output`
+    pub(crate) fn conversation(&self, output: &str) -> Vec<(Role, String)> {
+        let mut conversation = self.history.clone();
+        conversation.push((Role::User, self.user_prompt.clone()));
+        conversation.push((Role::Assistant, output.to_string()));
+        conversation
+    }
+}
+
+/// Outcome of an agent execution
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionResult {
+    /// Whether the agent finished without error
+    pub success: bool,
+    /// Concatenated assistant text
+    pub output: String,
+    /// Wall-clock duration
+    pub duration: Duration,
+    /// Turns, tokens and cost
+    pub stats: ExecutionStats,
+    /// Whether output was cut off at `Config::max_output_bytes`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    /// Bytes of output dropped by the truncation
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub dropped_bytes: usize,
+    /// Model the agent ran with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Rendered records of the hooks that ran around the agent
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub hook_output: String,
+    /// Hooks that ran around the agent
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<HookRecord>,
+    /// Runs of the test command after the test phase
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub test_runs: Vec<TestRun>,
+    /// Tool uses that weren't allowed to run, with the reason
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_tools: Vec<ToolDenial>,
+    /// History, prompt and output of the request, to continue it with
+    /// [`crate::Engine::continue_execution`]
+    #[serde(skip)]
+    pub conversation: Vec<(Role, String)>,
+}
+
+impl ExecutionResult {
+    /// Deterministic result of an offline engine: the prompt echoed back
+    pub(crate) fn offline(request: &ExecutionRequest, model: &str) -> Self {
+        Self {
+            success: true,
+            output: request.prompt(),
+            model: Some(model.to_string()),
+            ..Self::default()
+        }
+    }
+
+    /// Deserialize the structured part of the output.
+    ///
+    /// Uses the last fenced ```` ```json ```` block, or the last bare JSON
+    /// object when the output has no fenced block.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::InvalidAgentOutput` (quoting the offending snippet)
+    /// if no JSON is found or it doesn't match `T`.
+    pub fn extract_json<T: DeserializeOwned>(&self) -> Result<T> {
+        extract_json(&self.output)
+    }
+}
+
+/// Deserialize the last JSON block or object found in `output`
+pub(crate) fn extract_json<T: DeserializeOwned>(output: &str) -> Result<T> {
+    let json = last_fenced_json(output)
+        .or_else(|| last_bare_object(output))
+        .ok_or_else(|| CoreError::InvalidAgentOutput("no JSON found in agent output".into()))?;
+    serde_json::from_str(json)
+        .map_err(|e| CoreError::InvalidAgentOutput(format!("{e} in `{}`", snippet(json))))
+}
+
+fn last_fenced_json(output: &str) -> Option<&str> {
+    let mut found = None;
+    let mut rest = output;
+    let mut offset = 0;
+    while let Some(open) = rest.find("```json").or_else(|| rest.find("```JSON")) {
+        let start = open + "```json".len();
+        let Some(len) = rest[start..].find("```") else {
+            break;
+        };
+        found = Some((offset + start, offset + start + len));
+        let next = start + len + "```".len();
+        offset += next;
+        rest = &rest[next..];
+    }
+    found.map(|(start, end)| output[start..end].trim())
+}
+
+fn last_bare_object(output: &str) -> Option<&str> {
+    let mut found = None;
+    let mut pos = 0;
+    while let Some(open) = output[pos..].find('{') {
+        let start = pos + open;
+        let mut stream =
+            serde_json::Deserializer::from_str(&output[start..]).into_iter::<serde_json::Value>();
+        match stream.next() {
+            Some(Ok(serde_json::Value::Object(_))) => {
+                let end = start + stream.byte_offset();
+                found = Some(&output[start..end]);
+                pos = end;
+            }
+            _ => pos = start + 1,
+        }
+    }
+    found
+}
+
+fn snippet(json: &str) -> String {
+    truncate_chars(json, ERROR_SNIPPET_CHARS, "...")
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Pr {
+        title: String,
+        body: String,
+    }
+
+    fn result(output: &str) -> ExecutionResult {
+        ExecutionResult {
+            success: true,
+            output: output.to_string(),
+            ..ExecutionResult::default()
+        }
+    }
+
+    #[test]
+    fn test_extract_json_uses_last_fenced_block() {
+        let output = "First try:\n```json\n{\"title\": \"old\", \"body\": \"x\"}\n```\n\
+                      Better:\n```json\n{\"title\": \"Add auth\", \"body\": \"JWT login\"}\n```\n\
+                      Let me know if you need anything else.";
+
+        let pr: Pr = result(output).extract_json().unwrap();
+
+        assert_eq!(pr.title, "Add auth");
+        assert_eq!(pr.body, "JWT login");
+    }
+
+    #[test]
+    fn test_extract_json_bare_object_with_trailing_prose() {
+        let output = "Here you go: {\"title\": \"Fix {braces}\", \"body\": \"nested {\\\"a\\\": 1}\"} \
+                      and that's {not json}.";
+
+        let pr: Pr = result(output).extract_json().unwrap();
+
+        assert_eq!(pr.title, "Fix {braces}");
+    }
+
+    #[test]
+    fn test_extract_json_invalid_reports_snippet() {
+        let err = result("```json\n{\"title\": 42}\n```")
+            .extract_json::<Pr>()
+            .unwrap_err();
+
+        assert!(matches!(
+            err,
+            CoreError::InvalidAgentOutput(msg) if msg.contains("{\"title\": 42}")
+        ));
+        assert!(matches!(
+            result("no structure here").extract_json::<Pr>(),
+            Err(CoreError::InvalidAgentOutput(_))
+        ));
+    }
+
+    #[test]
+    fn test_history_is_sent_and_trimmed_oldest_first() {
+        let mut request = ExecutionRequest {
+            history: vec![
+                (Role::User, "a".repeat(400)),
+                (Role::Assistant, "b".repeat(400)),
+                (Role::User, "Write tests".to_string()),
+                (Role::Assistant, "Wrote tests".to_string()),
+            ],
+            ..ExecutionRequest::new("Fix the failing test")
+        };
+
+        let prompt = request.prompt();
+        assert!(prompt.starts_with("## Conversation So Far\n\n### User\n\naaaa"));
+        assert!(prompt.contains("### Assistant\n\nWrote tests\n\n## Current Request\n\n"));
+        assert!(prompt.ends_with("Fix the failing test"));
+
+        assert_eq!(request.trim_history(1000), 0);
+        assert_eq!(request.trim_history(100), 2);
+        assert_eq!(request.history[0], (Role::User, "Write tests".to_string()));
+        assert_eq!(request.trim_history(1), 2);
+        assert_eq!(request.prompt(), "Fix the failing test");
+    }
+
+    #[test]
+    fn test_request_prompt_appends_schema() {
+        let request = ExecutionRequest::new("Create the PR.")
+            .with_json_schema(r#"{"title": "string", "body": "string"}"#);
+
+        let prompt = request.prompt();
+
+        assert!(prompt.starts_with("Create the PR."));
+        assert!(prompt.contains("```json\n{\"title\": \"string\", \"body\": \"string\"}\n```"));
+        assert_eq!(ExecutionRequest::new("plain").prompt(), "plain");
+    }
+}
@@ -0,0 +1,212 @@
+//! What executes a phase: an agent request or a shell command.
+//!
+//! Phases are agent requests unless their `config.yml` entry says otherwise:
+//!
+//! ```yaml
+//! phases:
+//!   - name: lint
+//!     kind: command
+//!     command: cargo clippy --all-targets -- -D warnings
+//! ```
+//!
+//! Hooks, test runs, state and stats are handled around the executor, so
+//! they work the same for every kind.
+
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::Engine;
+use crate::error::{CoreError, Result};
+use crate::execution::ExecutionResult;
+use crate::hooks::HookContext;
+use crate::phase::Phase;
+use crate::progress::ProgressSender;
+
+/// How a phase is executed (`kind:` of a phase entry)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PhaseKind {
+    /// Prompt the agent
+    #[default]
+    Agent,
+    /// Run the phase's `command`
+    Command,
+}
+
+/// Context a phase executes in
+#[derive(Debug, Clone)]
+pub struct ExecutionContext {
+    /// Feature, phase and working directory, as passed to hooks
+    pub hook: HookContext,
+    /// Receives the agent's streamed events, if given
+    pub progress: Option<ProgressSender>,
+}
+
+/// Executes the work of a phase, without its hooks
+pub trait PhaseExecutor: Send + Sync {
+    /// Execute `phase` in `ctx`.
+    ///
+    /// An unsuccessful result fails the phase; errors abort it.
+    fn execute<'a>(
+        &'a self,
+        phase: &'a Phase,
+        ctx: &'a ExecutionContext,
+    ) -> BoxFuture<'a, Result<ExecutionResult>>;
+}
+
+/// The default executor: the phase's prompts sent to the agent
+impl PhaseExecutor for Engine {
+    fn execute<'a>(
+        &'a self,
+        phase: &'a Phase,
+        ctx: &'a ExecutionContext,
+    ) -> BoxFuture<'a, Result<ExecutionResult>> {
+        Box::pin(self.run_request(phase.request(), ctx.progress.as_ref()))
+    }
+}
+
+/// Runs the phase's `command` through `sh -c`.
+///
+/// The command runs in the phase's working directory with `GBA_FEATURE_ID`,
+/// `GBA_FEATURE_SLUG` and `GBA_PHASE` set. Exit code 0 is success; the
+/// output is stdout followed by stderr.
+#[derive(Debug, Clone, Copy)]
+pub struct CommandExecutor {
+    /// Timeout of phases without their own
+    pub timeout: Duration,
+}
+
+impl CommandExecutor {
+    /// Executor with `timeout` for phases without their own
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl PhaseExecutor for CommandExecutor {
+    fn execute<'a>(
+        &'a self,
+        phase: &'a Phase,
+        ctx: &'a ExecutionContext,
+    ) -> BoxFuture<'a, Result<ExecutionResult>> {
+        Box::pin(async move {
+            let command = phase.command.as_deref().ok_or_else(|| {
+                CoreError::ConfigError(format!("phase `{}` has no command", phase.name))
+            })?;
+            let working_dir: PathBuf = phase
+                .working_dir
+                .clone()
+                .unwrap_or_else(|| ctx.hook.working_dir.clone());
+            let timeout = phase
+                .timeout_seconds
+                .map_or(self.timeout, Duration::from_secs);
+
+            let start = Instant::now();
+            let child = Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .current_dir(&working_dir)
+                .env("GBA_FEATURE_ID", &ctx.hook.feature_id)
+                .env("GBA_FEATURE_SLUG", &ctx.hook.feature_slug)
+                .env("GBA_PHASE", &phase.name)
+                .stdin(Stdio::null())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()?;
+            let output = tokio::time::timeout(timeout, child.wait_with_output())
+                .await
+                .map_err(|_| CoreError::CommandFailed {
+                    command: command.to_string(),
+                    stderr: format!("timed out after {timeout:?}"),
+                })??;
+            tracing::info!(
+                phase = %phase.name,
+                command,
+                exit_code = ?output.status.code(),
+                "phase command finished"
+            );
+
+            Ok(ExecutionResult {
+                success: output.status.success(),
+                output: format!(
+                    "{}{}",
+                    String::from_utf8_lossy(&output.stdout),
+                    String::from_utf8_lossy(&output.stderr)
+                ),
+                duration: start.elapsed(),
+                ..ExecutionResult::default()
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(dir: &std::path::Path) -> ExecutionContext {
+        ExecutionContext {
+            hook: HookContext {
+                working_dir: dir.to_path_buf(),
+                feature_id: "0001".to_string(),
+                feature_slug: "auth".to_string(),
+                phase: "lint".to_string(),
+            },
+            progress: None,
+        }
+    }
+
+    fn phase(command: &str) -> Phase {
+        Phase {
+            name: "lint".to_string(),
+            kind: PhaseKind::Command,
+            command: Some(command.to_string()),
+            ..Phase::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_command_output_and_exit_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let executor = CommandExecutor::new(Duration::from_secs(5));
+        let ctx = context(dir.path());
+
+        let phase = phase("echo $GBA_FEATURE_ID-$GBA_PHASE; echo warning >&2");
+        let result = executor.execute(&phase, &ctx).await.unwrap();
+        assert!(result.success);
+        assert_eq!(result.output, "0001-lint\nwarning\n");
+        assert_eq!(result.stats.cost_usd, 0.0);
+
+        let failing = self::phase("echo 'error: unused import'; exit 1");
+        let result = executor.execute(&failing, &ctx).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(result.output, "error: unused import\n");
+    }
+
+    #[tokio::test]
+    async fn test_command_timeout_and_missing_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let executor = CommandExecutor::new(Duration::from_secs(5));
+        let ctx = context(dir.path());
+
+        let slow = Phase {
+            timeout_seconds: Some(1),
+            ..phase("sleep 5")
+        };
+        let err = executor.execute(&slow, &ctx).await.unwrap_err();
+        assert!(err.to_string().contains("timed out after 1s"));
+
+        let missing = Phase {
+            command: None,
+            ..phase("")
+        };
+        let err = executor.execute(&missing, &ctx).await.unwrap_err();
+        assert!(matches!(err, CoreError::ConfigError(msg) if msg.contains("no command")));
+    }
+}
@@ -0,0 +1,291 @@
+//! Bundling a feature's specs, state and docs into a single document.
+//!
+//! A bundle maps paths relative to the feature directory to their contents,
+//! so it can be shared as one JSON file and restored elsewhere with
+//! [`import`].
+
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CoreError, Result};
+use crate::state::{DESIGN_FILE, FEATURES_DIR, FeatureState, STATE_FILE};
+use crate::verification::VERIFICATION_FILE;
+
+/// Format version of export bundles
+pub const EXPORT_VERSION: u32 = 1;
+
+/// Extension of export bundle files
+pub const EXPORT_EXTENSION: &str = "gba-export.json";
+
+/// Directory of the feature whose files are all exported
+const DOCS_DIR: &str = "docs";
+
+/// A feature's files with their contents inlined
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportBundle {
+    /// Bundle format version
+    pub version: u32,
+    /// Feature directory name, e.g. `0001_login`
+    pub feature: String,
+    /// When the bundle was created
+    pub exported_at: DateTime<Utc>,
+    /// File contents by path relative to the feature directory
+    pub files: BTreeMap<String, String>,
+}
+
+impl ExportBundle {
+    /// Default file name, e.g. `0001_login.gba-export.json`
+    pub fn file_name(&self) -> String {
+        format!("{}.{EXPORT_EXTENSION}", self.feature)
+    }
+}
+
+/// Bundle `state.yml`, the specs and everything under `docs/` of a feature.
+///
+/// Archived features are found as well. Missing specs are left out.
+///
+/// # Errors
+///
+/// Returns `CoreError::FeatureNotFound` if the feature doesn't exist, or an
+/// IO error if a file can't be read.
+pub fn export(gba_path: &Path, feature: &str) -> Result<ExportBundle> {
+    let feature_path = match FeatureState::find_dir(gba_path, feature) {
+        Err(CoreError::FeatureNotFound(_)) => FeatureState::find_archived_dir(gba_path, feature)?,
+        found => found?,
+    };
+    let state = FeatureState::load(&feature_path)?;
+
+    let mut files = BTreeMap::new();
+    for name in [STATE_FILE, DESIGN_FILE, VERIFICATION_FILE] {
+        let path = feature_path.join(name);
+        if path.is_file() {
+            files.insert(name.to_string(), std::fs::read_to_string(path)?);
+        }
+    }
+    collect_dir(&feature_path, &feature_path.join(DOCS_DIR), &mut files)?;
+
+    Ok(ExportBundle {
+        version: EXPORT_VERSION,
+        feature: state.dir_name(),
+        exported_at: Utc::now(),
+        files,
+    })
+}
+
+/// What `import` did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Imported {
+    /// Directory of the imported feature
+    pub path: PathBuf,
+    /// Original ID, if the feature got a new one because it was taken
+    pub reassigned_from: Option<String>,
+}
+
+/// Recreate the feature of `bundle` under `.gba/features`.
+///
+/// The feature keeps its ID unless another feature uses it, in which case
+/// it gets the next free one. A feature with the same slug is only
+/// replaced with `force`.
+///
+/// # Errors
+///
+/// Returns `CoreError::ConfigError` if the bundle has another version, no
+/// valid `state.yml` or a file outside the feature directory, an
+/// `AlreadyExists` IO error if the feature exists and `force` isn't set, or
+/// an IO error if the files can't be written.
+pub fn import(gba_path: &Path, bundle: &ExportBundle, force: bool) -> Result<Imported> {
+    if bundle.version != EXPORT_VERSION {
+        return Err(CoreError::ConfigError(format!(
+            "unsupported export bundle version {} (expected {EXPORT_VERSION})",
+            bundle.version
+        )));
+    }
+    let state = bundle
+        .files
+        .get(STATE_FILE)
+        .ok_or_else(|| CoreError::ConfigError(format!("bundle has no {STATE_FILE}")))?;
+    let mut state: FeatureState = serde_yaml::from_str(state)?;
+    if let Some(name) = bundle.files.keys().find(|name| !is_feature_file(name)) {
+        return Err(CoreError::ConfigError(format!(
+            "bundle file `{name}` is outside the feature directory"
+        )));
+    }
+
+    match FeatureState::find_dir(gba_path, &state.feature.slug) {
+        Ok(existing) if force => std::fs::remove_dir_all(existing)?,
+        Ok(existing) => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!(
+                    "{} already exists (use --force to replace it)",
+                    existing.display()
+                ),
+            )
+            .into());
+        }
+        Err(CoreError::FeatureNotFound(_)) => {}
+        Err(e) => return Err(e),
+    }
+    let reassigned_from = if id_taken(gba_path, &state.feature.id)? {
+        let original = std::mem::replace(&mut state.feature.id, FeatureState::next_id(gba_path)?);
+        Some(original)
+    } else {
+        None
+    };
+
+    let path = gba_path.join(FEATURES_DIR).join(state.dir_name());
+    for (name, content) in &bundle.files {
+        let file = path.join(name);
+        if let Some(parent) = file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(file, content)?;
+    }
+    // Rewritten rather than copied, in case the ID changed.
+    state.save(&path)?;
+    Ok(Imported {
+        path,
+        reassigned_from,
+    })
+}
+
+/// Whether `name` is a plain relative path inside the feature directory
+fn is_feature_file(name: &str) -> bool {
+    let path = Path::new(name);
+    path.components().count() > 0
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+}
+
+/// Whether an active or archived feature uses `id`
+fn id_taken(gba_path: &Path, id: &str) -> Result<bool> {
+    for found in [
+        FeatureState::find_dir(gba_path, id),
+        FeatureState::find_archived_dir(gba_path, id),
+    ] {
+        match found {
+            Ok(_) => return Ok(true),
+            Err(CoreError::FeatureNotFound(_)) => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(false)
+}
+
+/// Add every file under `dir` to `files`, keyed relative to `root`
+fn collect_dir(root: &Path, dir: &Path, files: &mut BTreeMap<String, String>) -> Result<()> {
+    if !dir.is_dir() {
+        return Ok(());
+    }
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_dir(root, &path, files)?;
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let name = relative.to_string_lossy().replace('\\', "/");
+            files.insert(name, std::fs::read_to_string(&path)?);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::FEATURES_DIR;
+
+    #[test]
+    fn test_export_bundles_state_specs_and_docs() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = FeatureState::new("0001", "login");
+        let feature_path = dir.path().join(FEATURES_DIR).join(state.dir_name());
+        std::fs::create_dir_all(feature_path.join("specs")).unwrap();
+        std::fs::create_dir_all(feature_path.join("docs/notes")).unwrap();
+        state.save(&feature_path).unwrap();
+        std::fs::write(feature_path.join(DESIGN_FILE), "# Design\n").unwrap();
+        std::fs::write(feature_path.join("docs/review.md"), "# Review\n").unwrap();
+        std::fs::write(feature_path.join("docs/notes/a.md"), "note\n").unwrap();
+
+        let bundle = export(dir.path(), "login").unwrap();
+
+        assert_eq!(bundle.feature, "0001_login");
+        assert_eq!(bundle.file_name(), "0001_login.gba-export.json");
+        assert_eq!(
+            bundle.files.keys().collect::<Vec<_>>(),
+            ["docs/notes/a.md", "docs/review.md", DESIGN_FILE, STATE_FILE]
+        );
+        assert!(bundle.files[STATE_FILE].contains("login"));
+        assert_eq!(bundle.files[DESIGN_FILE], "# Design\n");
+
+        assert!(matches!(
+            export(dir.path(), "signup"),
+            Err(CoreError::FeatureNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_import_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = FeatureState::new("0003", "login");
+        let feature_path = dir.path().join(FEATURES_DIR).join(state.dir_name());
+        std::fs::create_dir_all(feature_path.join("specs")).unwrap();
+        state.save(&feature_path).unwrap();
+        std::fs::write(feature_path.join(DESIGN_FILE), "# Design\n").unwrap();
+        let bundle = export(dir.path(), "login").unwrap();
+
+        let fresh = tempfile::tempdir().unwrap();
+        let imported = import(fresh.path(), &bundle, false).unwrap();
+
+        assert_eq!(imported.reassigned_from, None);
+        assert!(imported.path.ends_with("features/0003_login"));
+        assert_eq!(FeatureState::load(&imported.path).unwrap(), state);
+        assert_eq!(
+            std::fs::read_to_string(imported.path.join(DESIGN_FILE)).unwrap(),
+            "# Design\n"
+        );
+
+        // The same slug is only replaced with --force.
+        let err = import(fresh.path(), &bundle, false).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        import(fresh.path(), &bundle, true).unwrap();
+    }
+
+    #[test]
+    fn test_import_reassigns_taken_id_and_checks_bundle() {
+        let dir = tempfile::tempdir().unwrap();
+        let taken = FeatureState::new("0001", "signup");
+        let taken_path = dir.path().join(FEATURES_DIR).join(taken.dir_name());
+        std::fs::create_dir_all(&taken_path).unwrap();
+        taken.save(&taken_path).unwrap();
+        let mut bundle = ExportBundle {
+            version: EXPORT_VERSION,
+            feature: "0001_login".to_string(),
+            exported_at: Utc::now(),
+            files: BTreeMap::from([(
+                STATE_FILE.to_string(),
+                serde_yaml::to_string(&FeatureState::new("0001", "login")).unwrap(),
+            )]),
+        };
+
+        let imported = import(dir.path(), &bundle, false).unwrap();
+        assert_eq!(imported.reassigned_from.as_deref(), Some("0001"));
+        assert!(imported.path.ends_with("features/0002_login"));
+        let state = FeatureState::load(&imported.path).unwrap();
+        assert_eq!(state.feature.id, "0002");
+
+        bundle
+            .files
+            .insert("../escape.md".to_string(), String::new());
+        let err = import(dir.path(), &bundle, true).unwrap_err();
+        assert!(matches!(err, CoreError::ConfigError(msg) if msg.contains("outside")));
+
+        bundle.version = 2;
+        let err = import(dir.path(), &bundle, true).unwrap_err();
+        assert!(matches!(err, CoreError::ConfigError(msg) if msg.contains("version 2")));
+    }
+}
@@ -0,0 +1,69 @@
+//! Thin wrappers around the GitHub `gh` CLI.
+
+use std::path::Path;
+
+use crate::command::{CommandRunner, run_checked};
+use crate::error::Result;
+
+/// Open a pull request for the current branch and return its URL.
+///
+/// Without `base`, `gh` targets the repository's default branch.
+///
+/// # Errors
+///
+/// Returns an error if `gh` is missing, unauthenticated or rejects the request.
+pub fn create_pull_request(
+    runner: &dyn CommandRunner,
+    cwd: &Path,
+    title: &str,
+    body: &str,
+    base: Option<&str>,
+) -> Result<String> {
+    let mut args = vec!["pr", "create", "--title", title, "--body", body];
+    if let Some(base) = base {
+        args.extend(["--base", base]);
+    }
+    let stdout = run_checked(runner, "gh", &args, cwd)?;
+    // `gh pr create` prints progress lines before the URL; the URL comes last.
+    Ok(stdout.lines().last().unwrap_or_default().trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::fake::FakeCommandRunner;
+
+    #[test]
+    fn test_create_pull_request_returns_url() {
+        let runner = FakeCommandRunner::default();
+        runner.respond(
+            0,
+            "Creating pull request...\nhttps://github.com/o/r/pull/7\n",
+            "",
+        );
+
+        let url = create_pull_request(&runner, Path::new("/wt"), "Add auth", "Body", Some("main"))
+            .unwrap();
+
+        assert_eq!(url, "https://github.com/o/r/pull/7");
+        let (program, args, _) = &runner.calls()[0];
+        assert_eq!(program, "gh");
+        assert_eq!(
+            args,
+            &[
+                "pr", "create", "--title", "Add auth", "--body", "Body", "--base", "main"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_create_pull_request_without_base() {
+        let runner = FakeCommandRunner::default();
+        runner.respond(0, "https://github.com/o/r/pull/8\n", "");
+
+        create_pull_request(&runner, Path::new("/wt"), "Add auth", "Body", None).unwrap();
+
+        let (_, args, _) = &runner.calls()[0];
+        assert!(!args.iter().any(|arg| arg == "--base"));
+    }
+}
@@ -0,0 +1,380 @@
+//! Thin wrappers around the `git` CLI.
+
+use std::path::Path;
+
+use crate::command::{CommandRunner, run_checked};
+use crate::error::{CoreError, Result};
+
+/// Pathspecs leaving out what gba itself writes into a repository: its
+/// `.gba` state and the `.trees` worktrees. They are never the user's
+/// uncommitted changes, nor part of a phase's commit.
+const EXCLUDE_GBA_PATHS: [&str; 2] = [":(top,exclude).gba", ":(top,exclude).trees"];
+
+/// Create a worktree at `worktree_path` on a new `branch` started from `base`.
+///
+/// # Errors
+///
+/// Returns an error if git fails (e.g. the branch already exists).
+pub fn worktree_add(
+    runner: &dyn CommandRunner,
+    repo: &Path,
+    worktree_path: &Path,
+    branch: &str,
+    base: &str,
+) -> Result<()> {
+    let path = worktree_path.to_string_lossy();
+    run_checked(
+        runner,
+        "git",
+        &["worktree", "add", "-b", branch, &path, base],
+        repo,
+    )?;
+    Ok(())
+}
+
+/// Remove the worktree at `worktree_path`.
+///
+/// # Errors
+///
+/// Returns an error if git fails.
+pub fn worktree_remove(
+    runner: &dyn CommandRunner,
+    repo: &Path,
+    worktree_path: &Path,
+    force: bool,
+) -> Result<()> {
+    let path = worktree_path.to_string_lossy();
+    let mut args = vec!["worktree", "remove"];
+    if force {
+        args.push("--force");
+    }
+    args.push(&path);
+    run_checked(runner, "git", &args, repo)?;
+    Ok(())
+}
+
+/// SHA of the commit `HEAD` points to.
+///
+/// # Errors
+///
+/// Returns an error if `cwd` is not inside a git repository.
+pub fn head_commit(runner: &dyn CommandRunner, cwd: &Path) -> Result<String> {
+    run_checked(runner, "git", &["rev-parse", "HEAD"], cwd)
+}
+
+/// Name of the checked out branch.
+///
+/// # Errors
+///
+/// Returns an error if `cwd` is not inside a git repository.
+pub fn current_branch(runner: &dyn CommandRunner, cwd: &Path) -> Result<String> {
+    run_checked(runner, "git", &["rev-parse", "--abbrev-ref", "HEAD"], cwd)
+}
+
+/// Whether a local branch exists.
+///
+/// # Errors
+///
+/// Returns an error if git cannot be run.
+pub fn branch_exists(runner: &dyn CommandRunner, repo: &Path, branch: &str) -> Result<bool> {
+    let reference = format!("refs/heads/{branch}");
+    let output = runner.run(
+        "git",
+        &["rev-parse", "--verify", "--quiet", &reference],
+        repo,
+    )?;
+    Ok(output.status.success())
+}
+
+/// Force-delete a local branch.
+///
+/// # Errors
+///
+/// Returns an error if git fails.
+pub fn branch_delete(runner: &dyn CommandRunner, repo: &Path, branch: &str) -> Result<()> {
+    run_checked(runner, "git", &["branch", "-D", branch], repo)?;
+    Ok(())
+}
+
+/// Stage everything but gba's own files and commit it, returning the new
+/// commit SHA.
+///
+/// # Errors
+///
+/// Returns an error if staging or committing fails (including "nothing to commit").
+pub fn commit_all(runner: &dyn CommandRunner, cwd: &Path, message: &str) -> Result<String> {
+    let mut args = vec!["add", "-A", "--", ":/"];
+    args.extend(EXCLUDE_GBA_PATHS);
+    run_checked(runner, "git", &args, cwd)?;
+    run_checked(runner, "git", &["commit", "-m", message], cwd)?;
+    head_commit(runner, cwd)
+}
+
+/// Commit the changes at `cwd`, if there are any, and return the SHA of
+/// the commit `HEAD` then points to: the checkpoint a phase ends at.
+///
+/// Returns `None` outside a git repository or before its first commit.
+///
+/// # Errors
+///
+/// Returns an error if committing fails, e.g. without a configured
+/// identity.
+pub fn checkpoint(runner: &dyn CommandRunner, cwd: &Path, message: &str) -> Result<Option<String>> {
+    let sha = match dirty_paths(runner, cwd) {
+        Ok(paths) if paths.is_empty() => head_commit(runner, cwd),
+        Ok(_) => return commit_all(runner, cwd, message).map(Some),
+        Err(e) => Err(e),
+    };
+    match sha {
+        Ok(sha) => Ok(Some(sha)),
+        Err(CoreError::CommandFailed { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Point the branch checked out at `cwd` to `commit`, discarding the
+/// commits after it and uncommitted changes to tracked files.
+///
+/// # Errors
+///
+/// Returns an error if `commit` is unknown or the reset fails.
+pub fn reset_hard(runner: &dyn CommandRunner, cwd: &Path, commit: &str) -> Result<()> {
+    run_checked(runner, "git", &["reset", "--hard", "--quiet", commit], cwd)?;
+    Ok(())
+}
+
+/// Paths with uncommitted changes, including untracked files, but not
+/// gba's own `.gba` state and `.trees` worktrees.
+///
+/// # Errors
+///
+/// Returns an error if `cwd` is not inside a git repository.
+pub fn dirty_paths(runner: &dyn CommandRunner, cwd: &Path) -> Result<Vec<String>> {
+    let mut args = vec!["status", "--porcelain", "--", ":/"];
+    args.extend(EXCLUDE_GBA_PATHS);
+    let output = runner.run("git", &args, cwd)?;
+    if !output.status.success() {
+        return Err(CoreError::CommandFailed {
+            command: format!("git {}", args.join(" ")),
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        });
+    }
+    // Not trimmed: each line starts with a two-column status.
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.get(3..))
+        .map(String::from)
+        .collect())
+}
+
+/// Whether `ancestor` is `HEAD` or one of its ancestors.
+///
+/// # Errors
+///
+/// Returns an error if git cannot be run.
+pub fn is_ancestor(runner: &dyn CommandRunner, cwd: &Path, ancestor: &str) -> Result<bool> {
+    let output = runner.run(
+        "git",
+        &["merge-base", "--is-ancestor", ancestor, "HEAD"],
+        cwd,
+    )?;
+    Ok(output.status.success())
+}
+
+/// Refuse to run on a working tree with uncommitted changes.
+///
+/// Changes on top of `resume_base`, the base commit of an interrupted
+/// feature run, are that run's own work and are allowed. A directory
+/// outside any git repository has nothing to protect and passes.
+///
+/// # Errors
+///
+/// Returns `CoreError::DirtyWorkingTree` listing the changed paths, or an
+/// error if git cannot be run.
+pub fn ensure_clean(
+    runner: &dyn CommandRunner,
+    cwd: &Path,
+    resume_base: Option<&str>,
+) -> Result<()> {
+    let paths = match dirty_paths(runner, cwd) {
+        Ok(paths) => paths,
+        Err(CoreError::CommandFailed { .. }) => return Ok(()),
+        Err(e) => return Err(e),
+    };
+    if paths.is_empty() {
+        return Ok(());
+    }
+    if let Some(base) = resume_base
+        && is_ancestor(runner, cwd, base)?
+    {
+        tracing::debug!(base, "keeping changes of the interrupted run");
+        return Ok(());
+    }
+    Err(CoreError::DirtyWorkingTree {
+        path: cwd.to_path_buf(),
+        paths,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::CoreError;
+    use crate::command::fake::FakeCommandRunner;
+
+    #[test]
+    fn test_worktree_add_argv() {
+        let runner = FakeCommandRunner::default();
+        let repo = PathBuf::from("/repo");
+
+        worktree_add(
+            &runner,
+            &repo,
+            Path::new(".trees/0001_user-auth"),
+            "feature/0001-user-auth",
+            "main",
+        )
+        .unwrap();
+
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 1);
+        let (program, args, cwd) = &calls[0];
+        assert_eq!(program, "git");
+        assert_eq!(
+            args,
+            &[
+                "worktree",
+                "add",
+                "-b",
+                "feature/0001-user-auth",
+                ".trees/0001_user-auth",
+                "main"
+            ]
+        );
+        assert_eq!(cwd, &repo);
+    }
+
+    #[test]
+    fn test_commit_all_returns_head_sha() {
+        let runner = FakeCommandRunner::default();
+        runner.respond(0, "", "");
+        runner.respond(0, "[main abc1234] msg", "");
+        runner.respond(0, "abc1234def\n", "");
+
+        let sha = commit_all(&runner, Path::new("/repo"), "Phase build").unwrap();
+
+        assert_eq!(sha, "abc1234def");
+        let programs: Vec<_> = runner.calls().into_iter().map(|c| c.1[0].clone()).collect();
+        assert_eq!(programs, ["add", "commit", "rev-parse"]);
+    }
+
+    #[test]
+    fn test_checkpoint_commits_only_changes() {
+        let runner = FakeCommandRunner::default();
+        runner.respond(0, " M src/lib.rs\n", "");
+        runner.respond(0, "", "");
+        runner.respond(0, "[main abc1234] msg", "");
+        runner.respond(0, "abc1234def\n", "");
+        let sha = checkpoint(&runner, Path::new("/repo"), "build phase").unwrap();
+        assert_eq!(sha.as_deref(), Some("abc1234def"));
+
+        runner.respond(0, "", "");
+        runner.respond(0, "abc1234def\n", "");
+        let sha = checkpoint(&runner, Path::new("/repo"), "review phase").unwrap();
+        assert_eq!(sha.as_deref(), Some("abc1234def"));
+        let programs: Vec<_> = runner.calls().into_iter().map(|c| c.1[0].clone()).collect();
+        assert_eq!(
+            programs,
+            [
+                "status",
+                "add",
+                "commit",
+                "rev-parse",
+                "status",
+                "rev-parse"
+            ]
+        );
+
+        runner.respond(128, "", "fatal: not a git repository");
+        assert_eq!(checkpoint(&runner, Path::new("/tmp"), "x").unwrap(), None);
+    }
+
+    #[test]
+    fn test_nonzero_exit_is_command_failed() {
+        let runner = FakeCommandRunner::default();
+        runner.respond(128, "", "fatal: not a git repository");
+
+        let err = head_commit(&runner, Path::new("/tmp")).unwrap_err();
+
+        assert!(matches!(
+            err,
+            CoreError::CommandFailed { ref stderr, .. } if stderr.contains("not a git repository")
+        ));
+    }
+
+    #[test]
+    fn test_gba_paths_are_not_dirty() {
+        let dir = tempfile::tempdir().unwrap();
+        let runner = crate::RealCommandRunner;
+        run_checked(&runner, "git", &["init", "--quiet"], dir.path()).unwrap();
+        for path in [
+            ".gba/features/0001_auth/state.yml",
+            ".trees/0001_auth/lib.rs",
+            "notes.md",
+        ] {
+            let path = dir.path().join(path);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "x").unwrap();
+        }
+
+        assert_eq!(dirty_paths(&runner, dir.path()).unwrap(), ["notes.md"]);
+        let err = ensure_clean(&runner, dir.path(), None).unwrap_err();
+        assert!(
+            err.to_string().contains("uncommitted changes: notes.md ("),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_ensure_clean_lists_dirty_paths() {
+        let runner = FakeCommandRunner::default();
+        let status: String = (0..12).map(|i| format!(" M src/file{i}.rs\n")).collect();
+        runner.respond(0, &format!("?? notes.md\n{status}"), "");
+
+        let err = ensure_clean(&runner, Path::new("/repo"), None).unwrap_err();
+
+        let CoreError::DirtyWorkingTree { ref paths, .. } = err else {
+            panic!("unexpected error: {err}");
+        };
+        assert_eq!(paths.len(), 13);
+        assert_eq!(paths[0], "notes.md");
+        assert_eq!(paths[1], "src/file0.rs");
+        let message = err.to_string();
+        assert!(message.contains("/repo has uncommitted changes: notes.md, src/file0.rs"));
+        assert!(message.contains("src/file7.rs and 4 more"));
+    }
+
+    #[test]
+    fn test_ensure_clean_allows_resumed_and_clean_trees() {
+        let runner = FakeCommandRunner::default();
+        runner.respond(0, "", "");
+        ensure_clean(&runner, Path::new("/repo"), None).unwrap();
+
+        runner.respond(0, " M src/lib.rs\n", "");
+        runner.respond(0, "", "");
+        ensure_clean(&runner, Path::new("/repo"), Some("abc123")).unwrap();
+        assert_eq!(
+            runner.calls()[2].1,
+            ["merge-base", "--is-ancestor", "abc123", "HEAD"]
+        );
+
+        runner.respond(0, " M src/lib.rs\n", "");
+        runner.respond(1, "", "");
+        assert!(ensure_clean(&runner, Path::new("/repo"), Some("abc123")).is_err());
+
+        runner.respond(128, "", "fatal: not a git repository");
+        ensure_clean(&runner, Path::new("/tmp"), None).unwrap();
+    }
+}
@@ -0,0 +1,628 @@
+//! Shell hooks executed before and after a phase.
+//!
+//! Hooks are configured per task in `prompts/{task}/config.yml`:
+//!
+//! ```yaml
+//! hooks:
+//!   preCommand: []
+//!   postCommand: ["cargo fmt", "cargo clippy -- -D warnings"]
+//!   onHookFailure: retry   # fail | retry | ignore
+//!   timeoutSeconds: 300
+//! ```
+//!
+//! A phase entry in `.gba/config.yml` may carry its own `hooks:` section,
+//! which replaces the task's.
+//!
+//! Each command runs through `sh -c`, with the engine's
+//! [`CommandRunner`](crate::CommandRunner), in the phase working directory (the
+//! feature worktree) with `GBA_FEATURE_ID`, `GBA_FEATURE_SLUG`, `GBA_PHASE`
+//! and `GBA_HOOK_STAGE` set. Commands of a stage run in order and the stage
+//! stops at the first failing command.
+
+use std::fmt;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::command::CommandRunner;
+use crate::error::{CoreError, Result};
+use crate::execution::{ExecutionRequest, ExecutionResult};
+
+const DEFAULT_HOOK_TIMEOUT_SECONDS: u64 = 300;
+
+/// Hook configuration of a phase
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct PhaseHooks {
+    /// Commands run before the agent executes
+    pub pre_command: Vec<String>,
+    /// Commands run after the agent finished
+    pub post_command: Vec<String>,
+    /// What to do when a hook command fails
+    pub on_hook_failure: HookFailurePolicy,
+    /// Timeout applied to each hook command
+    pub timeout_seconds: u64,
+}
+
+impl Default for PhaseHooks {
+    fn default() -> Self {
+        Self {
+            pre_command: Vec::new(),
+            post_command: Vec::new(),
+            on_hook_failure: HookFailurePolicy::default(),
+            timeout_seconds: DEFAULT_HOOK_TIMEOUT_SECONDS,
+        }
+    }
+}
+
+/// Policy applied when a hook command fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HookFailurePolicy {
+    /// Fail the phase
+    #[default]
+    Fail,
+    /// Feed the hook output back to the agent in a retry prompt (post hooks only)
+    Retry,
+    /// Log the failure and continue
+    Ignore,
+}
+
+/// Stage at which a hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum HookStage {
+    /// Before the agent executes
+    Pre,
+    /// After the agent finished
+    Post,
+}
+
+impl fmt::Display for HookStage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pre => write!(f, "pre"),
+            Self::Post => write!(f, "post"),
+        }
+    }
+}
+
+/// Environment a hook runs in
+#[derive(Debug, Clone)]
+pub struct HookContext {
+    /// Working directory (the feature worktree)
+    pub working_dir: PathBuf,
+    /// Feature ID (e.g. "0001")
+    pub feature_id: String,
+    /// Feature slug
+    pub feature_slug: String,
+    /// Phase name
+    pub phase: String,
+}
+
+/// Outcome of a single hook command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookRecord {
+    /// Command line that was executed
+    pub command: String,
+    /// Stage the command ran in
+    pub stage: HookStage,
+    /// Exit code (None if killed or it could not be spawned)
+    pub exit_code: Option<i32>,
+    /// Whether the command hit the timeout
+    pub timed_out: bool,
+    /// Wall-clock duration in milliseconds
+    pub duration_ms: u64,
+    /// Captured standard output
+    #[serde(skip)]
+    pub stdout: String,
+    /// Captured standard error
+    #[serde(skip)]
+    pub stderr: String,
+}
+
+impl HookRecord {
+    /// Whether the command succeeded
+    pub fn success(&self) -> bool {
+        !self.timed_out && self.exit_code == Some(0)
+    }
+
+    fn failure_reason(&self) -> String {
+        if self.timed_out {
+            format!("timed out after {}ms", self.duration_ms)
+        } else {
+            match self.exit_code {
+                Some(code) => format!("exited with code {code}"),
+                None => "was terminated by a signal".to_string(),
+            }
+        }
+    }
+}
+
+impl fmt::Display for HookRecord {
+    /// Render the record as a block suitable for the phase log
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.success() {
+            "ok".to_string()
+        } else {
+            self.failure_reason()
+        };
+        writeln!(
+            f,
+            "[{} hook] $ {} ({status}, {}ms)",
+            self.stage, self.command, self.duration_ms
+        )?;
+        if !self.stdout.is_empty() {
+            writeln!(f, "--- stdout ---\n{}", self.stdout.trim_end())?;
+        }
+        if !self.stderr.is_empty() {
+            writeln!(f, "--- stderr ---\n{}", self.stderr.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// What the caller should do after a hook stage ran
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookVerdict {
+    /// All commands succeeded
+    Passed,
+    /// A command failed but the policy says to ignore it
+    Ignored,
+    /// A post hook failed and the agent should retry with this feedback
+    Retry {
+        /// Text to append to the retry prompt
+        feedback: String,
+    },
+    /// The phase must fail
+    Fail {
+        /// Human readable failure description
+        message: String,
+    },
+}
+
+/// Records and verdict of a hook stage
+#[derive(Debug, Clone)]
+pub struct HookReport {
+    /// Stage that ran
+    pub stage: HookStage,
+    /// One record per executed command
+    pub records: Vec<HookRecord>,
+    /// Resulting decision
+    pub verdict: HookVerdict,
+}
+
+impl HookReport {
+    /// Total time spent in this stage
+    pub fn duration(&self) -> Duration {
+        Duration::from_millis(self.records.iter().map(|r| r.duration_ms).sum())
+    }
+
+    /// Convert a `Fail` verdict into an error, passing other verdicts through.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::AgentExecutionFailed` when the verdict is `Fail`.
+    pub fn into_result(self) -> Result<Self> {
+        match &self.verdict {
+            HookVerdict::Fail { message } => Err(CoreError::AgentExecutionFailed(message.clone())),
+            _ => Ok(self),
+        }
+    }
+}
+
+impl PhaseHooks {
+    /// Whether no hook is configured
+    pub fn is_empty(&self) -> bool {
+        self.pre_command.is_empty() && self.post_command.is_empty()
+    }
+
+    /// Commands configured for a stage
+    pub fn commands(&self, stage: HookStage) -> &[String] {
+        match stage {
+            HookStage::Pre => &self.pre_command,
+            HookStage::Post => &self.post_command,
+        }
+    }
+
+    /// Run all commands of a stage and decide how the phase should proceed.
+    ///
+    /// Failures of the hooks themselves are reported through the verdict,
+    /// never as an `Err`, so the records are always available to the caller.
+    pub async fn run(
+        &self,
+        runner: &Arc<dyn CommandRunner>,
+        stage: HookStage,
+        ctx: &HookContext,
+    ) -> HookReport {
+        let timeout = Duration::from_secs(self.timeout_seconds);
+        let mut records = Vec::with_capacity(self.commands(stage).len());
+
+        for command in self.commands(stage) {
+            let record = run_command(runner, command, stage, ctx, timeout).await;
+            let success = record.success();
+            records.push(record);
+            if !success {
+                break;
+            }
+        }
+
+        let verdict = self.verdict(stage, records.iter().find(|r| !r.success()));
+        HookReport {
+            stage,
+            records,
+            verdict,
+        }
+    }
+
+    fn verdict(&self, stage: HookStage, failed: Option<&HookRecord>) -> HookVerdict {
+        let Some(failed) = failed else {
+            return HookVerdict::Passed;
+        };
+
+        let message = format!(
+            "{stage} hook `{}` {}",
+            failed.command,
+            failed.failure_reason()
+        );
+        match (self.on_hook_failure, stage) {
+            (HookFailurePolicy::Ignore, _) => {
+                tracing::warn!("{message} (ignored)");
+                HookVerdict::Ignored
+            }
+            (HookFailurePolicy::Retry, HookStage::Post) => {
+                let output = if failed.stderr.trim().is_empty() {
+                    &failed.stdout
+                } else {
+                    &failed.stderr
+                };
+                HookVerdict::Retry {
+                    feedback: format!(
+                        "The command `{}` {} after your changes. Fix the problems below:\n\n{}",
+                        failed.command,
+                        failed.failure_reason(),
+                        output.trim_end()
+                    ),
+                }
+            }
+            _ => HookVerdict::Fail { message },
+        }
+    }
+}
+
+/// Run `execute` between the pre and post hooks of a phase.
+///
+/// A failing pre hook aborts before `execute` is called. With the `retry`
+/// policy a failing post hook re-runs `execute` once with the hook output as
+/// feedback. The hook records end up in `hooks`, rendered in `hook_output`.
+pub(crate) async fn run_with_hooks<F, Fut>(
+    runner: &Arc<dyn CommandRunner>,
+    hooks: &PhaseHooks,
+    ctx: &HookContext,
+    request: ExecutionRequest,
+    execute: F,
+) -> Result<ExecutionResult>
+where
+    F: Fn(ExecutionRequest) -> Fut,
+    Fut: Future<Output = Result<ExecutionResult>>,
+{
+    let mut records = Vec::new();
+    let pre = hooks.run(runner, HookStage::Pre, ctx).await;
+    check(pre, &mut records)?;
+
+    let mut result = execute(request.clone()).await?;
+    if result.success && !hooks.post_command.is_empty() {
+        let post = hooks.run(runner, HookStage::Post, ctx).await;
+        if let HookVerdict::Retry { feedback } = &post.verdict {
+            tracing::info!(phase = %ctx.phase, "post hook failed, retrying the agent");
+            let retry = ExecutionRequest {
+                user_prompt: format!("{}\n\n{feedback}", request.user_prompt.trim_end()),
+                ..request
+            };
+            records.extend(post.records);
+            let retried = execute(retry).await?;
+            let mut stats = result.stats;
+            stats.accumulate(&retried.stats);
+            result = ExecutionResult {
+                duration: result.duration + retried.duration,
+                stats,
+                ..retried
+            };
+
+            let mut post = hooks.run(runner, HookStage::Post, ctx).await;
+            if let HookVerdict::Retry { .. } = post.verdict {
+                // Only one retry: a second failure fails the phase.
+                post.verdict = HookVerdict::Fail {
+                    message: format!(
+                        "post hook still failing after retry: {}",
+                        post.records.last().map_or("", |r| r.command.as_str())
+                    ),
+                };
+            }
+            check(post, &mut records)?;
+        } else {
+            check(post, &mut records)?;
+        }
+    }
+
+    result.hook_output = records.iter().map(ToString::to_string).collect();
+    result.hooks = records;
+    Ok(result)
+}
+
+/// Collect the records of a stage, failing with its output if required
+fn check(report: HookReport, records: &mut Vec<HookRecord>) -> Result<()> {
+    records.extend(report.records);
+    match report.verdict {
+        HookVerdict::Fail { message } => {
+            let output: String = records
+                .iter()
+                .filter(|r| !r.success())
+                .map(ToString::to_string)
+                .collect();
+            Err(CoreError::AgentExecutionFailed(format!(
+                "{message}\n{}",
+                output.trim_end()
+            )))
+        }
+        _ => Ok(()),
+    }
+}
+
+async fn run_command(
+    runner: &Arc<dyn CommandRunner>,
+    command: &str,
+    stage: HookStage,
+    ctx: &HookContext,
+    timeout: Duration,
+) -> HookRecord {
+    let start = Instant::now();
+    let mut record = HookRecord {
+        command: command.to_string(),
+        stage,
+        exit_code: None,
+        timed_out: false,
+        duration_ms: 0,
+        stdout: String::new(),
+        stderr: String::new(),
+    };
+
+    let runner = runner.clone();
+    let script = command.to_string();
+    let cwd = ctx.working_dir.clone();
+    let env = [
+        ("GBA_FEATURE_ID", ctx.feature_id.clone()),
+        ("GBA_FEATURE_SLUG", ctx.feature_slug.clone()),
+        ("GBA_PHASE", ctx.phase.clone()),
+        ("GBA_HOOK_STAGE", stage.to_string()),
+    ];
+    let output = tokio::task::spawn_blocking(move || {
+        let env: Vec<_> = env.iter().map(|(k, v)| (*k, v.as_str())).collect();
+        runner.run_with_timeout("sh", &["-c", &script], &cwd, &env, timeout)
+    })
+    .await;
+
+    match output {
+        Ok(Ok(output)) => {
+            record.exit_code = output.status.code();
+            record.stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+            record.stderr = String::from_utf8_lossy(&output.stderr).into_owned();
+        }
+        Ok(Err(CoreError::CommandTimedOut { .. })) => record.timed_out = true,
+        Ok(Err(e)) => record.stderr = format!("failed to run hook: {e}"),
+        Err(e) => record.stderr = format!("hook task failed: {e}"),
+    }
+
+    record.duration_ms = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+    tracing::info!(
+        phase = %ctx.phase,
+        stage = %stage,
+        command,
+        exit_code = ?record.exit_code,
+        duration_ms = record.duration_ms,
+        "hook finished"
+    );
+    tracing::debug!(stdout = %record.stdout, stderr = %record.stderr, "hook output");
+    record
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::fake::FakeCommandRunner;
+
+    fn context(dir: &std::path::Path) -> HookContext {
+        HookContext {
+            working_dir: dir.to_path_buf(),
+            feature_id: "0001".to_string(),
+            feature_slug: "user-auth".to_string(),
+            phase: "build".to_string(),
+        }
+    }
+
+    fn hooks(post: &[&str], policy: HookFailurePolicy) -> PhaseHooks {
+        PhaseHooks {
+            post_command: post.iter().map(|s| s.to_string()).collect(),
+            on_hook_failure: policy,
+            ..PhaseHooks::default()
+        }
+    }
+
+    fn runner() -> (Arc<FakeCommandRunner>, Arc<dyn CommandRunner>) {
+        let fake = Arc::new(FakeCommandRunner::default());
+        (fake.clone(), fake)
+    }
+
+    #[tokio::test]
+    async fn test_hooks_pass_with_env_and_cwd() {
+        let dir = tempfile::tempdir().unwrap();
+        let (fake, runner) = runner();
+        let hooks = hooks(&["cargo fmt --check"], HookFailurePolicy::Fail);
+
+        let report = hooks
+            .run(&runner, HookStage::Post, &context(dir.path()))
+            .await;
+
+        assert_eq!(report.verdict, HookVerdict::Passed);
+        assert_eq!(report.records.len(), 1);
+        let calls = fake.calls();
+        assert_eq!(calls[0].0, "sh");
+        assert_eq!(calls[0].1, ["-c", "cargo fmt --check"]);
+        assert_eq!(calls[0].2, dir.path());
+        let env = &fake.envs()[0];
+        for (name, value) in [
+            ("GBA_FEATURE_ID", "0001"),
+            ("GBA_FEATURE_SLUG", "user-auth"),
+            ("GBA_PHASE", "build"),
+            ("GBA_HOOK_STAGE", "post"),
+        ] {
+            assert!(env.contains(&(name.to_string(), value.to_string())));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_hook_fails_phase_and_stops_stage() {
+        let dir = tempfile::tempdir().unwrap();
+        let (fake, runner) = runner();
+        fake.respond(3, "", "");
+        let hooks = hooks(&["exit 3", "touch never"], HookFailurePolicy::Fail);
+
+        let report = hooks
+            .run(&runner, HookStage::Post, &context(dir.path()))
+            .await;
+
+        assert_eq!(report.records.len(), 1);
+        assert_eq!(report.records[0].exit_code, Some(3));
+        assert_eq!(fake.calls().len(), 1);
+        assert!(matches!(
+            report.into_result(),
+            Err(CoreError::AgentExecutionFailed(msg)) if msg.contains("exit 3")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_feeds_stderr_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let (fake, runner) = runner();
+        fake.respond(1, "", "boom");
+        let hooks = hooks(&["cargo clippy"], HookFailurePolicy::Retry);
+
+        let report = hooks
+            .run(&runner, HookStage::Post, &context(dir.path()))
+            .await;
+
+        match report.verdict {
+            HookVerdict::Retry { feedback } => assert!(feedback.contains("boom")),
+            other => panic!("unexpected verdict: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_on_pre_hook_fails() {
+        let dir = tempfile::tempdir().unwrap();
+        let (fake, runner) = runner();
+        fake.respond(1, "", "");
+        let hooks = PhaseHooks {
+            pre_command: vec!["false".to_string()],
+            on_hook_failure: HookFailurePolicy::Retry,
+            ..PhaseHooks::default()
+        };
+
+        let report = hooks
+            .run(&runner, HookStage::Pre, &context(dir.path()))
+            .await;
+
+        assert!(matches!(report.verdict, HookVerdict::Fail { .. }));
+    }
+
+    fn agent(
+        calls: &std::sync::atomic::AtomicUsize,
+    ) -> impl Fn(ExecutionRequest) -> std::future::Ready<Result<ExecutionResult>> + '_ {
+        move |request| {
+            calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            std::future::ready(Ok(ExecutionResult {
+                success: true,
+                output: request.user_prompt,
+                ..ExecutionResult::default()
+            }))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_phase_hooks_surround_agent() {
+        let dir = tempfile::tempdir().unwrap();
+        let (fake, runner) = runner();
+        fake.respond(0, "preparing\n", "");
+        fake.respond(0, "checked\n", "");
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let hooks = PhaseHooks {
+            pre_command: vec!["echo preparing".to_string()],
+            ..hooks(&["echo checked"], HookFailurePolicy::Fail)
+        };
+
+        let result = run_with_hooks(
+            &runner,
+            &hooks,
+            &context(dir.path()),
+            ExecutionRequest::new("build it"),
+            agent(&calls),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(calls.into_inner(), 1);
+        assert_eq!(result.output, "build it");
+        assert!(result.hook_output.contains("[pre hook] $ echo preparing"));
+        assert!(result.hook_output.contains("--- stdout ---\nchecked"));
+        let stages: Vec<_> = result.hooks.iter().map(|r| r.stage).collect();
+        assert_eq!(stages, [HookStage::Pre, HookStage::Post]);
+    }
+
+    #[tokio::test]
+    async fn test_failing_pre_hook_skips_agent() {
+        let dir = tempfile::tempdir().unwrap();
+        let (fake, runner) = runner();
+        fake.respond(1, "", "dirty tree");
+        let calls = std::sync::atomic::AtomicUsize::new(0);
+        let hooks = PhaseHooks {
+            pre_command: vec!["git diff --quiet".to_string()],
+            ..PhaseHooks::default()
+        };
+
+        let err = run_with_hooks(
+            &runner,
+            &hooks,
+            &context(dir.path()),
+            ExecutionRequest::new("build it"),
+            agent(&calls),
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(calls.into_inner(), 0);
+        assert!(matches!(
+            err,
+            CoreError::AgentExecutionFailed(msg)
+                if msg.contains("pre hook") && msg.contains("dirty tree")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_ignore_policy_and_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let (fake, runner) = runner();
+        fake.time_out();
+        let hooks = hooks(&["sleep 5"], HookFailurePolicy::Ignore);
+
+        let report = hooks
+            .run(&runner, HookStage::Post, &context(dir.path()))
+            .await;
+
+        assert_eq!(report.verdict, HookVerdict::Ignored);
+        assert!(report.records[0].timed_out);
+    }
+}
@@ -1,16 +1,122 @@
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use client::{AgentClientFactory, ClaudeClientFactory};
+use metrics::{Metrics, NoopMetrics};
+use permissions::{PermissionsConfig, ToolApprover};
+use rate_limit::{LimiterState, RateLimiter};
+use transcript::{AgentBackend, TranscriptRecorder};
+
+mod agent;
+pub mod archive;
+pub mod auth;
+pub mod client;
+mod command;
+mod config;
+mod cost;
+pub mod delete;
+mod environment;
+mod error;
+mod estimate;
+mod execution;
+mod executor;
+pub mod export;
+pub mod gh;
+pub mod git;
+mod hooks;
+mod loader;
+pub mod lock;
+pub mod mcp;
+pub mod metrics;
+mod model;
+pub mod notify;
+pub mod observations;
+pub mod permissions;
+mod phase;
+pub mod pr;
+mod progress;
+pub mod rate_limit;
+pub mod review;
+mod sanitize;
+mod scheduler;
+mod state;
+pub mod summary;
+mod task;
+pub mod testing;
+pub mod text;
+pub mod transcript;
+pub mod verification;
+
+pub use command::{CommandRunner, RealCommandRunner};
+pub use config::{
+    AgentConfig, CONFIG_FILE, ConfigPermissionMode, DEFAULT_CONFIG, GitConfig, NotificationConfig,
+    PROMPTS_DIR, PhaseConfig, ProjectConfig, RateLimitConfig, SpecsConfig, SummariesConfig,
+};
+pub use cost::{CostReport, CostSummary, FeatureCost, ModelCost, PhaseCost};
+pub use error::{CoreError, Result};
+pub use estimate::{CostEstimate, DEFAULT_PHASE_COST_USD, EstimateSource, PhaseEstimate};
+pub use execution::{ExecutionRequest, ExecutionResult, HISTORY_BUDGET_TOKENS, Role};
+pub use executor::{CommandExecutor, ExecutionContext, PhaseExecutor, PhaseKind};
+pub use hooks::{
+    HookContext, HookFailurePolicy, HookRecord, HookReport, HookStage, HookVerdict, PhaseHooks,
+};
+pub use loader::{
+    ConfigLoader, ConfigSource, DOTENV_FILE, ENV_OVERRIDES, GLOBAL_CONFIG_ENV, GLOBAL_PROMPTS_ENV,
+    LoadedConfig, SETTABLE_KEYS, global_config_path, global_prompts_dir, merge_default_config,
+    set_config_value,
+};
+pub use model::{KNOWN_MODELS, MODEL_ALIASES, validate_model};
+pub use phase::{Phase, dependency_order};
+pub use progress::{ProgressEvent, ProgressSender, RunEvent, RunEventSender};
+pub use sanitize::sanitize;
+pub use state::{
+    ARCHIVE_DIR, DEFAULT_EVENT_LIMIT, DESIGN_FILE, EventKind, ExecutionStats, ExecutionTiming,
+    FEATURES_DIR, FeatureInfo, FeatureListing, FeatureState, FeatureStatus, GitInfo,
+    InterruptReason, PhaseState, PhaseStatus, PreviousOutput, PullRequestInfo, ResumeInfo,
+    STATE_FILE, StateEvent,
+};
+pub use task::TaskConfig;
 
 /// Configuration for the GBA core engine
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     /// Repository path to work with
     pub repo_path: PathBuf,
-    /// Claude API key
+    /// Claude API key; never serialized
+    #[serde(default, skip_serializing)]
     pub api_key: String,
     /// Model to use (default: claude-sonnet-4-5-20250929)
     pub model: String,
+    /// Tool permission mode (default: bypassPermissions)
+    pub permission_mode: ConfigPermissionMode,
+    /// Maximum agent turns per request (None = SDK default)
+    pub max_turns: Option<u32>,
+    /// Response timeout for phases without their own (default: 300)
+    pub timeout_seconds: u64,
+    /// Maximum bytes of agent output kept per request (None = unlimited)
+    #[serde(default)]
+    pub max_output_bytes: Option<usize>,
+    /// Stop the agent once its output exceeds `max_output_bytes`
+    #[serde(default)]
+    pub stop_on_output_limit: bool,
+    /// Pacing of the engine's requests
+    #[serde(default)]
+    pub rate_limit: RateLimitConfig,
+    /// Approval of the agent's tool uses
+    #[serde(default)]
+    pub permissions: PermissionsConfig,
+    /// Variables set in the agent's environment, `${VAR}` expanded
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    /// Inherited variables (names or `*` globs) removed for the agent
+    #[serde(default)]
+    pub env_deny: Vec<String>,
+    /// Skip the SDK and return a canned result for every request (dry runs, tests)
+    #[serde(default)]
+    pub offline: bool,
 }
 
 impl Default for Config {
@@ -19,19 +125,213 @@ impl Default for Config {
             repo_path: PathBuf::from("."),
             api_key: String::new(),
             model: "claude-sonnet-4-5-20250929".to_string(),
+            permission_mode: ConfigPermissionMode::default(),
+            max_turns: None,
+            timeout_seconds: config::DEFAULT_TIMEOUT_SECONDS,
+            max_output_bytes: Some(config::DEFAULT_MAX_OUTPUT_BYTES),
+            stop_on_output_limit: false,
+            rate_limit: RateLimitConfig::default(),
+            permissions: PermissionsConfig::default(),
+            env: BTreeMap::new(),
+            env_deny: Vec::new(),
+            offline: false,
         }
     }
 }
 
-/// Core execution engine for GBA
+impl Config {
+    /// Builder starting from the defaults
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder::default()
+    }
+}
+
+/// Builds a [`Config`], keeping the default of every value not set
+#[derive(Debug, Clone, Default)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl ConfigBuilder {
+    /// Repository to work in
+    pub fn repo_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.repo_path = path.into();
+        self
+    }
+
+    /// Anthropic API key
+    pub fn api_key(mut self, key: impl Into<String>) -> Self {
+        self.config.api_key = key.into();
+        self
+    }
+
+    /// Model to use
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.config.model = model.into();
+        self
+    }
+
+    /// Maximum agent turns per request (None = SDK default)
+    pub fn max_turns(mut self, turns: Option<u32>) -> Self {
+        self.config.max_turns = turns;
+        self
+    }
+
+    /// Response timeout for phases without their own
+    pub fn timeout_seconds(mut self, seconds: u64) -> Self {
+        self.config.timeout_seconds = seconds;
+        self
+    }
+
+    /// Tool permission mode
+    pub fn permission_mode(mut self, mode: ConfigPermissionMode) -> Self {
+        self.config.permission_mode = mode;
+        self
+    }
+
+    /// Maximum bytes of agent output kept per request (None = unlimited)
+    pub fn max_output_bytes(mut self, bytes: Option<usize>) -> Self {
+        self.config.max_output_bytes = bytes;
+        self
+    }
+
+    /// Stop the agent once its output exceeds `max_output_bytes`
+    pub fn stop_on_output_limit(mut self, stop: bool) -> Self {
+        self.config.stop_on_output_limit = stop;
+        self
+    }
+
+    /// Pacing of the engine's requests
+    pub fn rate_limit(mut self, rate_limit: RateLimitConfig) -> Self {
+        self.config.rate_limit = rate_limit;
+        self
+    }
+
+    /// Approval of the agent's tool uses
+    pub fn permissions(mut self, permissions: PermissionsConfig) -> Self {
+        self.config.permissions = permissions;
+        self
+    }
+
+    /// Variables set in the agent's environment, `${VAR}` expanded
+    pub fn env(mut self, env: BTreeMap<String, String>) -> Self {
+        self.config.env = env;
+        self
+    }
+
+    /// Inherited variables (names or `*` globs) removed for the agent
+    pub fn env_deny(mut self, patterns: Vec<String>) -> Self {
+        self.config.env_deny = patterns;
+        self
+    }
+
+    /// Return canned results instead of calling the SDK
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.config.offline = offline;
+        self
+    }
+
+    /// The configuration
+    pub fn build(self) -> Config {
+        self.config
+    }
+}
+
+/// Core execution engine for GBA.
+///
+/// Clones share one [`RateLimiter`], so requests of all clones are paced
+/// together.
+#[derive(Debug, Clone)]
 pub struct Engine {
     config: Config,
+    limiter: Arc<RateLimiter>,
+    approver: Option<Arc<dyn ToolApprover>>,
+    metrics: Arc<dyn Metrics>,
+    backend: Option<Arc<dyn AgentBackend>>,
+    clients: Arc<dyn AgentClientFactory>,
+    recorder: Option<Arc<TranscriptRecorder>>,
+    runner: Arc<dyn CommandRunner>,
 }
 
 impl Engine {
     /// Create a new engine instance
     pub fn new(config: Config) -> Self {
-        Self { config }
+        let limiter = Arc::new(RateLimiter::new(&config.rate_limit));
+        Self {
+            config,
+            limiter,
+            approver: None,
+            metrics: Arc::new(NoopMetrics),
+            backend: None,
+            clients: Arc::new(ClaudeClientFactory),
+            recorder: None,
+            runner: Arc::new(RealCommandRunner),
+        }
+    }
+
+    /// Ask `approver` about tool uses `Config::permissions` doesn't allow by
+    /// itself, instead of denying them
+    pub fn with_approver(mut self, approver: Arc<dyn ToolApprover>) -> Self {
+        self.approver = Some(approver);
+        self
+    }
+
+    /// Report the engine's metrics (see [`metrics`]) to `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<dyn Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    /// Answer requests with `backend` instead of the Claude Agent SDK, e.g.
+    /// a [`transcript::ReplayBackend`]
+    pub fn with_backend(mut self, backend: Arc<dyn AgentBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Create the agent client of each request with `factory` instead of
+    /// [`ClaudeClientFactory`], e.g. a scripted client in tests
+    pub fn with_client_factory(mut self, factory: Arc<dyn AgentClientFactory>) -> Self {
+        self.clients = factory;
+        self
+    }
+
+    /// Record the messages of every response with `recorder`
+    pub fn with_recorder(mut self, recorder: Arc<TranscriptRecorder>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    /// Run hook, `git` and `gh` commands with `runner` instead of
+    /// [`RealCommandRunner`], e.g. a fake in tests
+    pub fn with_command_runner(mut self, runner: Arc<dyn CommandRunner>) -> Self {
+        self.runner = runner;
+        self
+    }
+
+    /// Engine for `config` that shares this engine's rate limiter, e.g. to
+    /// run features in their own worktrees under common limits
+    pub fn with_config(&self, config: Config) -> Self {
+        Self {
+            config,
+            limiter: self.limiter.clone(),
+            approver: self.approver.clone(),
+            metrics: self.metrics.clone(),
+            backend: self.backend.clone(),
+            clients: self.clients.clone(),
+            recorder: self.recorder.clone(),
+            runner: self.runner.clone(),
+        }
+    }
+
+    /// Runner of hook, `git` and `gh` commands
+    pub fn command_runner(&self) -> &Arc<dyn CommandRunner> {
+        &self.runner
+    }
+
+    /// Current state of the request rate limiter
+    pub fn rate_limit_state(&self) -> LimiterState {
+        self.limiter.state()
     }
 
     /// Execute a task with the given prompt
@@ -40,6 +340,497 @@ impl Engine {
         Ok(format!("Executing: {}", prompt))
     }
 
+    /// Execute a structured request with the Claude Agent SDK.
+    ///
+    /// With `Config::offline` the SDK is skipped and the result echoes the
+    /// prompt at zero cost.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::AgentTimeout` (carrying the output received so
+    /// far) if the agent doesn't finish within the request's timeout, or
+    /// `CoreError::AgentExecutionFailed` if the SDK reports an error.
+    pub async fn execute_request(&self, request: ExecutionRequest) -> Result<ExecutionResult> {
+        self.run_request(request, None).await
+    }
+
+    /// Execute a structured request, reporting turns and text to `progress`.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Engine::execute_request`].
+    pub async fn execute_request_with_progress(
+        &self,
+        request: ExecutionRequest,
+        progress: ProgressSender,
+    ) -> Result<ExecutionResult> {
+        self.run_request(request, Some(&progress)).await
+    }
+
+    /// Run `request`, recording its metrics
+    async fn run_request(
+        &self,
+        request: ExecutionRequest,
+        progress: Option<&ProgressSender>,
+    ) -> Result<ExecutionResult> {
+        let mut request = request;
+        let dropped = request.trim_history(HISTORY_BUDGET_TOKENS);
+        if dropped > 0 {
+            tracing::warn!(
+                target: metrics::TARGET,
+                dropped,
+                "conversation history exceeds the context budget, dropped the oldest turns"
+            );
+        }
+        let model = self.model_for(&request).to_string();
+        tracing::info!(target: metrics::TARGET, model, "execution started");
+        let start = Instant::now();
+        let result = if self.config.offline {
+            Ok(ExecutionResult::offline(&request, &model))
+        } else {
+            self.send_request(&request, progress).await
+        };
+        self.record_execution(&model, start.elapsed(), &result);
+        result.map(|mut result| {
+            result.stats.history_turns_dropped = dropped;
+            result.conversation = request.conversation(&result.output);
+            result
+        })
+    }
+
+    /// Continue the conversation of `previous` with `prompt`.
+    ///
+    /// The new request only carries the conversation; the oldest turns are
+    /// dropped if it grows beyond [`HISTORY_BUDGET_TOKENS`].
+    ///
+    /// # Errors
+    ///
+    /// Same as [`Engine::execute_request`].
+    pub async fn continue_execution(
+        &self,
+        previous: &ExecutionResult,
+        prompt: impl Into<String>,
+    ) -> Result<ExecutionResult> {
+        let request = ExecutionRequest {
+            history: previous.conversation.clone(),
+            ..ExecutionRequest::new(prompt)
+        };
+        self.execute_request(request).await
+    }
+
+    /// Send `request` once the rate limiter allows it, retrying with
+    /// backoff while the API rejects it as rate-limited
+    async fn send_request(
+        &self,
+        request: &ExecutionRequest,
+        progress: Option<&ProgressSender>,
+    ) -> Result<ExecutionResult> {
+        let mut attempt = 0;
+        loop {
+            let permit = self.limiter.acquire().await;
+            let result = self.query_agent(request, progress).await;
+            drop(permit);
+            match result {
+                Err(e)
+                    if rate_limit::is_rate_limited(&e) && attempt < self.limiter.max_retries() =>
+                {
+                    attempt += 1;
+                    let delay = rate_limit::backoff(attempt);
+                    tracing::warn!(
+                        target: metrics::TARGET,
+                        attempt,
+                        delay_ms = delay.as_millis() as u64,
+                        "agent request rate-limited, retrying: {e}"
+                    );
+                    self.metrics.counter(metrics::RETRIES, 1, &[]);
+                    self.limiter.throttle();
+                    tokio::time::sleep(delay).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Emit the completion event and metrics of an agent request
+    fn record_execution(&self, model: &str, elapsed: Duration, result: &Result<ExecutionResult>) {
+        let duration_ms = elapsed.as_millis() as u64;
+        let labels = [("model", model)];
+        match result {
+            Ok(result) => {
+                let tokens = result.stats.input_tokens + result.stats.output_tokens;
+                tracing::info!(
+                    target: metrics::TARGET,
+                    model,
+                    duration_ms,
+                    cost_usd = result.stats.cost_usd,
+                    tokens,
+                    success = result.success,
+                    "execution completed"
+                );
+                self.metrics
+                    .histogram(metrics::COST_USD, result.stats.cost_usd, &labels);
+                self.metrics.counter(metrics::TOKENS, tokens, &labels);
+            }
+            Err(CoreError::AgentTimeout { .. }) => {
+                tracing::warn!(target: metrics::TARGET, model, duration_ms, "execution timed out");
+                self.metrics.counter(metrics::TIMEOUTS, 1, &[]);
+            }
+            Err(e) => {
+                tracing::warn!(target: metrics::TARGET, model, duration_ms, "execution failed: {e}");
+            }
+        }
+        let outcome = metrics::outcome(result, |r| r.success);
+        self.metrics.counter(
+            metrics::EXECUTIONS,
+            1,
+            &[("model", model), ("outcome", outcome)],
+        );
+        self.metrics
+            .histogram(metrics::EXECUTION_DURATION_MS, duration_ms as f64, &labels);
+    }
+
+    /// Send `request` to the agent and collect its response
+    async fn query_agent(
+        &self,
+        request: &ExecutionRequest,
+        progress: Option<&ProgressSender>,
+    ) -> Result<ExecutionResult> {
+        let timeout = self.timeout_for(request);
+        let mut options = agent::build_options(&self.config, request);
+        let denials = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        if self.config.permissions.is_enabled() {
+            options.hooks = Some(permissions::hooks(
+                self.config.permissions.clone(),
+                self.approver.clone(),
+                denials.clone(),
+            ));
+        }
+        let failed =
+            |e: claude_agent_sdk_rs::ClaudeError| CoreError::AgentExecutionFailed(e.to_string());
+
+        let limit = self
+            .config
+            .max_output_bytes
+            .map(|bytes| agent::OutputLimit {
+                bytes,
+                stop: self.config.stop_on_output_limit,
+            });
+        let writer = self
+            .recorder
+            .as_ref()
+            .map(|recorder| {
+                let secrets = agent::secrets(&self.config, request, &std::env::vars().collect());
+                recorder.start(request, secrets)
+            })
+            .transpose()?;
+
+        let start = Instant::now();
+        let mut full_output = String::new();
+        let response = match &self.backend {
+            Some(backend) => {
+                let stream = transcript::recorded(backend.respond(request).await?, writer);
+                agent::collect_with_timeout(stream, timeout, &mut full_output, limit, progress)
+                    .await
+            }
+            None => {
+                options.cli_path =
+                    agent::launcher(&self.config, request, &options, &std::env::vars().collect())?
+                        .or(options.cli_path.take());
+                let mut client = self.clients.create(options).map_err(failed)?;
+                client.connect().await.map_err(failed)?;
+                client.query(request.prompt()).await.map_err(failed)?;
+                let stream = transcript::recorded(client.receive_response(), writer);
+                let response =
+                    agent::collect_with_timeout(stream, timeout, &mut full_output, limit, progress)
+                        .await;
+                if let Err(e) = client.disconnect().await {
+                    tracing::warn!("failed to disconnect agent: {e}");
+                }
+                response
+            }
+        };
+        let mut response = response?;
+        let denied_tools = std::mem::take(&mut *denials.lock());
+        response.stats.denied_tool_uses = denied_tools.len() as u32;
+        if response.dropped_bytes > 0 {
+            tracing::warn!(
+                "agent output truncated, {} bytes dropped",
+                response.dropped_bytes
+            );
+        }
+
+        Ok(ExecutionResult {
+            success: response.success,
+            output: full_output,
+            duration: start.elapsed(),
+            stats: response.stats,
+            truncated: response.dropped_bytes > 0,
+            dropped_bytes: response.dropped_bytes,
+            // Prefer what the agent reported over what was asked for.
+            model: response
+                .model
+                .or_else(|| Some(self.model_for(request).to_string())),
+            hook_output: String::new(),
+            hooks: Vec::new(),
+            test_runs: Vec::new(),
+            denied_tools,
+            conversation: Vec::new(),
+        })
+    }
+
+    /// Execute a phase between its pre and post hooks.
+    ///
+    /// Agent phases are sent to the agent, command phases run their
+    /// [`CommandExecutor`] (only echoed when offline). `progress` receives
+    /// the agent's streamed events, if given.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::AgentExecutionFailed` describing the hook if one
+    /// fails (a failing pre hook aborts before the agent runs), otherwise the
+    /// errors of [`Engine::execute_request`] or of the command.
+    pub async fn execute_phase(
+        &self,
+        phase: &Phase,
+        context: &HookContext,
+        progress: Option<ProgressSender>,
+    ) -> Result<ExecutionResult> {
+        tracing::info!(
+            target: metrics::TARGET,
+            feature_id = %context.feature_id,
+            phase = %phase.name,
+            "phase started"
+        );
+        let start = Instant::now();
+        let result = self.run_phase(phase, context, progress).await;
+        self.record_phase(phase, context, start.elapsed(), &result);
+        result
+    }
+
+    /// Emit the completion event and metrics of a phase
+    fn record_phase(
+        &self,
+        phase: &Phase,
+        context: &HookContext,
+        elapsed: Duration,
+        result: &Result<ExecutionResult>,
+    ) {
+        let duration_ms = elapsed.as_millis() as u64;
+        match result {
+            Ok(result) => tracing::info!(
+                target: metrics::TARGET,
+                feature_id = %context.feature_id,
+                phase = %phase.name,
+                duration_ms,
+                cost_usd = result.stats.cost_usd,
+                tokens = result.stats.input_tokens + result.stats.output_tokens,
+                success = result.success,
+                "phase completed"
+            ),
+            Err(e) => tracing::warn!(
+                target: metrics::TARGET,
+                feature_id = %context.feature_id,
+                phase = %phase.name,
+                duration_ms,
+                "phase failed: {e}"
+            ),
+        }
+        let outcome = metrics::outcome(result, |r| r.success);
+        self.metrics.counter(
+            metrics::PHASE_EXECUTIONS,
+            1,
+            &[("phase", &phase.name), ("outcome", outcome)],
+        );
+        self.metrics.histogram(
+            metrics::PHASE_DURATION_MS,
+            duration_ms as f64,
+            &[("phase", &phase.name)],
+        );
+    }
+
+    /// Execute a phase with the executor of its kind
+    async fn run_phase(
+        &self,
+        phase: &Phase,
+        context: &HookContext,
+        progress: Option<ProgressSender>,
+    ) -> Result<ExecutionResult> {
+        let ctx = ExecutionContext {
+            hook: context.clone(),
+            progress,
+        };
+        match phase.kind {
+            PhaseKind::Agent => self.execute_phase_with(self, phase, &ctx).await,
+            PhaseKind::Command if self.config.offline => Ok(ExecutionResult {
+                success: true,
+                output: format!("{}\n", phase.command.as_deref().unwrap_or_default()),
+                ..ExecutionResult::default()
+            }),
+            PhaseKind::Command => {
+                let executor =
+                    CommandExecutor::new(Duration::from_secs(self.config.timeout_seconds));
+                self.execute_phase_with(&executor, phase, &ctx).await
+            }
+        }
+    }
+
+    /// Execute a phase with `executor` between its pre and post hooks.
+    ///
+    /// A post hook asking for a retry, and failing tests of `phase.test`,
+    /// run the executor again with the feedback appended to the user prompt.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::AgentExecutionFailed` describing the hook if one
+    /// fails, otherwise the errors of the executor.
+    pub async fn execute_phase_with(
+        &self,
+        executor: &dyn PhaseExecutor,
+        phase: &Phase,
+        ctx: &ExecutionContext,
+    ) -> Result<ExecutionResult> {
+        let execute = |request: ExecutionRequest| {
+            let phase = Phase {
+                user_prompt: request.user_prompt,
+                history: request.history,
+                ..phase.clone()
+            };
+            async move { executor.execute(&phase, ctx).await }
+        };
+        let result = hooks::run_with_hooks(
+            &self.runner,
+            &phase.hooks,
+            &ctx.hook,
+            phase.request(),
+            execute,
+        )
+        .await?;
+        match &phase.test {
+            Some(test) if test.enabled && result.success => {
+                testing::run_with_fixes(
+                    test,
+                    &ctx.hook.working_dir,
+                    phase.request(),
+                    result,
+                    execute,
+                )
+                .await
+            }
+            _ => Ok(result),
+        }
+    }
+
+    /// Hook context for phases run outside of a feature
+    fn hook_context(&self, phase: &Phase) -> HookContext {
+        HookContext {
+            working_dir: phase
+                .working_dir
+                .clone()
+                .unwrap_or_else(|| self.config.repo_path.clone()),
+            feature_id: String::new(),
+            feature_slug: String::new(),
+            phase: phase.name.clone(),
+        }
+    }
+
+    /// Execute phases in order, stopping at the first failure.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first execution error, or `CoreError::AgentExecutionFailed`
+    /// if a phase completes unsuccessfully.
+    pub async fn execute_phases(&self, phases: Vec<Phase>) -> Result<Vec<ExecutionResult>> {
+        let mut results = Vec::with_capacity(phases.len());
+        for (idx, phase) in phases.iter().enumerate() {
+            tracing::info!("Executing phase {}: {}", idx + 1, phase.name);
+            let result = self
+                .execute_phase(phase, &self.hook_context(phase), None)
+                .await?;
+            if !result.success {
+                return Err(CoreError::AgentExecutionFailed(format!(
+                    "Phase {} failed",
+                    phase.name
+                )));
+            }
+            results.push(result);
+        }
+        Ok(results)
+    }
+
+    /// Execute phases in dependency order (serially), stopping at the first failure.
+    ///
+    /// Returns the phase names with their results, in execution order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::ConfigError` before executing anything if the
+    /// dependencies are unknown or cyclic, otherwise the first phase error.
+    pub async fn execute_phase_graph(
+        &self,
+        phases: Vec<Phase>,
+    ) -> Result<Vec<(String, ExecutionResult)>> {
+        let order = dependency_order(
+            phases
+                .iter()
+                .map(|p| (p.name.as_str(), p.depends_on.as_slice())),
+        )?;
+        let mut results = Vec::with_capacity(phases.len());
+        for idx in order {
+            let phase = &phases[idx];
+            tracing::info!("Executing phase {}", phase.name);
+            let result = self
+                .execute_phase(phase, &self.hook_context(phase), None)
+                .await?;
+            if !result.success {
+                return Err(CoreError::AgentExecutionFailed(format!(
+                    "Phase {} failed",
+                    phase.name
+                )));
+            }
+            results.push((phase.name.clone(), result));
+        }
+        Ok(results)
+    }
+
+    /// Execute phases concurrently, running up to `max_parallel` phases whose
+    /// dependencies have completed at the same time.
+    ///
+    /// Phases that share a working directory may conflict when they edit
+    /// files; give each an isolated worktree via [`Phase::working_dir`].
+    /// Results are returned in completion order.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::ConfigError` before executing anything if the
+    /// dependencies are unknown or cyclic, otherwise the first phase error
+    /// (the phases still running are cancelled).
+    pub async fn execute_phase_graph_concurrent(
+        &self,
+        phases: Vec<Phase>,
+        max_parallel: usize,
+    ) -> Result<Vec<(String, ExecutionResult)>> {
+        scheduler::run_concurrent(phases, max_parallel, |phase| {
+            let engine = self.clone();
+            async move {
+                let context = engine.hook_context(&phase);
+                engine.execute_phase(&phase, &context, None).await
+            }
+        })
+        .await
+    }
+
+    /// Response timeout of a request: its own, else `Config::timeout_seconds`
+    pub fn timeout_for(&self, request: &ExecutionRequest) -> Duration {
+        request
+            .timeout
+            .unwrap_or(Duration::from_secs(self.config.timeout_seconds))
+    }
+
+    /// Model a request runs with: its own, else the configured one
+    fn model_for<'a>(&'a self, request: &'a ExecutionRequest) -> &'a str {
+        request.model.as_deref().unwrap_or(&self.config.model)
+    }
+
     /// Get the current configuration
     pub fn config(&self) -> &Config {
         &self.config
@@ -56,6 +847,313 @@ mod tests {
         assert_eq!(config.model, "claude-sonnet-4-5-20250929");
     }
 
+    #[test]
+    fn test_config_builder() {
+        let config = Config::builder().build();
+        assert_eq!(config.model, Config::default().model);
+        assert_eq!(config.timeout_seconds, Config::default().timeout_seconds);
+
+        let config = Config::builder()
+            .repo_path("/work/repo")
+            .api_key("sk-ant-test")
+            .model("claude-opus-4")
+            .max_turns(Some(40))
+            .timeout_seconds(900)
+            .permission_mode(ConfigPermissionMode::Plan)
+            .build();
+        assert_eq!(config.repo_path, PathBuf::from("/work/repo"));
+        assert_eq!(config.api_key, "sk-ant-test");
+        assert_eq!(config.model, "claude-opus-4");
+        assert_eq!(config.max_turns, Some(40));
+        assert_eq!(config.timeout_seconds, 900);
+        assert_eq!(config.permission_mode, ConfigPermissionMode::Plan);
+        assert!(!config.offline);
+    }
+
+    #[test]
+    fn test_api_key_is_never_serialized() {
+        let config = Config::builder().api_key("sk-ant-secret").build();
+
+        let json = serde_json::to_string(&config).unwrap();
+        let yaml = serde_yaml::to_string(&config).unwrap();
+
+        assert!(!json.contains("sk-ant-secret") && !json.contains("api_key"));
+        assert!(!yaml.contains("sk-ant-secret"));
+        let parsed: Config = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.api_key, "");
+    }
+
+    #[test]
+    fn test_phase_timeout_overrides_config() {
+        let engine = Engine::new(Config::builder().timeout_seconds(300).build());
+        let task = TaskConfig::default();
+        let build = Phase::from_config(
+            &PhaseConfig {
+                name: "build".to_string(),
+                description: String::new(),
+                kind: PhaseKind::Agent,
+                command: None,
+                timeout_seconds: Some(1800),
+                depends_on: Vec::new(),
+                hooks: None,
+                enabled: true,
+                env: BTreeMap::new(),
+                env_deny: Vec::new(),
+                inputs: Vec::new(),
+            },
+            &task,
+        );
+        let observe = Phase {
+            name: "observe".to_string(),
+            ..Phase::default()
+        };
+
+        assert_eq!(
+            engine.timeout_for(&build.request()),
+            Duration::from_secs(1800)
+        );
+        assert_eq!(
+            engine.timeout_for(&observe.request()),
+            Duration::from_secs(300)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_phase_graph_cycle_fails_before_executing() {
+        let engine = Engine::new(Config::default());
+        let phase = |name: &str, dep: &str| Phase {
+            name: name.to_string(),
+            depends_on: vec![dep.to_string()],
+            ..Phase::default()
+        };
+
+        let result = engine
+            .execute_phase_graph(vec![phase("build", "test"), phase("test", "build")])
+            .await;
+
+        assert!(matches!(result, Err(CoreError::ConfigError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_offline_engine_echoes_prompt() {
+        let engine = Engine::new(Config::builder().offline(true).build());
+
+        let result = engine
+            .execute_request(ExecutionRequest::new("Implement login"))
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.output, "Implement login");
+        assert_eq!(result.stats, ExecutionStats::default());
+        assert_eq!(
+            result.model.as_deref(),
+            Some(engine.config().model.as_str())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_phase_executions_record_metrics() {
+        let recorder = Arc::new(metrics::Recorder::default());
+        let engine =
+            Engine::new(Config::builder().offline(true).build()).with_metrics(recorder.clone());
+        let phases = vec![
+            Phase {
+                name: "build".to_string(),
+                user_prompt: "Implement login".to_string(),
+                ..Phase::default()
+            },
+            Phase {
+                name: "fmt".to_string(),
+                kind: PhaseKind::Command,
+                command: Some("cargo fmt".to_string()),
+                ..Phase::default()
+            },
+        ];
+
+        engine.execute_phases(phases).await.unwrap();
+
+        let labels = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<Vec<_>>()
+        };
+        let phase_runs = recorder.get(metrics::PHASE_EXECUTIONS);
+        assert_eq!(phase_runs.len(), 2);
+        assert_eq!(
+            phase_runs[0].labels,
+            labels(&[("phase", "build"), ("outcome", "success")])
+        );
+        assert_eq!(
+            phase_runs[1].labels,
+            labels(&[("phase", "fmt"), ("outcome", "success")])
+        );
+        assert_eq!(recorder.get(metrics::PHASE_DURATION_MS).len(), 2);
+        // Only the agent phase sends a request.
+        let executions = recorder.get(metrics::EXECUTIONS);
+        assert_eq!(executions.len(), 1);
+        assert_eq!(executions[0].value, 1.0);
+        assert_eq!(
+            executions[0].labels,
+            labels(&[
+                ("model", engine.config().model.as_str()),
+                ("outcome", "success")
+            ])
+        );
+        assert_eq!(recorder.get(metrics::TOKENS)[0].value, 0.0);
+        assert!(recorder.get(metrics::TIMEOUTS).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_replayed_phase_is_recorded_redacted() {
+        let recorded = tempfile::tempdir().unwrap();
+        let messages = [
+            serde_json::json!({
+                "type": "assistant",
+                "message": {"content": [{"type": "text", "text": "Built it with sk-ant-key-123"}]}
+            }),
+            serde_json::json!({
+                "type": "result",
+                "subtype": "success",
+                "duration_ms": 1200,
+                "duration_api_ms": 1000,
+                "is_error": false,
+                "num_turns": 1,
+                "session_id": "s",
+                "total_cost_usd": 0.25
+            }),
+        ];
+        let lines: Vec<String> = messages.iter().map(ToString::to_string).collect();
+        std::fs::write(
+            recorded.path().join("0001-build.jsonl"),
+            lines.join("\n") + "\n",
+        )
+        .unwrap();
+        let rerecorded = tempfile::tempdir().unwrap();
+        let engine = Engine::new(Config::builder().api_key("sk-ant-key-123").build())
+            .with_backend(Arc::new(
+                transcript::ReplayBackend::load(recorded.path()).unwrap(),
+            ))
+            .with_recorder(Arc::new(
+                TranscriptRecorder::create(rerecorded.path()).unwrap(),
+            ));
+        let phase = Phase {
+            name: "build".to_string(),
+            user_prompt: "Implement login".to_string(),
+            ..Phase::default()
+        };
+
+        let result = engine
+            .execute_phase(&phase, &engine.hook_context(&phase), None)
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.output, "Built it with sk-ant-key-123");
+        assert_eq!(result.stats.turns, 1);
+        assert_eq!(result.stats.cost_usd, 0.25);
+        let transcript =
+            std::fs::read_to_string(rerecorded.path().join("0001-build.jsonl")).unwrap();
+        assert_eq!(transcript.lines().count(), 2);
+        assert!(transcript.contains("Built it with [REDACTED]"));
+    }
+
+    #[tokio::test]
+    async fn test_execute_request_with_a_scripted_client() {
+        use client::scripted::{Call, ScriptedFactory};
+
+        let factory = Arc::new(ScriptedFactory::default());
+        factory.respond(serde_json::json!([
+            {
+                "type": "assistant",
+                "message": {
+                    "content": [{"type": "text", "text": "Added the login form"}],
+                    "model": "claude-opus-4-5"
+                }
+            },
+            {
+                "type": "result",
+                "subtype": "success",
+                "duration_ms": 2000,
+                "duration_api_ms": 1800,
+                "is_error": false,
+                "num_turns": 3,
+                "session_id": "s",
+                "total_cost_usd": 0.4,
+                "usage": {"input_tokens": 1200, "output_tokens": 300}
+            }
+        ]));
+        let engine = Engine::new(
+            Config::builder()
+                .api_key("sk-ant-key")
+                .model("claude-opus-4-5")
+                .build(),
+        )
+        .with_client_factory(factory.clone());
+
+        let result = engine
+            .execute_request(ExecutionRequest::new("Implement login"))
+            .await
+            .unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.output, "Added the login form");
+        assert_eq!(result.model.as_deref(), Some("claude-opus-4-5"));
+        assert_eq!(result.stats.turns, 3);
+        assert_eq!(result.stats.cost_usd, 0.4);
+        assert_eq!(result.stats.input_tokens, 1200);
+        assert_eq!(
+            *factory.calls.lock(),
+            [
+                Call::Connect,
+                Call::Query("Implement login".to_string()),
+                Call::Disconnect
+            ]
+        );
+        assert_eq!(
+            *factory.models.lock(),
+            [Some("claude-opus-4-5".to_string())]
+        );
+
+        // Without a scripted response the agent ends without a result.
+        let err = engine
+            .execute_request(ExecutionRequest::new("Again"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CoreError::AgentExecutionFailed(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_continue_execution_threads_the_conversation() {
+        let engine = Engine::new(Config::builder().offline(true).build());
+        let first = engine
+            .execute_request(ExecutionRequest::new("Implement login"))
+            .await
+            .unwrap();
+
+        let second = engine
+            .continue_execution(&first, "Now add tests")
+            .await
+            .unwrap();
+
+        assert_eq!(
+            second.output,
+            "## Conversation So Far\n\n### User\n\nImplement login\n\n\
+             ### Assistant\n\nImplement login\n\n## Current Request\n\nNow add tests"
+        );
+        assert_eq!(second.conversation.len(), 4);
+        assert_eq!(second.stats.history_turns_dropped, 0);
+
+        let huge = ExecutionResult {
+            conversation: vec![(Role::User, "x".repeat(HISTORY_BUDGET_TOKENS * 4))],
+            ..first
+        };
+        let trimmed = engine.continue_execution(&huge, "Go on").await.unwrap();
+        assert_eq!(trimmed.output, "Go on");
+        assert_eq!(trimmed.stats.history_turns_dropped, 1);
+    }
+
     #[tokio::test]
     async fn test_engine_execute() {
         let config = Config::default();
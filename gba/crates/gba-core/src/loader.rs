@@ -0,0 +1,990 @@
+//! Layered configuration: defaults, global file, repo file, environment
+//! and CLI flags, each overriding the ones before it.
+//!
+//! The global file lives at `$GBA_CONFIG`, else
+//! `$XDG_CONFIG_HOME/gba/config.yml`, else `~/.config/gba/config.yml`. Each
+//! layer only needs to contain the keys it overrides: mappings are merged key
+//! by key, while scalars and lists (such as `phases`) replace the value of the
+//! layer below. The loader records which layer every value came from.
+//!
+//! The environment layer is made of the variables in [`ENV_OVERRIDES`],
+//! taken from the process environment or, failing that, from a `.env` file
+//! in the repository root.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use serde_yaml::Value;
+
+use crate::config::{CONFIG_FILE, DEFAULT_CONFIG, PROMPTS_DIR, ProjectConfig, closest_key};
+use crate::error::{CoreError, Result};
+
+/// Environment variable overriding the global config file location
+pub const GLOBAL_CONFIG_ENV: &str = "GBA_CONFIG";
+
+/// Environment variable overriding the global prompt template directory
+pub const GLOBAL_PROMPTS_ENV: &str = "GBA_PROMPTS_DIR";
+
+/// Environment variables overriding config keys, e.g. in CI
+pub const ENV_OVERRIDES: &[(&str, &str)] = &[
+    ("GBA_MODEL", "agent.model"),
+    ("GBA_MAX_TURNS", "agent.maxTurns"),
+    ("GBA_TIMEOUT_SECONDS", "agent.timeoutSeconds"),
+    ("GBA_PERMISSION_MODE", "agent.permissionMode"),
+];
+
+/// File in the repository root read for [`ENV_OVERRIDES`]
+pub const DOTENV_FILE: &str = ".env";
+
+/// Keys accepted by [`set_config_value`]
+pub const SETTABLE_KEYS: &[&str] = &[
+    "version",
+    "agent.apiKeyEnv",
+    "agent.apiKeyFile",
+    "agent.apiKeyKeychain",
+    "agent.model",
+    "agent.permissionMode",
+    "agent.maxTurns",
+    "agent.timeoutSeconds",
+    "agent.maxAttempts",
+    "agent.maxOutputBytes",
+    "agent.stopOnOutputLimit",
+    "agent.maxPromptTokens",
+    "agent.warnOnLargePrompt",
+    "notifications.desktop",
+    "notifications.webhookUrl",
+    "git.allowDirty",
+    "git.useWorktree",
+    "git.autoCommit",
+    "specs.minLength",
+    "summaries.enabled",
+    "summaries.model",
+    "rateLimit.requestsPerMinute",
+    "rateLimit.maxConcurrentExecutions",
+    "rateLimit.maxRetries",
+];
+
+/// Where a configuration value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigSource {
+    /// Built-in default
+    Default,
+    /// Global user config file
+    Global(PathBuf),
+    /// Repository `.gba/config.yml`
+    Repo(PathBuf),
+    /// Environment variable (or `.env` entry) with this name
+    Env(String),
+    /// Command line flag
+    Cli,
+}
+
+impl fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::Global(path) => write!(f, "global {}", path.display()),
+            Self::Repo(path) => write!(f, "repo {}", path.display()),
+            Self::Env(name) => write!(f, "env {name}"),
+            Self::Cli => write!(f, "command line"),
+        }
+    }
+}
+
+/// Merged configuration with the source of each value
+#[derive(Debug, Clone)]
+pub struct LoadedConfig {
+    /// Effective configuration
+    pub config: ProjectConfig,
+    /// Source per dotted key (`agent.model`); lists are tracked as a whole
+    pub provenance: BTreeMap<String, ConfigSource>,
+}
+
+impl LoadedConfig {
+    /// Source of a dotted key, falling back to its closest tracked parent
+    pub fn source(&self, key: &str) -> Option<&ConfigSource> {
+        let mut key = key;
+        loop {
+            if let Some(source) = self.provenance.get(key) {
+                return Some(source);
+            }
+            key = &key[..key.rfind(['.', '['])?];
+        }
+    }
+
+    /// Effective value of a dotted key; mappings and lists render as YAML
+    pub fn get(&self, key: &str) -> Option<String> {
+        let root = serde_yaml::to_value(&self.config).ok()?;
+        let mut node = &root;
+        for part in key.split('.') {
+            node = node.as_mapping()?.get(part)?;
+        }
+        Some(match node {
+            Value::String(s) => s.clone(),
+            other => serde_yaml::to_string(other).ok()?.trim_end().to_string(),
+        })
+    }
+
+    /// Every scalar value as `(dotted key, value, source)`, in file order
+    pub fn entries(&self) -> Vec<(String, String, ConfigSource)> {
+        let mut leaves = Vec::new();
+        // ProjectConfig always serializes.
+        if let Ok(value) = serde_yaml::to_value(&self.config) {
+            flatten(&value, String::new(), &mut leaves);
+        }
+        leaves
+            .into_iter()
+            .map(|(key, value)| {
+                let source = self.source(&key).cloned().unwrap_or(ConfigSource::Default);
+                (key, value, source)
+            })
+            .collect()
+    }
+}
+
+/// Loads and merges the configuration layers
+#[derive(Debug, Clone, Default)]
+pub struct ConfigLoader {
+    global: Option<PathBuf>,
+    repo: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    dotenv: Option<PathBuf>,
+    overrides: Vec<(String, Value)>,
+}
+
+impl ConfigLoader {
+    /// Loader for the repository whose `.gba` directory is `gba_path`,
+    /// using the global config file and overrides from the environment
+    pub fn new(gba_path: &Path) -> Self {
+        let env = ENV_OVERRIDES
+            .iter()
+            .filter_map(|(name, _)| Some((name.to_string(), std::env::var(name).ok()?)))
+            .collect();
+        Self {
+            global: global_config_path(),
+            repo: Some(gba_path.join(CONFIG_FILE)),
+            env,
+            dotenv: gba_path.parent().map(|repo| repo.join(DOTENV_FILE)),
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Use `path` as the global config file (None = no global config)
+    pub fn with_global(mut self, path: Option<PathBuf>) -> Self {
+        self.global = path;
+        self
+    }
+
+    /// Use `vars` as the environment and `dotenv` as the `.env` file
+    /// (None = no `.env`) instead of the real ones
+    pub fn with_env(
+        mut self,
+        vars: impl IntoIterator<Item = (String, String)>,
+        dotenv: Option<PathBuf>,
+    ) -> Self {
+        self.env = vars.into_iter().collect();
+        self.dotenv = dotenv;
+        self
+    }
+
+    /// Override a dotted key (e.g. `agent.model`) from the command line
+    pub fn with_override(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.overrides.push((key.to_string(), value.into()));
+        self
+    }
+
+    /// Global config file in use, if any
+    pub fn global_path(&self) -> Option<&Path> {
+        self.global.as_deref()
+    }
+
+    /// Repository config file in use, if any
+    pub fn repo_path(&self) -> Option<&Path> {
+        self.repo.as_deref()
+    }
+
+    /// Merge all layers into the effective configuration.
+    ///
+    /// Missing files are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file can't be read or parsed, isn't a mapping,
+    /// contains an unknown key, or the merged result isn't a valid
+    /// configuration. An environment variable with a value its key doesn't
+    /// accept is a `CoreError::ConfigError` naming the variable.
+    pub fn load(&self) -> Result<LoadedConfig> {
+        let mut merged = serde_yaml::to_value(ProjectConfig::default())?;
+        let mut provenance = BTreeMap::new();
+        let mut leaves = Vec::new();
+        collect_keys(&merged, String::new(), &mut leaves);
+        for key in leaves {
+            provenance.insert(key, ConfigSource::Default);
+        }
+
+        let layers = [
+            self.global
+                .as_ref()
+                .map(|p| (p, ConfigSource::Global(p.clone()))),
+            self.repo
+                .as_ref()
+                .map(|p| (p, ConfigSource::Repo(p.clone()))),
+        ];
+        for (path, source) in layers.into_iter().flatten() {
+            if let Some(layer) = read_layer(path)? {
+                merge(&mut merged, layer, "", &source, &mut provenance);
+            }
+        }
+        for (name, key, raw) in self.env_values()? {
+            let value =
+                serde_yaml::from_str::<Value>(&raw).unwrap_or_else(|_| Value::from(raw.as_str()));
+            set_path(&mut merged, key, value)?;
+            if let Err(e) = serde_yaml::from_value::<ProjectConfig>(merged.clone()) {
+                return Err(CoreError::ConfigError(format!(
+                    "invalid value `{raw}` in {name} for `{key}`: {e}"
+                )));
+            }
+            provenance.insert(key.to_string(), ConfigSource::Env(name));
+        }
+        for (key, value) in &self.overrides {
+            if value.is_null() {
+                continue;
+            }
+            set_path(&mut merged, key, value.clone())?;
+            provenance.insert(key.clone(), ConfigSource::Cli);
+        }
+
+        Ok(LoadedConfig {
+            config: serde_yaml::from_value(merged)?,
+            provenance,
+        })
+    }
+}
+
+impl ConfigLoader {
+    /// Non-empty override variables as `(name, key, value)`, the process
+    /// environment winning over `.env`
+    fn env_values(&self) -> Result<Vec<(String, &'static str, String)>> {
+        let dotenv = match &self.dotenv {
+            Some(path) => read_dotenv(path)?,
+            None => Vec::new(),
+        };
+        let lookup = |name: &str| {
+            let find = |vars: &[(String, String)]| {
+                vars.iter()
+                    .rev()
+                    .find(|(var, _)| var == name)
+                    .map(|(_, value)| value.trim().to_string())
+            };
+            find(&self.env).or_else(|| find(&dotenv))
+        };
+        Ok(ENV_OVERRIDES
+            .iter()
+            .filter_map(|(name, key)| {
+                let value = lookup(name).filter(|v| !v.is_empty())?;
+                Some((name.to_string(), *key, value))
+            })
+            .collect())
+    }
+}
+
+/// `NAME=value` entries of a `.env` file; a missing file has none
+fn read_dotenv(path: &Path) -> Result<Vec<(String, String)>> {
+    let entries = match dotenvy::from_path_iter(path) {
+        Ok(entries) => entries,
+        Err(dotenvy::Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(Vec::new());
+        }
+        Err(e) => return Err(dotenv_error(path, e)),
+    };
+    entries
+        .map(|entry| entry.map_err(|e| dotenv_error(path, e)))
+        .collect()
+}
+
+fn dotenv_error(path: &Path, e: dotenvy::Error) -> CoreError {
+    CoreError::ConfigError(format!("{}: {e}", path.display()))
+}
+
+/// Location of the global config file according to the environment
+pub fn global_config_path() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+    if let Some(path) = var(GLOBAL_CONFIG_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    let config_dir = var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join("gba").join(CONFIG_FILE))
+}
+
+/// Location of the global prompt templates according to the environment:
+/// `$GBA_PROMPTS_DIR`, else `~/.gba/prompts`
+pub fn global_prompts_dir() -> Option<PathBuf> {
+    let var = |name| std::env::var_os(name).filter(|v| !v.is_empty());
+    if let Some(path) = var(GLOBAL_PROMPTS_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    var("HOME").map(|home| PathBuf::from(home).join(".gba").join(PROMPTS_DIR))
+}
+
+/// Set `key` to `raw` (parsed as a YAML scalar) in the config file at `path`.
+///
+/// The line holding the key is edited in place so comments and unrelated
+/// keys are kept; a file whose layout can't be edited that way is rewritten
+/// without its comments. A missing file is created.
+///
+/// # Errors
+///
+/// Returns `CoreError::ConfigError` for an unknown key or a value the key
+/// doesn't accept (listing the allowed values), or an error if the file
+/// can't be read, parsed or written.
+pub fn set_config_value(path: &Path, key: &str, raw: &str) -> Result<()> {
+    if !SETTABLE_KEYS.contains(&key) {
+        let hint = match closest_key(key, SETTABLE_KEYS) {
+            Some(close) => format!("did you mean `{close}`?"),
+            None => format!("valid keys: {}", SETTABLE_KEYS.join(", ")),
+        };
+        return Err(CoreError::ConfigError(format!(
+            "unknown key `{key}` ({hint})"
+        )));
+    }
+    let value = serde_yaml::from_str::<Value>(raw).unwrap_or_else(|_| Value::from(raw));
+    if value.is_mapping() || value.is_sequence() || value.is_null() {
+        return Err(CoreError::ConfigError(format!(
+            "`{key}` takes a single value, got `{raw}`"
+        )));
+    }
+
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut document: Value = serde_yaml::from_str(&content)?;
+    set_path(&mut document, key, value.clone())?;
+    if let Err(e) = serde_yaml::from_value::<ProjectConfig>(document.clone()) {
+        return Err(CoreError::ConfigError(format!(
+            "invalid value `{raw}` for `{key}`: {e}"
+        )));
+    }
+
+    let rendered = serde_yaml::to_string(&value)?;
+    let updated = match edit_in_place(&content, key, rendered.trim_end()) {
+        // Only trust the textual edit if it means the same as the document.
+        Some(text) if serde_yaml::from_str::<Value>(&text).ok().as_ref() == Some(&document) => text,
+        _ => serde_yaml::to_string(&document)?,
+    };
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, updated)?;
+    Ok(())
+}
+
+/// Add the keys of [`DEFAULT_CONFIG`] that the config file at `path` lacks.
+///
+/// Values already in the file are kept, as are its comments where
+/// possible. A missing file is created from [`DEFAULT_CONFIG`]. Returns the
+/// dotted keys that were added.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read, parsed or written, contains
+/// an unknown key, or isn't a valid configuration after the merge.
+pub fn merge_default_config(path: &Path) -> Result<Vec<String>> {
+    let defaults: Value = serde_yaml::from_str(DEFAULT_CONFIG)?;
+    let mut keys = Vec::new();
+    collect_keys(&defaults, String::new(), &mut keys);
+
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, DEFAULT_CONFIG)?;
+            return Ok(keys);
+        }
+        Err(e) => return Err(e.into()),
+    };
+    ProjectConfig::from_yaml(&content).map_err(|e| in_file(path, e))?;
+    let mut document: Value = serde_yaml::from_str(&content)?;
+    keys.retain(|key| get_path(&document, key).is_none());
+    if keys.is_empty() {
+        return Ok(keys);
+    }
+    let mut text = Some(content);
+    for key in &keys {
+        let value = get_path(&defaults, key).cloned().unwrap_or_default();
+        set_path(&mut document, key, value.clone())?;
+        let rendered = serde_yaml::to_string(&value)?;
+        text = text.and_then(|text| edit_in_place(&text, key, rendered.trim_end()));
+    }
+    if let Err(e) = serde_yaml::from_value::<ProjectConfig>(document.clone()) {
+        return Err(CoreError::ConfigError(format!("{}: {e}", path.display())));
+    }
+    let updated = match text {
+        // Only trust the textual edits if they mean the same as the document.
+        Some(text) if serde_yaml::from_str::<Value>(&text).ok().as_ref() == Some(&document) => text,
+        _ => serde_yaml::to_string(&document)?,
+    };
+    std::fs::write(path, updated)?;
+    Ok(keys)
+}
+
+/// Replace (or insert) the line of a dotted key in block-style YAML text
+fn edit_in_place(content: &str, key: &str, value: &str) -> Option<String> {
+    let is_entry = |line: &str| {
+        let trimmed = line.trim_start();
+        !trimmed.is_empty() && !trimmed.starts_with('#')
+    };
+    let indent_of = |line: &str| line.len() - line.trim_start().len();
+    let finish = |lines: Vec<String>| lines.join("\n") + "\n";
+
+    let parts: Vec<&str> = key.split('.').collect();
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let (mut start, mut end) = (0, lines.len());
+    let mut indent = 0;
+    for (depth, part) in parts.iter().enumerate() {
+        let last = depth == parts.len() - 1;
+        indent = lines[start..end]
+            .iter()
+            .find(|l| is_entry(l))
+            .map_or(indent, |l| indent_of(l));
+        let found = (start..end).find(|&i| {
+            indent_of(&lines[i]) == indent
+                && lines[i]
+                    .trim_start()
+                    .strip_prefix(part)
+                    .is_some_and(|rest| rest.starts_with(':'))
+        });
+
+        let Some(i) = found else {
+            // Append the missing keys to the end of the enclosing block.
+            let mut at = end;
+            while at > start && !is_entry(&lines[at - 1]) {
+                at -= 1;
+            }
+            let new_lines = parts[depth..].iter().enumerate().map(|(k, part)| {
+                let pad = " ".repeat(indent + 2 * k);
+                if depth + k < parts.len() - 1 {
+                    format!("{pad}{part}:")
+                } else if value.contains('\n') {
+                    // A block value (e.g. a list) goes below its key.
+                    let block = value.lines().map(|line| format!("{pad}  {line}"));
+                    std::iter::once(format!("{pad}{part}:"))
+                        .chain(block)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                } else {
+                    format!("{pad}{part}: {value}")
+                }
+            });
+            lines.splice(at..at, new_lines);
+            return Some(finish(lines));
+        };
+
+        let rest = lines[i].trim_start()[part.len() + 1..].to_string();
+        let trimmed = rest.trim();
+        if last {
+            // Quoted, flow, block or anchored values are left to the rewrite.
+            if value.contains('\n')
+                || trimmed.is_empty()
+                || trimmed.starts_with(['"', '\'', '[', '{', '|', '>', '&', '*', '!'])
+            {
+                return None;
+            }
+            let comment = rest.find(" #").map_or("", |pos| &rest[pos..]);
+            lines[i] = format!("{}{part}: {value}{comment}", " ".repeat(indent));
+            return Some(finish(lines));
+        }
+        if !trimmed.is_empty() && !trimmed.starts_with('#') {
+            return None;
+        }
+        start = i + 1;
+        end = (start..end)
+            .find(|&j| is_entry(&lines[j]) && indent_of(&lines[j]) <= indent)
+            .unwrap_or(end);
+        indent += 2;
+    }
+    None
+}
+
+fn read_layer(path: &Path) -> Result<Option<Value>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let value: Value = serde_yaml::from_str(&content)?;
+    if value.is_mapping() {
+        // Parse each file on its own so unknown keys are reported with a line.
+        ProjectConfig::from_yaml(&content).map_err(|e| in_file(path, e))?;
+    }
+    match value {
+        Value::Null => Ok(None),
+        value @ Value::Mapping(_) => Ok(Some(value)),
+        _ => Err(CoreError::ConfigError(format!(
+            "{} must contain a mapping",
+            path.display()
+        ))),
+    }
+}
+
+/// Prefix a configuration error with the file it was found in
+fn in_file(path: &Path, e: CoreError) -> CoreError {
+    match e {
+        CoreError::ConfigError(msg) => CoreError::ConfigError(format!("{}: {msg}", path.display())),
+        e => e,
+    }
+}
+
+fn merge(
+    base: &mut Value,
+    layer: Value,
+    path: &str,
+    source: &ConfigSource,
+    provenance: &mut BTreeMap<String, ConfigSource>,
+) {
+    match (base, layer) {
+        // An empty key (`agent:`) leaves the lower layer untouched.
+        (_, Value::Null) => {}
+        (Value::Mapping(base), Value::Mapping(layer)) => {
+            for (key, value) in layer {
+                let Some(name) = key.as_str() else {
+                    continue;
+                };
+                let child = join(path, name);
+                let entry = base.entry(key.clone()).or_insert(Value::Null);
+                merge(entry, value, &child, source, provenance);
+            }
+        }
+        (base, layer) => {
+            *base = layer;
+            provenance.retain(|key, _| !is_child(key, path));
+            let mut keys = Vec::new();
+            collect_keys(base, path.to_string(), &mut keys);
+            for key in keys {
+                provenance.insert(key, source.clone());
+            }
+        }
+    }
+}
+
+/// Set a dotted key, creating intermediate mappings as needed
+pub(crate) fn set_path(root: &mut Value, key: &str, value: Value) -> Result<()> {
+    let mut node = root;
+    for part in key.split('.') {
+        if node.is_null() {
+            *node = Value::Mapping(serde_yaml::Mapping::new());
+        }
+        let Value::Mapping(map) = node else {
+            return Err(CoreError::ConfigError(format!(
+                "cannot set `{key}`: `{part}` is not inside a mapping"
+            )));
+        };
+        node = map.entry(Value::from(part)).or_insert(Value::Null);
+    }
+    *node = value;
+    Ok(())
+}
+
+/// Value of a dotted key; `None` if it is missing or null
+fn get_path<'a>(root: &'a Value, key: &str) -> Option<&'a Value> {
+    key.split('.')
+        .try_fold(root, |node, part| node.as_mapping()?.get(part))
+        .filter(|value| !value.is_null())
+}
+
+/// Keys tracked for provenance: mapping leaves and whole lists
+fn collect_keys(value: &Value, path: String, keys: &mut Vec<String>) {
+    match value {
+        Value::Mapping(map) => {
+            for (key, value) in map {
+                if let Some(name) = key.as_str() {
+                    collect_keys(value, join(&path, name), keys);
+                }
+            }
+        }
+        _ if !path.is_empty() => keys.push(path),
+        _ => {}
+    }
+}
+
+/// Scalar leaves with their rendered values
+fn flatten(value: &Value, path: String, leaves: &mut Vec<(String, String)>) {
+    match value {
+        Value::Mapping(map) => {
+            for (key, value) in map {
+                if let Some(name) = key.as_str() {
+                    flatten(value, join(&path, name), leaves);
+                }
+            }
+        }
+        Value::Sequence(items) => {
+            for (i, item) in items.iter().enumerate() {
+                flatten(item, format!("{path}[{i}]"), leaves);
+            }
+        }
+        Value::String(s) => leaves.push((path, s.clone())),
+        Value::Null => leaves.push((path, "~".to_string())),
+        other => {
+            let rendered = serde_yaml::to_string(other).unwrap_or_default();
+            leaves.push((path, rendered.trim_end().to_string()));
+        }
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+fn is_child(key: &str, parent: &str) -> bool {
+    key.strip_prefix(parent)
+        .is_some_and(|rest| rest.starts_with(['.', '[']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigPermissionMode;
+
+    fn write(dir: &Path, name: &str, content: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_layer_precedence() {
+        let dir = tempfile::tempdir().unwrap();
+        let global = write(
+            dir.path(),
+            "global.yml",
+            "agent:\n  model: claude-opus-4\n  timeoutSeconds: 900\n  maxTurns: 40\n",
+        );
+        let gba = dir.path().join(".gba");
+        std::fs::create_dir(&gba).unwrap();
+        let repo = write(&gba, CONFIG_FILE, "agent:\n  timeoutSeconds: 600\n");
+
+        let loaded = ConfigLoader::new(&gba)
+            .with_global(Some(global.clone()))
+            .with_override("agent.maxTurns", 80u32)
+            .load()
+            .unwrap();
+
+        let agent = &loaded.config.agent;
+        assert_eq!(agent.model, "claude-opus-4");
+        assert_eq!(agent.timeout_seconds, 600);
+        assert_eq!(agent.max_turns, Some(80));
+        assert_eq!(
+            agent.permission_mode,
+            ConfigPermissionMode::BypassPermissions
+        );
+        assert_eq!(
+            loaded.source("agent.model"),
+            Some(&ConfigSource::Global(global))
+        );
+        assert_eq!(
+            loaded.source("agent.timeoutSeconds"),
+            Some(&ConfigSource::Repo(repo))
+        );
+        assert_eq!(loaded.source("agent.maxTurns"), Some(&ConfigSource::Cli));
+        assert_eq!(
+            loaded.source("agent.permissionMode"),
+            Some(&ConfigSource::Default)
+        );
+    }
+
+    #[test]
+    fn test_env_overrides_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let gba = dir.path().join(".gba");
+        std::fs::create_dir(&gba).unwrap();
+        write(
+            &gba,
+            CONFIG_FILE,
+            "agent:\n  model: claude-opus-4\n  maxTurns: 40\n  timeoutSeconds: 600\n",
+        );
+        let dotenv = write(
+            dir.path(),
+            DOTENV_FILE,
+            "# CI overrides\nexport GBA_MODEL=\"claude-haiku\"\nGBA_TIMEOUT_SECONDS=120\n",
+        );
+        let vars = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        let loaded = ConfigLoader::new(&gba)
+            .with_global(None)
+            .with_env(
+                vars(&[
+                    ("GBA_MODEL", "claude-sonnet-4"),
+                    ("GBA_MAX_TURNS", "12"),
+                    ("GBA_PERMISSION_MODE", "plan"),
+                ]),
+                Some(dotenv.clone()),
+            )
+            .load()
+            .unwrap();
+
+        let agent = &loaded.config.agent;
+        assert_eq!(agent.model, "claude-sonnet-4");
+        assert_eq!(agent.max_turns, Some(12));
+        assert_eq!(agent.timeout_seconds, 120);
+        assert_eq!(agent.permission_mode, ConfigPermissionMode::Plan);
+        assert_eq!(
+            loaded.source("agent.model"),
+            Some(&ConfigSource::Env("GBA_MODEL".to_string()))
+        );
+        assert_eq!(
+            loaded.source("agent.timeoutSeconds"),
+            Some(&ConfigSource::Env("GBA_TIMEOUT_SECONDS".to_string()))
+        );
+
+        let loaded = ConfigLoader::new(&gba)
+            .with_global(None)
+            .with_env(vars(&[("GBA_MODEL", "")]), None)
+            .with_override("agent.maxTurns", 80u32)
+            .load()
+            .unwrap();
+        assert_eq!(loaded.config.agent.model, "claude-opus-4");
+        assert_eq!(loaded.config.agent.max_turns, Some(80));
+
+        let err = ConfigLoader::new(&gba)
+            .with_global(None)
+            .with_env(vars(&[("GBA_PERMISSION_MODE", "yolo")]), None)
+            .load()
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            CoreError::ConfigError(msg) if msg.contains("GBA_PERMISSION_MODE")
+        ));
+        let err = ConfigLoader::new(&gba)
+            .with_global(None)
+            .with_env(vars(&[("GBA_MAX_TURNS", "lots")]), None)
+            .load()
+            .unwrap_err();
+        assert!(matches!(err, CoreError::ConfigError(_)));
+
+        write(dir.path(), DOTENV_FILE, "GBA_MODEL='unterminated\n");
+        let err = ConfigLoader::new(&gba)
+            .with_global(None)
+            .with_env(Vec::new(), Some(dotenv))
+            .load()
+            .unwrap_err();
+        assert!(matches!(
+            &err,
+            CoreError::ConfigError(msg) if msg.contains(DOTENV_FILE)
+        ));
+    }
+
+    #[test]
+    fn test_lists_replace_and_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let global = write(
+            dir.path(),
+            "global.yml",
+            "phases:\n  - name: build\n  - name: review\n",
+        );
+
+        let loaded = ConfigLoader::new(&dir.path().join("missing"))
+            .with_global(Some(global.clone()))
+            .load()
+            .unwrap();
+
+        let names: Vec<&str> = loaded
+            .config
+            .phases
+            .iter()
+            .map(|p| p.name.as_str())
+            .collect();
+        assert_eq!(names, ["build", "review"]);
+        assert_eq!(
+            loaded.source("phases[1].name"),
+            Some(&ConfigSource::Global(global))
+        );
+        assert!(
+            loaded
+                .entries()
+                .iter()
+                .any(|(key, value, _)| key == "phases[1].name" && value == "review")
+        );
+
+        let defaults = ConfigLoader::default().load().unwrap();
+        assert_eq!(defaults.config, ProjectConfig::default());
+    }
+
+    const FULL_CONFIG: &str = "\
+# GBA project configuration
+version: \"0.1.0\"
+
+agent:
+  apiKeyEnv: ANTHROPIC_API_KEY
+  model: claude-sonnet-4-5 # team default
+  permissionMode: bypassPermissions
+  timeoutSeconds: 300
+
+# Phases run in this order
+phases:
+  - name: build
+    description: Implement the feature
+    timeoutSeconds: 1800
+    hooks:
+      postCommand: [cargo fmt]
+  - name: review
+    dependsOn: [build]
+";
+
+    #[test]
+    fn test_set_config_value_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), CONFIG_FILE, FULL_CONFIG);
+
+        set_config_value(&path, "agent.maxTurns", "80").unwrap();
+        set_config_value(&path, "agent.model", "claude-opus-4").unwrap();
+        set_config_value(&path, "agent.permissionMode", "acceptEdits").unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("# GBA project configuration"));
+        assert!(content.contains("  model: claude-opus-4 # team default"));
+        assert!(content.contains("  maxTurns: 80\n\n# Phases run in this order"));
+        let config = ProjectConfig::from_yaml(&content).unwrap();
+        assert_eq!(config.agent.max_turns, Some(80));
+        assert_eq!(
+            config.agent.permission_mode,
+            ConfigPermissionMode::AcceptEdits
+        );
+        assert_eq!(config.phases.len(), 2);
+        assert_eq!(
+            config.phases[0].hooks.as_ref().unwrap().post_command,
+            ["cargo fmt"]
+        );
+        assert_eq!(config.phases[1].depends_on, ["build"]);
+
+        let loaded = ConfigLoader::new(dir.path())
+            .with_global(None)
+            .load()
+            .unwrap();
+        assert_eq!(loaded.get("agent.maxTurns").as_deref(), Some("80"));
+        assert_eq!(loaded.get("agent.model").as_deref(), Some("claude-opus-4"));
+        assert_eq!(loaded.get("agent.missing"), None);
+    }
+
+    #[test]
+    fn test_merge_default_config_keeps_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), CONFIG_FILE, FULL_CONFIG);
+        set_config_value(&path, "agent.model", "claude-opus-4").unwrap();
+
+        let added = merge_default_config(&path).unwrap();
+
+        assert_eq!(
+            added,
+            [
+                "agent.maxAttempts",
+                "notifications.desktop",
+                "git.allowDirty",
+                "git.useWorktree",
+                "git.autoCommit",
+                "summaries.enabled",
+                "summaries.model",
+                "specs.minLength",
+                "specs.draftMarkers"
+            ]
+        );
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.contains("  model: claude-opus-4 # team default"));
+        assert!(content.contains("# Phases run in this order"));
+        let config = ProjectConfig::from_yaml(&content).unwrap();
+        assert_eq!(config.agent.max_attempts, 3);
+        assert_eq!(config.phases.len(), 2);
+        assert!(merge_default_config(&path).unwrap().is_empty());
+
+        let missing = dir.path().join("new").join(CONFIG_FILE);
+        assert!(
+            merge_default_config(&missing)
+                .unwrap()
+                .contains(&"phases".to_string())
+        );
+        assert_eq!(std::fs::read_to_string(&missing).unwrap(), DEFAULT_CONFIG);
+    }
+
+    #[test]
+    fn test_set_config_value_creates_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("gba").join(CONFIG_FILE);
+
+        set_config_value(&path, "agent.timeoutSeconds", "900").unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, "agent:\n  timeoutSeconds: 900\n");
+    }
+
+    #[test]
+    fn test_set_config_value_rejects_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(dir.path(), CONFIG_FILE, FULL_CONFIG);
+
+        let err = set_config_value(&path, "agent.permissionMode", "bypass").unwrap_err();
+        assert!(matches!(
+            &err,
+            CoreError::ConfigError(msg) if msg.contains("acceptEdits") && msg.contains("bypassPermissions")
+        ));
+        let err = set_config_value(&path, "agent.modle", "x").unwrap_err();
+        assert!(
+            matches!(&err, CoreError::ConfigError(msg) if msg.contains("did you mean `agent.model`?"))
+        );
+        let err = set_config_value(&path, "git.branchPattern", "x").unwrap_err();
+        assert!(matches!(&err, CoreError::ConfigError(msg) if msg.contains("valid keys: version")));
+        assert!(set_config_value(&path, "agent.maxTurns", "many").is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), FULL_CONFIG);
+    }
+
+    #[test]
+    fn test_invalid_layer_is_reported() {
+        let dir = tempfile::tempdir().unwrap();
+        let global = write(dir.path(), "global.yml", "- just\n- a list\n");
+
+        let result = ConfigLoader::default().with_global(Some(global)).load();
+
+        assert!(matches!(result, Err(CoreError::ConfigError(msg)) if msg.contains("mapping")));
+    }
+
+    #[test]
+    fn test_unknown_key_names_file_and_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = write(
+            dir.path(),
+            CONFIG_FILE,
+            "agent:\n  model: claude-opus-4\n  permisionMode: plan\n",
+        );
+
+        let err = ConfigLoader::new(dir.path())
+            .with_global(None)
+            .load()
+            .unwrap_err();
+        let expected = format!(
+            "{}: unknown key `agent.permisionMode` at line 3 (did you mean `permissionMode`?)",
+            repo.display()
+        );
+        assert!(matches!(&err, CoreError::ConfigError(msg) if *msg == expected));
+
+        let err = merge_default_config(&repo).unwrap_err();
+        assert!(matches!(&err, CoreError::ConfigError(msg) if *msg == expected));
+        assert!(
+            !std::fs::read_to_string(&repo)
+                .unwrap()
+                .contains("maxAttempts")
+        );
+    }
+}
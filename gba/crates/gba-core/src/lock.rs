@@ -0,0 +1,120 @@
+//! Lock on a feature directory while a `gba run` works on it.
+//!
+//! The lock is a file holding the PID of the running process, created
+//! exclusively so only one process (or one task of `gba run-all`) runs a
+//! feature at a time. It is removed when the [`FeatureLock`] is dropped; a
+//! lock left behind by a process that no longer exists is taken over.
+
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::{CoreError, Result};
+
+/// Lock file in the feature directory
+pub const LOCK_FILE: &str = "run.lock";
+
+/// Held lock on a feature directory, released on drop
+#[derive(Debug)]
+pub struct FeatureLock {
+    path: PathBuf,
+}
+
+impl FeatureLock {
+    /// Lock the feature at `feature_path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::FeatureLocked` if another running process holds
+    /// the lock, or an error if the lock file can't be written.
+    pub fn acquire(feature_path: &Path) -> Result<Self> {
+        let path = feature_path.join(LOCK_FILE);
+        // Two attempts: the second after removing a stale lock.
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    write!(file, "{}", std::process::id())?;
+                    return Ok(Self { path });
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let pid = std::fs::read_to_string(&path)?.trim().parse::<u32>().ok();
+                    if pid.is_some_and(is_running) {
+                        return Err(CoreError::FeatureLocked {
+                            feature: dir_name(feature_path),
+                            path,
+                        });
+                    }
+                    std::fs::remove_file(&path)?;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(CoreError::FeatureLocked {
+            feature: dir_name(feature_path),
+            path,
+        })
+    }
+}
+
+impl Drop for FeatureLock {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_file(&self.path) {
+            tracing::warn!("failed to remove {}: {e}", self.path.display());
+        }
+    }
+}
+
+fn dir_name(feature_path: &Path) -> String {
+    feature_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Whether process `pid` is alive; assumed so where that can't be told
+fn is_running(pid: u32) -> bool {
+    let proc = Path::new("/proc");
+    !proc.is_dir() || proc.join(pid.to_string()).exists()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lock_is_exclusive_and_released_on_drop() {
+        let dir = tempfile::tempdir().unwrap();
+        let feature = dir.path().join("0001_auth");
+        std::fs::create_dir(&feature).unwrap();
+
+        let lock = FeatureLock::acquire(&feature).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(feature.join(LOCK_FILE)).unwrap(),
+            std::process::id().to_string()
+        );
+        let err = FeatureLock::acquire(&feature).unwrap_err();
+        assert!(matches!(err, CoreError::FeatureLocked { .. }));
+        assert!(
+            err.to_string()
+                .starts_with("Feature 0001_auth is being run")
+        );
+
+        drop(lock);
+        assert!(!feature.join(LOCK_FILE).exists());
+        drop(FeatureLock::acquire(&feature).unwrap());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_stale_lock_is_taken_over() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join(LOCK_FILE), u32::MAX.to_string()).unwrap();
+
+        let _lock = FeatureLock::acquire(dir.path()).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join(LOCK_FILE)).unwrap(),
+            std::process::id().to_string()
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! MCP servers passed through to the agent.
+//!
+//! Servers are configured by name in `.gba/config.yml` and in a task's
+//! `config.yml`, whose entries win:
+//!
+//! ```yaml
+//! mcpServers:
+//!   docs:
+//!     command: docs-mcp
+//!     args: [--stdio]
+//!     env:
+//!       DOCS_TOKEN: secret
+//!   db:
+//!     url: https://mcp.example.com/db
+//!     type: sse
+//! ```
+//!
+//! A task's `tools` refer to their tools as `mcp__{server}__{tool}` (or
+//! `mcp__{server}` for all of them).
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use claude_agent_sdk_rs::types::mcp::{
+    McpHttpServerConfig, McpSseServerConfig, McpStdioServerConfig,
+};
+use claude_agent_sdk_rs::{McpServerConfig, McpServers};
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::error::{CoreError, Result};
+
+/// MCP servers by name
+pub type McpServerMap = BTreeMap<String, McpServer>;
+
+/// Prefix of the tool names of MCP servers
+const TOOL_PREFIX: &str = "mcp__";
+
+/// A configured MCP server
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum McpServer {
+    /// Server started by the agent, speaking over stdio
+    Stdio(StdioServer),
+    /// Server reached over the network
+    Remote(RemoteServer),
+}
+
+/// A server started as a child process of the agent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct StdioServer {
+    /// Program to run
+    pub command: String,
+    /// Its arguments
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Environment variables set for it
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+/// A server reached by URL
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct RemoteServer {
+    /// Server URL
+    pub url: String,
+    /// Transport (default: http)
+    #[serde(rename = "type", default)]
+    pub transport: RemoteTransport,
+    /// HTTP headers sent with every request
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+/// Transport of a remote server
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RemoteTransport {
+    /// Streamable HTTP
+    #[default]
+    Http,
+    /// Server-sent events
+    Sse,
+}
+
+impl McpServer {
+    fn to_sdk(&self) -> McpServerConfig {
+        let map = |entries: &BTreeMap<String, String>| {
+            (!entries.is_empty()).then(|| {
+                entries
+                    .iter()
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect::<HashMap<_, _>>()
+            })
+        };
+        match self {
+            Self::Stdio(server) => McpServerConfig::Stdio(McpStdioServerConfig {
+                command: server.command.clone(),
+                args: (!server.args.is_empty()).then(|| server.args.clone()),
+                env: map(&server.env),
+            }),
+            Self::Remote(server) => match server.transport {
+                RemoteTransport::Http => McpServerConfig::Http(McpHttpServerConfig {
+                    url: server.url.clone(),
+                    headers: map(&server.headers),
+                }),
+                RemoteTransport::Sse => McpServerConfig::Sse(McpSseServerConfig {
+                    url: server.url.clone(),
+                    headers: map(&server.headers),
+                }),
+            },
+        }
+    }
+}
+
+/// The SDK form of `servers`
+pub(crate) fn to_sdk(servers: &McpServerMap) -> McpServers {
+    if servers.is_empty() {
+        return McpServers::Empty;
+    }
+    McpServers::Dict(
+        servers
+            .iter()
+            .map(|(name, server)| (name.clone(), server.to_sdk()))
+            .collect(),
+    )
+}
+
+/// `project` servers overridden and extended by `task` servers
+pub fn merge(project: &McpServerMap, task: &McpServerMap) -> McpServerMap {
+    let mut servers = project.clone();
+    servers.extend(task.iter().map(|(k, v)| (k.clone(), v.clone())));
+    servers
+}
+
+/// Check that every `mcp__{server}` tool in `tools` names a configured server.
+///
+/// # Errors
+///
+/// Returns `CoreError::ConfigError` naming the first unknown server.
+pub fn check_tools(tools: &[String], servers: &McpServerMap) -> Result<()> {
+    for tool in tools {
+        let Some(rest) = tool.strip_prefix(TOOL_PREFIX) else {
+            continue;
+        };
+        let server = rest.split("__").next().unwrap_or(rest);
+        if !servers.contains_key(server) {
+            let known: Vec<&str> = servers.keys().map(String::as_str).collect();
+            return Err(CoreError::ConfigError(format!(
+                "tool `{tool}` refers to unknown MCP server `{server}` (configured: {})",
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Start a stdio server in `cwd` and check it is still running after
+/// `grace`; the server is stopped again either way.
+///
+/// Remote servers aren't contacted and always pass.
+///
+/// # Errors
+///
+/// Returns `CoreError::CommandFailed` if the server can't be started or
+/// exits within `grace`.
+pub async fn probe(server: &McpServer, cwd: &Path, grace: Duration) -> Result<()> {
+    let McpServer::Stdio(server) = server else {
+        return Ok(());
+    };
+    let command_line = std::iter::once(server.command.as_str())
+        .chain(server.args.iter().map(String::as_str))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let failed = |stderr: String| CoreError::CommandFailed {
+        command: command_line.clone(),
+        stderr,
+    };
+
+    let mut child = Command::new(&server.command)
+        .args(&server.args)
+        .envs(&server.env)
+        .current_dir(cwd)
+        // Open stdin keeps the server waiting for requests.
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| failed(format!("failed to start: {e}")))?;
+    // `wait` would close stdin, which stops most servers.
+    let _stdin = child.stdin.take();
+    match tokio::time::timeout(grace, child.wait()).await {
+        Err(_) => {
+            let _ = child.kill().await;
+            Ok(())
+        }
+        Ok(status) => {
+            let status = status?;
+            let output = child.wait_with_output().await?;
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            Err(failed(format!(
+                "exited right after starting ({status}): {}",
+                stderr.trim()
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "\
+docs:
+  command: docs-mcp
+  args: [--stdio]
+  env:
+    DOCS_TOKEN: secret
+db:
+  url: https://mcp.example.com/db
+  type: sse
+search:
+  url: https://mcp.example.com/search
+";
+
+    #[test]
+    fn test_parse_and_serialize_servers() {
+        let servers: McpServerMap = serde_yaml::from_str(CONFIG).unwrap();
+
+        assert_eq!(
+            servers["docs"],
+            McpServer::Stdio(StdioServer {
+                command: "docs-mcp".to_string(),
+                args: vec!["--stdio".to_string()],
+                env: BTreeMap::from([("DOCS_TOKEN".to_string(), "secret".to_string())]),
+            })
+        );
+        assert!(matches!(
+            &servers["db"],
+            McpServer::Remote(RemoteServer {
+                transport: RemoteTransport::Sse,
+                ..
+            })
+        ));
+        assert!(matches!(
+            &servers["search"],
+            McpServer::Remote(RemoteServer {
+                transport: RemoteTransport::Http,
+                ..
+            })
+        ));
+
+        let yaml = serde_yaml::to_string(&servers).unwrap();
+        assert_eq!(
+            serde_yaml::from_str::<McpServerMap>(&yaml).unwrap(),
+            servers
+        );
+        assert!(yaml.contains("  type: sse"));
+        assert!(serde_yaml::from_str::<McpServerMap>("x:\n  cmd: docs-mcp\n").is_err());
+
+        let McpServers::Dict(sdk) = to_sdk(&servers) else {
+            panic!("expected a server dictionary");
+        };
+        assert!(
+            matches!(&sdk["docs"], McpServerConfig::Stdio(s) if s.args.as_deref() == Some(&["--stdio".to_string()][..]))
+        );
+        assert!(matches!(&sdk["db"], McpServerConfig::Sse(_)));
+        assert!(matches!(&sdk["search"], McpServerConfig::Http(s) if s.headers.is_none()));
+    }
+
+    #[test]
+    fn test_tools_must_name_configured_servers() {
+        let project: McpServerMap = serde_yaml::from_str(CONFIG).unwrap();
+        let task: McpServerMap = serde_yaml::from_str("wiki:\n  command: wiki-mcp\n").unwrap();
+        let servers = merge(&project, &task);
+        let tools = |names: &[&str]| names.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+        assert!(
+            check_tools(
+                &tools(&["Read", "mcp__docs__search", "mcp__wiki"]),
+                &servers
+            )
+            .is_ok()
+        );
+        let err = check_tools(&tools(&["mcp__jira__create"]), &servers).unwrap_err();
+        assert!(err.to_string().contains(
+            "tool `mcp__jira__create` refers to unknown MCP server `jira` (configured: db, docs, search, wiki)"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_probe_stdio_servers() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = |command: &str, args: &[&str]| {
+            McpServer::Stdio(StdioServer {
+                command: command.to_string(),
+                args: args.iter().map(ToString::to_string).collect(),
+                env: BTreeMap::new(),
+            })
+        };
+        let grace = Duration::from_millis(300);
+
+        // `cat` waits on stdin like a server.
+        probe(&server("cat", &[]), dir.path(), grace).await.unwrap();
+        let err = probe(
+            &server("sh", &["-c", "echo bad config >&2; exit 2"]),
+            dir.path(),
+            grace,
+        )
+        .await
+        .unwrap_err();
+        assert!(err.to_string().contains("exited right after starting"));
+        assert!(err.to_string().contains("bad config"));
+        let err = probe(&server("no-such-mcp-server", &[]), dir.path(), grace)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("failed to start"));
+    }
+}
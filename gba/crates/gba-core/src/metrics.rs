@@ -0,0 +1,124 @@
+//! Engine events for observability.
+//!
+//! The engine emits `tracing` events under the `gba::engine` target with
+//! stable field names (`feature_id`, `phase`, `duration_ms`, `cost_usd`,
+//! `tokens`, `attempt`, `success`), and reports the numbers below to the
+//! [`Metrics`] of [`crate::Engine::with_metrics`], so an embedder can bridge
+//! them to e.g. Prometheus.
+//!
+//! | Metric | Kind | Labels |
+//! |---|---|---|
+//! | [`EXECUTIONS`] | counter | `model`, `outcome` |
+//! | [`EXECUTION_DURATION_MS`] | histogram | `model` |
+//! | [`COST_USD`] | histogram | `model` |
+//! | [`TOKENS`] | counter | `model` |
+//! | [`RETRIES`] | counter | |
+//! | [`TIMEOUTS`] | counter | |
+//! | [`PHASE_EXECUTIONS`] | counter | `phase`, `outcome` |
+//! | [`PHASE_DURATION_MS`] | histogram | `phase` |
+//!
+//! `outcome` is `success`, `failure` or `error`.
+
+use std::fmt;
+
+/// Target of the engine's tracing events
+pub const TARGET: &str = "gba::engine";
+
+/// Agent requests executed
+pub const EXECUTIONS: &str = "gba_executions_total";
+/// Duration of agent requests in milliseconds
+pub const EXECUTION_DURATION_MS: &str = "gba_execution_duration_ms";
+/// Cost of agent requests in USD
+pub const COST_USD: &str = "gba_cost_usd";
+/// Input and output tokens of agent requests
+pub const TOKENS: &str = "gba_tokens_total";
+/// Agent requests retried after being rate-limited
+pub const RETRIES: &str = "gba_retries_total";
+/// Agent requests that timed out
+pub const TIMEOUTS: &str = "gba_timeouts_total";
+/// Phases executed
+pub const PHASE_EXECUTIONS: &str = "gba_phase_executions_total";
+/// Duration of phases, hooks and test runs included, in milliseconds
+pub const PHASE_DURATION_MS: &str = "gba_phase_duration_ms";
+
+/// Metric labels as `(name, value)` pairs
+pub type Labels<'a> = &'a [(&'a str, &'a str)];
+
+/// Receives the engine's metrics
+pub trait Metrics: Send + Sync + fmt::Debug {
+    /// Add `value` to the counter `name`
+    fn counter(&self, name: &str, value: u64, labels: Labels<'_>);
+
+    /// Record `value` in the histogram `name`
+    fn histogram(&self, name: &str, value: f64, labels: Labels<'_>);
+}
+
+/// Metrics that are dropped; the engine's default
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopMetrics;
+
+impl Metrics for NoopMetrics {
+    fn counter(&self, _name: &str, _value: u64, _labels: Labels<'_>) {}
+
+    fn histogram(&self, _name: &str, _value: f64, _labels: Labels<'_>) {}
+}
+
+/// `outcome` label of a result
+pub(crate) fn outcome<T>(result: &crate::Result<T>, success: impl Fn(&T) -> bool) -> &'static str {
+    match result {
+        Ok(value) if success(value) => "success",
+        Ok(_) => "failure",
+        Err(_) => "error",
+    }
+}
+
+/// Records metrics in memory, for tests
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct Recorder {
+    pub(crate) records: parking_lot::Mutex<Vec<Record>>,
+}
+
+/// A recorded metric
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Record {
+    pub(crate) name: String,
+    pub(crate) value: f64,
+    pub(crate) labels: Vec<(String, String)>,
+}
+
+#[cfg(test)]
+impl Recorder {
+    fn record(&self, name: &str, value: f64, labels: Labels<'_>) {
+        self.records.lock().push(Record {
+            name: name.to_string(),
+            value,
+            labels: labels
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        });
+    }
+
+    /// Records of the metric `name`
+    pub(crate) fn get(&self, name: &str) -> Vec<Record> {
+        self.records
+            .lock()
+            .iter()
+            .filter(|r| r.name == name)
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+impl Metrics for Recorder {
+    fn counter(&self, name: &str, value: u64, labels: Labels<'_>) {
+        self.record(name, value as f64, labels);
+    }
+
+    fn histogram(&self, name: &str, value: f64, labels: Labels<'_>) {
+        self.record(name, value, labels);
+    }
+}
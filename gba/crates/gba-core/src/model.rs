@@ -0,0 +1,127 @@
+//! Checking model IDs before they reach the SDK.
+
+use crate::config::closest_key;
+use crate::error::{CoreError, Result};
+
+/// Claude model IDs known to this version, without date suffixes
+pub const KNOWN_MODELS: &[&str] = &[
+    "claude-opus-4-5",
+    "claude-opus-4-1",
+    "claude-opus-4-0",
+    "claude-opus-4",
+    "claude-sonnet-4-5",
+    "claude-sonnet-4-0",
+    "claude-sonnet-4",
+    "claude-haiku-4-5",
+    "claude-3-7-sonnet",
+    "claude-3-5-sonnet",
+    "claude-3-5-haiku",
+    "claude-3-opus",
+    "claude-3-haiku",
+];
+
+/// Short names the SDK resolves to its current models
+pub const MODEL_ALIASES: &[&str] = &["opus", "sonnet", "haiku", "opusplan"];
+
+const FAMILIES: [&str; 3] = ["opus", "sonnet", "haiku"];
+
+/// Check that `model` looks like a Claude model ID.
+///
+/// Known IDs (optionally dated, like `claude-sonnet-4-5-20250929`, or
+/// `-latest`) and aliases pass. A well-formed ID that isn't known passes
+/// with a warning, so newer models aren't blocked, unless it is a
+/// misspelling of a known one.
+///
+/// # Errors
+///
+/// Returns `CoreError::ConfigError`, suggesting the closest known ID, if
+/// `model` isn't a well-formed `claude-...` ID or misspells a model family.
+pub fn validate_model(model: &str) -> Result<()> {
+    if MODEL_ALIASES.contains(&model) {
+        return Ok(());
+    }
+    let base = without_version(model);
+    let well_formed = model.strip_prefix("claude-").is_some_and(|rest| {
+        rest.split('-').all(|part| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '.')
+        })
+    });
+    if well_formed && KNOWN_MODELS.contains(&base) {
+        return Ok(());
+    }
+
+    let suggestion =
+        closest_key(base.trim(), KNOWN_MODELS).or_else(|| closest_key(model.trim(), MODEL_ALIASES));
+    let hint = match suggestion {
+        Some(known) => format!("did you mean `{known}`?"),
+        None => "expected an ID like `claude-sonnet-4-5`".to_string(),
+    };
+    if !well_formed {
+        return Err(CoreError::ConfigError(format!(
+            "`{model}` is not a Claude model ID ({hint})"
+        )));
+    }
+    // A model without a known family name is more likely a typo than new.
+    let has_family = base.split('-').any(|part| FAMILIES.contains(&part));
+    if !has_family && suggestion.is_some() {
+        return Err(CoreError::ConfigError(format!(
+            "unknown model `{model}` ({hint})"
+        )));
+    }
+    tracing::warn!(model, "unknown model, passing it to the SDK as is");
+    Ok(())
+}
+
+/// `model` without a `-YYYYMMDD` or `-latest` suffix
+fn without_version(model: &str) -> &str {
+    match model.rsplit_once('-') {
+        Some((base, "latest")) => base,
+        Some((base, date)) if date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit()) => base,
+        _ => model,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_known_models_pass() {
+        for model in [
+            "claude-sonnet-4-5",
+            "claude-sonnet-4-5-20250929",
+            "claude-3-5-haiku-latest",
+            "opus",
+        ] {
+            assert!(validate_model(model).is_ok(), "{model}");
+        }
+        // Unknown but well-formed, e.g. a model newer than this list.
+        assert!(validate_model("claude-opus-5").is_ok());
+        assert!(validate_model("claude-sonnet-5-20260101").is_ok());
+    }
+
+    #[test]
+    fn test_typo_suggests_known_model() {
+        let err = validate_model("claude-sonet-4-5").unwrap_err();
+        assert!(matches!(
+            &err,
+            CoreError::ConfigError(msg) if msg.contains("did you mean `claude-sonnet-4-5`?")
+        ));
+        let err = validate_model("Claude-Haiku-4-5").unwrap_err();
+        assert!(err.to_string().contains("did you mean `claude-haiku-4-5`?"));
+    }
+
+    #[test]
+    fn test_malformed_model_is_rejected() {
+        for model in ["", "gpt-4o", "claude sonnet!", "claude--4", "claude-"] {
+            let err = validate_model(model).unwrap_err();
+            assert!(
+                err.to_string().contains("is not a Claude model ID"),
+                "{model}: {err}"
+            );
+        }
+    }
+}
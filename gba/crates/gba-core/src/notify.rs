@@ -0,0 +1,205 @@
+//! Notifications sent when a feature run completes, fails or is interrupted.
+//!
+//! Both channels shell out through a [`CommandRunner`]: desktop
+//! notifications use `notify-send` (or `osascript` on macOS) and the webhook
+//! is posted with `curl`.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::command::{CommandRunner, run_checked};
+use crate::config::NotificationConfig;
+use crate::error::{CoreError, Result};
+use crate::state::{FeatureState, FeatureStatus};
+
+/// Seconds `curl` may spend delivering the webhook
+const WEBHOOK_TIMEOUT_SECONDS: &str = "10";
+
+/// JSON body posted to `notifications.webhookUrl`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunNotification {
+    /// Feature ID (e.g. "0001")
+    pub feature_id: String,
+    /// Feature slug
+    pub feature_slug: String,
+    /// Feature status after the run
+    pub status: FeatureStatus,
+    /// Time spent running phases
+    pub duration_seconds: u64,
+    /// Total cost of the feature in USD
+    pub cost_usd: f64,
+    /// Why the run stopped, if it didn't complete
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl RunNotification {
+    /// Notification for the current state of a feature
+    pub fn new(state: &FeatureState, error: Option<String>) -> Self {
+        let duration = state.duration().unwrap_or_default();
+        Self {
+            feature_id: state.feature.id.clone(),
+            feature_slug: state.feature.slug.clone(),
+            status: state.status,
+            duration_seconds: duration.num_seconds().max(0).unsigned_abs(),
+            cost_usd: state.total_stats.cost_usd,
+            error: error.or_else(|| state.error.clone()),
+        }
+    }
+
+    /// Headline, e.g. `gba: 0001_auth completed`
+    pub fn title(&self) -> String {
+        let status = match (&self.error, self.status) {
+            (Some(_), FeatureStatus::InProgress) => "stopped".to_string(),
+            (_, status) => status.to_string(),
+        };
+        format!("gba: {}_{} {status}", self.feature_id, self.feature_slug)
+    }
+
+    /// Duration, cost and error of the run
+    pub fn body(&self) -> String {
+        let (minutes, seconds) = (self.duration_seconds / 60, self.duration_seconds % 60);
+        let mut body = format!("{minutes}m{seconds:02}s, ${:.2}", self.cost_usd);
+        if let Some(error) = &self.error {
+            body.push('\n');
+            body.push_str(error);
+        }
+        body
+    }
+}
+
+/// Deliver `notification` through every channel enabled in `config`.
+///
+/// Every channel is attempted even if an earlier one fails.
+///
+/// # Errors
+///
+/// Returns `CoreError::NotificationFailed` describing each channel that
+/// failed.
+pub fn send(
+    runner: &dyn CommandRunner,
+    config: &NotificationConfig,
+    notification: &RunNotification,
+) -> Result<()> {
+    let mut failures = Vec::new();
+    if config.desktop
+        && let Err(e) = desktop(runner, &notification.title(), &notification.body())
+    {
+        failures.push(format!("desktop: {e}"));
+    }
+    if let Some(url) = &config.webhook_url
+        && let Err(e) = webhook(runner, url, notification)
+    {
+        failures.push(format!("webhook: {e}"));
+    }
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(CoreError::NotificationFailed(failures.join("; ")))
+    }
+}
+
+fn desktop(runner: &dyn CommandRunner, title: &str, body: &str) -> Result<()> {
+    if cfg!(target_os = "macos") {
+        let quote = |s: &str| s.replace('\\', "\\\\").replace('"', "\\\"");
+        let script = format!(
+            "display notification \"{}\" with title \"{}\"",
+            quote(body),
+            quote(title)
+        );
+        run_checked(runner, "osascript", &["-e", &script], Path::new("."))?;
+    } else {
+        run_checked(runner, "notify-send", &[title, body], Path::new("."))?;
+    }
+    Ok(())
+}
+
+fn webhook(runner: &dyn CommandRunner, url: &str, notification: &RunNotification) -> Result<()> {
+    let payload = serde_json::to_string(notification)
+        .map_err(|e| CoreError::NotificationFailed(e.to_string()))?;
+    run_checked(
+        runner,
+        "curl",
+        &[
+            "--silent",
+            "--show-error",
+            "--fail",
+            "--max-time",
+            WEBHOOK_TIMEOUT_SECONDS,
+            "-X",
+            "POST",
+            "-H",
+            "Content-Type: application/json",
+            "--data",
+            &payload,
+            url,
+        ],
+        Path::new("."),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::fake::FakeCommandRunner;
+
+    fn notification() -> RunNotification {
+        let mut state = FeatureState::new("0001", "auth");
+        state.total_stats.cost_usd = 1.5;
+        state.total_stats.wall_clock_seconds = 252;
+        state.status = FeatureStatus::Failed;
+        RunNotification::new(&state, Some("Phase build failed".to_string()))
+    }
+
+    #[test]
+    fn test_webhook_posts_json() {
+        let runner = FakeCommandRunner::default();
+        let config = NotificationConfig {
+            webhook_url: Some("https://hooks.example.com/gba".to_string()),
+            ..NotificationConfig::default()
+        };
+
+        send(&runner, &config, &notification()).unwrap();
+
+        let calls = runner.calls();
+        assert_eq!(calls.len(), 1);
+        let (program, args, _) = &calls[0];
+        assert_eq!(program, "curl");
+        assert_eq!(args.last().unwrap(), "https://hooks.example.com/gba");
+        assert_eq!(
+            args[args.len() - 2],
+            r#"{"featureId":"0001","featureSlug":"auth","status":"failed","durationSeconds":252,"costUsd":1.5,"error":"Phase build failed"}"#
+        );
+    }
+
+    #[test]
+    fn test_failures_are_collected() {
+        let runner = FakeCommandRunner::default();
+        runner.respond(1, "", "connection refused");
+        runner.respond(22, "", "The requested URL returned error: 500");
+        let config = NotificationConfig {
+            desktop: true,
+            webhook_url: Some("https://hooks.example.com/gba".to_string()),
+        };
+
+        let err = send(&runner, &config, &notification())
+            .unwrap_err()
+            .to_string();
+
+        assert_eq!(runner.calls().len(), 2);
+        assert!(err.contains("desktop: ") && err.contains("connection refused"));
+        assert!(err.contains("webhook: ") && err.contains("error: 500"));
+        assert_eq!(notification().title(), "gba: 0001_auth failed");
+        assert_eq!(notification().body(), "4m12s, $1.50\nPhase build failed");
+    }
+
+    #[test]
+    fn test_disabled_channels_send_nothing() {
+        let runner = FakeCommandRunner::default();
+        send(&runner, &NotificationConfig::default(), &notification()).unwrap();
+        assert!(runner.calls().is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+//! Observations of the observe phase, written to `docs/observations.md`.
+//!
+//! The observe phase explores the repository; its output is saved so later
+//! phases start from it instead of exploring again. Prompts receive it as
+//! the `observations` variable, cut to a token budget.
+
+use std::path::Path;
+
+use crate::error::Result;
+use crate::text::{CHARS_PER_TOKEN, truncate_chars};
+
+/// Name of the phase whose output is recorded
+pub const OBSERVE_PHASE: &str = "observe";
+
+/// Location of the observations relative to the feature directory
+pub const OBSERVATIONS_FILE: &str = "docs/observations.md";
+
+/// Approximate number of tokens of observations injected into prompts
+pub const OBSERVATIONS_MAX_TOKENS: usize = 8_000;
+
+/// Whether the feature already has observations
+pub fn exists(feature_path: &Path) -> bool {
+    feature_path.join(OBSERVATIONS_FILE).is_file()
+}
+
+/// Write the observe phase's `output` to `docs/observations.md`.
+///
+/// # Errors
+///
+/// Returns an IO error if the file cannot be written.
+pub fn record(feature_path: &Path, output: &str) -> Result<()> {
+    let path = feature_path.join(OBSERVATIONS_FILE);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, format!("{}\n", output.trim()))?;
+    Ok(())
+}
+
+/// Observations to inject into a prompt, cut to about `max_tokens` tokens.
+///
+/// Returns `None` if the feature has no (or empty) observations.
+///
+/// # Errors
+///
+/// Returns an IO error if the file exists but cannot be read.
+pub fn load(feature_path: &Path, max_tokens: usize) -> Result<Option<String>> {
+    let path = feature_path.join(OBSERVATIONS_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)?;
+    let content = content.trim();
+    if content.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(truncate(content, max_tokens)))
+}
+
+/// Cut `content` to about `max_tokens` tokens, noting what was dropped
+fn truncate(content: &str, max_tokens: usize) -> String {
+    let head = truncate_chars(content, max_tokens.saturating_mul(CHARS_PER_TOKEN), "");
+    let mut end = head.len();
+    if end == content.len() {
+        return head;
+    }
+    // Prefer cutting at a line break.
+    if let Some(line_end) = content[..end].rfind('\n') {
+        end = line_end;
+    }
+    format!(
+        "{}\n\n[Observations truncated: {} more characters in {OBSERVATIONS_FILE}]",
+        content[..end].trim_end(),
+        content[end..].chars().count()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!exists(dir.path()));
+        assert_eq!(load(dir.path(), 100).unwrap(), None);
+
+        record(
+            dir.path(),
+            "\n## Architecture\n\nThe engine lives in gba-core.\n\n",
+        )
+        .unwrap();
+
+        assert!(exists(dir.path()));
+        assert_eq!(
+            load(dir.path(), 100).unwrap().as_deref(),
+            Some("## Architecture\n\nThe engine lives in gba-core.")
+        );
+    }
+
+    #[test]
+    fn test_load_truncates_to_budget() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = "first line\n".repeat(10) + &"é".repeat(100);
+        record(dir.path(), &output).unwrap();
+
+        let loaded = load(dir.path(), 10).unwrap().unwrap();
+
+        assert!(
+            loaded.starts_with("first line\nfirst line\nfirst line\n\n[Observations truncated")
+        );
+        assert!(loaded.ends_with("more characters in docs/observations.md]"));
+        assert!(!loaded.contains('é'));
+    }
+}
@@ -0,0 +1,241 @@
+//! Approval of the agent's tool uses (`permissions:` section).
+//!
+//! ```yaml
+//! permissions:
+//!   autoAllow: [Read, Grep, Glob, "mcp__docs__*"]
+//!   askFor: [Bash, Write]
+//! ```
+//!
+//! With a policy configured, every tool use is checked before it runs:
+//! `autoAllow` tools run right away, others are put to the engine's
+//! [`ToolApprover`] (the user at a terminal), and denied if there is none so
+//! headless runs never hang. `askFor` wins over `autoAllow`, e.g. to ask
+//! for one tool of an allowed MCP server. Denials are logged, counted in
+//! the stats and recorded with their reason.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use claude_agent_sdk_rs::{
+    HookCallback, HookEvent, HookInput, HookJsonOutput, HookMatcher, HookSpecificOutput,
+    PreToolUseHookSpecificOutput, SyncHookJsonOutput,
+};
+use futures::future::BoxFuture;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+/// Tool approval policy (`permissions:` section)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default, deny_unknown_fields)]
+pub struct PermissionsConfig {
+    /// Tools that need approval even if `autoAllow` matches them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ask_for: Vec<String>,
+    /// Tools that run without approval
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub auto_allow: Vec<String>,
+}
+
+impl PermissionsConfig {
+    /// Whether a policy is configured; without one tool uses are left to
+    /// the permission mode
+    pub fn is_enabled(&self) -> bool {
+        !self.ask_for.is_empty() || !self.auto_allow.is_empty()
+    }
+
+    /// Whether `tool` runs without approval
+    pub fn allows(&self, tool: &str) -> bool {
+        let matches = |patterns: &[String]| patterns.iter().any(|p| tool_matches(p, tool));
+        !matches(&self.ask_for) && matches(&self.auto_allow)
+    }
+}
+
+/// Whether `pattern` (a tool name, or a prefix ending in `*`) matches `tool`
+fn tool_matches(pattern: &str, tool: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => tool.starts_with(prefix),
+        None => pattern == tool,
+    }
+}
+
+/// A tool the agent wants to use
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolUse {
+    /// Tool name, e.g. `Bash`
+    pub tool: String,
+    /// Its input, e.g. `{"command": "rm -rf target"}`
+    pub input: serde_json::Value,
+}
+
+/// A tool use that wasn't allowed to run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolDenial {
+    /// Tool name
+    pub tool: String,
+    /// Why it was denied
+    pub reason: String,
+}
+
+impl fmt::Display for ToolDenial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.tool, self.reason)
+    }
+}
+
+/// Decides on tool uses the policy doesn't allow by itself
+pub trait ToolApprover: Send + Sync + fmt::Debug {
+    /// Whether `tool_use` may run
+    fn approve<'a>(&'a self, tool_use: &'a ToolUse) -> BoxFuture<'a, bool>;
+}
+
+/// Decide on `tool_use`, returning the reason of a denial
+pub(crate) async fn decide(
+    policy: &PermissionsConfig,
+    approver: Option<&dyn ToolApprover>,
+    tool_use: &ToolUse,
+) -> std::result::Result<(), String> {
+    if policy.allows(&tool_use.tool) {
+        return Ok(());
+    }
+    match approver {
+        Some(approver) if approver.approve(tool_use).await => Ok(()),
+        Some(_) => Err("denied by the user".to_string()),
+        None => Err("needs approval, but nobody can be asked in a non-interactive run".to_string()),
+    }
+}
+
+/// SDK hooks checking every tool use against `policy`, recording denials
+/// in `denials`
+pub(crate) fn hooks(
+    policy: PermissionsConfig,
+    approver: Option<Arc<dyn ToolApprover>>,
+    denials: Arc<Mutex<Vec<ToolDenial>>>,
+) -> HashMap<HookEvent, Vec<HookMatcher>> {
+    let policy = Arc::new(policy);
+    let callback: HookCallback = Arc::new(move |input, _tool_use_id, _context| {
+        let policy = policy.clone();
+        let approver = approver.clone();
+        let denials = denials.clone();
+        Box::pin(async move {
+            let HookInput::PreToolUse(input) = input else {
+                return HookJsonOutput::Sync(SyncHookJsonOutput::default());
+            };
+            let tool_use = ToolUse {
+                tool: input.tool_name,
+                input: input.tool_input,
+            };
+            let decision = decide(&policy, approver.as_deref(), &tool_use).await;
+            let output = match decision {
+                Ok(()) => PreToolUseHookSpecificOutput::builder()
+                    .permission_decision("allow")
+                    .build(),
+                Err(reason) => {
+                    tracing::warn!(tool = %tool_use.tool, "tool use denied: {reason}");
+                    let output = PreToolUseHookSpecificOutput::builder()
+                        .permission_decision("deny")
+                        .permission_decision_reason(reason.clone())
+                        .build();
+                    denials.lock().push(ToolDenial {
+                        tool: tool_use.tool,
+                        reason,
+                    });
+                    output
+                }
+            };
+            HookJsonOutput::Sync(
+                SyncHookJsonOutput::builder()
+                    .hook_specific_output(HookSpecificOutput::PreToolUse(output))
+                    .build(),
+            )
+        })
+    });
+    HashMap::from([(
+        HookEvent::PreToolUse,
+        vec![HookMatcher::builder().hooks(vec![callback]).build()],
+    )])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct Answer(bool);
+
+    impl ToolApprover for Answer {
+        fn approve<'a>(&'a self, _tool_use: &'a ToolUse) -> BoxFuture<'a, bool> {
+            Box::pin(async move { self.0 })
+        }
+    }
+
+    fn policy() -> PermissionsConfig {
+        serde_yaml::from_str("autoAllow: [Read, Bash, 'mcp__docs__*']\naskFor: [Bash]\n").unwrap()
+    }
+
+    fn tool(name: &str) -> ToolUse {
+        ToolUse {
+            tool: name.to_string(),
+            input: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn test_policy_matching() {
+        let policy = policy();
+        assert!(policy.is_enabled());
+        assert!(!PermissionsConfig::default().is_enabled());
+        assert!(policy.allows("Read"));
+        assert!(policy.allows("mcp__docs__search"));
+        // askFor wins over autoAllow.
+        assert!(!policy.allows("Bash"));
+        assert!(!policy.allows("Write"));
+        assert!(!policy.allows("mcp__db__query"));
+    }
+
+    #[tokio::test]
+    async fn test_unlisted_tools_need_approval() {
+        let policy = policy();
+        assert_eq!(decide(&policy, None, &tool("Read")).await, Ok(()));
+        assert_eq!(
+            decide(&policy, Some(&Answer(true)), &tool("Bash")).await,
+            Ok(())
+        );
+        assert_eq!(
+            decide(&policy, Some(&Answer(false)), &tool("Bash")).await,
+            Err("denied by the user".to_string())
+        );
+        let denied = decide(&policy, None, &tool("Write")).await.unwrap_err();
+        assert!(denied.contains("non-interactive"));
+    }
+
+    #[tokio::test]
+    async fn test_hook_records_denials() {
+        let denials = Arc::new(Mutex::new(Vec::new()));
+        let hooks = hooks(policy(), None, denials.clone());
+        let callback = &hooks[&HookEvent::PreToolUse][0].hooks[0];
+        let input = |tool: &str| {
+            serde_json::from_value::<HookInput>(serde_json::json!({
+                "hook_event_name": "PreToolUse",
+                "session_id": "s",
+                "transcript_path": "t",
+                "cwd": ".",
+                "tool_name": tool,
+                "tool_input": {"command": "ls"},
+            }))
+            .unwrap()
+        };
+        let decision = |output: HookJsonOutput| {
+            serde_json::to_value(output).unwrap()["hookSpecificOutput"]["permissionDecision"]
+                .clone()
+        };
+
+        let allowed = callback(input("Read"), None, Default::default()).await;
+        assert_eq!(decision(allowed), "allow");
+        let denied = callback(input("Bash"), None, Default::default()).await;
+        assert_eq!(decision(denied), "deny");
+        assert_eq!(denials.lock().len(), 1);
+        assert_eq!(denials.lock()[0].tool, "Bash");
+    }
+}
@@ -0,0 +1,271 @@
+//! Executable phase definitions and dependency ordering.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::PhaseConfig;
+use crate::error::{CoreError, Result};
+use crate::execution::{ExecutionRequest, Role};
+use crate::executor::PhaseKind;
+use crate::hooks::PhaseHooks;
+use crate::mcp::McpServerMap;
+use crate::task::TaskConfig;
+use crate::testing::TestConfig;
+
+/// A phase ready to be executed by the engine
+#[derive(Debug, Clone, Default)]
+pub struct Phase {
+    /// Phase name
+    pub name: String,
+    /// Human readable description
+    pub description: String,
+    /// Whether the agent or `command` executes the phase
+    pub kind: PhaseKind,
+    /// Shell command of a command phase
+    pub command: Option<String>,
+    /// Custom system prompt (None = use the `claude_code` preset)
+    pub system_prompt: Option<String>,
+    /// Text appended to the `claude_code` preset
+    pub append: Option<String>,
+    /// Rendered user prompt
+    pub user_prompt: String,
+    /// Earlier turns of the conversation, sent before the user prompt
+    pub history: Vec<(Role, String)>,
+    /// Allowed tools (empty = all tools)
+    pub tools: Vec<String>,
+    /// Tools to explicitly disallow
+    pub disallowed_tools: Vec<String>,
+    /// MCP servers of the task; merged with the project's by the caller
+    pub mcp_servers: McpServerMap,
+    /// Variables set for the agent on top of `Config::env`
+    pub env: BTreeMap<String, String>,
+    /// Variables removed for the agent on top of `Config::env_deny`
+    pub env_deny: Vec<String>,
+    /// Response timeout overriding `Config::timeout_seconds`
+    pub timeout_seconds: Option<u64>,
+    /// Phases that must complete before this one
+    pub depends_on: Vec<String>,
+    /// Working directory overriding `Config::repo_path`, e.g. an isolated
+    /// worktree when phases run concurrently
+    pub working_dir: Option<PathBuf>,
+    /// Shell commands run before and after the agent
+    pub hooks: PhaseHooks,
+    /// Schema of the JSON block the agent must end its response with
+    pub json_schema: Option<String>,
+    /// Test command run after the agent, whose failures it must fix
+    pub test: Option<TestConfig>,
+}
+
+impl Phase {
+    /// Build a phase from its `config.yml` entry and task configuration.
+    ///
+    /// Prompts are left empty; set them with [`Phase::with_prompts`].
+    pub fn from_config(config: &PhaseConfig, task: &TaskConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            description: config.description.clone(),
+            kind: config.kind,
+            command: config.command.clone(),
+            system_prompt: None,
+            append: task.append.clone(),
+            user_prompt: String::new(),
+            history: Vec::new(),
+            tools: task.tools.clone(),
+            disallowed_tools: task.disallowed_tools.clone(),
+            mcp_servers: task.mcp_servers.clone(),
+            env: config.env.clone(),
+            env_deny: config.env_deny.clone(),
+            timeout_seconds: config.timeout_seconds,
+            depends_on: config.depends_on.clone(),
+            working_dir: None,
+            hooks: config.hooks.clone().unwrap_or_else(|| task.hooks.clone()),
+            json_schema: None,
+            test: None,
+        }
+    }
+
+    /// Set the rendered prompts
+    pub fn with_prompts(mut self, system_prompt: Option<String>, user_prompt: String) -> Self {
+        self.system_prompt = system_prompt;
+        self.user_prompt = user_prompt;
+        self
+    }
+
+    /// Execution request for this phase
+    pub fn request(&self) -> ExecutionRequest {
+        ExecutionRequest {
+            system_prompt: self.system_prompt.clone(),
+            append: self.append.clone(),
+            user_prompt: self.user_prompt.clone(),
+            history: self.history.clone(),
+            tools: self.tools.clone(),
+            disallowed_tools: self.disallowed_tools.clone(),
+            mcp_servers: self.mcp_servers.clone(),
+            env: self.env.clone(),
+            env_deny: self.env_deny.clone(),
+            timeout: self.timeout_seconds.map(Duration::from_secs),
+            json_schema: self.json_schema.clone(),
+            working_dir: self.working_dir.clone(),
+            model: None,
+            max_turns: None,
+            label: Some(self.name.clone()),
+        }
+    }
+}
+
+/// Order phases so every phase comes after its dependencies.
+///
+/// Takes `(name, depends_on)` pairs and returns their indices in execution
+/// order. Independent phases keep their relative input order.
+///
+/// # Errors
+///
+/// Returns `CoreError::ConfigError` if a dependency is unknown, a name is
+/// duplicated, or the dependencies form a cycle.
+pub fn dependency_order<'a>(
+    phases: impl IntoIterator<Item = (&'a str, &'a [String])>,
+) -> Result<Vec<usize>> {
+    let phases: Vec<(&str, &[String])> = phases.into_iter().collect();
+    let mut graph = DependencyGraph::new(phases.iter().copied())?;
+
+    let mut ready: VecDeque<usize> = graph.roots().collect();
+    let mut order = Vec::with_capacity(phases.len());
+    while let Some(i) = ready.pop_front() {
+        order.push(i);
+        ready.extend(graph.complete(i));
+        ready.make_contiguous().sort_unstable();
+    }
+
+    if order.len() < phases.len() {
+        let cycle: Vec<&str> = graph.blocked().map(|i| phases[i].0).collect();
+        return Err(CoreError::ConfigError(format!(
+            "phase dependencies form a cycle: {}",
+            cycle.join(", ")
+        )));
+    }
+    Ok(order)
+}
+
+/// Dependency edges between phases, consumed as phases complete
+#[derive(Debug, Clone)]
+pub(crate) struct DependencyGraph {
+    /// Number of unfinished dependencies per phase
+    in_degree: Vec<usize>,
+    /// Phases waiting on each phase
+    dependents: Vec<Vec<usize>>,
+}
+
+impl DependencyGraph {
+    /// Build the graph from `(name, depends_on)` pairs.
+    ///
+    /// Cycles are not detected here; see [`dependency_order`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::ConfigError` if a dependency is unknown or a name
+    /// is duplicated.
+    pub(crate) fn new<'a>(
+        phases: impl IntoIterator<Item = (&'a str, &'a [String])>,
+    ) -> Result<Self> {
+        let phases: Vec<(&str, &[String])> = phases.into_iter().collect();
+        let mut index = HashMap::new();
+        for (i, (name, _)) in phases.iter().enumerate() {
+            if index.insert(*name, i).is_some() {
+                return Err(CoreError::ConfigError(format!(
+                    "phase `{name}` is defined twice"
+                )));
+            }
+        }
+
+        let mut in_degree = vec![0; phases.len()];
+        let mut dependents = vec![Vec::new(); phases.len()];
+        for (i, (name, depends_on)) in phases.iter().enumerate() {
+            for dep in depends_on.iter() {
+                let Some(&d) = index.get(dep.as_str()) else {
+                    return Err(CoreError::ConfigError(format!(
+                        "phase `{name}` depends on unknown phase `{dep}`"
+                    )));
+                };
+                in_degree[i] += 1;
+                dependents[d].push(i);
+            }
+        }
+        Ok(Self {
+            in_degree,
+            dependents,
+        })
+    }
+
+    /// Phases without dependencies, in input order
+    pub(crate) fn roots(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.in_degree.len()).filter(|&i| self.in_degree[i] == 0)
+    }
+
+    /// Mark phase `i` as done, returning the phases that became ready
+    pub(crate) fn complete(&mut self, i: usize) -> Vec<usize> {
+        let mut ready = Vec::new();
+        for &next in &self.dependents[i] {
+            self.in_degree[next] -= 1;
+            if self.in_degree[next] == 0 {
+                ready.push(next);
+            }
+        }
+        ready
+    }
+
+    /// Phases still waiting on unfinished dependencies
+    fn blocked(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.in_degree.len()).filter(|&i| self.in_degree[i] > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(phases: &[(&str, Vec<String>)]) -> Result<Vec<String>> {
+        let indices = dependency_order(phases.iter().map(|(n, d)| (*n, d.as_slice())))?;
+        Ok(indices
+            .into_iter()
+            .map(|i| phases[i].0.to_string())
+            .collect())
+    }
+
+    fn deps(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_dependency_order() {
+        let phases = [
+            ("docs", deps(&["build"])),
+            ("test", deps(&["build"])),
+            ("observe", deps(&[])),
+            ("build", deps(&["observe"])),
+            ("review", deps(&["test", "docs"])),
+        ];
+
+        assert_eq!(
+            order(&phases).unwrap(),
+            ["observe", "build", "docs", "test", "review"]
+        );
+    }
+
+    #[test]
+    fn test_dependency_cycle_and_unknown() {
+        let cycle = [
+            ("observe", deps(&[])),
+            ("build", deps(&["test"])),
+            ("test", deps(&["build"])),
+        ];
+        let err = order(&cycle).unwrap_err();
+        assert!(matches!(&err, CoreError::ConfigError(msg) if msg.contains("build, test")));
+
+        let unknown = [("build", deps(&["plan"]))];
+        assert!(matches!(
+            order(&unknown),
+            Err(CoreError::ConfigError(msg)) if msg.contains("unknown phase `plan`")
+        ));
+    }
+}
@@ -0,0 +1,71 @@
+//! Pull request phase: the agent drafts a title and body as JSON, `gh` opens the PR.
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CoreError, Result};
+use crate::execution::ExecutionResult;
+
+/// Name of the phase that opens the pull request
+pub const PR_PHASE: &str = "pr";
+
+/// Schema the agent's PR draft must follow
+pub const PR_SCHEMA: &str = r#"{"title": "string, imperative mood, under 72 characters", "body": "string, markdown description of the change"}"#;
+
+/// Pull request title and body drafted by the agent
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PullRequestDraft {
+    /// PR title
+    pub title: String,
+    /// PR body (markdown)
+    pub body: String,
+}
+
+impl PullRequestDraft {
+    /// Parse the draft from the agent's response.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::InvalidAgentOutput` if the JSON is missing,
+    /// malformed, or has an empty title.
+    pub fn from_result(result: &ExecutionResult) -> Result<Self> {
+        let draft: Self = result.extract_json()?;
+        if draft.title.trim().is_empty() {
+            return Err(CoreError::InvalidAgentOutput(
+                "pull request title is empty".to_string(),
+            ));
+        }
+        Ok(draft)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_draft_from_result() {
+        let result = ExecutionResult {
+            success: true,
+            output: "Pushed the branch.\n```json\n{\"title\": \"Add JWT auth\", \"body\": \"## Summary\\nAdds login\"}\n```".to_string(),
+            ..ExecutionResult::default()
+        };
+
+        let draft = PullRequestDraft::from_result(&result).unwrap();
+
+        assert_eq!(draft.title, "Add JWT auth");
+        assert!(draft.body.starts_with("## Summary"));
+    }
+
+    #[test]
+    fn test_draft_rejects_empty_title() {
+        let result = ExecutionResult {
+            output: r#"{"title": " ", "body": "x"}"#.to_string(),
+            ..ExecutionResult::default()
+        };
+
+        assert!(matches!(
+            PullRequestDraft::from_result(&result),
+            Err(CoreError::InvalidAgentOutput(_))
+        ));
+    }
+}
@@ -0,0 +1,149 @@
+//! Streaming progress events emitted while the agent runs.
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::state::{ExecutionStats, FeatureStatus};
+
+/// Event emitted while a request is being executed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// The agent sent an assistant message (1-based turn count so far)
+    Turn(u32),
+    /// Assistant text as it arrives
+    Text(String),
+    /// The agent invoked a tool
+    ToolUse {
+        /// Tool name, e.g. `Edit`
+        name: String,
+        /// What the tool acts on (file, command or pattern), if known
+        target: Option<String>,
+    },
+}
+
+/// Sender half used to subscribe to progress events
+pub type ProgressSender = UnboundedSender<ProgressEvent>;
+
+/// Sender half used to follow a feature run, e.g. from the TUI
+pub type RunEventSender = UnboundedSender<RunEvent>;
+
+/// Machine-readable event of a feature run, written as one JSON object
+/// per line by `gba run --json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "event",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+pub enum RunEvent {
+    /// The run is about to execute `phases`, in order
+    RunStarted {
+        /// Feature directory name (`{id}_{slug}`)
+        feature: String,
+        /// Phases that will run
+        phases: Vec<String>,
+    },
+    /// A phase started
+    PhaseStarted {
+        /// Phase name
+        phase: String,
+    },
+    /// A phase was skipped without running
+    PhaseSkipped {
+        /// Phase name
+        phase: String,
+        /// Why it was skipped
+        reason: String,
+    },
+    /// A chunk of assistant text
+    AssistantText {
+        /// Phase name
+        phase: String,
+        /// Text as streamed by the agent
+        text: String,
+    },
+    /// A phase completed successfully
+    PhaseCompleted {
+        /// Phase name
+        phase: String,
+        /// Statistics of the phase
+        stats: ExecutionStats,
+    },
+    /// A phase failed or was interrupted
+    PhaseFailed {
+        /// Phase name
+        phase: String,
+        /// What went wrong
+        error: String,
+    },
+    /// The run finished without a failure
+    RunCompleted {
+        /// Feature directory name (`{id}_{slug}`)
+        feature: String,
+        /// Feature status after the run
+        status: FeatureStatus,
+        /// Statistics accumulated across all phases
+        stats: ExecutionStats,
+    },
+}
+
+impl RunEvent {
+    /// Serialize as a single JSON line (without the newline)
+    pub fn to_json_line(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_event_json_round_trip() {
+        let events = [
+            RunEvent::RunStarted {
+                feature: "0001_auth".to_string(),
+                phases: vec!["build".to_string()],
+            },
+            RunEvent::AssistantText {
+                phase: "build".to_string(),
+                text: "Reading src/".to_string(),
+            },
+            RunEvent::PhaseCompleted {
+                phase: "build".to_string(),
+                stats: ExecutionStats {
+                    turns: 2,
+                    cost_usd: 0.25,
+                    ..ExecutionStats::default()
+                },
+            },
+            RunEvent::RunCompleted {
+                feature: "0001_auth".to_string(),
+                status: FeatureStatus::Completed,
+                stats: ExecutionStats::default(),
+            },
+        ];
+        let lines: Vec<String> = events.iter().map(RunEvent::to_json_line).collect();
+
+        assert_eq!(
+            lines[0],
+            r#"{"event":"run_started","feature":"0001_auth","phases":["build"]}"#
+        );
+        assert_eq!(
+            lines[1],
+            r#"{"event":"assistant_text","phase":"build","text":"Reading src/"}"#
+        );
+        assert_eq!(
+            lines[2],
+            r#"{"event":"phase_completed","phase":"build","stats":{"turns":2,"inputTokens":0,"outputTokens":0,"costUsd":0.25}}"#
+        );
+        assert!(
+            lines[3].starts_with(
+                r#"{"event":"run_completed","feature":"0001_auth","status":"completed""#
+            )
+        );
+        for (event, line) in events.iter().zip(&lines) {
+            assert_eq!(&serde_json::from_str::<RunEvent>(line).unwrap(), event);
+        }
+    }
+}
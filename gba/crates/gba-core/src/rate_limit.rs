@@ -0,0 +1,312 @@
+//! Pacing of agent requests shared by every clone of an [`Engine`].
+//!
+//! Requests wait for a free execution slot (`rateLimit.maxConcurrentExecutions`)
+//! and then for a token of a bucket refilled at `rateLimit.requestsPerMinute`.
+//! A rate-limited response halves the refill rate for a minute and is
+//! retried with exponential backoff.
+//!
+//! [`Engine`]: crate::Engine
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::time::Instant;
+
+use crate::config::RateLimitConfig;
+use crate::error::CoreError;
+
+/// How long the refill rate stays lowered after a rate-limited response
+pub const THROTTLE_DURATION: Duration = Duration::from_secs(60);
+
+/// Delay before the first retry of a rate-limited request; doubled after
+/// every further attempt
+pub const BACKOFF_BASE: Duration = Duration::from_secs(2);
+
+/// Snapshot of the limiter, for status output
+#[derive(Debug, Clone, PartialEq)]
+pub struct LimiterState {
+    /// Configured requests per minute (None = unlimited)
+    pub requests_per_minute: Option<u32>,
+    /// Requests per minute currently allowed, lower while throttled
+    pub effective_per_minute: Option<f64>,
+    /// Requests that may start right away
+    pub available_tokens: Option<f64>,
+    /// Requests currently executing
+    pub in_flight: usize,
+    /// Configured maximum of concurrent requests (None = unlimited)
+    pub max_concurrent: Option<usize>,
+    /// Time left until a rate-limited response stops lowering the rate
+    pub throttled_for: Option<Duration>,
+}
+
+impl fmt::Display for LimiterState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.requests_per_minute, self.effective_per_minute) {
+            (Some(configured), Some(effective)) => {
+                write!(f, "{effective:.0}/{configured} requests per minute")?;
+            }
+            _ => write!(f, "no request rate limit")?,
+        }
+        match self.max_concurrent {
+            Some(max) => write!(f, ", {}/{max} running", self.in_flight)?,
+            None => write!(f, ", {} running", self.in_flight)?,
+        }
+        if let Some(left) = self.throttled_for {
+            write!(f, ", throttled for {}s", left.as_secs())?;
+        }
+        Ok(())
+    }
+}
+
+/// Token bucket refilled at a per-minute rate
+#[derive(Debug)]
+struct Bucket {
+    /// Configured requests per minute
+    per_minute: f64,
+    /// Tokens available now, at most `per_minute`
+    tokens: f64,
+    /// When `tokens` was last refilled
+    refilled: Instant,
+    /// Until when the rate is halved
+    throttled_until: Option<Instant>,
+}
+
+impl Bucket {
+    fn new(per_minute: u32) -> Self {
+        let per_minute = f64::from(per_minute.max(1));
+        Self {
+            per_minute,
+            tokens: per_minute,
+            refilled: Instant::now(),
+            throttled_until: None,
+        }
+    }
+
+    /// Requests per minute allowed at `now`
+    fn rate(&self, now: Instant) -> f64 {
+        match self.throttled_until {
+            Some(until) if until > now => self.per_minute / 2.0,
+            _ => self.per_minute,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate(now) / 60.0).min(self.per_minute);
+        self.refilled = now;
+    }
+
+    /// Take a token, or return how long to wait for one
+    fn take(&mut self, now: Instant) -> Option<Duration> {
+        self.refill(now);
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            None
+        } else {
+            let missing = 1.0 - self.tokens;
+            Some(Duration::from_secs_f64(missing * 60.0 / self.rate(now)))
+        }
+    }
+}
+
+/// Limits how many agent requests run at once and how often they start
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    slots: Option<Semaphore>,
+    bucket: Option<Mutex<Bucket>>,
+    running: AtomicUsize,
+}
+
+impl RateLimiter {
+    /// Limiter enforcing `config`
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            config: config.clone(),
+            slots: config
+                .max_concurrent_executions
+                .map(|max| Semaphore::new(max.max(1))),
+            bucket: config
+                .requests_per_minute
+                .map(|rpm| Mutex::new(Bucket::new(rpm))),
+            running: AtomicUsize::new(0),
+        }
+    }
+
+    /// Wait for an execution slot and a token.
+    ///
+    /// The request may run while the returned permit is held.
+    pub async fn acquire(&self) -> RatePermit<'_> {
+        let slot = match &self.slots {
+            // The semaphore is never closed.
+            Some(slots) => slots.acquire().await.ok(),
+            None => None,
+        };
+        if let Some(bucket) = &self.bucket {
+            loop {
+                let wait = bucket.lock().take(Instant::now());
+                match wait {
+                    Some(wait) => {
+                        tracing::debug!(?wait, "waiting for the request rate limit");
+                        tokio::time::sleep(wait).await;
+                    }
+                    None => break,
+                }
+            }
+        }
+        self.running.fetch_add(1, Ordering::SeqCst);
+        RatePermit {
+            running: &self.running,
+            _slot: slot,
+        }
+    }
+
+    /// Lower the rate after a rate-limited response
+    pub fn throttle(&self) {
+        if let Some(bucket) = &self.bucket {
+            let now = Instant::now();
+            let mut bucket = bucket.lock();
+            bucket.refill(now);
+            bucket.tokens = 0.0;
+            bucket.throttled_until = Some(now + THROTTLE_DURATION);
+        }
+    }
+
+    /// How many times a rate-limited request is retried
+    pub fn max_retries(&self) -> u32 {
+        self.config.max_retries
+    }
+
+    /// Current state of the limiter
+    pub fn state(&self) -> LimiterState {
+        let now = Instant::now();
+        let bucket = self.bucket.as_ref().map(|bucket| {
+            let mut bucket = bucket.lock();
+            bucket.refill(now);
+            let throttled = bucket
+                .throttled_until
+                .and_then(|until| until.checked_duration_since(now))
+                .filter(|left| !left.is_zero());
+            (bucket.rate(now), bucket.tokens, throttled)
+        });
+        let max_concurrent = self.config.max_concurrent_executions;
+        LimiterState {
+            requests_per_minute: self.config.requests_per_minute,
+            effective_per_minute: bucket.map(|b| b.0),
+            available_tokens: bucket.map(|b| b.1),
+            in_flight: self.running.load(Ordering::SeqCst),
+            max_concurrent,
+            throttled_for: bucket.and_then(|b| b.2),
+        }
+    }
+}
+
+/// Permission to run one request, releasing its slot when dropped
+#[derive(Debug)]
+pub struct RatePermit<'a> {
+    running: &'a AtomicUsize,
+    _slot: Option<SemaphorePermit<'a>>,
+}
+
+impl Drop for RatePermit<'_> {
+    fn drop(&mut self) {
+        self.running.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Whether `error` says the API rejected the request for its rate
+pub fn is_rate_limited(error: &CoreError) -> bool {
+    let CoreError::AgentExecutionFailed(message) = error else {
+        return false;
+    };
+    let message = message.to_lowercase();
+    ["429", "rate limit", "rate_limit", "too many requests"]
+        .iter()
+        .any(|needle| message.contains(needle))
+}
+
+/// Delay before retry number `attempt` (starting at 1)
+pub fn backoff(attempt: u32) -> Duration {
+    BACKOFF_BASE * 2u32.saturating_pow(attempt.saturating_sub(1)).min(64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    fn limiter(rpm: Option<u32>, concurrent: Option<usize>) -> Arc<RateLimiter> {
+        Arc::new(RateLimiter::new(&RateLimitConfig {
+            requests_per_minute: rpm,
+            max_concurrent_executions: concurrent,
+            ..RateLimitConfig::default()
+        }))
+    }
+
+    #[tokio::test]
+    async fn test_bucket_paces_requests() {
+        tokio::time::pause();
+        let limiter = limiter(Some(2), None);
+        let start = Instant::now();
+
+        // The bucket starts full, then refills one token per 30 seconds.
+        limiter.acquire().await;
+        limiter.acquire().await;
+        assert!(start.elapsed() < Duration::from_secs(1));
+        limiter.acquire().await;
+        assert!(start.elapsed() >= Duration::from_secs(30));
+
+        limiter.throttle();
+        let throttled = Instant::now();
+        let state = limiter.state();
+        assert_eq!(state.effective_per_minute, Some(1.0));
+        assert_eq!(state.throttled_for, Some(THROTTLE_DURATION));
+        limiter.acquire().await;
+        assert!(throttled.elapsed() >= Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_requests_queue() {
+        tokio::time::pause();
+        let limiter = limiter(None, Some(1));
+
+        let first = limiter.acquire().await;
+        assert_eq!(limiter.state().in_flight, 1);
+        assert_eq!(
+            limiter.state().to_string(),
+            "no request rate limit, 1/1 running"
+        );
+
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move {
+                let _permit = limiter.acquire().await;
+            }
+        });
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        assert!(!waiting.is_finished());
+
+        drop(first);
+        waiting.await.unwrap();
+        assert_eq!(limiter.state().in_flight, 0);
+    }
+
+    #[test]
+    fn test_rate_limited_errors_and_backoff() {
+        let limited = CoreError::AgentExecutionFailed(
+            "API Error: 429 {\"type\":\"rate_limit_error\"}".to_string(),
+        );
+        assert!(is_rate_limited(&limited));
+        assert!(!is_rate_limited(&CoreError::AgentExecutionFailed(
+            "invalid API key".to_string()
+        )));
+        assert!(!is_rate_limited(&CoreError::ConfigError("429".to_string())));
+
+        assert_eq!(backoff(1), Duration::from_secs(2));
+        assert_eq!(backoff(3), Duration::from_secs(8));
+        assert_eq!(backoff(40), Duration::from_secs(128));
+    }
+}
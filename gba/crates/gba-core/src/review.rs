@@ -0,0 +1,328 @@
+//! Structured findings of the review phase, written to `docs/review.md`.
+//!
+//! The review phase asks the agent for its findings in a fenced JSON block.
+//! They are rendered as markdown grouped by severity, and their counts are
+//! stored in the review `PhaseState`. With `review.failOnBlocker` a blocker
+//! fails the phase, and the blockers become the note fed back to the agent
+//! when the phase is retried.
+
+use std::fmt;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::execution::extract_json;
+use crate::state::{PhaseState, PhaseStatus};
+
+/// Name of the phase whose findings are recorded
+pub const REVIEW_PHASE: &str = "review";
+
+/// Location of the review report relative to the feature directory
+pub const REVIEW_FILE: &str = "docs/review.md";
+
+/// Schema of the agent's findings
+pub const REVIEW_SCHEMA: &str = r#"{"findings": [{"severity": "blocker | major | minor | nit", "file": "path/to/file.rs:42", "description": "what is wrong and how to fix it"}]}"#;
+
+/// Review settings (`review:` in `prompts/review/config.yml`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReviewConfig {
+    /// Fail the review phase if any finding is a blocker
+    pub fail_on_blocker: bool,
+}
+
+/// How serious a finding is, most serious first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Must be fixed before merging
+    Blocker,
+    /// Should be fixed
+    Major,
+    /// Worth fixing
+    Minor,
+    /// Style or taste
+    Nit,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Blocker => "blocker",
+            Self::Major => "major",
+            Self::Minor => "minor",
+            Self::Nit => "nit",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// A single review finding
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Finding {
+    /// How serious it is
+    pub severity: Severity,
+    /// File (and line) it refers to; empty for general findings
+    #[serde(default)]
+    pub file: String,
+    /// What is wrong
+    pub description: String,
+}
+
+/// Finding counts stored in `PhaseState`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewSummary {
+    /// Number of findings
+    pub findings: usize,
+    /// Number of blocker findings
+    pub blockers: usize,
+}
+
+impl ReviewSummary {
+    /// Count `findings`
+    pub fn new(findings: &[Finding]) -> Self {
+        Self {
+            findings: findings.len(),
+            blockers: findings
+                .iter()
+                .filter(|f| f.severity == Severity::Blocker)
+                .count(),
+        }
+    }
+}
+
+/// Result of recording a review
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReviewOutcome {
+    /// Status the review phase should end with
+    pub status: PhaseStatus,
+    /// Finding counts
+    pub summary: ReviewSummary,
+    /// Findings, most serious first
+    pub findings: Vec<Finding>,
+}
+
+impl ReviewOutcome {
+    /// Record the finding counts on the review phase state
+    pub fn apply_to(&self, phase: &mut PhaseState) {
+        phase.review = Some(self.summary.clone());
+    }
+
+    /// Why the phase failed, if it did
+    pub fn error(&self) -> Option<String> {
+        (self.status == PhaseStatus::Failed).then(|| {
+            format!(
+                "Review found {} blocker finding(s), see {REVIEW_FILE}",
+                self.summary.blockers
+            )
+        })
+    }
+
+    /// Output summary of the phase: the counts, and the blockers to fix when
+    /// it failed, which is what a retry passes back to the agent
+    pub fn output_summary(&self) -> String {
+        let mut out = format!(
+            "{} finding(s), {} blocker(s)",
+            self.summary.findings, self.summary.blockers
+        );
+        if self.status == PhaseStatus::Failed {
+            out.push_str("\n\nFix these blocker findings before reviewing again:\n");
+            for finding in self
+                .findings
+                .iter()
+                .filter(|f| f.severity == Severity::Blocker)
+            {
+                out.push_str(&format!("- {}\n", describe(finding)));
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AgentReport {
+    findings: Vec<Finding>,
+}
+
+/// Instructions appended to the review prompt
+pub fn build_prompt() -> String {
+    "## Review Findings\n\nReport every problem you find as a finding with a severity: \
+     `blocker` (must be fixed before merging: bugs, data loss, security issues, failing \
+     builds), `major`, `minor` or `nit`. Report an empty list if there is nothing to fix.\n"
+        .to_string()
+}
+
+/// Parse the agent's findings, most serious first.
+///
+/// # Errors
+///
+/// Returns `CoreError::InvalidAgentOutput` if no JSON is found or it
+/// doesn't match the expected shape.
+pub fn parse_findings(output: &str) -> Result<Vec<Finding>> {
+    let mut findings = extract_json::<AgentReport>(output)?.findings;
+    findings.sort_by_key(|f| f.severity);
+    Ok(findings)
+}
+
+/// Markdown report of `findings`, grouped by severity
+pub fn render_markdown(feature: &str, findings: &[Finding]) -> String {
+    let summary = ReviewSummary::new(findings);
+    let mut out = format!("# Review: {feature}\n\n");
+    if findings.is_empty() {
+        out.push_str("No findings.\n");
+        return out;
+    }
+    out.push_str(&format!(
+        "{} finding(s), {} blocker(s).\n",
+        summary.findings, summary.blockers
+    ));
+    for severity in [
+        Severity::Blocker,
+        Severity::Major,
+        Severity::Minor,
+        Severity::Nit,
+    ] {
+        let group: Vec<_> = findings.iter().filter(|f| f.severity == severity).collect();
+        if group.is_empty() {
+            continue;
+        }
+        let mut title = severity.to_string();
+        title[..1].make_ascii_uppercase();
+        out.push_str(&format!("\n## {title}\n\n"));
+        for finding in group {
+            out.push_str(&format!("- {}\n", describe(finding)));
+        }
+    }
+    out
+}
+
+fn describe(finding: &Finding) -> String {
+    let description = finding.description.trim();
+    match finding.file.trim() {
+        "" => description.to_string(),
+        file => format!("`{file}`: {description}"),
+    }
+}
+
+/// Parse the review phase's `output`, write `docs/review.md` in the feature
+/// directory and decide the phase status.
+///
+/// # Errors
+///
+/// Returns `CoreError::InvalidAgentOutput` if the findings can't be parsed,
+/// or an error if the report can't be written.
+pub fn record(
+    feature_path: &Path,
+    feature: &str,
+    output: &str,
+    config: &ReviewConfig,
+) -> Result<ReviewOutcome> {
+    let findings = parse_findings(output)?;
+    let path = feature_path.join(REVIEW_FILE);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, render_markdown(feature, &findings))?;
+
+    let summary = ReviewSummary::new(&findings);
+    let status = if summary.blockers > 0 && config.fail_on_blocker {
+        PhaseStatus::Failed
+    } else {
+        PhaseStatus::Completed
+    };
+    Ok(ReviewOutcome {
+        status,
+        summary,
+        findings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CoreError;
+
+    const OUTPUT: &str = r#"I reviewed the login change.
+
+```json
+{"findings": [
+  {"severity": "nit", "file": "src/auth.rs:12", "description": "Rename `tok` to `token`."},
+  {"severity": "blocker", "file": "src/auth.rs:40", "description": "Passwords are compared in plain text."},
+  {"severity": "minor", "description": "The README doesn't mention login."}
+]}
+```"#;
+
+    #[test]
+    fn test_parse_findings_sorts_by_severity() {
+        let findings = parse_findings(OUTPUT).unwrap();
+
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings[0].severity, Severity::Blocker);
+        assert_eq!(findings[1].file, "");
+        assert_eq!(findings[2].severity, Severity::Nit);
+        assert_eq!(
+            ReviewSummary::new(&findings),
+            ReviewSummary {
+                findings: 3,
+                blockers: 1
+            }
+        );
+
+        assert!(matches!(
+            parse_findings("Looks good to me!"),
+            Err(CoreError::InvalidAgentOutput(_))
+        ));
+        assert!(
+            parse_findings(r#"{"findings": [{"severity": "fatal", "description": "x"}]}"#).is_err()
+        );
+        assert!(parse_findings(r#"{"findings": []}"#).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let findings = parse_findings(OUTPUT).unwrap();
+
+        let markdown = render_markdown("0001_auth", &findings);
+
+        assert_eq!(
+            markdown,
+            "# Review: 0001_auth\n\n3 finding(s), 1 blocker(s).\n\n## Blocker\n\n\
+             - `src/auth.rs:40`: Passwords are compared in plain text.\n\n## Minor\n\n\
+             - The README doesn't mention login.\n\n## Nit\n\n\
+             - `src/auth.rs:12`: Rename `tok` to `token`.\n"
+        );
+        assert_eq!(
+            render_markdown("0001_auth", &[]),
+            "# Review: 0001_auth\n\nNo findings.\n"
+        );
+    }
+
+    #[test]
+    fn test_record_fails_on_blocker() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = ReviewConfig {
+            fail_on_blocker: true,
+        };
+
+        let outcome = record(dir.path(), "0001_auth", OUTPUT, &config).unwrap();
+
+        assert_eq!(outcome.status, PhaseStatus::Failed);
+        assert!(dir.path().join(REVIEW_FILE).is_file());
+        assert!(outcome.error().unwrap().contains("1 blocker finding(s)"));
+        assert!(
+            outcome
+                .output_summary()
+                .contains("- `src/auth.rs:40`: Passwords are compared in plain text.")
+        );
+        let mut phase = PhaseState::new(REVIEW_PHASE);
+        outcome.apply_to(&mut phase);
+        assert_eq!(phase.review.unwrap().blockers, 1);
+
+        let outcome = record(dir.path(), "0001_auth", OUTPUT, &ReviewConfig::default()).unwrap();
+        assert_eq!(outcome.status, PhaseStatus::Completed);
+        assert_eq!(outcome.error(), None);
+        assert_eq!(outcome.output_summary(), "3 finding(s), 1 blocker(s)");
+    }
+}
@@ -0,0 +1,124 @@
+//! Making agent output safe to store and print as plain text.
+//!
+//! Tool results echoed by the agent carry terminal escape sequences (colors,
+//! cursor movement, OSC-8 hyperlinks) and stray control characters. They
+//! garble `gba status` and can make `state.yml` invalid YAML, so summaries,
+//! errors and plain-text logs are sanitized; JSON events keep the raw text.
+
+/// Escape
+const ESC: char = '\u{1b}';
+/// Bell, one of the terminators of an OSC sequence
+const BEL: char = '\u{7}';
+/// 8-bit control sequence introducer
+const CSI_8BIT: char = '\u{9b}';
+/// 8-bit operating system command
+const OSC_8BIT: char = '\u{9d}';
+/// 8-bit string terminator
+const ST_8BIT: char = '\u{9c}';
+
+/// `text` without ANSI escape sequences and control characters.
+///
+/// CSI sequences (colors, cursor movement) and OSC sequences (titles,
+/// hyperlinks; their link text is kept) are removed. Carriage returns become
+/// line breaks, and other control characters except newline and tab become
+/// U+FFFD.
+pub fn sanitize(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            ESC => match chars.next() {
+                Some('[') => skip_csi(&mut chars),
+                Some(']') => skip_osc(&mut chars),
+                // Other escapes: intermediate bytes, then one final byte.
+                Some(' '..='/') => {
+                    while chars.next_if(|c| matches!(c, ' '..='/')).is_some() {}
+                    chars.next();
+                }
+                Some(_) | None => {}
+            },
+            CSI_8BIT => skip_csi(&mut chars),
+            OSC_8BIT => skip_osc(&mut chars),
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                out.push('\n');
+            }
+            '\n' | '\t' => out.push(c),
+            c if c.is_control() => out.push(char::REPLACEMENT_CHARACTER),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Skip the rest of a CSI sequence: parameter and intermediate bytes, then
+/// the final byte
+fn skip_csi(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while chars
+        .next_if(|c| matches!(c, '0'..='?' | ' '..='/'))
+        .is_some()
+    {}
+    chars.next_if(|c| matches!(c, '@'..='~'));
+}
+
+/// Skip the rest of an OSC sequence, up to BEL or the string terminator
+fn skip_osc(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    while let Some(c) = chars.next() {
+        match c {
+            BEL | ST_8BIT => return,
+            ESC => {
+                chars.next_if_eq(&'\\');
+                return;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize_strips_colors_and_cursor_movement() {
+        // `cargo test` output with colors, and a progress bar redraw.
+        let colored = "test auth::login ... \u{1b}[32mok\u{1b}[0m\n\u{1b}[1m\u{1b}[91merror\u{1b}[0m: 1 failed";
+        assert_eq!(
+            sanitize(colored),
+            "test auth::login ... ok\nerror: 1 failed"
+        );
+
+        let redraw = "\u{1b}[2K\u{1b}[1G  Compiling gba v0.1.0\r\n\u{1b}[?25hdone";
+        assert_eq!(sanitize(redraw), "  Compiling gba v0.1.0\ndone");
+        assert_eq!(sanitize("\u{1b}(Bplain\u{1b}7"), "plain");
+        assert_eq!(sanitize("\u{9b}31mred"), "red");
+    }
+
+    #[test]
+    fn test_sanitize_strips_osc_hyperlinks() {
+        // OSC-8 hyperlinks as printed by `ls --hyperlink` and `gh`, ended
+        // with ST and with BEL.
+        let st = "see \u{1b}]8;;file:///repo/src/lib.rs\u{1b}\\src/lib.rs\u{1b}]8;;\u{1b}\\ now";
+        assert_eq!(sanitize(st), "see src/lib.rs now");
+        let bel = "\u{1b}]8;id=1;https://github.com/o/r/pull/7\u{7}#7\u{1b}]8;;\u{7}";
+        assert_eq!(sanitize(bel), "#7");
+        let title = "\u{1b}]0;cargo build\u{7}Finished";
+        assert_eq!(sanitize(title), "Finished");
+    }
+
+    #[test]
+    fn test_sanitize_replaces_control_characters() {
+        assert_eq!(sanitize("a\tb\nc\rd"), "a\tb\nc\nd");
+        assert_eq!(
+            sanitize("bell\u{7} nul\0 del\u{7f}"),
+            "bell\u{fffd} nul\u{fffd} del\u{fffd}"
+        );
+        assert_eq!(sanitize("héllo 世界"), "héllo 世界");
+        assert_eq!(sanitize("cut off \u{1b}["), "cut off ");
+
+        // What used to end up in state.yml now round-trips as YAML.
+        let summary = sanitize("done\u{1b}[0m\u{8}\u{1}");
+        let yaml = serde_yaml::to_string(&summary).unwrap();
+        assert_eq!(serde_yaml::from_str::<String>(&yaml).unwrap(), summary);
+    }
+}
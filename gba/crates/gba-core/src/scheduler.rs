@@ -0,0 +1,144 @@
+//! Concurrent execution of a phase dependency graph.
+
+use std::future::Future;
+
+use tokio::task::JoinSet;
+
+use crate::error::{CoreError, Result};
+use crate::execution::ExecutionResult;
+use crate::phase::{DependencyGraph, Phase, dependency_order};
+
+/// Run `phases` with `execute`, starting each as soon as its dependencies
+/// have completed and keeping at most `max_parallel` running at once.
+///
+/// Results are returned in completion order. The first error or
+/// unsuccessful result aborts the phases still running.
+pub(crate) async fn run_concurrent<F, Fut>(
+    phases: Vec<Phase>,
+    max_parallel: usize,
+    execute: F,
+) -> Result<Vec<(String, ExecutionResult)>>
+where
+    F: Fn(Phase) -> Fut,
+    Fut: Future<Output = Result<ExecutionResult>> + Send + 'static,
+{
+    let edges = || {
+        phases
+            .iter()
+            .map(|p| (p.name.as_str(), p.depends_on.as_slice()))
+    };
+    // Rejects cycles before anything is started.
+    dependency_order(edges())?;
+    let mut graph = DependencyGraph::new(edges())?;
+
+    let names: Vec<String> = phases.iter().map(|p| p.name.clone()).collect();
+    let mut pending: Vec<Option<Phase>> = phases.into_iter().map(Some).collect();
+    let mut ready: Vec<usize> = graph.roots().collect();
+    let mut running = JoinSet::new();
+    let mut results = Vec::with_capacity(names.len());
+
+    loop {
+        while running.len() < max_parallel.max(1) && !ready.is_empty() {
+            let idx = ready.remove(0);
+            let Some(phase) = pending[idx].take() else {
+                continue;
+            };
+            tracing::info!("Executing phase {}", phase.name);
+            let execution = execute(phase);
+            running.spawn(async move { (idx, execution.await) });
+        }
+
+        // Dropping `running` on an early return aborts the remaining phases.
+        let Some(joined) = running.join_next().await else {
+            break;
+        };
+        let (idx, result) = joined
+            .map_err(|e| CoreError::AgentExecutionFailed(format!("phase task failed: {e}")))?;
+        let result = result?;
+        if !result.success {
+            return Err(CoreError::AgentExecutionFailed(format!(
+                "Phase {} failed",
+                names[idx]
+            )));
+        }
+        results.push((names[idx].clone(), result));
+        ready.extend(graph.complete(idx));
+        ready.sort_unstable();
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use super::*;
+
+    fn phase(name: &str, depends_on: &[&str]) -> Phase {
+        Phase {
+            name: name.to_string(),
+            depends_on: depends_on.iter().map(|s| s.to_string()).collect(),
+            ..Phase::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_independent_phases_run_concurrently() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let phases = vec![
+            phase("docs", &[]),
+            phase("test", &[]),
+            phase("review", &["docs", "test"]),
+        ];
+
+        let results = run_concurrent(phases, 2, |phase| {
+            let (running, peak) = (running.clone(), peak.clone());
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(ExecutionResult {
+                    success: true,
+                    output: phase.name,
+                    ..ExecutionResult::default()
+                })
+            }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+        let names: Vec<&str> = results.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(names.len(), 3);
+        assert!(names[..2].contains(&"docs") && names[..2].contains(&"test"));
+        assert_eq!(names[2], "review");
+        assert_eq!(results[2].1.output, "review");
+    }
+
+    #[tokio::test]
+    async fn test_first_failure_stops_the_graph() {
+        let started = Arc::new(AtomicUsize::new(0));
+        let phases = vec![phase("build", &[]), phase("test", &["build"])];
+
+        let result = run_concurrent(phases, 4, |phase| {
+            started.fetch_add(1, Ordering::SeqCst);
+            async move {
+                Ok(ExecutionResult {
+                    success: phase.name != "build",
+                    ..ExecutionResult::default()
+                })
+            }
+        })
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(CoreError::AgentExecutionFailed(msg)) if msg == "Phase build failed"
+        ));
+        assert_eq!(started.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,1465 @@
+//! Feature execution state persisted in `.gba/features/{id}_{slug}/state.yml`.
+
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::config::SpecsConfig;
+use crate::error::{CoreError, Result};
+use crate::execution::ExecutionResult;
+use crate::hooks::HookRecord;
+use crate::review::ReviewSummary;
+use crate::sanitize::sanitize;
+use crate::testing::{TestRun, TestSummary};
+use crate::verification::{VERIFICATION_FILE, VerificationSummary};
+
+/// Current state file format version
+pub const STATE_VERSION: &str = "0.1.0";
+
+/// State file name inside a feature directory
+pub const STATE_FILE: &str = "state.yml";
+
+/// Design document of a feature, relative to its directory
+pub const DESIGN_FILE: &str = "specs/design.md";
+
+/// Features directory inside `.gba`
+pub const FEATURES_DIR: &str = "features";
+
+/// Archived features directory inside `.gba`
+pub const ARCHIVE_DIR: &str = "archive";
+
+/// Number of events kept in `state.yml` unless configured otherwise
+pub const DEFAULT_EVENT_LIMIT: usize = 200;
+
+/// Features found in a features directory
+#[derive(Debug, Default)]
+pub struct FeatureListing {
+    /// Loaded features as `(directory name, state)`, sorted by ID
+    pub loaded: Vec<(String, FeatureState)>,
+    /// Feature directories whose state failed to load, sorted by name
+    pub failed: Vec<(String, CoreError)>,
+}
+
+/// Execution state of a feature
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureState {
+    /// State file format version
+    pub version: String,
+    /// Feature identification
+    pub feature: FeatureInfo,
+    /// Overall status
+    pub status: FeatureStatus,
+    /// Index of the current phase (0-based)
+    #[serde(default)]
+    pub current_phase: usize,
+    /// Git worktree information
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitInfo>,
+    /// Phase execution history
+    #[serde(default)]
+    pub phases: Vec<PhaseState>,
+    /// Statistics accumulated across all phases
+    #[serde(default)]
+    pub total_stats: ExecutionStats,
+    /// Execution timing
+    #[serde(default)]
+    pub execution: ExecutionTiming,
+    /// Where to pick up after an interrupted run
+    #[serde(default)]
+    pub resume: ResumeInfo,
+    /// Error message if the feature failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Pull request opened for the feature
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<PullRequestInfo>,
+    /// Audit trail of what happened to the feature, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<StateEvent>,
+    /// Maximum number of events kept; older ones are dropped first
+    #[serde(skip, default = "default_event_limit")]
+    event_limit: usize,
+    /// Unknown fields (e.g. written by a newer gba), preserved on save
+    #[serde(flatten)]
+    pub extra: serde_yaml::Mapping,
+}
+
+/// Feature identification
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeatureInfo {
+    /// Sequential ID (e.g. "0001")
+    pub id: String,
+    /// Feature slug
+    pub slug: String,
+    /// Creation time
+    pub created_at: DateTime<Utc>,
+    /// Last update time
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Overall feature status
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureStatus {
+    /// Planned but not started
+    #[default]
+    Planned,
+    /// Phases are being executed
+    InProgress,
+    /// All phases completed
+    Completed,
+    /// A phase failed
+    Failed,
+}
+
+/// Git worktree information of a feature
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitInfo {
+    /// Worktree path (relative to the repository root)
+    pub worktree_path: PathBuf,
+    /// Feature branch
+    pub branch: String,
+    /// Branch the feature started from
+    pub base_branch: String,
+    /// Commit the feature started from
+    pub base_commit: String,
+}
+
+/// Pull request opened by the PR phase
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PullRequestInfo {
+    /// Pull request URL
+    pub url: String,
+    /// Pull request title
+    pub title: String,
+    /// When the pull request was opened
+    pub created_at: DateTime<Utc>,
+}
+
+/// Execution statistics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionStats {
+    /// Number of agent turns
+    pub turns: u32,
+    /// Input tokens consumed
+    pub input_tokens: u64,
+    /// Output tokens produced
+    pub output_tokens: u64,
+    /// Cost in USD
+    pub cost_usd: f64,
+    /// Time spent running phases, stored when the feature completes
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub wall_clock_seconds: u64,
+    /// Cost in USD of summarizing the output, on top of `cost_usd`
+    #[serde(default, skip_serializing_if = "is_zero_cost")]
+    pub summary_cost_usd: f64,
+    /// Times the agent was asked to fix failing tests
+    #[serde(default, skip_serializing_if = "is_zero_u32")]
+    pub fix_iterations: u32,
+    /// Tool uses denied by the `permissions` policy or the user
+    #[serde(default, skip_serializing_if = "is_zero_u32")]
+    pub denied_tool_uses: u32,
+    /// Oldest history turns dropped to fit the context
+    #[serde(default, skip_serializing_if = "is_zero_u32")]
+    pub history_turns_dropped: u32,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+fn is_zero_u32(n: &u32) -> bool {
+    *n == 0
+}
+
+fn is_zero_cost(cost: &f64) -> bool {
+    *cost == 0.0
+}
+
+impl ExecutionStats {
+    /// Add another set of statistics to this one
+    pub fn accumulate(&mut self, other: &Self) {
+        self.turns += other.turns;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost_usd += other.cost_usd;
+        self.wall_clock_seconds += other.wall_clock_seconds;
+        self.summary_cost_usd += other.summary_cost_usd;
+        self.fix_iterations += other.fix_iterations;
+        self.denied_tool_uses += other.denied_tool_uses;
+        self.history_turns_dropped += other.history_turns_dropped;
+    }
+}
+
+/// Execution timing
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionTiming {
+    /// When execution started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_time: Option<DateTime<Utc>>,
+    /// When execution ended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end_time: Option<DateTime<Utc>>,
+}
+
+impl ExecutionTiming {
+    /// Time from start to end, or to now while still running
+    pub fn duration(&self) -> Option<TimeDelta> {
+        let start = self.start_time?;
+        Some(self.end_time.unwrap_or_else(Utc::now) - start)
+    }
+}
+
+/// Resume information of an interrupted run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResumeInfo {
+    /// Whether the next run can continue where this one stopped
+    #[serde(default)]
+    pub can_resume: bool,
+    /// Last phase that completed before the interruption
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_completed_phase: Option<String>,
+    /// Phase the next run starts with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_phase: Option<String>,
+    /// When the run was interrupted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interrupted_at: Option<DateTime<Utc>>,
+    /// Why the run was interrupted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interrupt_reason: Option<InterruptReason>,
+    /// Unknown fields (e.g. written by a newer gba), preserved on save
+    #[serde(flatten)]
+    pub extra: serde_yaml::Mapping,
+}
+
+/// Entry of the feature's event log
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateEvent {
+    /// When it happened
+    pub at: DateTime<Utc>,
+    /// What happened
+    pub kind: EventKind,
+    /// Phase the event belongs to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phase: Option<String>,
+    /// Details
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+}
+
+/// Kind of a [`StateEvent`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    /// A phase started
+    PhaseStarted,
+    /// A phase completed
+    PhaseCompleted,
+    /// A phase failed
+    PhaseFailed,
+    /// A phase was skipped
+    PhaseSkipped,
+    /// The run was interrupted
+    Interrupted,
+    /// An interrupted run was picked up again
+    Resumed,
+    /// A phase added to the feature's cost
+    CostAdded,
+    /// A commit was created for a phase
+    CommitCreated,
+    /// A pull request was opened for the feature
+    PullRequestCreated,
+    /// The test command ran after a test phase
+    TestsRun,
+    /// The feature was rolled back to the commit of a phase
+    RolledBack,
+    /// All phases completed
+    Completed,
+}
+
+/// Why a run was interrupted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum InterruptReason {
+    /// The user pressed Ctrl-C
+    UserCancelled,
+    /// The run exceeded a time limit
+    Timeout,
+    /// An unexpected error stopped the run
+    Error,
+    /// The process was terminated by the system
+    SystemShutdown,
+}
+
+/// Summary of a completed phase, given to the prompts of later phases
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PreviousOutput {
+    /// Phase name
+    pub phase: String,
+    /// Output summary of the phase
+    pub summary: String,
+    /// Commit created after the phase
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub commit_sha: Option<String>,
+}
+
+/// State of a single phase
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PhaseState {
+    /// Phase name
+    pub name: String,
+    /// Phase status
+    pub status: PhaseStatus,
+    /// When the phase started
+    #[serde(default)]
+    pub started_at: Option<DateTime<Utc>>,
+    /// When the phase completed
+    #[serde(default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Commit created after the phase
+    #[serde(default)]
+    pub commit_sha: Option<String>,
+    /// Short summary of the agent output
+    #[serde(default)]
+    pub output_summary: Option<String>,
+    /// Execution statistics
+    #[serde(default)]
+    pub stats: Option<ExecutionStats>,
+    /// Per-criterion result of a verification phase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub verification: Option<VerificationSummary>,
+    /// Finding counts of a review phase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review: Option<ReviewSummary>,
+    /// Outcome of the test runs after a test phase
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tests: Option<TestSummary>,
+    /// Model the phase ran with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Hooks that ran around the last attempt
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<HookRecord>,
+    /// How often the phase has been started
+    #[serde(default)]
+    pub attempts: u32,
+    /// Unknown fields (e.g. written by a newer gba), preserved on save
+    #[serde(flatten)]
+    pub extra: serde_yaml::Mapping,
+}
+
+impl PhaseState {
+    /// Time the last attempt took, or has taken so far while in progress
+    pub fn duration(&self) -> Option<TimeDelta> {
+        let started = self.started_at?;
+        Some(self.completed_at.unwrap_or_else(Utc::now) - started)
+    }
+
+    /// Create a pending phase
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status: PhaseStatus::Pending,
+            started_at: None,
+            completed_at: None,
+            commit_sha: None,
+            output_summary: None,
+            stats: None,
+            verification: None,
+            review: None,
+            tests: None,
+            model: None,
+            hooks: Vec::new(),
+            attempts: 0,
+            extra: serde_yaml::Mapping::new(),
+        }
+    }
+}
+
+/// Status of a single phase
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhaseStatus {
+    /// Not started
+    #[default]
+    Pending,
+    /// Running
+    InProgress,
+    /// Finished successfully
+    Completed,
+    /// Finished with an error
+    Failed,
+    /// Not run on purpose (`--skip-phase` or `enabled: false`)
+    Skipped,
+}
+
+impl PhaseStatus {
+    /// Icon used when rendering the status
+    pub fn icon(self) -> &'static str {
+        match self {
+            Self::Pending => "○",
+            Self::InProgress => "◐",
+            Self::Completed => "✓",
+            Self::Failed => "✗",
+            Self::Skipped => "⊘",
+        }
+    }
+
+    /// Whether the phase needs no further runs (completed or skipped)
+    pub fn is_done(self) -> bool {
+        matches!(self, Self::Completed | Self::Skipped)
+    }
+}
+
+impl fmt::Display for PhaseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Pending => "pending",
+            Self::InProgress => "in_progress",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+            Self::Skipped => "skipped",
+        };
+        f.write_str(s)
+    }
+}
+
+impl fmt::Display for EventKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::PhaseStarted => "phase_started",
+            Self::PhaseCompleted => "phase_completed",
+            Self::PhaseFailed => "phase_failed",
+            Self::PhaseSkipped => "phase_skipped",
+            Self::Interrupted => "interrupted",
+            Self::Resumed => "resumed",
+            Self::CostAdded => "cost_added",
+            Self::CommitCreated => "commit_created",
+            Self::PullRequestCreated => "pull_request_created",
+            Self::TestsRun => "tests_run",
+            Self::RolledBack => "rolled_back",
+            Self::Completed => "completed",
+        };
+        f.write_str(s)
+    }
+}
+
+impl fmt::Display for InterruptReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::UserCancelled => "cancelled by the user",
+            Self::Timeout => "timed out",
+            Self::Error => "stopped by an error",
+            Self::SystemShutdown => "system shutdown",
+        };
+        f.write_str(s)
+    }
+}
+
+impl fmt::Display for FeatureStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Planned => "planned",
+            Self::InProgress => "in_progress",
+            Self::Completed => "completed",
+            Self::Failed => "failed",
+        };
+        f.write_str(s)
+    }
+}
+
+impl FeatureState {
+    /// Create the state of a freshly planned feature
+    pub fn new(id: impl Into<String>, slug: impl Into<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            version: STATE_VERSION.to_string(),
+            feature: FeatureInfo {
+                id: id.into(),
+                slug: slug.into(),
+                created_at: now,
+                updated_at: now,
+            },
+            status: FeatureStatus::Planned,
+            current_phase: 0,
+            git: None,
+            phases: Vec::new(),
+            total_stats: ExecutionStats::default(),
+            execution: ExecutionTiming::default(),
+            resume: ResumeInfo::default(),
+            error: None,
+            pull_request: None,
+            events: Vec::new(),
+            event_limit: DEFAULT_EVENT_LIMIT,
+            extra: serde_yaml::Mapping::new(),
+        }
+    }
+
+    /// Load state from `state.yml` in the feature directory.
+    ///
+    /// Unknown fields are kept in `extra` and written back by [`Self::save`];
+    /// unknown top-level keys are logged as a warning.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or parsed.
+    pub fn load(feature_path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(feature_path.join(STATE_FILE))?;
+        let state: Self = serde_yaml::from_str(&content)?;
+        if !state.extra.is_empty() {
+            tracing::warn!(
+                "{} has unknown fields (written by a newer gba?): {}",
+                feature_path.join(STATE_FILE).display(),
+                state.unknown_keys().join(", ")
+            );
+        }
+        Ok(state)
+    }
+
+    /// Top-level keys of `state.yml` this version doesn't know
+    pub fn unknown_keys(&self) -> Vec<String> {
+        self.extra
+            .keys()
+            .map(|key| match key.as_str() {
+                Some(key) => key.to_string(),
+                None => serde_yaml::to_string(key)
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string(),
+            })
+            .collect()
+    }
+
+    /// Save state to `state.yml` in the feature directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state cannot be serialized or written.
+    pub fn save(&self, feature_path: &Path) -> Result<()> {
+        let content = serde_yaml::to_string(self)?;
+        std::fs::write(feature_path.join(STATE_FILE), content)?;
+        Ok(())
+    }
+
+    /// Check the state for internal consistency.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::ConfigError` listing every problem found.
+    pub fn validate(&self) -> Result<()> {
+        let mut problems = Vec::new();
+        if self.version != STATE_VERSION {
+            problems.push(format!("unsupported version `{}`", self.version));
+        }
+        if self.feature.id.is_empty() || !self.feature.id.chars().all(|c| c.is_ascii_digit()) {
+            problems.push(format!("invalid feature id `{}`", self.feature.id));
+        }
+        if self.feature.slug.trim().is_empty() {
+            problems.push("feature slug is empty".to_string());
+        }
+        if self.feature.updated_at < self.feature.created_at {
+            problems.push("updatedAt is before createdAt".to_string());
+        }
+        if self.current_phase > self.phases.len() {
+            problems.push(format!(
+                "currentPhase {} is out of range ({} phases)",
+                self.current_phase,
+                self.phases.len()
+            ));
+        }
+        for (idx, phase) in self.phases.iter().enumerate() {
+            if self.phases[..idx].iter().any(|p| p.name == phase.name) {
+                problems.push(format!("phase `{}` is recorded twice", phase.name));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(CoreError::ConfigError(problems.join("; ")))
+        }
+    }
+
+    /// Time spent running phases.
+    ///
+    /// Completed features report the `wallClockSeconds` stored at
+    /// completion; others add up the durations of their phases.
+    pub fn duration(&self) -> Option<TimeDelta> {
+        if self.total_stats.wall_clock_seconds > 0 {
+            return i64::try_from(self.total_stats.wall_clock_seconds)
+                .ok()
+                .map(TimeDelta::seconds);
+        }
+        self.phases
+            .iter()
+            .filter_map(PhaseState::duration)
+            .reduce(|a, b| a + b)
+    }
+
+    /// Directory name of the feature (`{id}_{slug}`)
+    pub fn dir_name(&self) -> String {
+        format!("{}_{}", self.feature.id, self.feature.slug)
+    }
+
+    /// Get a phase by name
+    pub fn phase(&self, name: &str) -> Option<&PhaseState> {
+        self.phases.iter().find(|p| p.name == name)
+    }
+
+    /// Get a phase by name, appending a pending entry if it doesn't exist
+    pub fn phase_mut(&mut self, name: &str) -> &mut PhaseState {
+        let idx = match self.phases.iter().position(|p| p.name == name) {
+            Some(idx) => idx,
+            None => {
+                self.phases.push(PhaseState::new(name));
+                self.phases.len() - 1
+            }
+        };
+        &mut self.phases[idx]
+    }
+
+    /// Mark the phase at `index` as started and the feature as in progress
+    pub fn start_phase(&mut self, index: usize, name: &str) {
+        let now = Utc::now();
+        self.status = FeatureStatus::InProgress;
+        self.current_phase = index;
+        self.error = None;
+        if std::mem::take(&mut self.resume).can_resume {
+            self.record(EventKind::Resumed, Some(name), "");
+        }
+        self.execution.start_time.get_or_insert(now);
+        self.feature.updated_at = now;
+        let phase = self.phase_mut(name);
+        phase.status = PhaseStatus::InProgress;
+        phase.started_at = Some(now);
+        phase.completed_at = None;
+        phase.attempts += 1;
+        let attempt = format!("attempt {}", phase.attempts);
+        self.record(EventKind::PhaseStarted, Some(name), attempt);
+    }
+
+    /// Record a successful phase execution
+    pub fn complete_phase(&mut self, name: &str, result: &ExecutionResult, summary: String) {
+        let now = Utc::now();
+        self.total_stats.accumulate(&result.stats);
+        self.feature.updated_at = now;
+        let phase = self.phase_mut(name);
+        phase.status = PhaseStatus::Completed;
+        phase.completed_at = Some(now);
+        phase.output_summary = Some(sanitize(&summary));
+        phase.stats = Some(result.stats.clone());
+        phase.hooks = result.hooks.clone();
+        if result.model.is_some() {
+            phase.model = result.model.clone();
+        }
+        let mut message = format!("{} turns", result.stats.turns);
+        if result.truncated {
+            message.push_str(&format!(
+                ", output truncated ({} bytes dropped)",
+                result.dropped_bytes
+            ));
+        }
+        self.record(EventKind::PhaseCompleted, Some(name), message);
+        if result.stats.cost_usd > 0.0 {
+            let cost = format!(
+                "${:.2} (total ${:.2})",
+                result.stats.cost_usd, self.total_stats.cost_usd
+            );
+            self.record(EventKind::CostAdded, Some(name), cost);
+        }
+    }
+
+    /// Last phase that failed, if any
+    pub fn last_failed_phase(&self) -> Option<&PhaseState> {
+        self.phases
+            .iter()
+            .filter(|p| p.status == PhaseStatus::Failed)
+            .max_by_key(|p| p.completed_at)
+    }
+
+    /// Summaries of the phases completed before `name`, in the order they
+    /// are recorded; skipped phases and those without a summary are left
+    /// out
+    pub fn previous_outputs(&self, name: &str) -> Vec<PreviousOutput> {
+        self.phases
+            .iter()
+            .take_while(|p| p.name != name)
+            .filter(|p| p.status == PhaseStatus::Completed)
+            .filter_map(|p| {
+                Some(PreviousOutput {
+                    phase: p.name.clone(),
+                    summary: p.output_summary.clone()?,
+                    commit_sha: p.commit_sha.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// Record phase `name` as skipped, with the reason as its summary
+    pub fn skip_phase(&mut self, name: &str, reason: String) {
+        let now = Utc::now();
+        self.status = FeatureStatus::InProgress;
+        self.feature.updated_at = now;
+        let phase = self.phase_mut(name);
+        phase.status = PhaseStatus::Skipped;
+        phase.completed_at = Some(now);
+        phase.output_summary = Some(reason.clone());
+        self.record(EventKind::PhaseSkipped, Some(name), reason);
+    }
+
+    /// Record the commit created after phase `name`
+    pub fn record_commit(&mut self, name: &str, sha: impl Into<String>) {
+        let sha = sha.into();
+        self.phase_mut(name).commit_sha = Some(sha.clone());
+        self.record(EventKind::CommitCreated, Some(name), sha);
+    }
+
+    /// Record the pull request phase `name` opened
+    pub fn record_pull_request(&mut self, name: &str, url: &str, title: &str) {
+        self.pull_request = Some(PullRequestInfo {
+            url: url.to_string(),
+            title: title.to_string(),
+            created_at: Utc::now(),
+        });
+        self.record(EventKind::PullRequestCreated, Some(name), url);
+    }
+
+    /// Roll back to the end of phase `name`: forget the phases recorded
+    /// after it and mark the feature resumable.
+    ///
+    /// Returns the commit recorded for `name`; resetting the worktree to it,
+    /// and naming the `resume.next_phase` in the project's phase order, is
+    /// up to the caller.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::ConfigError` if the feature has no phase `name`
+    /// or it recorded no commit.
+    pub fn rollback_to(&mut self, name: &str) -> Result<String> {
+        let Some(index) = self.phases.iter().position(|p| p.name == name) else {
+            return Err(CoreError::ConfigError(format!(
+                "{} has no phase `{name}`",
+                self.dir_name()
+            )));
+        };
+        let Some(sha) = self.phases[index].commit_sha.clone() else {
+            return Err(CoreError::ConfigError(format!(
+                "phase {name} of {} has no recorded commit to roll back to",
+                self.dir_name()
+            )));
+        };
+
+        let now = Utc::now();
+        self.phases.truncate(index + 1);
+        self.current_phase = index + 1;
+        self.status = FeatureStatus::InProgress;
+        self.error = None;
+        self.feature.updated_at = now;
+        self.execution.end_time = None;
+        self.record(EventKind::RolledBack, Some(name), sha.clone());
+        self.resume = ResumeInfo {
+            can_resume: true,
+            last_completed_phase: Some(name.to_string()),
+            interrupted_at: Some(now),
+            ..ResumeInfo::default()
+        };
+        Ok(sha)
+    }
+
+    /// Record the test runs after phase `name`, one event per run
+    pub fn record_test_runs(&mut self, name: &str, runs: &[TestRun]) {
+        if runs.is_empty() {
+            return;
+        }
+        self.phase_mut(name).tests = Some(TestSummary::new(runs));
+        for (i, run) in runs.iter().enumerate() {
+            self.record(
+                EventKind::TestsRun,
+                Some(name),
+                format!("run {}: {run}", i + 1),
+            );
+        }
+    }
+
+    /// Record an interruption so the next run can resume.
+    ///
+    /// Phases that were running go back to pending; completed phases are
+    /// kept.
+    pub fn mark_for_resume(&mut self, reason: InterruptReason) {
+        let now = Utc::now();
+        self.feature.updated_at = now;
+        for phase in &mut self.phases {
+            if phase.status == PhaseStatus::InProgress {
+                phase.status = PhaseStatus::Pending;
+                phase.started_at = None;
+            }
+        }
+        let name = |p: &PhaseState| p.name.clone();
+        self.record(EventKind::Interrupted, None, reason.to_string());
+        self.resume = ResumeInfo {
+            can_resume: true,
+            last_completed_phase: self
+                .phases
+                .iter()
+                .rev()
+                .find(|p| p.status == PhaseStatus::Completed)
+                .map(name),
+            next_phase: self.phases.iter().find(|p| !p.status.is_done()).map(name),
+            interrupted_at: Some(now),
+            interrupt_reason: Some(reason),
+            ..ResumeInfo::default()
+        };
+    }
+
+    /// Record a failed phase, failing the feature
+    pub fn fail_phase(&mut self, name: &str, error: String, summary: Option<String>) {
+        let now = Utc::now();
+        let error = sanitize(&error);
+        self.status = FeatureStatus::Failed;
+        self.feature.updated_at = now;
+        self.execution.end_time = Some(now);
+        self.record(EventKind::PhaseFailed, Some(name), error.clone());
+        self.error = Some(error);
+        let phase = self.phase_mut(name);
+        phase.status = PhaseStatus::Failed;
+        phase.completed_at = Some(now);
+        if let Some(summary) = summary {
+            phase.output_summary = Some(sanitize(&summary));
+        }
+    }
+
+    /// Mark the feature as completed
+    pub fn complete(&mut self) {
+        let now = Utc::now();
+        self.status = FeatureStatus::Completed;
+        self.feature.updated_at = now;
+        self.execution.end_time = Some(now);
+        self.total_stats.wall_clock_seconds = self
+            .phases
+            .iter()
+            .filter_map(PhaseState::duration)
+            .map(|d| d.num_seconds().max(0).unsigned_abs())
+            .sum();
+        self.record(EventKind::Completed, None, "");
+    }
+
+    /// Keep at most `limit` events, dropping the oldest
+    pub fn set_event_limit(&mut self, limit: usize) {
+        self.event_limit = limit;
+        self.trim_events();
+    }
+
+    fn record(&mut self, kind: EventKind, phase: Option<&str>, message: impl Into<String>) {
+        self.events.push(StateEvent {
+            at: Utc::now(),
+            kind,
+            phase: phase.map(str::to_string),
+            message: message.into(),
+        });
+        self.trim_events();
+    }
+
+    fn trim_events(&mut self) {
+        let excess = self.events.len().saturating_sub(self.event_limit);
+        self.events.drain(..excess);
+    }
+
+    /// Problems that make the specs of the feature at `feature_path` drafts:
+    /// a missing spec, one with less than `specs.minLength` characters of
+    /// content, or a scaffold marker line left in it
+    pub fn draft_problems(feature_path: &Path, specs: &SpecsConfig) -> Vec<String> {
+        let mut problems = Vec::new();
+        for file in [DESIGN_FILE, VERIFICATION_FILE] {
+            let Ok(content) = std::fs::read_to_string(feature_path.join(file)) else {
+                problems.push(format!("{file} is missing"));
+                continue;
+            };
+            let mut length = 0;
+            for (number, line) in content.lines().enumerate() {
+                let line = line.trim();
+                if specs.draft_markers.iter().any(|marker| marker == line) {
+                    problems.push(format!(
+                        "{file} line {} is still the scaffold `{line}`",
+                        number + 1
+                    ));
+                } else if !line.starts_with('#') {
+                    length += line.chars().count();
+                }
+            }
+            if length < specs.min_length {
+                problems.push(format!(
+                    "{file} has {length} characters of content, fewer than {}",
+                    specs.min_length
+                ));
+            }
+        }
+        problems
+    }
+
+    /// Check that the feature at `feature_path` has specs worth running.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::FeatureNotReady` listing the
+    /// [draft problems](Self::draft_problems), if there are any.
+    pub fn validate_ready(feature_path: &Path, specs: &SpecsConfig) -> Result<()> {
+        let problems = Self::draft_problems(feature_path, specs);
+        if problems.is_empty() {
+            return Ok(());
+        }
+        let feature = feature_path.file_name().map_or_else(
+            || feature_path.display().to_string(),
+            |name| name.to_string_lossy().into_owned(),
+        );
+        Err(CoreError::FeatureNotReady { feature, problems })
+    }
+
+    /// Locate a feature directory by ID (`0001`), full name (`0001_slug`) or slug.
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::FeatureNotFound` if no feature directory matches,
+    /// or `CoreError::AmbiguousFeature` if a slug names several features.
+    pub fn find_dir(gba_path: &Path, feature: &str) -> Result<PathBuf> {
+        find_in(&gba_path.join(FEATURES_DIR), feature)
+    }
+
+    /// Locate an archived feature directory, matching like [`Self::find_dir`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `CoreError::FeatureNotFound` if no archived feature matches,
+    /// or `CoreError::AmbiguousFeature` if a slug names several.
+    pub fn find_archived_dir(gba_path: &Path, feature: &str) -> Result<PathBuf> {
+        find_in(&gba_path.join(ARCHIVE_DIR), feature)
+    }
+
+    /// Load every feature under `.gba/features`.
+    ///
+    /// Only `{id}_{slug}` directories with a numeric ID are considered;
+    /// those whose state can't be loaded are reported in
+    /// [`FeatureListing::failed`] rather than skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the features directory exists but can't be read.
+    pub fn list_all(gba_path: &Path) -> Result<FeatureListing> {
+        list_in(&gba_path.join(FEATURES_DIR))
+    }
+
+    /// Load every archived feature, like [`Self::list_all`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the archive directory exists but can't be read.
+    pub fn list_archived(gba_path: &Path) -> Result<FeatureListing> {
+        list_in(&gba_path.join(ARCHIVE_DIR))
+    }
+
+    /// Next free sequential ID, considering active and archived features.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a features directory exists but can't be read.
+    pub fn next_id(gba_path: &Path) -> Result<String> {
+        let mut max = 0;
+        for dir in [FEATURES_DIR, ARCHIVE_DIR] {
+            let entries = match std::fs::read_dir(gba_path.join(dir)) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for entry in entries {
+                let name = entry?.file_name().to_string_lossy().into_owned();
+                let id = name.split_once('_').map_or(name.as_str(), |(id, _)| id);
+                if let Ok(id) = id.parse::<u32>() {
+                    max = max.max(id);
+                }
+            }
+        }
+        Ok(format!("{:04}", max + 1))
+    }
+}
+
+fn default_event_limit() -> usize {
+    DEFAULT_EVENT_LIMIT
+}
+
+fn list_in(features_dir: &Path) -> Result<FeatureListing> {
+    let entries = match std::fs::read_dir(features_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(FeatureListing::default());
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut listing = FeatureListing::default();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let is_feature = name
+            .split_once('_')
+            .is_some_and(|(id, _)| !id.is_empty() && id.bytes().all(|b| b.is_ascii_digit()));
+        if !is_feature || !entry.path().is_dir() {
+            continue;
+        }
+        match FeatureState::load(&entry.path()) {
+            Ok(state) => listing.loaded.push((name, state)),
+            Err(e) => listing.failed.push((name, e)),
+        }
+    }
+    listing
+        .loaded
+        .sort_by(|(_, a), (_, b)| a.feature.id.cmp(&b.feature.id));
+    listing.failed.sort_by(|(a, _), (b, _)| a.cmp(b));
+    Ok(listing)
+}
+
+fn find_in(features_dir: &Path, feature: &str) -> Result<PathBuf> {
+    let entries = match std::fs::read_dir(features_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err(CoreError::FeatureNotFound(feature.to_string()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // IDs and full names are unique; slugs may repeat.
+    let mut by_slug = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let Some((id, slug)) = name.split_once('_') else {
+            continue;
+        };
+        if !entry.path().is_dir() {
+            continue;
+        }
+        if name == feature || id == feature {
+            return Ok(entry.path());
+        }
+        if slug == feature {
+            by_slug.push((name, entry.path()));
+        }
+    }
+    by_slug.sort();
+    match by_slug.len() {
+        0 => Err(CoreError::FeatureNotFound(feature.to_string())),
+        1 => Ok(by_slug.remove(0).1),
+        _ => Err(CoreError::AmbiguousFeature {
+            feature: feature.to_string(),
+            matches: by_slug.into_iter().map(|(name, _)| name).collect(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = FeatureState::new("0001", "user-auth");
+        state.phase_mut("observe").status = PhaseStatus::Completed;
+
+        state.save(dir.path()).unwrap();
+        let loaded = FeatureState::load(dir.path()).unwrap();
+
+        assert_eq!(loaded, state);
+        let yaml = std::fs::read_to_string(dir.path().join(STATE_FILE)).unwrap();
+        assert!(yaml.contains("status: planned"));
+        assert!(yaml.contains("currentPhase: 0"));
+    }
+
+    #[test]
+    fn test_complete_phase_records_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = FeatureState::new("0001", "user-auth");
+        let hook = HookRecord {
+            command: "cargo fmt --check".to_string(),
+            stage: crate::hooks::HookStage::Post,
+            exit_code: Some(0),
+            timed_out: false,
+            duration_ms: 120,
+            stdout: "formatted".to_string(),
+            stderr: String::new(),
+        };
+        let result = ExecutionResult {
+            success: true,
+            hooks: vec![hook.clone()],
+            ..ExecutionResult::default()
+        };
+
+        state.start_phase(0, "observe");
+        state.complete_phase("observe", &ExecutionResult::default(), String::new());
+        state.start_phase(1, "build");
+        state.complete_phase("build", &result, "built".to_string());
+        state.save(dir.path()).unwrap();
+        let loaded = FeatureState::load(dir.path()).unwrap();
+
+        let hooks = &loaded.phase("build").unwrap().hooks;
+        assert_eq!(hooks.len(), 1);
+        assert_eq!(hooks[0].command, "cargo fmt --check");
+        assert_eq!(hooks[0].exit_code, Some(0));
+        assert_eq!(hooks[0].duration_ms, 120);
+        // The output stays in the phase log, not in state.yml.
+        assert!(hooks[0].stdout.is_empty());
+        let yaml = std::fs::read_to_string(dir.path().join(STATE_FILE)).unwrap();
+        assert!(yaml.contains("exitCode: 0"));
+        assert!(!yaml.contains("formatted"));
+        assert!(loaded.phase("observe").unwrap().hooks.is_empty());
+    }
+
+    #[test]
+    fn test_list_all_reports_unloadable_features() {
+        let dir = tempfile::tempdir().unwrap();
+        let features = dir.path().join(FEATURES_DIR);
+        for (id, slug) in [("0002", "search"), ("0001", "auth")] {
+            let path = features.join(format!("{id}_{slug}"));
+            std::fs::create_dir_all(&path).unwrap();
+            FeatureState::new(id, slug).save(&path).unwrap();
+        }
+        std::fs::create_dir_all(features.join("0003_broken")).unwrap();
+        std::fs::write(features.join("0003_broken").join(STATE_FILE), "status: [").unwrap();
+        std::fs::create_dir_all(features.join("0004_empty")).unwrap();
+        std::fs::create_dir_all(features.join("drafts")).unwrap();
+        std::fs::write(features.join("0005_notes.txt"), "").unwrap();
+
+        let listing = FeatureState::list_all(dir.path()).unwrap();
+
+        let loaded: Vec<_> = listing.loaded.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(loaded, ["0001_auth", "0002_search"]);
+        assert_eq!(listing.loaded[1].1.feature.slug, "search");
+        let failed: Vec<_> = listing.failed.iter().map(|(n, _)| n.as_str()).collect();
+        assert_eq!(failed, ["0003_broken", "0004_empty"]);
+        assert!(matches!(listing.failed[0].1, CoreError::Yaml(_)));
+
+        let missing = FeatureState::list_archived(dir.path()).unwrap();
+        assert!(missing.loaded.is_empty() && missing.failed.is_empty());
+    }
+
+    #[test]
+    fn test_find_dir_by_id_and_slug() {
+        let dir = tempfile::tempdir().unwrap();
+        let feature = dir.path().join(FEATURES_DIR).join("0003_user-auth");
+        std::fs::create_dir_all(&feature).unwrap();
+
+        assert_eq!(FeatureState::find_dir(dir.path(), "0003").unwrap(), feature);
+        assert_eq!(
+            FeatureState::find_dir(dir.path(), "user-auth").unwrap(),
+            feature
+        );
+        assert!(matches!(
+            FeatureState::find_dir(dir.path(), "missing"),
+            Err(CoreError::FeatureNotFound(_))
+        ));
+
+        let again = dir.path().join(FEATURES_DIR).join("0007_user-auth");
+        std::fs::create_dir_all(&again).unwrap();
+        let err = FeatureState::find_dir(dir.path(), "user-auth").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Feature user-auth is ambiguous: 0003_user-auth, 0007_user-auth (use the ID)"
+        );
+        assert_eq!(FeatureState::find_dir(dir.path(), "0007").unwrap(), again);
+    }
+
+    #[test]
+    fn test_unknown_fields_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let yaml = r#"
+version: 0.1.0
+feature:
+  id: "0001"
+  slug: user-auth
+  createdAt: 2026-02-10T10:00:00Z
+  updatedAt: 2026-02-10T12:00:00Z
+status: in_progress
+currentPhase: 1
+labels: [backend, auth]
+phases:
+  - name: observe
+    status: completed
+    reviewer: alice
+resume:
+  canResume: true
+  checkpoint: abc123
+"#;
+        std::fs::write(dir.path().join(STATE_FILE), yaml).unwrap();
+
+        let mut state = FeatureState::load(dir.path()).unwrap();
+        assert_eq!(state.unknown_keys(), ["labels"]);
+        assert!(state.events.is_empty());
+        assert_eq!(state.phases[0].extra.len(), 1);
+        assert!(state.resume.can_resume);
+
+        state.phase_mut("build").status = PhaseStatus::InProgress;
+        state.save(dir.path()).unwrap();
+        let saved = std::fs::read_to_string(dir.path().join(STATE_FILE)).unwrap();
+        assert!(saved.contains("labels:\n- backend\n- auth"));
+        assert!(saved.contains("reviewer: alice"));
+        assert!(saved.contains("checkpoint: abc123"));
+        assert_eq!(FeatureState::load(dir.path()).unwrap(), state);
+    }
+
+    #[test]
+    fn test_mark_for_resume() {
+        let mut state = FeatureState::new("0001", "user-auth");
+        state.start_phase(0, "observe");
+        state.complete_phase("observe", &ExecutionResult::default(), String::new());
+        state.start_phase(1, "build");
+
+        state.mark_for_resume(InterruptReason::UserCancelled);
+
+        assert_eq!(state.phase("build").unwrap().status, PhaseStatus::Pending);
+        assert!(state.resume.can_resume);
+        assert_eq!(
+            state.resume.last_completed_phase.as_deref(),
+            Some("observe")
+        );
+        assert_eq!(state.resume.next_phase.as_deref(), Some("build"));
+        let yaml = serde_yaml::to_string(&state).unwrap();
+        assert!(yaml.contains("interruptReason: userCancelled"));
+
+        state.start_phase(1, "build");
+        assert_eq!(state.resume, ResumeInfo::default());
+    }
+
+    #[test]
+    fn test_durations() {
+        let mut state = FeatureState::new("0001", "user-auth");
+        assert_eq!(state.duration(), None);
+        let started = Utc::now() - TimeDelta::minutes(10);
+        state.execution.start_time = Some(started);
+        let observe = state.phase_mut("observe");
+        observe.started_at = Some(started);
+        observe.completed_at = Some(started + TimeDelta::seconds(90));
+        state.phase_mut("build").started_at = Some(Utc::now() - TimeDelta::seconds(30));
+
+        assert_eq!(
+            state.phase("observe").unwrap().duration(),
+            Some(TimeDelta::seconds(90))
+        );
+        assert!(state.phase("build").unwrap().duration().unwrap() >= TimeDelta::seconds(30));
+        assert!(state.execution.duration().unwrap() >= TimeDelta::minutes(10));
+
+        state.phase_mut("build").completed_at = state.phase("build").unwrap().started_at;
+        state.complete();
+        assert_eq!(state.total_stats.wall_clock_seconds, 90);
+        state.phase_mut("observe").completed_at = Some(started);
+        assert_eq!(state.duration(), Some(TimeDelta::seconds(90)));
+    }
+
+    #[test]
+    fn test_skipped_phase_is_done() {
+        let mut state = FeatureState::new("0001", "user-auth");
+        state.start_phase(0, "observe");
+        state.complete_phase("observe", &ExecutionResult::default(), String::new());
+        state.skip_phase("build", "disabled in config.yml".to_string());
+
+        let build = state.phase("build").unwrap();
+        assert_eq!(build.status, PhaseStatus::Skipped);
+        assert!(build.status.is_done());
+        assert_eq!(
+            build.output_summary.as_deref(),
+            Some("disabled in config.yml")
+        );
+        assert!(build.stats.is_none());
+        assert_eq!(state.events.last().unwrap().kind, EventKind::PhaseSkipped);
+        assert_eq!(
+            serde_yaml::to_string(&PhaseStatus::Skipped).unwrap(),
+            "skipped\n"
+        );
+        assert_eq!(
+            serde_yaml::from_str::<PhaseStatus>("skipped").unwrap(),
+            PhaseStatus::Skipped
+        );
+
+        state.start_phase(2, "pr");
+        state.mark_for_resume(InterruptReason::Timeout);
+        assert_eq!(state.resume.next_phase.as_deref(), Some("pr"));
+    }
+
+    #[test]
+    fn test_event_log() {
+        let mut state = FeatureState::new("0001", "user-auth");
+        let result = ExecutionResult {
+            stats: ExecutionStats {
+                cost_usd: 0.5,
+                ..ExecutionStats::default()
+            },
+            ..ExecutionResult::default()
+        };
+        state.start_phase(0, "build");
+        state.mark_for_resume(InterruptReason::UserCancelled);
+        state.start_phase(0, "build");
+        state.complete_phase("build", &result, String::new());
+        state.record_commit("build", "abc1234");
+        state.complete();
+
+        let kinds: Vec<_> = state.events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::PhaseStarted,
+                EventKind::Interrupted,
+                EventKind::Resumed,
+                EventKind::PhaseStarted,
+                EventKind::PhaseCompleted,
+                EventKind::CostAdded,
+                EventKind::CommitCreated,
+                EventKind::Completed,
+            ]
+        );
+        assert_eq!(state.events[5].message, "$0.50 (total $0.50)");
+        let yaml = serde_yaml::to_string(&state.events[6]).unwrap();
+        assert!(yaml.contains("kind: commit_created\nphase: build\nmessage: abc1234"));
+
+        state.set_event_limit(3);
+        let kinds: Vec<_> = state.events.iter().map(|e| e.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                EventKind::CostAdded,
+                EventKind::CommitCreated,
+                EventKind::Completed
+            ]
+        );
+    }
+
+    #[test]
+    fn test_phase_transitions() {
+        let mut state = FeatureState::new("0001", "user-auth");
+        let result = ExecutionResult {
+            success: true,
+            stats: ExecutionStats {
+                turns: 4,
+                cost_usd: 0.4,
+                ..ExecutionStats::default()
+            },
+            model: Some("claude-opus-4".to_string()),
+            ..ExecutionResult::default()
+        };
+
+        state.start_phase(0, "observe");
+        assert_eq!(state.status, FeatureStatus::InProgress);
+        assert_eq!(
+            state.phase("observe").unwrap().status,
+            PhaseStatus::InProgress
+        );
+
+        state.complete_phase("observe", &result, "Found the auth module".to_string());
+        state.start_phase(1, "build");
+        state.fail_phase("build", "Agent timed out".to_string(), None);
+
+        assert_eq!(state.current_phase, 1);
+        assert_eq!(state.total_stats.turns, 4);
+        assert_eq!(
+            state.phase("observe").unwrap().status,
+            PhaseStatus::Completed
+        );
+        assert_eq!(
+            state.phase("observe").unwrap().model.as_deref(),
+            Some("claude-opus-4")
+        );
+        assert_eq!(state.phase("build").unwrap().status, PhaseStatus::Failed);
+        assert_eq!(state.status, FeatureStatus::Failed);
+        assert_eq!(state.error.as_deref(), Some("Agent timed out"));
+        assert_eq!(
+            state.previous_outputs("build"),
+            [PreviousOutput {
+                phase: "observe".to_string(),
+                summary: "Found the auth module".to_string(),
+                commit_sha: None,
+            }]
+        );
+        assert!(state.previous_outputs("observe").is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut state = FeatureState::new("0001", "user-auth");
+        state.phase_mut("observe");
+        assert!(state.validate().is_ok());
+
+        state.feature.id = "abc".to_string();
+        state.current_phase = 5;
+        let err = state.validate().unwrap_err().to_string();
+
+        assert!(err.contains("invalid feature id `abc`"));
+        assert!(err.contains("currentPhase 5 is out of range"));
+    }
+
+    #[test]
+    fn test_validate_ready() {
+        let dir = tempfile::tempdir().unwrap();
+        let feature_path = dir.path().join("0001_auth");
+        std::fs::create_dir_all(feature_path.join("specs")).unwrap();
+        let specs = SpecsConfig::default();
+        std::fs::write(
+            feature_path.join(DESIGN_FILE),
+            "# Design: auth\n\n## Overview\n\nTODO: describe the feature.\n",
+        )
+        .unwrap();
+
+        let problems = FeatureState::draft_problems(&feature_path, &specs);
+        assert_eq!(
+            problems,
+            [
+                "specs/design.md line 5 is still the scaffold `TODO: describe the feature.`",
+                "specs/design.md has 0 characters of content, fewer than 40",
+                "specs/verification.md is missing",
+            ]
+        );
+        let err = FeatureState::validate_ready(&feature_path, &specs).unwrap_err();
+        assert!(matches!(
+            &err,
+            CoreError::FeatureNotReady { feature, problems } if feature == "0001_auth" && problems.len() == 3
+        ));
+
+        std::fs::write(
+            feature_path.join(DESIGN_FILE),
+            "# Design: auth\n\nSessions are JWTs signed with the server key.\n",
+        )
+        .unwrap();
+        std::fs::write(
+            feature_path.join(VERIFICATION_FILE),
+            "# Verification\n\n- [ ] Logging in returns a token that expires in an hour\n",
+        )
+        .unwrap();
+        assert!(FeatureState::validate_ready(&feature_path, &specs).is_ok());
+        let strict = SpecsConfig {
+            min_length: 100,
+            ..SpecsConfig::default()
+        };
+        assert_eq!(
+            FeatureState::draft_problems(&feature_path, &strict).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_next_id_scans_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(FeatureState::next_id(dir.path()).unwrap(), "0001");
+
+        std::fs::create_dir_all(dir.path().join(FEATURES_DIR).join("0002_search")).unwrap();
+        std::fs::create_dir_all(dir.path().join(ARCHIVE_DIR).join("0007_auth")).unwrap();
+
+        assert_eq!(FeatureState::next_id(dir.path()).unwrap(), "0008");
+    }
+}
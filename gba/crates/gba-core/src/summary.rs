@@ -0,0 +1,210 @@
+//! Phase output summaries, extracted from the output or written by a cheap
+//! model.
+
+use crate::Engine;
+use crate::error::{CoreError, Result};
+use crate::execution::{ExecutionRequest, ExecutionResult};
+use crate::text::{tail_chars, truncate_chars};
+
+/// System prompt of the summary request
+pub const SUMMARY_PROMPT: &str = "Summarize the work report you are given in exactly 3 short \
+markdown bullet points: what was changed, what was verified, and what is left open. Reply with \
+the bullet points only.";
+
+/// Most characters of output sent to the model; the end is kept, since that
+/// is where agents report what they did
+pub const SUMMARY_INPUT_CHARS: usize = 50_000;
+
+/// Short summary of an agent's `output` of at most `max_chars` characters
+/// (plus an ellipsis).
+///
+/// Uses the section under the last "Summary" heading (`## Summary`,
+/// `**Summary:**`, `Summary: ...`) if there is one, otherwise the last
+/// paragraph, where agents report what they did. Long text is cut at a
+/// sentence or word boundary, never inside a character.
+pub fn summarize_output(output: &str, max_chars: usize) -> String {
+    let text = summary_section(output)
+        .or_else(|| {
+            output
+                .trim_end()
+                .rsplit("\n\n")
+                .map(collapse_whitespace)
+                .find(|p| !p.is_empty())
+        })
+        .unwrap_or_default();
+    let head = truncate_chars(&text, max_chars, "");
+    if head.len() == text.len() {
+        return text;
+    }
+    let limit = head.len();
+    // Prefer whole sentences, then whole words, as long as half is kept.
+    let cut = [". ", "! ", "? "]
+        .iter()
+        .filter_map(|end| head.rfind(end).map(|idx| idx + 1))
+        .max()
+        .filter(|&idx| idx >= limit / 2);
+    if let Some(idx) = cut {
+        return head[..idx].to_string();
+    }
+    match head.rfind(' ').filter(|&idx| idx >= limit / 2) {
+        Some(idx) => format!("{}...", &head[..idx]),
+        None => format!("{head}..."),
+    }
+}
+
+/// Text under the last "Summary" heading or label
+fn summary_section(output: &str) -> Option<String> {
+    let lines: Vec<&str> = output.lines().collect();
+    let (start, inline) = lines
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(i, line)| summary_heading(line).map(|rest| (i, rest)))?;
+    let heading = lines[start].trim_start().starts_with('#');
+    let mut section = vec![inline];
+    for line in &lines[start + 1..] {
+        let blank = line.trim().is_empty();
+        // A heading's section runs to the next heading, a label's to the
+        // end of its paragraph.
+        if line.trim_start().starts_with('#')
+            || (!heading && blank && section.iter().any(|l| !l.trim().is_empty()))
+        {
+            break;
+        }
+        section.push(line);
+    }
+    let section = collapse_whitespace(&section.join("\n"));
+    (!section.is_empty()).then_some(section)
+}
+
+/// Whether `line` starts a summary, returning the text following the label
+fn summary_heading(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let label = line.trim_start_matches('#').trim().trim_matches('*').trim();
+    if !label.to_lowercase().starts_with("summary") {
+        return None;
+    }
+    if line.starts_with('#') {
+        return Some("");
+    }
+    match label.split_once(':') {
+        Some((_, rest)) => Some(rest.trim_start_matches('*').trim()),
+        None if label.eq_ignore_ascii_case("summary") => Some(""),
+        None => None,
+    }
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Request asking `model` to summarize `output` in one turn without tools
+pub fn summary_request(output: &str, model: &str) -> ExecutionRequest {
+    let output = output.trim();
+    let tail = tail_chars(output, SUMMARY_INPUT_CHARS);
+    ExecutionRequest {
+        system_prompt: Some(SUMMARY_PROMPT.to_string()),
+        model: Some(model.to_string()),
+        max_turns: Some(1),
+        label: Some("summary".to_string()),
+        ..ExecutionRequest::new(tail)
+    }
+}
+
+/// Summarize a phase's `output` with `model`.
+///
+/// The summary is the result's trimmed output; its stats are the cost of
+/// summarizing.
+///
+/// # Errors
+///
+/// Returns the errors of [`Engine::execute_request`], or
+/// `CoreError::InvalidAgentOutput` if the model fails or replies with
+/// nothing.
+pub async fn summarize(engine: &Engine, output: &str, model: &str) -> Result<ExecutionResult> {
+    let mut result = engine
+        .execute_request(summary_request(output, model))
+        .await?;
+    result.output = result.output.trim().to_string();
+    if !result.success || result.output.is_empty() {
+        return Err(CoreError::InvalidAgentOutput(format!(
+            "{model} returned no summary"
+        )));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Config;
+
+    #[test]
+    fn test_summarize_output_last_paragraph() {
+        assert_eq!(
+            summarize_output(
+                "I'll look around.\n\n  Implemented login.\nTests pass.\n\n",
+                200
+            ),
+            "Implemented login. Tests pass."
+        );
+
+        let sentences = format!("Added login. {}", "Wrote tests. ".repeat(20));
+        let summary = summarize_output(&sentences, 200);
+        assert!(summary.ends_with("tests.") && summary.chars().count() <= 200);
+        let words = "word ".repeat(60);
+        assert!(summarize_output(&words, 200).ends_with("word..."));
+        assert_eq!(summarize_output(" \n", 200), "");
+    }
+
+    #[test]
+    fn test_summarize_output_prefers_summary_section() {
+        let output = "## Summary\n\n- Added login\n- Added tests\n\n## Next Steps\n\nDeploy it.";
+        assert_eq!(summarize_output(output, 200), "- Added login - Added tests");
+
+        let inline = "Reading files.\n\n**Summary:** Fixed the parser.\n\nLet me know!";
+        assert_eq!(summarize_output(inline, 200), "Fixed the parser.");
+
+        // A sentence merely starting with "Summary" is no heading.
+        let prose = "Summary statistics are now cached.\n\nDone.";
+        assert_eq!(summarize_output(prose, 200), "Done.");
+    }
+
+    #[test]
+    fn test_summarize_output_multibyte() {
+        // Byte 200 falls inside a two-byte character.
+        let long = format!("a{}", "é".repeat(300));
+        let summary = summarize_output(&long, 200);
+        assert_eq!(summary.chars().count(), 203);
+        assert!(summary.ends_with("é..."));
+
+        let cjk = "已实现登录功能。".repeat(40);
+        assert_eq!(summarize_output(&cjk, 10).chars().count(), 13);
+    }
+
+    #[test]
+    fn test_summary_request_keeps_the_end() {
+        let output = format!("{}Done: added login.", "x".repeat(SUMMARY_INPUT_CHARS));
+
+        let request = summary_request(&output, "claude-haiku-4-5");
+
+        assert_eq!(request.model.as_deref(), Some("claude-haiku-4-5"));
+        assert_eq!(request.max_turns, Some(1));
+        assert_eq!(request.system_prompt.as_deref(), Some(SUMMARY_PROMPT));
+        assert_eq!(request.user_prompt.chars().count(), SUMMARY_INPUT_CHARS);
+        assert!(request.user_prompt.ends_with("Done: added login."));
+    }
+
+    #[tokio::test]
+    async fn test_summarize_uses_the_summary_model() {
+        let engine = Engine::new(Config::builder().offline(true).build());
+
+        let result = summarize(&engine, "  - Added login\n", "claude-haiku-4-5")
+            .await
+            .unwrap();
+
+        assert_eq!(result.output, "- Added login");
+        assert_eq!(result.model.as_deref(), Some("claude-haiku-4-5"));
+        assert!(summarize(&engine, " \n", "claude-haiku-4-5").await.is_err());
+    }
+}
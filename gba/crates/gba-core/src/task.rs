@@ -0,0 +1,103 @@
+//! Per-task configuration loaded from `prompts/{task}/config.yml`.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::hooks::PhaseHooks;
+use crate::mcp::McpServerMap;
+use crate::review::ReviewConfig;
+use crate::testing::TestConfig;
+use crate::verification::VerificationConfig;
+
+/// Task configuration for a single phase (`prompts/{task}/config.yml`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TaskConfig {
+    /// Use the `claude_code` preset instead of the custom `system.md`
+    pub preset: bool,
+    /// Extra instructions appended to the preset (only with `preset: true`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub append: Option<String>,
+    /// Allowed tools (empty = all tools)
+    pub tools: Vec<String>,
+    /// Tools to explicitly disallow
+    pub disallowed_tools: Vec<String>,
+    /// MCP servers added to (or replacing) the project's `mcpServers`
+    #[serde(skip_serializing_if = "McpServerMap::is_empty")]
+    pub mcp_servers: McpServerMap,
+    /// Shell commands run before and after the phase
+    pub hooks: PhaseHooks,
+    /// Verification settings (verification task only)
+    pub verification: VerificationConfig,
+    /// Review settings (review task only)
+    pub review: ReviewConfig,
+    /// Test command settings (test task only)
+    pub test: TestConfig,
+}
+
+impl TaskConfig {
+    /// File name of the task configuration inside a task directory
+    pub const FILE_NAME: &'static str = "config.yml";
+
+    /// Load the task configuration from a task directory.
+    ///
+    /// A missing `config.yml` yields the default configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file exists but cannot be read or parsed.
+    pub fn load(task_dir: &Path) -> Result<Self> {
+        let path = task_dir.join(Self::FILE_NAME);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => Self::from_yaml(&content),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Parse a task configuration from YAML text.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the YAML is malformed.
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        // An all-comment file deserializes as `null`, which means "defaults".
+        let config: Option<Self> = serde_yaml::from_str(content)?;
+        Ok(config.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::HookFailurePolicy;
+
+    #[test]
+    fn test_task_config_parses_hooks() {
+        let yaml = r#"
+preset: false
+tools: []
+disallowedTools: []
+hooks:
+  postCommand:
+    - cargo fmt
+    - cargo clippy
+  onHookFailure: retry
+  timeoutSeconds: 120
+"#;
+        let config = TaskConfig::from_yaml(yaml).unwrap();
+        assert!(config.hooks.pre_command.is_empty());
+        assert_eq!(config.hooks.post_command, vec!["cargo fmt", "cargo clippy"]);
+        assert_eq!(config.hooks.on_hook_failure, HookFailurePolicy::Retry);
+        assert_eq!(config.hooks.timeout_seconds, 120);
+    }
+
+    #[test]
+    fn test_task_config_missing_file_is_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = TaskConfig::load(dir.path()).unwrap();
+        assert_eq!(config, TaskConfig::default());
+    }
+}
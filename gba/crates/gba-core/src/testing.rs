@@ -0,0 +1,383 @@
+//! Running the project's tests after the test phase.
+//!
+//! The agent claiming that tests pass is not verification. After the test
+//! phase the configured command runs in the worktree; while it fails, its
+//! output goes back to the agent with a request to fix the tests, up to
+//! `test.maxFixIterations` times. Configured in `prompts/test/config.yml`:
+//!
+//! ```yaml
+//! test:
+//!   command: cargo test --all
+//!   maxFixIterations: 3
+//!   timeoutSeconds: 600
+//! ```
+
+use std::fmt;
+use std::future::Future;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+use crate::error::{CoreError, Result};
+use crate::execution::{ExecutionRequest, ExecutionResult};
+use crate::text::tail_chars;
+
+/// Name of the phase followed by a test run
+pub const TEST_PHASE: &str = "test";
+
+/// Exit code of `sh -c` when the command doesn't exist
+const COMMAND_NOT_FOUND: i32 = 127;
+
+/// Maximum length of the test output fed back to the agent
+const FEEDBACK_CHARS: usize = 20_000;
+
+/// Test run settings (`test:` in `prompts/test/config.yml`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TestConfig {
+    /// Run the tests after the test phase
+    pub enabled: bool,
+    /// Test command, run through `sh -c` in the worktree
+    pub command: String,
+    /// How often the agent may try to fix failing tests
+    pub max_fix_iterations: u32,
+    /// Timeout of each test run
+    pub timeout_seconds: u64,
+}
+
+impl Default for TestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            command: "cargo test --all".to_string(),
+            max_fix_iterations: 3,
+            timeout_seconds: 600,
+        }
+    }
+}
+
+/// Outcome of one run of the test command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestRun {
+    /// Command that ran
+    pub command: String,
+    /// Exit code (None if killed or timed out)
+    pub exit_code: Option<i32>,
+    /// Whether the run hit the timeout
+    pub timed_out: bool,
+    /// Wall-clock duration in milliseconds
+    pub duration_ms: u64,
+    /// Combined standard output and error
+    #[serde(skip)]
+    pub output: String,
+}
+
+impl TestRun {
+    /// Whether the tests passed
+    pub fn passed(&self) -> bool {
+        !self.timed_out && self.exit_code == Some(0)
+    }
+
+    fn failure_reason(&self) -> String {
+        if self.timed_out {
+            format!("timed out after {}ms", self.duration_ms)
+        } else {
+            match self.exit_code {
+                Some(code) => format!("exited with code {code}"),
+                None => "was terminated by a signal".to_string(),
+            }
+        }
+    }
+
+    /// Prompt asking the agent to fix the failures of this run
+    pub fn fix_prompt(&self) -> String {
+        let output = self.output.trim_end();
+        // The end is kept: test runners report the failures last.
+        let tail = tail_chars(output, FEEDBACK_CHARS);
+        format!(
+            "## Failing Tests\n\nThe test command `{}` {}. Fix the failing tests (or the code \
+             they test) so that it passes. Its output:\n\n```\n{}\n```",
+            self.command,
+            self.failure_reason(),
+            tail
+        )
+    }
+}
+
+impl fmt::Display for TestRun {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = if self.passed() {
+            "passed".to_string()
+        } else {
+            self.failure_reason()
+        };
+        write!(f, "`{}` {status} ({}ms)", self.command, self.duration_ms)
+    }
+}
+
+/// Test result stored in the test `PhaseState`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestSummary {
+    /// Whether the last run passed
+    pub passed: bool,
+    /// Number of test runs
+    pub runs: usize,
+}
+
+impl TestSummary {
+    /// Summary of `runs`, the last one deciding the outcome
+    pub fn new(runs: &[TestRun]) -> Self {
+        Self {
+            passed: runs.last().is_some_and(TestRun::passed),
+            runs: runs.len(),
+        }
+    }
+}
+
+/// Run the test command once in `working_dir`.
+///
+/// # Errors
+///
+/// Returns `CoreError::ConfigError` if the command doesn't exist, or an IO
+/// error if the shell can't be started.
+pub async fn run_tests(config: &TestConfig, working_dir: &Path) -> Result<TestRun> {
+    let start = Instant::now();
+    let mut run = TestRun {
+        command: config.command.clone(),
+        exit_code: None,
+        timed_out: false,
+        duration_ms: 0,
+        output: String::new(),
+    };
+
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(&config.command)
+        .current_dir(working_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let timeout = Duration::from_secs(config.timeout_seconds);
+    match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => {
+            let output = output?;
+            run.exit_code = output.status.code();
+            run.output = format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Err(_) => run.timed_out = true,
+    }
+    run.duration_ms = u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX);
+    tracing::info!(
+        command = %config.command,
+        exit_code = ?run.exit_code,
+        duration_ms = run.duration_ms,
+        "tests finished"
+    );
+
+    if run.exit_code == Some(COMMAND_NOT_FOUND) {
+        return Err(CoreError::ConfigError(format!(
+            "test command `{}` not found in {}: {}",
+            config.command,
+            working_dir.display(),
+            run.output.trim()
+        )));
+    }
+    Ok(run)
+}
+
+/// Run the tests after the agent's `result`, letting it fix failures.
+///
+/// Each failing run re-runs `execute` with the test output as the prompt
+/// and the previous exchange as history, until the tests pass or `max_fix_iterations` is reached. The
+/// runs end up in `test_runs` and the fix turns in `stats.fix_iterations`;
+/// the result is unsuccessful if the last run failed.
+pub(crate) async fn run_with_fixes<F, Fut>(
+    config: &TestConfig,
+    working_dir: &Path,
+    request: ExecutionRequest,
+    mut result: ExecutionResult,
+    execute: F,
+) -> Result<ExecutionResult>
+where
+    F: Fn(ExecutionRequest) -> Fut,
+    Fut: Future<Output = Result<ExecutionResult>>,
+{
+    let mut runs = Vec::new();
+    let mut conversation = request.conversation(&result.output);
+    loop {
+        let run = run_tests(config, working_dir).await?;
+        let passed = run.passed();
+        // The fix continues the conversation that wrote the failing code.
+        let fix = ExecutionRequest {
+            user_prompt: run.fix_prompt(),
+            history: conversation.clone(),
+            ..request.clone()
+        };
+        runs.push(run);
+        if passed || result.stats.fix_iterations >= config.max_fix_iterations {
+            break;
+        }
+
+        tracing::info!("tests failed, asking the agent to fix them");
+        let fixed = execute(fix.clone()).await?;
+        conversation = fix.conversation(&fixed.output);
+        let mut stats = std::mem::take(&mut result.stats);
+        stats.accumulate(&fixed.stats);
+        stats.fix_iterations += 1;
+        result = ExecutionResult {
+            duration: result.duration + fixed.duration,
+            stats,
+            hook_output: result.hook_output,
+            hooks: result.hooks,
+            ..fixed
+        };
+        if !result.success {
+            break;
+        }
+    }
+
+    result.success = result.success && runs.last().is_some_and(TestRun::passed);
+    result.test_runs = runs;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::Role;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn config(command: &str) -> TestConfig {
+        TestConfig {
+            command: command.to_string(),
+            max_fix_iterations: 2,
+            timeout_seconds: 5,
+            ..TestConfig::default()
+        }
+    }
+
+    fn agent_result() -> ExecutionResult {
+        ExecutionResult {
+            success: true,
+            ..ExecutionResult::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_tests_are_fed_back_until_fixed() {
+        let dir = tempfile::tempdir().unwrap();
+        // Fails until the "agent" has created fixed.txt.
+        let config = config("echo 'assertion failed'; test -f fixed.txt");
+        let calls = AtomicU32::new(0);
+        let request = ExecutionRequest {
+            user_prompt: "write tests".to_string(),
+            ..ExecutionRequest::default()
+        };
+
+        let written = ExecutionResult {
+            output: "wrote tests".to_string(),
+            ..agent_result()
+        };
+
+        let result = run_with_fixes(&config, dir.path(), request, written, |request| {
+            calls.fetch_add(1, Ordering::SeqCst);
+            assert!(request.user_prompt.starts_with("## Failing Tests"));
+            assert!(request.user_prompt.contains("assertion failed"));
+            assert_eq!(
+                request.history,
+                [
+                    (Role::User, "write tests".to_string()),
+                    (Role::Assistant, "wrote tests".to_string())
+                ]
+            );
+            std::fs::write(dir.path().join("fixed.txt"), "").unwrap();
+            async { Ok(agent_result()) }
+        })
+        .await
+        .unwrap();
+
+        assert!(result.success);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(result.stats.fix_iterations, 1);
+        assert_eq!(result.test_runs.len(), 2);
+        assert_eq!(TestSummary::new(&result.test_runs).runs, 2);
+        assert!(result.test_runs[1].passed());
+    }
+
+    #[tokio::test]
+    async fn test_fix_iterations_are_limited() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let result = run_with_fixes(
+            &config("exit 1"),
+            dir.path(),
+            ExecutionRequest::default(),
+            agent_result(),
+            |_| async { Ok(agent_result()) },
+        )
+        .await
+        .unwrap();
+
+        assert!(!result.success);
+        assert_eq!(result.stats.fix_iterations, 2);
+        assert_eq!(result.test_runs.len(), 3);
+        assert!(!TestSummary::new(&result.test_runs).passed);
+        assert_eq!(
+            result.test_runs[0].to_string(),
+            format!(
+                "`exit 1` exited with code 1 ({}ms)",
+                result.test_runs[0].duration_ms
+            )
+        );
+    }
+
+    #[test]
+    fn test_fix_prompt_keeps_the_end_of_multibyte_output() {
+        let run = TestRun {
+            command: "cargo test".to_string(),
+            exit_code: Some(101),
+            timed_out: false,
+            duration_ms: 10,
+            // Cutting FEEDBACK_CHARS bytes from the end would split a "—".
+            output: format!("{}test result: FAILED", "—".repeat(FEEDBACK_CHARS)),
+        };
+
+        let prompt = run.fix_prompt();
+
+        assert!(prompt.contains("`cargo test` exited with code 101"));
+        assert!(prompt.ends_with("test result: FAILED\n```"));
+        assert_eq!(
+            prompt.matches('—').count(),
+            FEEDBACK_CHARS - "test result: FAILED".len()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_missing_command_and_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = run_tests(&config("no-such-test-runner --all"), dir.path())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, CoreError::ConfigError(msg) if msg.contains("not found")));
+
+        let config = TestConfig {
+            timeout_seconds: 1,
+            ..config("sleep 5")
+        };
+        let run = run_tests(&config, dir.path()).await.unwrap();
+        assert!(run.timed_out);
+        assert!(!run.passed());
+    }
+}
@@ -0,0 +1,51 @@
+//! Text size estimates and cuts shared by prompts, summaries and logs.
+//!
+//! Lengths are counted in characters, so a cut never splits a multi-byte
+//! character.
+
+/// Characters [`estimate_tokens`] counts as one token
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Estimated tokens of `text`: one per 4 characters (rounded up), close
+/// enough for English prose and code to decide what to trim
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN)
+}
+
+/// The first `max_chars` characters of `text`, followed by `ellipsis` if
+/// anything was cut
+pub fn truncate_chars(text: &str, max_chars: usize, ellipsis: &str) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{}{ellipsis}", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// The last `max_chars` characters of `text`
+pub fn tail_chars(text: &str, max_chars: usize) -> &str {
+    let skip = text.chars().count().saturating_sub(max_chars);
+    text.char_indices()
+        .nth(skip)
+        .map_or(text, |(start, _)| &text[start..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("abcde"), 2);
+        assert_eq!(estimate_tokens(&"🦀".repeat(8)), 2);
+    }
+
+    #[test]
+    fn test_cuts_keep_whole_characters() {
+        assert_eq!(truncate_chars("héllo", 2, "..."), "hé...");
+        assert_eq!(truncate_chars("héllo", 5, "..."), "héllo");
+        assert_eq!(truncate_chars("🦀🦀", 1, "…"), "🦀…");
+        assert_eq!(tail_chars("héllo", 4), "éllo");
+        assert_eq!(tail_chars("héllo", 10), "héllo");
+    }
+}
@@ -0,0 +1,345 @@
+//! Recording agent responses and replaying them without the API.
+//!
+//! A [`TranscriptRecorder`] writes the messages of every request as JSON
+//! lines to `{dir}/{seq}-{label}.jsonl`, e.g. `0001-observe.jsonl`, with
+//! the API key and the configured environment values redacted. A
+//! [`ReplayBackend`] streams the transcripts of such a directory back in the
+//! same order, so a run can be repeated deterministically for demos and CI.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use claude_agent_sdk_rs::{ClaudeError, Message};
+use futures::future::BoxFuture;
+use futures::stream::{self, BoxStream, StreamExt};
+use parking_lot::Mutex;
+
+use crate::error::{CoreError, Result};
+use crate::execution::ExecutionRequest;
+
+/// Extension of transcript files
+const EXTENSION: &str = "jsonl";
+
+/// Label of transcripts of requests without one
+const DEFAULT_LABEL: &str = "request";
+
+/// Replaces secrets in recorded transcripts
+const REDACTED: &str = "[REDACTED]";
+
+/// Shortest value redacted; shorter ones (`1`, `dev`) would garble the
+/// transcript without hiding anything
+const MIN_SECRET_LEN: usize = 4;
+
+/// Stream of the messages of an agent response
+pub type MessageStream<'a> = BoxStream<'a, std::result::Result<Message, ClaudeError>>;
+
+/// Answers requests in place of the Claude Agent SDK
+pub trait AgentBackend: Send + Sync + fmt::Debug {
+    /// The messages the agent responds to `request` with
+    fn respond<'a>(
+        &'a self,
+        request: &'a ExecutionRequest,
+    ) -> BoxFuture<'a, Result<MessageStream<'a>>>;
+}
+
+/// Label a request's transcript is recorded and replayed under
+fn label(request: &ExecutionRequest) -> &str {
+    request.label.as_deref().unwrap_or(DEFAULT_LABEL)
+}
+
+/// Writes the transcript of every request to a directory
+#[derive(Debug)]
+pub struct TranscriptRecorder {
+    dir: PathBuf,
+    next: AtomicUsize,
+}
+
+impl TranscriptRecorder {
+    /// Record into `dir`, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `dir` can't be created, or
+    /// `CoreError::ConfigError` if it already holds transcripts.
+    pub fn create(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+        if !transcripts(&dir)?.is_empty() {
+            return Err(CoreError::ConfigError(format!(
+                "{} already holds transcripts",
+                dir.display()
+            )));
+        }
+        Ok(Self {
+            dir,
+            next: AtomicUsize::new(1),
+        })
+    }
+
+    /// Start the transcript of `request`, redacting `secrets`
+    pub(crate) fn start(
+        &self,
+        request: &ExecutionRequest,
+        secrets: Vec<String>,
+    ) -> Result<TranscriptWriter> {
+        let seq = self.next.fetch_add(1, Ordering::Relaxed);
+        let path = self
+            .dir
+            .join(format!("{seq:04}-{}.{EXTENSION}", label(request)));
+        let file = std::fs::File::create(&path)?;
+        Ok(TranscriptWriter {
+            path,
+            file: BufWriter::new(file),
+            secrets: redactions(secrets),
+        })
+    }
+}
+
+/// Strings to redact for `secrets`: each as is and JSON-escaped
+fn redactions(secrets: Vec<String>) -> Vec<String> {
+    let mut redactions: Vec<String> = secrets
+        .into_iter()
+        .filter(|secret| secret.len() >= MIN_SECRET_LEN)
+        .flat_map(|secret| {
+            let escaped = serde_json::to_string(&secret).unwrap_or_default();
+            let escaped = escaped[1..escaped.len() - 1].to_string();
+            [secret, escaped]
+        })
+        .collect();
+    // Longest first, so a secret containing another is redacted whole.
+    redactions.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+    redactions.dedup();
+    redactions
+}
+
+/// Transcript of one request being written
+pub(crate) struct TranscriptWriter {
+    path: PathBuf,
+    file: BufWriter<std::fs::File>,
+    secrets: Vec<String>,
+}
+
+impl TranscriptWriter {
+    /// Append `message` as a redacted JSON line
+    pub(crate) fn write(&mut self, message: &Message) {
+        if let Err(e) = self.try_write(message) {
+            tracing::warn!(path = %self.path.display(), "failed to record message: {e}");
+        }
+    }
+
+    fn try_write(&mut self, message: &Message) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(message).map_err(std::io::Error::other)?;
+        for secret in &self.secrets {
+            line = line.replace(secret.as_str(), REDACTED);
+        }
+        writeln!(self.file, "{line}")?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// `stream` with every message written to `writer`, if given
+pub(crate) fn recorded<'a>(
+    stream: MessageStream<'a>,
+    writer: Option<TranscriptWriter>,
+) -> MessageStream<'a> {
+    match writer {
+        Some(mut writer) => stream
+            .inspect(move |message| {
+                if let Ok(message) = message {
+                    writer.write(message);
+                }
+            })
+            .boxed(),
+        None => stream,
+    }
+}
+
+/// Transcript files of `dir`, in recording order
+fn transcripts(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == EXTENSION) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    Ok(paths)
+}
+
+/// Streams recorded transcripts back in the order they were recorded
+#[derive(Debug)]
+pub struct ReplayBackend {
+    transcripts: Mutex<VecDeque<PathBuf>>,
+    delay: Duration,
+}
+
+impl ReplayBackend {
+    /// Replay the transcripts of `dir`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `dir` can't be read, or `CoreError::ConfigError`
+    /// if it holds no transcripts.
+    pub fn load(dir: &Path) -> Result<Self> {
+        let transcripts = transcripts(dir)?;
+        if transcripts.is_empty() {
+            return Err(CoreError::ConfigError(format!(
+                "no transcripts in {}",
+                dir.display()
+            )));
+        }
+        Ok(Self {
+            transcripts: Mutex::new(transcripts.into()),
+            delay: Duration::ZERO,
+        })
+    }
+
+    /// Wait `delay` before each message, e.g. to pace a demo
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Messages of the next transcript, which must have been recorded for
+    /// a request with the label of `request`
+    fn next(&self, request: &ExecutionRequest) -> Result<Vec<Message>> {
+        let path = self.transcripts.lock().pop_front().ok_or_else(|| {
+            CoreError::AgentExecutionFailed(format!(
+                "no transcript left to replay for `{}`",
+                label(request)
+            ))
+        })?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let recorded = name.split_once('-').map_or("", |(_, label)| label);
+        if recorded != label(request) {
+            return Err(CoreError::AgentExecutionFailed(format!(
+                "transcript {name} was recorded for `{recorded}`, not `{}`",
+                label(request)
+            )));
+        }
+        std::fs::read_to_string(&path)?
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line).map_err(|e| {
+                    CoreError::AgentExecutionFailed(format!("invalid transcript {name}: {e}"))
+                })
+            })
+            .collect()
+    }
+}
+
+impl AgentBackend for ReplayBackend {
+    fn respond<'a>(
+        &'a self,
+        request: &'a ExecutionRequest,
+    ) -> BoxFuture<'a, Result<MessageStream<'a>>> {
+        Box::pin(async move {
+            let messages = self.next(request)?;
+            let delay = self.delay;
+            Ok(stream::iter(messages)
+                .then(move |message| async move {
+                    if !delay.is_zero() {
+                        tokio::time::sleep(delay).await;
+                    }
+                    Ok(message)
+                })
+                .boxed())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn messages() -> Vec<Message> {
+        serde_json::from_value(serde_json::json!([
+            {
+                "type": "assistant",
+                "message": {
+                    "content": [{"type": "text", "text": "Using key sk-ant-secret and \"quoted\\secret\""}],
+                    "model": "claude-sonnet-4-5-20250929"
+                }
+            },
+            {
+                "type": "result",
+                "subtype": "success",
+                "duration_ms": 1200,
+                "duration_api_ms": 1000,
+                "is_error": false,
+                "num_turns": 1,
+                "session_id": "s",
+                "total_cost_usd": 0.25
+            }
+        ]))
+        .unwrap()
+    }
+
+    fn labelled(label: &str) -> ExecutionRequest {
+        ExecutionRequest {
+            label: Some(label.to_string()),
+            ..ExecutionRequest::new("build it")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_record_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let recorder = TranscriptRecorder::create(dir.path()).unwrap();
+        for label in ["observe", "build"] {
+            let writer = recorder
+                .start(
+                    &labelled(label),
+                    vec![
+                        "sk-ant-secret".to_string(),
+                        "\"quoted\\secret\"".to_string(),
+                        "1".to_string(),
+                    ],
+                )
+                .unwrap();
+            let stream = stream::iter(messages().into_iter().map(Ok)).boxed();
+            let replayed: Vec<_> = recorded(stream, Some(writer)).collect().await;
+            assert_eq!(replayed.len(), 2);
+        }
+
+        let observe = std::fs::read_to_string(dir.path().join("0001-observe.jsonl")).unwrap();
+        assert_eq!(observe.lines().count(), 2);
+        assert!(!observe.contains("sk-ant-secret"));
+        assert!(!observe.contains("secret\\\""));
+        assert!(observe.contains("Using key [REDACTED] and [REDACTED]"));
+        assert!(observe.contains("\"num_turns\":1"));
+        assert!(TranscriptRecorder::create(dir.path()).is_err());
+
+        let replay = ReplayBackend::load(dir.path())
+            .unwrap()
+            .with_delay(Duration::from_millis(1));
+        let observe: Vec<_> = replay
+            .respond(&labelled("observe"))
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(observe.len(), 2);
+        assert!(matches!(observe[1], Ok(Message::Result(_))));
+        let err = replay.respond(&labelled("test")).await.err().unwrap();
+        assert!(
+            err.to_string()
+                .contains("transcript 0002-build was recorded for `build`, not `test`")
+        );
+        let err = replay.respond(&labelled("build")).await.err().unwrap();
+        assert!(err.to_string().contains("no transcript left to replay"));
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(ReplayBackend::load(empty.path()).is_err());
+    }
+}
@@ -0,0 +1,422 @@
+//! Structured verification of the acceptance criteria in `specs/verification.md`.
+//!
+//! The checklist items (`- [ ] ...`) are parsed from the file, sent to the
+//! agent with instructions to answer in a fenced JSON block, and the verdicts
+//! are written back as checked/unchecked boxes. The per-criterion results are
+//! stored in the verification `PhaseState`.
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{CoreError, Result};
+use crate::execution::extract_json;
+use crate::state::{PhaseState, PhaseStatus};
+
+/// Name of the phase whose output is verified
+pub const VERIFICATION_PHASE: &str = "verification";
+
+/// Location of the acceptance criteria relative to the feature directory
+pub const VERIFICATION_FILE: &str = "specs/verification.md";
+
+/// Schema of the agent's per-criterion verdicts
+pub const VERIFICATION_SCHEMA: &str =
+    r#"{"criteria": [{"index": 1, "passed": true, "notes": "short justification"}]}"#;
+
+/// Verification settings (`verification:` in `prompts/verification/config.yml`)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VerificationConfig {
+    /// Only warn about failing criteria instead of failing the phase
+    pub warn_only: bool,
+}
+
+/// A checklist item from `verification.md`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Criterion {
+    /// 1-based position among the criteria
+    pub index: usize,
+    /// Criterion text (without the checkbox)
+    pub text: String,
+    /// Whether the box is currently checked
+    pub checked: bool,
+    /// 0-based line number in the source document
+    line: usize,
+}
+
+/// Verdict for a single criterion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CriterionResult {
+    /// 1-based criterion index
+    pub index: usize,
+    /// Criterion text
+    pub criterion: String,
+    /// Whether the criterion is met
+    pub passed: bool,
+    /// Agent's explanation
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+}
+
+/// Per-criterion verification results stored in `PhaseState`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerificationSummary {
+    /// Number of criteria that passed
+    pub passed: usize,
+    /// Number of criteria that failed
+    pub failed: usize,
+    /// Individual results
+    pub results: Vec<CriterionResult>,
+}
+
+impl VerificationSummary {
+    /// Build a summary from individual results
+    pub fn new(results: Vec<CriterionResult>) -> Self {
+        let passed = results.iter().filter(|r| r.passed).count();
+        Self {
+            passed,
+            failed: results.len() - passed,
+            results,
+        }
+    }
+
+    /// Criteria that were not met
+    pub fn failures(&self) -> impl Iterator<Item = &CriterionResult> {
+        self.results.iter().filter(|r| !r.passed)
+    }
+}
+
+/// Result of a verification run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerificationOutcome {
+    /// Status the verification phase should end with
+    pub status: PhaseStatus,
+    /// Per-criterion results
+    pub summary: VerificationSummary,
+}
+
+impl VerificationOutcome {
+    /// Record the outcome on the verification phase state
+    pub fn apply_to(&self, phase: &mut PhaseState) {
+        phase.status = self.status;
+        phase.output_summary = Some(self.output_summary());
+        phase.verification = Some(self.summary.clone());
+    }
+
+    /// Why the phase failed, if it did
+    pub fn error(&self) -> Option<String> {
+        (self.status == PhaseStatus::Failed).then(|| {
+            format!(
+                "Verification failed {} of {} criteria, see {VERIFICATION_FILE}",
+                self.summary.failed,
+                self.summary.results.len()
+            )
+        })
+    }
+
+    /// Output summary of the phase: the counts, and the unmet criteria,
+    /// which is what a retry passes back to the agent
+    pub fn output_summary(&self) -> String {
+        let mut out = format!(
+            "{} of {} criteria passed",
+            self.summary.passed,
+            self.summary.results.len()
+        );
+        if self.summary.failed > 0 {
+            out.push_str("\n\nUnmet criteria:\n");
+            for failure in self.summary.failures() {
+                match failure.notes.as_str() {
+                    "" => out.push_str(&format!("- {}\n", failure.criterion)),
+                    notes => out.push_str(&format!("- {}: {notes}\n", failure.criterion)),
+                }
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AgentReport {
+    criteria: Vec<AgentVerdict>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AgentVerdict {
+    index: usize,
+    passed: bool,
+    #[serde(default)]
+    notes: String,
+}
+
+/// Parse markdown checkboxes (`- [ ]`, `- [x]`, `* [ ]`) into criteria
+pub fn parse_checklist(markdown: &str) -> Vec<Criterion> {
+    markdown
+        .lines()
+        .enumerate()
+        .filter_map(|(line, text)| {
+            let (checked, text) = parse_checkbox(text)?;
+            Some((line, checked, text))
+        })
+        .enumerate()
+        .map(|(i, (line, checked, text))| Criterion {
+            index: i + 1,
+            text: text.to_string(),
+            checked,
+            line,
+        })
+        .collect()
+}
+
+fn parse_checkbox(line: &str) -> Option<(bool, &str)> {
+    let rest = line
+        .trim_start()
+        .strip_prefix(['-', '*', '+'])?
+        .strip_prefix(' ')?;
+    let (checked, text) = match rest.get(..3)? {
+        "[ ]" => (false, &rest[3..]),
+        "[x]" | "[X]" => (true, &rest[3..]),
+        _ => return None,
+    };
+    let text = text.trim();
+    (!text.is_empty()).then_some((checked, text))
+}
+
+/// Instructions asking the agent to evaluate each numbered criterion
+pub fn build_prompt(criteria: &[Criterion]) -> String {
+    let mut prompt = String::from(
+        "## Acceptance Criteria\n\nEvaluate each criterion below against the implementation. \
+         Inspect the code and run commands as needed.\n\n",
+    );
+    for c in criteria {
+        prompt.push_str(&format!("{}. {}\n", c.index, c.text));
+    }
+    prompt.push_str("\nReport a verdict for every criterion, referring to it by number.\n");
+    prompt
+}
+
+/// Parse the agent's JSON verdicts, matching them to the criteria.
+///
+/// Criteria the agent did not report on are treated as failed.
+///
+/// # Errors
+///
+/// Returns `CoreError::InvalidAgentOutput` if no JSON is found or it
+/// doesn't match the expected shape.
+pub fn parse_response(output: &str, criteria: &[Criterion]) -> Result<Vec<CriterionResult>> {
+    let report: AgentReport = extract_json(output)?;
+
+    Ok(criteria
+        .iter()
+        .map(
+            |c| match report.criteria.iter().find(|v| v.index == c.index) {
+                Some(v) => CriterionResult {
+                    index: c.index,
+                    criterion: c.text.clone(),
+                    passed: v.passed,
+                    notes: v.notes.clone(),
+                },
+                None => CriterionResult {
+                    index: c.index,
+                    criterion: c.text.clone(),
+                    passed: false,
+                    notes: "not evaluated by the agent".to_string(),
+                },
+            },
+        )
+        .collect())
+}
+
+/// Rewrite the checkboxes of `markdown` according to `results`
+pub fn apply_results(
+    markdown: &str,
+    criteria: &[Criterion],
+    results: &[CriterionResult],
+) -> String {
+    let mut lines: Vec<String> = markdown.lines().map(String::from).collect();
+    for c in criteria {
+        let Some(result) = results.iter().find(|r| r.index == c.index) else {
+            continue;
+        };
+        // The checkbox is the first bracket on a checklist line.
+        let line = &mut lines[c.line];
+        if let Some(pos) = line.find('[') {
+            let mark = if result.passed { "[x]" } else { "[ ]" };
+            line.replace_range(pos..pos + 3, mark);
+        }
+    }
+    let mut updated = lines.join("\n");
+    if markdown.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated
+}
+
+/// Acceptance criteria of a feature, to append to the verification prompt
+/// with [`build_prompt`].
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or contains no criteria.
+pub fn load_criteria(feature_path: &Path) -> Result<Vec<Criterion>> {
+    let path = feature_path.join(VERIFICATION_FILE);
+    let criteria = parse_checklist(&std::fs::read_to_string(&path)?);
+    if criteria.is_empty() {
+        return Err(CoreError::ConfigError(format!(
+            "no acceptance criteria (checkboxes) found in {}",
+            path.display()
+        )));
+    }
+    Ok(criteria)
+}
+
+/// Record the agent's verdicts on the criteria of a feature: the
+/// checkboxes in `verification.md` are updated in place.
+///
+/// # Errors
+///
+/// Returns an error if the file can't be read or written, contains no
+/// criteria, or the agent's response can't be parsed.
+pub fn record(
+    feature_path: &Path,
+    output: &str,
+    config: &VerificationConfig,
+) -> Result<VerificationOutcome> {
+    let path = feature_path.join(VERIFICATION_FILE);
+    let criteria = load_criteria(feature_path)?;
+    let results = parse_response(output, &criteria)?;
+    let markdown = std::fs::read_to_string(&path)?;
+    std::fs::write(&path, apply_results(&markdown, &criteria, &results))?;
+
+    let summary = VerificationSummary::new(results);
+    for failure in summary.failures() {
+        tracing::warn!(criterion = %failure.criterion, notes = %failure.notes, "criterion failed");
+    }
+    let status = if summary.failed > 0 && !config.warn_only {
+        PhaseStatus::Failed
+    } else {
+        PhaseStatus::Completed
+    };
+    Ok(VerificationOutcome { status, summary })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECKLIST: &str = "# Verification\n\n## Functional\n- [ ] Login returns a JWT\n- [x] Logout clears the session\n* [X] Passwords are hashed\n\nSome prose with [ ] brackets.\n- [ ]\n";
+
+    #[test]
+    fn test_parse_checklist() {
+        let criteria = parse_checklist(CHECKLIST);
+
+        assert_eq!(criteria.len(), 3);
+        assert_eq!(criteria[0].text, "Login returns a JWT");
+        assert!(!criteria[0].checked);
+        assert_eq!(criteria[1].index, 2);
+        assert!(criteria[1].checked);
+        assert_eq!(criteria[2].text, "Passwords are hashed");
+        assert!(criteria[2].checked);
+    }
+
+    #[test]
+    fn test_parse_response_uses_last_json_block() {
+        let criteria = parse_checklist(CHECKLIST);
+        let output = r#"Draft: ```json
+{"criteria": []}
+```
+Final verdict:
+```json
+{"criteria": [
+  {"index": 1, "passed": true, "notes": "token issued"},
+  {"index": 2, "passed": false, "notes": "cookie kept"}
+]}
+```
+Done."#;
+
+        let results = parse_response(output, &criteria).unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].passed);
+        assert_eq!(results[1].notes, "cookie kept");
+        assert!(!results[1].passed);
+        // Criterion 3 was not reported and counts as failed.
+        assert!(!results[2].passed);
+    }
+
+    #[test]
+    fn test_parse_response_errors() {
+        let criteria = parse_checklist(CHECKLIST);
+
+        assert!(matches!(
+            parse_response("all good!", &criteria),
+            Err(CoreError::InvalidAgentOutput(_))
+        ));
+        assert!(matches!(
+            parse_response("```json\n{\"criteria\": 3}\n```", &criteria),
+            Err(CoreError::InvalidAgentOutput(msg)) if msg.contains("\"criteria\": 3")
+        ));
+    }
+
+    #[test]
+    fn test_apply_results_updates_checkboxes() {
+        let criteria = parse_checklist(CHECKLIST);
+        let results = vec![
+            CriterionResult {
+                index: 1,
+                criterion: String::new(),
+                passed: true,
+                notes: String::new(),
+            },
+            CriterionResult {
+                index: 2,
+                criterion: String::new(),
+                passed: false,
+                notes: String::new(),
+            },
+            CriterionResult {
+                index: 3,
+                criterion: String::new(),
+                passed: false,
+                notes: String::new(),
+            },
+        ];
+
+        let updated = apply_results(CHECKLIST, &criteria, &results);
+
+        assert!(updated.contains("- [x] Login returns a JWT"));
+        assert!(updated.contains("- [ ] Logout clears the session"));
+        assert!(updated.contains("* [ ] Passwords are hashed"));
+        assert!(updated.contains("Some prose with [ ] brackets."));
+        assert!(updated.ends_with('\n'));
+    }
+
+    #[test]
+    fn test_outcome_applies_to_phase() {
+        let summary = VerificationSummary::new(vec![CriterionResult {
+            index: 1,
+            criterion: "Login returns a JWT".to_string(),
+            passed: false,
+            notes: "returns 500".to_string(),
+        }]);
+        let outcome = VerificationOutcome {
+            status: PhaseStatus::Failed,
+            summary,
+        };
+        let mut phase = PhaseState::new("verification");
+
+        outcome.apply_to(&mut phase);
+
+        assert_eq!(phase.status, PhaseStatus::Failed);
+        assert_eq!(phase.verification.unwrap().failed, 1);
+        assert_eq!(
+            phase.output_summary.as_deref(),
+            Some("0 of 1 criteria passed\n\nUnmet criteria:\n- Login returns a JWT: returns 500\n")
+        );
+        assert_eq!(
+            outcome.error().as_deref(),
+            Some("Verification failed 1 of 1 criteria, see specs/verification.md")
+        );
+    }
+}
@@ -0,0 +1,96 @@
+//! Variables available to prompt templates.
+
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Variable names of the context itself, which extra variables can't take
+pub const RESERVED_NAMES: &[&str] = &["repo_path", "feature_slug", "feature_id", "phase", "extra"];
+
+/// Context a template is rendered with.
+///
+/// Only essential variables are pre-loaded; the agent reads specs and
+/// source files on demand.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PromptContext {
+    /// Repository (or worktree) path
+    pub repo_path: String,
+    /// Feature slug
+    pub feature_slug: String,
+    /// Feature ID (e.g. "0001")
+    pub feature_id: String,
+    /// Phase being rendered
+    pub phase: Option<String>,
+    /// Additional variables
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl PromptContext {
+    /// Create a context for a feature
+    pub fn new(
+        repo_path: impl Into<String>,
+        feature_slug: impl Into<String>,
+        feature_id: impl Into<String>,
+    ) -> Self {
+        Self {
+            repo_path: repo_path.into(),
+            feature_slug: feature_slug.into(),
+            feature_id: feature_id.into(),
+            phase: None,
+            extra: HashMap::new(),
+        }
+    }
+
+    /// Set the current phase name
+    pub fn with_phase(mut self, phase: impl Into<String>) -> Self {
+        self.phase = Some(phase.into());
+        self
+    }
+
+    /// Add an extra variable.
+    ///
+    /// Keys from [`RESERVED_NAMES`] are rejected when the context is
+    /// rendered.
+    pub fn with_extra(mut self, key: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.extra.insert(key.into(), value.into());
+        self
+    }
+
+    /// Check that no extra variable takes a reserved name.
+    ///
+    /// Extras are flattened next to the built-in variables when the context
+    /// is serialized, so a colliding key would shadow one of them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the colliding keys.
+    pub fn check_extra(&self) -> Result<()> {
+        let mut colliding: Vec<&str> = self
+            .extra
+            .keys()
+            .map(String::as_str)
+            .filter(|key| RESERVED_NAMES.contains(key))
+            .collect();
+        if colliding.is_empty() {
+            return Ok(());
+        }
+        colliding.sort_unstable();
+        bail!(
+            "Extra prompt variables use reserved names: {} (reserved: {})",
+            colliding.join(", "),
+            RESERVED_NAMES.join(", ")
+        )
+    }
+
+    /// Whether the variable `name` is set, e.g. `phase` once given
+    pub fn has(&self, name: &str) -> bool {
+        match name {
+            "repo_path" | "feature_slug" | "feature_id" | "extra" => true,
+            "phase" => self.phase.is_some(),
+            _ => self.extra.contains_key(name),
+        }
+    }
+}
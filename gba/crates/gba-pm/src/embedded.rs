@@ -0,0 +1,66 @@
+//! Default phase templates compiled into the binary from `prompts/`.
+
+/// `(name, content)` of every embedded template
+pub(crate) const TEMPLATES: &[(&str, &str)] = &[
+    (
+        "build/system.md",
+        include_str!("../../../prompts/build/system.md"),
+    ),
+    (
+        "build/user.md",
+        include_str!("../../../prompts/build/user.md"),
+    ),
+    (
+        "init/system.md",
+        include_str!("../../../prompts/init/system.md"),
+    ),
+    (
+        "init/user.md",
+        include_str!("../../../prompts/init/user.md"),
+    ),
+    (
+        "observe/system.md",
+        include_str!("../../../prompts/observe/system.md"),
+    ),
+    (
+        "observe/user.md",
+        include_str!("../../../prompts/observe/user.md"),
+    ),
+    (
+        "plan/system.md",
+        include_str!("../../../prompts/plan/system.md"),
+    ),
+    (
+        "plan/user.md",
+        include_str!("../../../prompts/plan/user.md"),
+    ),
+    (
+        "pr/system.md",
+        include_str!("../../../prompts/pr/system.md"),
+    ),
+    ("pr/user.md", include_str!("../../../prompts/pr/user.md")),
+    (
+        "review/system.md",
+        include_str!("../../../prompts/review/system.md"),
+    ),
+    (
+        "review/user.md",
+        include_str!("../../../prompts/review/user.md"),
+    ),
+    (
+        "test/system.md",
+        include_str!("../../../prompts/test/system.md"),
+    ),
+    (
+        "test/user.md",
+        include_str!("../../../prompts/test/user.md"),
+    ),
+    (
+        "verification/system.md",
+        include_str!("../../../prompts/verification/system.md"),
+    ),
+    (
+        "verification/user.md",
+        include_str!("../../../prompts/verification/user.md"),
+    ),
+];
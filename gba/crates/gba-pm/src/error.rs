@@ -0,0 +1,40 @@
+//! Errors of the prompt manager callers may want to handle.
+//!
+//! They are returned inside `anyhow::Error`; use `downcast_ref` to match
+//! them.
+
+use thiserror::Error;
+
+/// A template that can't be loaded, or a prompt that can't be sent as
+/// rendered
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum PromptError {
+    /// The rendered prompt is estimated to exceed the configured limit
+    #[error(
+        "Prompt {template} is too large: ~{estimated} tokens, the limit is {limit} (trim inlined files with `truncate_tokens`)"
+    )]
+    TooLarge {
+        /// Template (or phase) name
+        template: String,
+        /// Estimated tokens of the rendered prompt
+        estimated: usize,
+        /// Configured `max_prompt_tokens`
+        limit: usize,
+    },
+    /// A template failed to parse
+    #[error("Template {template} is invalid: {message}")]
+    InvalidTemplate {
+        /// Template name
+        template: String,
+        /// Why it failed to parse
+        message: String,
+    },
+    /// Strict rendering found variables the template requires missing
+    #[error("Template {template} requires missing variables: {}", missing.join(", "))]
+    MissingVariables {
+        /// Template name
+        template: String,
+        /// Required variables not in the context
+        missing: Vec<String>,
+    },
+}
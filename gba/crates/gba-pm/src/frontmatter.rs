@@ -0,0 +1,128 @@
+//! YAML frontmatter describing a template.
+//!
+//! A template may start with a block between two `---` lines:
+//!
+//! ```text
+//! ---
+//! description: Implement the feature
+//! requires: [specs, coding_standards]
+//! version: 2
+//! ---
+//! ## Feature: {{ feature_slug }}
+//! ```
+//!
+//! The block is stripped before the template is compiled. Templates without
+//! one have empty metadata.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+
+/// Line opening and closing the frontmatter
+const DELIMITER: &str = "---";
+
+/// Metadata a template declares in its frontmatter
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplateMetadata {
+    /// What the template asks the agent to do
+    pub description: Option<String>,
+    /// Context variables the template needs, checked by strict rendering
+    pub requires: Vec<String>,
+    /// Template version, e.g. `2` or `1.1`
+    #[serde(deserialize_with = "scalar")]
+    pub version: Option<String>,
+}
+
+/// Deserialize a YAML scalar as a string, so `version: 2` is `"2"`
+fn scalar<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value = Option::<serde_yaml::Value>::deserialize(deserializer)?;
+    match value {
+        None | Some(serde_yaml::Value::Null) => Ok(None),
+        Some(serde_yaml::Value::String(s)) => Ok(Some(s)),
+        Some(serde_yaml::Value::Number(n)) => Ok(Some(n.to_string())),
+        Some(serde_yaml::Value::Bool(b)) => Ok(Some(b.to_string())),
+        Some(_) => Err(serde::de::Error::custom("expected a string or number")),
+    }
+}
+
+/// Split `content` into its frontmatter and the template body.
+///
+/// Content that doesn't open with a `---` line, or never closes it, is all
+/// body.
+///
+/// # Errors
+///
+/// Returns an error if the frontmatter isn't valid metadata.
+pub(crate) fn split(content: &str) -> Result<(TemplateMetadata, &str)> {
+    let Some(rest) = strip_delimiter(content) else {
+        return Ok((TemplateMetadata::default(), content));
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == DELIMITER {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            let metadata = if yaml.trim().is_empty() {
+                TemplateMetadata::default()
+            } else {
+                serde_yaml::from_str(yaml).context("Invalid frontmatter")?
+            };
+            return Ok((metadata, body));
+        }
+        offset += line.len();
+    }
+    Ok((TemplateMetadata::default(), content))
+}
+
+/// `content` after its opening `---` line, if it has one
+fn strip_delimiter(content: &str) -> Option<&str> {
+    let rest = content.strip_prefix(DELIMITER)?;
+    rest.strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_frontmatter() {
+        let content =
+            "---\ndescription: Build it\nrequires: [specs]\nversion: 2\n---\nBuild {{ specs }}\n";
+        let (metadata, body) = split(content).unwrap();
+        assert_eq!(
+            metadata,
+            TemplateMetadata {
+                description: Some("Build it".to_string()),
+                requires: vec!["specs".to_string()],
+                version: Some("2".to_string()),
+            }
+        );
+        assert_eq!(body, "Build {{ specs }}\n");
+
+        let (metadata, body) = split("---\r\n---\r\nBody").unwrap();
+        assert_eq!(metadata, TemplateMetadata::default());
+        assert_eq!(body, "Body");
+    }
+
+    #[test]
+    fn test_content_without_frontmatter_is_all_body() {
+        for content in [
+            "Build {{ specs }}",
+            "---\nno closing line",
+            "--- \nx\n---\n",
+            "",
+        ] {
+            let (metadata, body) = split(content).unwrap();
+            assert_eq!(metadata, TemplateMetadata::default());
+            assert_eq!(body, content);
+        }
+    }
+
+    #[test]
+    fn test_invalid_frontmatter() {
+        let err = split("---\ndescripton: typo\n---\nBody").unwrap_err();
+        assert!(format!("{err:#}").contains("unknown field `descripton`"));
+        assert!(split("---\nversion: [1]\n---\nBody").is_err());
+    }
+}
@@ -0,0 +1,469 @@
+//! Functions available to prompt templates.
+
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Utc};
+use minijinja::value::ValueKind;
+use minijinja::{Error, ErrorKind, State, Value};
+
+/// Default cap on the bytes `read_file` inlines
+pub const DEFAULT_READ_FILE_MAX_BYTES: usize = 256 * 1024;
+
+/// What `read_file` does with a file larger than its cap
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OversizedFile {
+    /// Inline the first bytes, followed by a truncation marker
+    #[default]
+    Truncate,
+    /// Fail the render
+    Error,
+}
+
+/// Counts the tokens of text for the `approx_tokens` filter
+pub trait Tokenizer: Send + Sync + fmt::Debug {
+    /// Tokens in `text`
+    fn count(&self, text: &str) -> usize;
+}
+
+/// The engine's estimate of one token per 4 characters, see
+/// [`gba_core::text::estimate_tokens`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ApproxTokenizer;
+
+impl Tokenizer for ApproxTokenizer {
+    fn count(&self, text: &str) -> usize {
+        gba_core::text::estimate_tokens(text)
+    }
+}
+
+/// Tells `now()` the time
+pub trait Clock: Send + Sync + fmt::Debug {
+    /// The current time
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The system clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// `now(format)`: the current UTC time formatted with a `strftime` format
+/// (default RFC 3339, e.g. `2026-03-01T09:30:00Z`)
+pub(crate) fn now(clock: &dyn Clock, format: Option<&str>) -> Result<String, Error> {
+    let format = format.unwrap_or("%Y-%m-%dT%H:%M:%SZ");
+    let items: Vec<Item<'_>> = StrftimeItems::new(format).collect();
+    if items.contains(&Item::Error) {
+        return Err(filter_error("now", format!("invalid format `{format}`")));
+    }
+    Ok(clock.now().format_with_items(items.into_iter()).to_string())
+}
+
+/// `env(name)`: the environment variable `name` (undefined if unset), only
+/// if it is on the `allowlist`
+pub(crate) fn env(name: &str, allowlist: &[String]) -> Result<Value, Error> {
+    if !allowlist.iter().any(|allowed| allowed == name) {
+        return Err(filter_error(
+            "env",
+            format!("{name} is not in templateEnvAllowlist"),
+        ));
+    }
+    Ok(std::env::var(name).map_or(Value::UNDEFINED, Value::from))
+}
+
+/// `value` lowercased, with every run of characters other than ASCII
+/// letters and digits replaced by one `-`, e.g. `User Auth!` -> `user-auth`
+pub(crate) fn slugify(value: &str) -> String {
+    let mut slug = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    if slug.ends_with('-') {
+        slug.pop();
+    }
+    slug
+}
+
+/// What rendering does with a prompt over `max_prompt_tokens`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OversizedPrompt {
+    /// Fail with [`crate::PromptError::TooLarge`]
+    #[default]
+    Error,
+    /// Log a warning and return the prompt
+    Warn,
+}
+
+/// The longest prefix of `text` estimated at most `max_tokens` by
+/// `tokenizer`, marked as truncated if anything was cut
+pub(crate) fn truncate_tokens(tokenizer: &dyn Tokenizer, text: &str, max_tokens: usize) -> String {
+    if tokenizer.count(text) <= max_tokens {
+        return text.to_string();
+    }
+    let ends: Vec<usize> = text
+        .char_indices()
+        .map(|(idx, _)| idx)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    // Longest prefix that fits, by binary search over character boundaries.
+    let (mut fits, mut too_long) = (0, ends.len() - 1);
+    while too_long - fits > 1 {
+        let mid = (fits + too_long) / 2;
+        if tokenizer.count(&text[..ends[mid]]) <= max_tokens {
+            fits = mid;
+        } else {
+            too_long = mid;
+        }
+    }
+    format!(
+        "{}\n[... truncated to ~{max_tokens} tokens ...]\n",
+        &text[..ends[fits]]
+    )
+}
+
+/// Render error of the filter (or function) `filter`
+fn filter_error(filter: &str, detail: impl fmt::Display) -> Error {
+    Error::new(ErrorKind::InvalidOperation, format!("{filter}: {detail}"))
+}
+
+/// Fail `filter` unless `value` is plain data: undefined values and
+/// functions would otherwise be serialized as `null` or their name
+fn check_data(filter: &str, value: &Value) -> Result<(), Error> {
+    match value.kind() {
+        ValueKind::Undefined | ValueKind::Invalid | ValueKind::Plain | ValueKind::Iterable => {
+            Err(filter_error(
+                filter,
+                format!("can't serialize {} value {value}", value.kind()),
+            ))
+        }
+        ValueKind::Seq | ValueKind::Map => {
+            for key in value.try_iter()? {
+                check_data(filter, &key)?;
+                if value.kind() == ValueKind::Map {
+                    check_data(filter, &value.get_item(&key)?)?;
+                }
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `value | tojson`: `value` as pretty-printed JSON
+pub(crate) fn tojson(value: &Value) -> Result<String, Error> {
+    check_data("tojson", value)?;
+    serde_json::to_string_pretty(value).map_err(|e| filter_error("tojson", e))
+}
+
+/// `value | toyaml`: `value` as YAML, without a trailing newline
+pub(crate) fn toyaml(value: &Value) -> Result<String, Error> {
+    check_data("toyaml", value)?;
+    let yaml = serde_yaml::to_string(value).map_err(|e| filter_error("toyaml", e))?;
+    Ok(yaml.trim_end().to_string())
+}
+
+/// `text | truncate_chars(n)`: the first `n` characters of `text`, followed
+/// by `…` if anything was cut
+pub(crate) fn truncate_chars(text: &str, max_chars: usize) -> String {
+    gba_core::text::truncate_chars(text, max_chars, "…")
+}
+
+/// `text | wrap(width)`: `text` with each line word-wrapped at `width`
+/// characters; longer words get a line of their own, and width 0 leaves
+/// the text as is
+pub(crate) fn wrap(text: &str, width: usize) -> String {
+    if width == 0 {
+        return text.to_string();
+    }
+    let mut wrapped = Vec::new();
+    for line in text.split('\n') {
+        let mut current = String::new();
+        let mut current_width = 0;
+        for word in line.split_whitespace() {
+            let word_width = word.chars().count();
+            if current_width > 0 && current_width + 1 + word_width > width {
+                wrapped.push(std::mem::take(&mut current));
+                current_width = 0;
+            }
+            if current_width > 0 {
+                current.push(' ');
+                current_width += 1;
+            }
+            current.push_str(word);
+            current_width += word_width;
+        }
+        wrapped.push(current);
+    }
+    wrapped.join("\n")
+}
+
+/// `path | relpath` or `path | relpath(base)`: `path` relative to `base`
+/// (default: the rendered `repo_path`), with `..` where it lies outside.
+///
+/// Paths are compared as written, without touching the filesystem; a
+/// relative `path`, or one without a common root with `base`, is returned
+/// as is.
+pub(crate) fn relpath(state: &State, path: &str, base: Option<&str>) -> String {
+    let base = match base {
+        Some(base) => PathBuf::from(base),
+        None => match repo_path(state) {
+            Some(repo_path) => repo_path,
+            None => return path.to_string(),
+        },
+    };
+    relative_to(Path::new(path), &base)
+        .map(|relative| relative.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// `path` relative to `base`, if both are absolute
+fn relative_to(path: &Path, base: &Path) -> Option<PathBuf> {
+    if !path.is_absolute() || !base.is_absolute() {
+        return None;
+    }
+    let path: Vec<_> = path.components().collect();
+    let base: Vec<_> = base.components().collect();
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    // Different roots (e.g. Windows drives) have nothing in common.
+    if common == 0 {
+        return None;
+    }
+    let mut relative: PathBuf = base[common..].iter().map(|_| "..").collect();
+    relative.extend(&path[common..]);
+    if relative.as_os_str().is_empty() {
+        relative.push(".");
+    }
+    Some(relative)
+}
+
+/// Settings of `read_file`
+#[derive(Debug, Clone)]
+pub(crate) struct ReadFileOptions {
+    /// Directory files must lie within (None = the rendered `repo_path`)
+    pub root: Option<PathBuf>,
+    /// Most bytes inlined
+    pub max_bytes: usize,
+    /// Handling of larger files
+    pub oversized: OversizedFile,
+}
+
+impl Default for ReadFileOptions {
+    fn default() -> Self {
+        Self {
+            root: None,
+            max_bytes: DEFAULT_READ_FILE_MAX_BYTES,
+            oversized: OversizedFile::default(),
+        }
+    }
+}
+
+/// `read_file(path)`: the content of a file.
+///
+/// Relative paths are resolved against the `repo_path` being rendered with,
+/// so templates work whatever directory gba runs from; without a
+/// `repo_path` they are relative to the current directory.
+///
+/// Paths may come from data (e.g. extra variables), so the file must lie
+/// within `root`, or within `repo_path` (the current directory) if no root
+/// is configured, after resolving symlinks and `..`.
+///
+/// Files over `max_bytes` are cut at a character boundary and marked as
+/// truncated, or rejected with [`OversizedFile::Error`].
+pub(crate) fn read_file(
+    state: &State,
+    path: &str,
+    options: &ReadFileOptions,
+) -> Result<String, Error> {
+    let resolved = resolve(state, path);
+    let read_error = |e: std::io::Error| {
+        Error::new(
+            ErrorKind::InvalidOperation,
+            format!("Failed to read file {}: {e}", resolved.display()),
+        )
+    };
+
+    let canonical = resolved.canonicalize().map_err(read_error)?;
+    check_sandbox(
+        state,
+        "read_file",
+        path,
+        &canonical,
+        options.root.as_deref(),
+    )?;
+    read_capped(&canonical, path, options).map_err(read_error)
+}
+
+/// `path` resolved against the rendered `repo_path`
+pub(crate) fn resolve(state: &State, path: &str) -> PathBuf {
+    match repo_path(state) {
+        Some(repo_path) => repo_path.join(path),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Fail `function` unless the `canonical` form of `path` lies within `root`,
+/// or within `repo_path` (the current directory) if no root is configured
+pub(crate) fn check_sandbox(
+    state: &State,
+    function: &str,
+    path: &str,
+    canonical: &Path,
+    root: Option<&Path>,
+) -> Result<(), Error> {
+    let root = match root {
+        Some(root) => root.to_path_buf(),
+        None => repo_path(state).unwrap_or_else(|| PathBuf::from(".")),
+    };
+    let root = root.canonicalize().map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidOperation,
+            format!("Invalid {function} root {}: {e}", root.display()),
+        )
+    })?;
+    if !canonical.starts_with(&root) {
+        return Err(Error::new(
+            ErrorKind::InvalidOperation,
+            format!("{function} denied: {path} is outside of {}", root.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// Read at most `options.max_bytes` of `file`
+fn read_capped(file: &Path, path: &str, options: &ReadFileOptions) -> std::io::Result<String> {
+    let size = std::fs::metadata(file)?.len();
+    let max_bytes = options.max_bytes as u64;
+    if size <= max_bytes {
+        return std::fs::read_to_string(file);
+    }
+    if options.oversized == OversizedFile::Error {
+        return Err(std::io::Error::other(format!(
+            "{size} bytes exceed the read_file limit of {max_bytes} bytes"
+        )));
+    }
+
+    let mut bytes = Vec::with_capacity(options.max_bytes);
+    std::fs::File::open(file)?
+        .take(max_bytes)
+        .read_to_end(&mut bytes)?;
+    // Drop a character cut in half by the limit; invalid UTF-8 elsewhere is
+    // still an error.
+    if let Err(e) = std::str::from_utf8(&bytes) {
+        if e.error_len().is_some() {
+            return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e));
+        }
+        bytes.truncate(e.valid_up_to());
+    }
+    let mut content = String::from_utf8(bytes).map_err(std::io::Error::other)?;
+    let omitted = size - content.len() as u64;
+    content.push_str(&format!(
+        "\n[... {path} truncated, {omitted} more bytes ...]\n"
+    ));
+    Ok(content)
+}
+
+/// The render's non-empty `repo_path`
+fn repo_path(state: &State) -> Option<PathBuf> {
+    state
+        .lookup("repo_path")
+        .and_then(|value| value.as_str().map(PathBuf::from))
+        .filter(|repo_path| !repo_path.as_os_str().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approx_tokens() {
+        let tokens = |text: &str| ApproxTokenizer.count(text);
+        assert_eq!(tokens(""), 0);
+        assert_eq!(tokens("a"), 1);
+        assert_eq!(tokens("fn main() {}"), 3);
+        // Characters, not bytes.
+        assert_eq!(tokens("héllo wörld"), 3);
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(100);
+        assert_eq!(tokens(&text), 1125);
+
+        let mut previous = 0;
+        for len in (0..text.len()).step_by(7) {
+            let estimate = tokens(&text[..len]);
+            assert!(estimate >= previous);
+            previous = estimate;
+        }
+        assert!(tokens(&text) > tokens(&text[..100]));
+    }
+
+    #[test]
+    fn test_truncate_chars() {
+        assert_eq!(truncate_chars("", 3), "");
+        assert_eq!(truncate_chars("abc", 3), "abc");
+        assert_eq!(truncate_chars("abcd", 3), "abc…");
+        assert_eq!(truncate_chars("héllo wörld", 7), "héllo w…");
+        assert_eq!(truncate_chars("日本語テキスト", 2), "日本…");
+        assert_eq!(truncate_chars("abc", 0), "…");
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(wrap("", 10), "");
+        assert_eq!(wrap("the quick brown fox", 10), "the quick\nbrown fox");
+        assert_eq!(wrap("the quick brown fox", 0), "the quick brown fox");
+        assert_eq!(wrap("a verylongword b", 4), "a\nverylongword\nb");
+        assert_eq!(wrap("héllo wörld ünïcode", 11), "héllo wörld\nünïcode");
+        assert_eq!(wrap("one two\n\nthree four", 7), "one two\n\nthree\nfour");
+    }
+
+    #[test]
+    fn test_relative_to() {
+        let relative = |path: &str, base: &str| relative_to(Path::new(path), Path::new(base));
+        assert_eq!(
+            relative("/repo/src/lib.rs", "/repo"),
+            Some("src/lib.rs".into())
+        );
+        assert_eq!(relative("/repo", "/repo"), Some(".".into()));
+        assert_eq!(
+            relative("/repo/src", "/repo/docs/api"),
+            Some("../../src".into())
+        );
+        assert_eq!(
+            relative("/tmp/ünï.md", "/repo"),
+            Some("../tmp/ünï.md".into())
+        );
+        assert_eq!(relative("src/lib.rs", "/repo"), None);
+        assert_eq!(relative("/repo/src", "repo"), None);
+    }
+
+    #[test]
+    fn test_truncate_tokens() {
+        assert_eq!(truncate_tokens(&ApproxTokenizer, "short", 10), "short");
+        assert_eq!(truncate_tokens(&ApproxTokenizer, "", 0), "");
+        assert_eq!(
+            truncate_tokens(&ApproxTokenizer, "ééééé fin", 1),
+            "éééé\n[... truncated to ~1 tokens ...]\n"
+        );
+        assert_eq!(
+            truncate_tokens(&ApproxTokenizer, "abc", 0),
+            "\n[... truncated to ~0 tokens ...]\n"
+        );
+    }
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("User Auth"), "user-auth");
+        assert_eq!(slugify("  --Add OAuth2 (v2)!  "), "add-oauth2-v2");
+        assert_eq!(slugify("café"), "caf");
+        assert_eq!(slugify("!!!"), "");
+    }
+}
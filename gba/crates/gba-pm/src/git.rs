@@ -0,0 +1,189 @@
+//! Template functions reading the git history of a repository.
+//!
+//! They shell out to `git` in a directory that must pass the same sandbox
+//! as `read_file`. When git can't answer, e.g. because the directory isn't
+//! a repository, the functions render a placeholder saying why instead of
+//! failing the prompt.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use minijinja::{Error, ErrorKind, State};
+
+use crate::functions::{check_sandbox, resolve};
+
+/// Default cap on the bytes of diff `git_diff` inlines
+pub const DEFAULT_GIT_DIFF_MAX_BYTES: usize = 64 * 1024;
+
+/// Commits `git_log` lists unless told otherwise
+const DEFAULT_LOG_COMMITS: usize = 10;
+
+/// Settings of the git functions
+#[derive(Debug, Clone)]
+pub(crate) struct GitOptions {
+    /// Directory repositories must lie within (None = the rendered `repo_path`)
+    pub(crate) root: Option<PathBuf>,
+    /// Bytes of `git_diff` output kept
+    pub(crate) diff_max_bytes: usize,
+}
+
+impl Default for GitOptions {
+    fn default() -> Self {
+        Self {
+            root: None,
+            diff_max_bytes: DEFAULT_GIT_DIFF_MAX_BYTES,
+        }
+    }
+}
+
+/// `current_branch(repo)`: the branch checked out in `repo`, or `HEAD` if
+/// it is detached
+pub(crate) fn current_branch(
+    state: &State,
+    repo: &str,
+    options: &GitOptions,
+) -> Result<String, Error> {
+    let dir = repository(state, "current_branch", repo, options)?;
+    Ok(
+        match git("current_branch", &dir, &["branch", "--show-current"]) {
+            Ok(branch) if branch.trim().is_empty() => "HEAD".to_string(),
+            Ok(branch) => branch.trim().to_string(),
+            Err(placeholder) => placeholder,
+        },
+    )
+}
+
+/// `git_log(repo, n)`: subject lines of the last `n` (default 10) commits,
+/// newest first
+pub(crate) fn git_log(
+    state: &State,
+    repo: &str,
+    n: Option<usize>,
+    options: &GitOptions,
+) -> Result<String, Error> {
+    let dir = repository(state, "git_log", repo, options)?;
+    let count = format!("-n{}", n.unwrap_or(DEFAULT_LOG_COMMITS));
+    Ok(
+        match git(
+            "git_log",
+            &dir,
+            &["log", &count, "--format=%s", "--no-color"],
+        ) {
+            Ok(log) => log.trim_end().to_string(),
+            Err(placeholder) => placeholder,
+        },
+    )
+}
+
+/// `git_diff(repo, base)`: unified diff of the working tree against `base`,
+/// cut after `diff_max_bytes` with a truncation marker
+pub(crate) fn git_diff(
+    state: &State,
+    repo: &str,
+    base: &str,
+    options: &GitOptions,
+) -> Result<String, Error> {
+    check_ref("git_diff", base)?;
+    let dir = repository(state, "git_diff", repo, options)?;
+    let args = ["diff", "--no-color", "--no-ext-diff", base, "--"];
+    Ok(match git("git_diff", &dir, &args) {
+        Ok(diff) => truncate(diff, options.diff_max_bytes),
+        Err(placeholder) => placeholder,
+    })
+}
+
+/// `repo` resolved against `repo_path`, if it is a directory within the
+/// sandbox
+fn repository(
+    state: &State,
+    function: &str,
+    repo: &str,
+    options: &GitOptions,
+) -> Result<PathBuf, Error> {
+    let canonical = resolve(state, repo).canonicalize().map_err(|e| {
+        Error::new(
+            ErrorKind::InvalidOperation,
+            format!("{function}: invalid repository {repo}: {e}"),
+        )
+    })?;
+    check_sandbox(state, function, repo, &canonical, options.root.as_deref())?;
+    Ok(canonical)
+}
+
+/// Reject refs git could take for an option, or that aren't plain ref or
+/// revision syntax (`main`, `origin/main`, `HEAD~3`, a SHA)
+fn check_ref(function: &str, reference: &str) -> Result<(), Error> {
+    let valid = !reference.is_empty()
+        && !reference.starts_with('-')
+        && reference
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._/-~^@{}".contains(c));
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::new(
+            ErrorKind::InvalidOperation,
+            format!("{function}: invalid ref `{reference}`"),
+        ))
+    }
+}
+
+/// Standard output of `git args` run in `dir`, or the placeholder to render
+/// when it fails
+fn git(function: &str, dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| format!("[{function} unavailable: can't run git: {e}]"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason = stderr.lines().next().unwrap_or("git failed").trim();
+        let reason = reason.strip_prefix("fatal: ").unwrap_or(reason);
+        return Err(format!("[{function} unavailable: {reason}]"));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `diff` cut to at most `max_bytes` at a character boundary, marked as
+/// truncated
+fn truncate(mut diff: String, max_bytes: usize) -> String {
+    if diff.len() <= max_bytes {
+        return diff;
+    }
+    let mut end = max_bytes;
+    while !diff.is_char_boundary(end) {
+        end -= 1;
+    }
+    let omitted = diff.len() - end;
+    diff.truncate(end);
+    diff.push_str(&format!(
+        "\n[... diff truncated, {omitted} more bytes ...]\n"
+    ));
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_ref() {
+        for valid in ["main", "origin/main", "HEAD~3", "HEAD^", "a1b2c3d", "@{u}"] {
+            assert!(check_ref("git_diff", valid).is_ok(), "{valid}");
+        }
+        for invalid in ["", "--output=/tmp/x", "-p", "main; rm", "a b", "main\n"] {
+            assert!(check_ref("git_diff", invalid).is_err(), "{invalid:?}");
+        }
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short".to_string(), 10), "short");
+        assert_eq!(
+            truncate("héllo".to_string(), 2),
+            "h\n[... diff truncated, 5 more bytes ...]\n"
+        );
+    }
+}
@@ -1,71 +1,604 @@
-use anyhow::{Context, Result};
-use minijinja::{context, Environment};
+use anyhow::{Context, Result, bail};
+use minijinja::{Environment, State, UndefinedBehavior, Value, context};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+mod context;
+mod embedded;
+mod error;
+mod frontmatter;
+mod functions;
+mod git;
+
+pub use context::{PromptContext, RESERVED_NAMES};
+pub use error::PromptError;
+pub use frontmatter::TemplateMetadata;
+pub use functions::{
+    ApproxTokenizer, Clock, DEFAULT_READ_FILE_MAX_BYTES, OversizedFile, OversizedPrompt,
+    SystemClock, Tokenizer,
+};
+pub use git::DEFAULT_GIT_DIFF_MAX_BYTES;
 
 /// Prompt template
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PromptTemplate {
     /// Template name
     pub name: String,
-    /// Template content
+    /// Template content, frontmatter included
     pub content: String,
     /// Template variables
     pub variables: Vec<String>,
 }
 
+/// The files a phase provides
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TemplateInfo {
+    /// Phase name, e.g. `build`
+    pub name: String,
+    /// Whether `system.md` is loaded
+    pub has_system: bool,
+    /// Whether `user.md` is loaded
+    pub has_user: bool,
+    /// Whether the template directory has the phase's `config.yml`
+    pub has_config: bool,
+    /// Template directory the phase's `user.md` (else `system.md`) was
+    /// loaded from, `None` for a built-in or code-added template
+    pub source: Option<PathBuf>,
+    /// Description from the frontmatter of `user.md`, else `system.md`
+    pub description: Option<String>,
+    /// Variables the phase's templates require, sorted
+    pub requires: Vec<String>,
+    /// Version from the frontmatter of `user.md`, else `system.md`
+    pub version: Option<String>,
+}
+
+/// Task config file in a phase's template directory
+const TASK_CONFIG_FILE: &str = "config.yml";
+
+/// A template that failed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateIssue {
+    /// Template name
+    pub template: String,
+    /// What went wrong
+    pub message: String,
+}
+
+impl fmt::Display for TemplateIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.template, self.message)
+    }
+}
+
 /// Prompt manager for handling templates
 pub struct PromptManager {
     env: Environment<'static>,
     templates: HashMap<String, PromptTemplate>,
+    sources: HashMap<String, PathBuf>,
+    metadata: HashMap<String, TemplateMetadata>,
+    strict: bool,
+    read_file: functions::ReadFileOptions,
+    git: git::GitOptions,
+    tokenizer: Arc<dyn Tokenizer>,
+    clock: Arc<dyn Clock>,
+    env_allowlist: Arc<Vec<String>>,
+    template_dirs: Vec<PathBuf>,
+    max_prompt_tokens: Option<usize>,
+    oversized_prompt: OversizedPrompt,
 }
 
 impl PromptManager {
-    /// Create a new prompt manager
+    /// Create a new prompt manager.
+    ///
+    /// Templates can call `read_file(path)`, which resolves relative paths
+    /// against `repo_path`, only reads files within it and truncates them
+    /// after [`DEFAULT_READ_FILE_MAX_BYTES`]; see [`PromptManager::set_root`]
+    /// and [`PromptManager::set_read_file_limit`]. `current_branch(repo)`,
+    /// `git_log(repo, n)` and `git_diff(repo, base)` ask git about a
+    /// repository in the same bounds, the diff cut after
+    /// [`DEFAULT_GIT_DIFF_MAX_BYTES`]. `now(format)` formats the current
+    /// UTC time, `uuid()` returns a random UUID and `env(name)` reads the
+    /// variables of [`PromptManager::set_env_allowlist`]. The `approx_tokens`
+    /// filter estimates the tokens of a string with [`ApproxTokenizer`],
+    /// `truncate_tokens(n)` cuts it to about `n` tokens, and `slugify` turns
+    /// it into a lowercase, dash-separated slug; `tojson`, `toyaml`,
+    /// `truncate_chars(n)`, `wrap(width)` and `relpath(base)` format data,
+    /// text and paths. Prompts of any size render
+    /// until [`PromptManager::set_max_prompt_tokens`] sets a limit.
     pub fn new() -> Self {
-        Self {
+        let mut pm = Self {
             env: Environment::new(),
             templates: HashMap::new(),
+            sources: HashMap::new(),
+            metadata: HashMap::new(),
+            strict: false,
+            read_file: functions::ReadFileOptions::default(),
+            git: git::GitOptions::default(),
+            tokenizer: Arc::new(ApproxTokenizer),
+            clock: Arc::new(SystemClock),
+            env_allowlist: Arc::new(Vec::new()),
+            template_dirs: Vec::new(),
+            max_prompt_tokens: None,
+            oversized_prompt: OversizedPrompt::default(),
+        };
+        pm.install_functions();
+        pm
+    }
+
+    /// Create a prompt manager with the default phase templates built into
+    /// gba, e.g. `build/user.md`.
+    ///
+    /// Templates loaded afterwards with [`PromptManager::load_templates`]
+    /// replace the embedded ones of the same name, so a project's
+    /// `prompts/` only needs the templates it customizes.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`PromptError::InvalidTemplate`] if an embedded template
+    /// fails to parse.
+    pub fn with_embedded_defaults() -> Result<Self, PromptError> {
+        let mut pm = Self::new();
+        for (name, content) in embedded::TEMPLATES {
+            let invalid = |e: anyhow::Error| PromptError::InvalidTemplate {
+                template: (*name).to_string(),
+                message: format!("{e:#}"),
+            };
+            let variables = pm.variables(content).map_err(invalid)?;
+            pm.add_template(PromptTemplate {
+                name: (*name).to_string(),
+                content: (*content).to_string(),
+                variables,
+            })
+            .map_err(invalid)?;
         }
+        Ok(pm)
     }
 
-    /// Load templates from a directory
-    pub fn load_templates(&mut self, _template_dir: &Path) -> Result<()> {
-        // TODO: Implement template loading from directory
+    /// Create a prompt manager resolving templates through `roots`, in
+    /// order of precedence, then the embedded defaults.
+    ///
+    /// A template is taken from the first root providing it, e.g. a repo's
+    /// `prompts/` before a global `~/.gba/prompts`, so each root only needs
+    /// the templates it customizes. Missing roots are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a root's templates can't be read or parsed.
+    pub fn with_search_path(roots: Vec<PathBuf>) -> Result<Self> {
+        let mut pm = Self::with_embedded_defaults()?;
+        // Loaded lowest precedence first, so each root replaces the ones
+        // after it.
+        for root in roots.iter().rev() {
+            pm.load_templates(root)?;
+        }
+        Ok(pm)
+    }
+
+    /// Only let `read_file` and the git functions access paths within
+    /// `root` instead of the rendered `repo_path`
+    pub fn set_root(&mut self, root: impl Into<PathBuf>) {
+        let root = root.into();
+        self.read_file.root = Some(root.clone());
+        self.git.root = Some(root);
+        self.install_functions();
+    }
+
+    /// Let `git_diff` inline at most `max_bytes` of a diff
+    pub fn set_git_diff_limit(&mut self, max_bytes: usize) {
+        self.git.diff_max_bytes = max_bytes;
+        self.install_functions();
+    }
+
+    /// Let `read_file` inline at most `max_bytes` of a file, handling
+    /// larger files as `oversized` says
+    pub fn set_read_file_limit(&mut self, max_bytes: usize, oversized: OversizedFile) {
+        self.read_file.max_bytes = max_bytes;
+        self.read_file.oversized = oversized;
+        self.install_functions();
+    }
+
+    /// Count tokens for `approx_tokens` with `tokenizer` instead of
+    /// [`ApproxTokenizer`]
+    pub fn set_tokenizer(&mut self, tokenizer: Arc<dyn Tokenizer>) {
+        self.tokenizer = tokenizer;
+        self.install_functions();
+    }
+
+    /// Tell `now()` the time with `clock` instead of [`SystemClock`]
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+        self.install_functions();
+    }
+
+    /// Let `env(name)` read the environment variables `names`; reading any
+    /// other fails the render
+    pub fn set_env_allowlist(&mut self, names: impl IntoIterator<Item = impl Into<String>>) {
+        self.env_allowlist = Arc::new(names.into_iter().map(Into::into).collect());
+        self.install_functions();
+    }
+
+    /// Handle rendered prompts estimated at more than `limit` tokens (None
+    /// = no limit) as `oversized` says
+    pub fn set_max_prompt_tokens(&mut self, limit: Option<usize>, oversized: OversizedPrompt) {
+        self.max_prompt_tokens = limit;
+        self.oversized_prompt = oversized;
+    }
+
+    /// Estimated tokens of a rendered prompt, counted by the tokenizer of
+    /// `approx_tokens`
+    pub fn estimate_tokens(&self, rendered: &str) -> usize {
+        self.tokenizer.count(rendered)
+    }
+
+    /// The start of `text` that is estimated to fit in `max_tokens`, with a
+    /// truncation marker if anything was cut; e.g. to fit a file inlined
+    /// into a prompt
+    pub fn truncate_to_tokens(&self, text: &str, max_tokens: usize) -> String {
+        functions::truncate_tokens(self.tokenizer.as_ref(), text, max_tokens)
+    }
+
+    /// Check a prompt of `estimated` tokens against `max_prompt_tokens`
+    fn check_size(&self, template: &str, estimated: usize) -> Result<()> {
+        let Some(limit) = self.max_prompt_tokens.filter(|limit| estimated > *limit) else {
+            return Ok(());
+        };
+        let error = PromptError::TooLarge {
+            template: template.to_string(),
+            estimated,
+            limit,
+        };
+        match self.oversized_prompt {
+            OversizedPrompt::Error => Err(error.into()),
+            OversizedPrompt::Warn => {
+                tracing::warn!("{error}");
+                Ok(())
+            }
+        }
+    }
+
+    fn install_functions(&mut self) {
+        self.env
+            .add_filter("slugify", |value: &str| functions::slugify(value));
+        self.env.add_filter("tojson", functions::tojson);
+        self.env.add_filter("toyaml", functions::toyaml);
+        self.env
+            .add_filter("truncate_chars", functions::truncate_chars);
+        self.env.add_filter("wrap", functions::wrap);
+        self.env.add_filter("relpath", functions::relpath);
+        let tokenizer = self.tokenizer.clone();
+        self.env
+            .add_filter("approx_tokens", move |text: &str| tokenizer.count(text));
+        let tokenizer = self.tokenizer.clone();
+        self.env
+            .add_filter("truncate_tokens", move |text: &str, max_tokens: usize| {
+                functions::truncate_tokens(tokenizer.as_ref(), text, max_tokens)
+            });
+        let clock = self.clock.clone();
+        self.env.add_function("now", move |format: Option<&str>| {
+            functions::now(clock.as_ref(), format)
+        });
+        self.env
+            .add_function("uuid", || uuid::Uuid::new_v4().to_string());
+        let allowlist = self.env_allowlist.clone();
+        self.env
+            .add_function("env", move |name: &str| functions::env(name, &allowlist));
+        let options = self.read_file.clone();
+        self.env
+            .add_function("read_file", move |state: &State, path: &str| {
+                functions::read_file(state, path, &options)
+            });
+        let options = self.git.clone();
+        self.env
+            .add_function("current_branch", move |state: &State, repo: &str| {
+                git::current_branch(state, repo, &options)
+            });
+        let options = self.git.clone();
+        self.env.add_function(
+            "git_log",
+            move |state: &State, repo: &str, n: Option<usize>| {
+                git::git_log(state, repo, n, &options)
+            },
+        );
+        let options = self.git.clone();
+        self.env
+            .add_function("git_diff", move |state: &State, repo: &str, base: &str| {
+                git::git_diff(state, repo, base, &options)
+            });
+    }
+
+    /// Load the task templates (`{task}/*.md`) from a directory.
+    ///
+    /// Templates are named by their path relative to the directory, e.g.
+    /// `build/user.md`, and replace loaded ones of the same name. All files
+    /// are attempted; the error names every template that failed to parse.
+    pub fn load_templates(&mut self, template_dir: &Path) -> Result<()> {
+        let pattern = template_dir.join("*").join("*.md");
+        let pattern = pattern.to_string_lossy();
+        let mut issues = Vec::new();
+        self.template_dirs.push(template_dir.to_path_buf());
+
+        for path in glob::glob(&pattern).context("Invalid template directory")? {
+            let path = path?;
+            let Ok(relative) = path.strip_prefix(template_dir) else {
+                continue;
+            };
+            let name = relative.to_string_lossy().replace('\\', "/");
+            let content = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read template {}", path.display()))?;
+            let variables = match self.variables(&content) {
+                Ok(variables) => variables,
+                Err(e) => {
+                    issues.push(TemplateIssue {
+                        template: name,
+                        message: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            self.add_template(PromptTemplate {
+                name: name.clone(),
+                content,
+                variables,
+            })?;
+            self.sources.insert(name, template_dir.to_path_buf());
+        }
+
+        if !issues.is_empty() {
+            let list: Vec<String> = issues.iter().map(ToString::to_string).collect();
+            bail!("Invalid templates:\n  {}", list.join("\n  "));
+        }
         Ok(())
     }
 
-    /// Add a template
+    /// Sorted variables `content` refers to, frontmatter aside
+    fn variables(&self, content: &str) -> Result<Vec<String>> {
+        let (_, body) = frontmatter::split(content)?;
+        let tmpl = self.env.template_from_str(body)?;
+        let mut variables: Vec<String> = tmpl.undeclared_variables(false).into_iter().collect();
+        variables.sort();
+        Ok(variables)
+    }
+
+    /// Check that every loaded template renders against an empty context.
+    ///
+    /// Catches errors that only surface at render time, such as unknown
+    /// filters or tests.
+    pub fn validate_all(&self) -> Vec<TemplateIssue> {
+        let mut names: Vec<&String> = self.templates.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .filter_map(|name| {
+                let result = self
+                    .env
+                    .get_template(name)
+                    .and_then(|tmpl| tmpl.render(context! {}));
+                result.err().map(|e| TemplateIssue {
+                    template: name.clone(),
+                    message: e.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Add a template, replacing a loaded one of the same name.
+    ///
+    /// Its frontmatter, if any, is parsed into [`TemplateMetadata`] and
+    /// left out of the rendered prompt.
     pub fn add_template(&mut self, template: PromptTemplate) -> Result<()> {
         let name = template.name.clone();
-        let content = template.content.clone();
+        let (metadata, body) = frontmatter::split(&template.content)
+            .with_context(|| format!("Failed to add template {name}"))?;
+        // The environment caches the compiled template under its name, so
+        // replacing it drops the version of the previous source.
         self.env
-            .add_template_owned(name.clone(), content)
+            .add_template_owned(name.clone(), body.to_string())
             .context("Failed to add template")?;
+        self.sources.remove(&name);
+        self.metadata.insert(name.clone(), metadata);
         self.templates.insert(name, template);
         Ok(())
     }
 
-    /// Render a template with the given context
-    pub fn render(
-        &self,
-        template_name: &str,
-        context_data: HashMap<String, String>,
-    ) -> Result<String> {
+    /// Frontmatter metadata of the template `template_name`, if loaded
+    pub fn template_metadata(&self, template_name: &str) -> Option<&TemplateMetadata> {
+        self.metadata.get(template_name)
+    }
+
+    /// Check the `requires` of a template's frontmatter before rendering
+    /// it, and fail on undefined variables instead of rendering them empty
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+        self.env.set_undefined_behavior(if strict {
+            UndefinedBehavior::Strict
+        } else {
+            UndefinedBehavior::default()
+        });
+    }
+
+    /// Template directory `template_name` was loaded from, `None` for a
+    /// built-in or code-added template
+    pub fn template_source(&self, template_name: &str) -> Option<&Path> {
+        self.sources.get(template_name).map(PathBuf::as_path)
+    }
+
+    /// Render a template with the given context.
+    ///
+    /// Extra variables are available both at the top level (`{{ key }}`) and
+    /// under `extra` (`{{ extra.key }}`). Fails if one takes one of the
+    /// [`RESERVED_NAMES`], or with [`PromptError::TooLarge`] if the result is
+    /// over the limit of [`PromptManager::set_max_prompt_tokens`].
+    pub fn render(&self, template_name: &str, context: &PromptContext) -> Result<String> {
+        let rendered = self.render_unchecked(template_name, context)?;
+        self.check_size(template_name, self.estimate_tokens(&rendered))?;
+        Ok(rendered)
+    }
+
+    /// Render a template without checking its size
+    fn render_unchecked(&self, template_name: &str, context: &PromptContext) -> Result<String> {
+        if self.strict {
+            self.check_requires(template_name, context)?;
+        }
+        let ctx = Self::context_value(context)?;
         let tmpl = self
             .env
             .get_template(template_name)
-            .context("Template not found")?;
+            .with_context(|| format!("Template not found: {template_name}"))?;
+        tmpl.render(ctx)
+            .with_context(|| format!("Failed to render template {template_name}"))
+    }
 
-        let ctx = context! { data => context_data };
-        tmpl.render(ctx).context("Failed to render template")
+    /// Render the template source `template_src` with the given context.
+    ///
+    /// For one-off templates, such as a prompt assembled in code; the
+    /// filters, functions and variables are those of
+    /// [`PromptManager::render`]. The template isn't cached.
+    pub fn render_string(&self, template_src: &str, context: &PromptContext) -> Result<String> {
+        let ctx = Self::context_value(context)?;
+        let rendered = self
+            .env
+            .render_str(template_src, ctx)
+            .context("Failed to render template string")?;
+        self.check_size("string", self.estimate_tokens(&rendered))?;
+        Ok(rendered)
+    }
+
+    /// Fail with [`PromptError::MissingVariables`] if `context` lacks a
+    /// variable the template requires
+    fn check_requires(&self, template_name: &str, context: &PromptContext) -> Result<()> {
+        let Some(metadata) = self.metadata.get(template_name) else {
+            return Ok(());
+        };
+        let missing: Vec<String> = metadata
+            .requires
+            .iter()
+            .filter(|name| !context.has(name))
+            .cloned()
+            .collect();
+        if !missing.is_empty() {
+            return Err(PromptError::MissingVariables {
+                template: template_name.to_string(),
+                missing,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    /// Template variables of `context`
+    fn context_value(context: &PromptContext) -> Result<Value> {
+        context.check_extra()?;
+        // Extras are top-level variables too; the reserved names checked
+        // above keep them from shadowing the built-in ones.
+        let ctx = context! {
+            repo_path => &context.repo_path,
+            feature_slug => &context.feature_slug,
+            feature_id => &context.feature_id,
+            phase => &context.phase,
+            extra => &context.extra,
+            ..Value::from_serialize(&context.extra)
+        };
+        Ok(ctx)
+    }
+
+    /// Render the system and user prompts of a phase.
+    ///
+    /// Both see the same variables as [`PromptManager::render`], extras
+    /// included, and are checked the same way in strict mode. The system
+    /// prompt is `None` when the phase has no `system.md`.
+    pub fn load_phase_prompts(
+        &self,
+        phase_name: &str,
+        context: &PromptContext,
+    ) -> Result<(Option<String>, String)> {
+        let system_name = format!("{phase_name}/system.md");
+        let system = if self.templates.contains_key(&system_name) {
+            Some(self.render_unchecked(&system_name, context)?)
+        } else {
+            None
+        };
+        let user = self.render_unchecked(&format!("{phase_name}/user.md"), context)?;
+        // Both are sent with the request, so the limit is on the total.
+        let estimated =
+            system.as_deref().map_or(0, |s| self.estimate_tokens(s)) + self.estimate_tokens(&user);
+        self.check_size(phase_name, estimated)?;
+        Ok((system, user))
     }
 
     /// List all available templates
     pub fn list_templates(&self) -> Vec<&str> {
         self.templates.keys().map(|s| s.as_str()).collect()
     }
+
+    /// List every phase with the files it provides, sorted by name.
+    ///
+    /// Covers phases with a loaded template and phase directories of the
+    /// loaded template directories, e.g. one with only a `config.yml`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a template directory can't be read.
+    pub fn list_templates_detailed(&self) -> Result<Vec<TemplateInfo>> {
+        let mut phases: Vec<String> = self
+            .templates
+            .keys()
+            .filter_map(|name| name.split_once('/').map(|(phase, _)| phase.to_string()))
+            .collect();
+        for dir in &self.template_dirs {
+            if !dir.is_dir() {
+                continue;
+            }
+            let entries = std::fs::read_dir(dir)
+                .with_context(|| format!("Failed to read template directory {}", dir.display()))?;
+            for entry in entries {
+                let entry = entry?;
+                if entry.file_type()?.is_dir() {
+                    phases.push(entry.file_name().to_string_lossy().into_owned());
+                }
+            }
+        }
+        phases.sort();
+        phases.dedup();
+
+        Ok(phases.iter().map(|name| self.template_info(name)).collect())
+    }
+
+    /// The files phase `name` provides and what their frontmatter says
+    /// about them
+    pub fn template_info(&self, name: &str) -> TemplateInfo {
+        let system = format!("{name}/system.md");
+        let user = format!("{name}/user.md");
+        // user.md describes the phase; system.md fills in what it leaves out.
+        let metadata: Vec<&TemplateMetadata> = [&user, &system]
+            .into_iter()
+            .filter_map(|template| self.metadata.get(template))
+            .collect();
+        let mut requires: Vec<String> = metadata
+            .iter()
+            .flat_map(|m| m.requires.iter().cloned())
+            .collect();
+        requires.sort();
+        requires.dedup();
+        TemplateInfo {
+            name: name.to_string(),
+            has_system: self.templates.contains_key(&system),
+            has_user: self.templates.contains_key(&user),
+            has_config: self
+                .template_dirs
+                .iter()
+                .any(|dir| dir.join(name).join(TASK_CONFIG_FILE).is_file()),
+            source: self
+                .template_source(&user)
+                .or_else(|| self.template_source(&system))
+                .map(Path::to_path_buf),
+            description: metadata.iter().find_map(|m| m.description.clone()),
+            requires,
+            version: metadata.iter().find_map(|m| m.version.clone()),
+        }
+    }
 }
 
 impl Default for PromptManager {
@@ -84,21 +617,692 @@ mod tests {
         assert_eq!(pm.list_templates().len(), 0);
     }
 
+    #[test]
+    fn test_embedded_templates_parse() {
+        let pm = PromptManager::with_embedded_defaults().unwrap();
+
+        assert_eq!(pm.list_templates().len(), embedded::TEMPLATES.len());
+        assert!(pm.validate_all().is_empty());
+    }
+
+    #[test]
+    fn test_embedded_defaults_render_without_disk_templates() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut pm = PromptManager::with_embedded_defaults().unwrap();
+        pm.load_templates(dir.path()).unwrap();
+        let context = PromptContext::new("/repo", "auth", "0001")
+            .with_extra("specs", "Add login")
+            .with_extra("coding_standards", "");
+
+        let (system, user) = pm.load_phase_prompts("build", &context).unwrap();
+
+        assert!(system.is_some());
+        assert!(user.contains("## Feature: auth"));
+        assert!(user.contains("Add login"));
+        assert!(pm.validate_all().is_empty());
+    }
+
+    #[test]
+    fn test_pr_prompt_pushes_the_feature_branch() {
+        let pm = PromptManager::with_embedded_defaults().unwrap();
+        let context = PromptContext::new("/repo", "auth", "0001")
+            .with_extra("specs", "Add login")
+            .with_extra("branch", "feature/0001-auth")
+            .with_extra("base_branch", "main");
+
+        let user = pm.render("pr/user.md", &context).unwrap();
+
+        assert!(user.contains("git push -u origin feature/0001-auth"));
+        assert!(user.contains("git log --oneline main..HEAD"));
+        assert!(!user.contains("checkout -b"));
+        let context = PromptContext::new("/repo", "auth", "0001");
+        let user = pm.render("pr/user.md", &context).unwrap();
+        assert!(user.contains("git push -u origin HEAD"));
+    }
+
+    #[test]
+    fn test_disk_templates_override_embedded_defaults() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("build")).unwrap();
+        std::fs::write(
+            dir.path().join("build/user.md"),
+            "Custom {{ feature_slug }}",
+        )
+        .unwrap();
+        let mut pm = PromptManager::with_embedded_defaults().unwrap();
+        pm.load_templates(dir.path()).unwrap();
+        let context = PromptContext::new("/repo", "auth", "0001");
+
+        assert_eq!(pm.render("build/user.md", &context).unwrap(), "Custom auth");
+        assert!(pm.render("plan/user.md", &context).is_ok());
+    }
+
+    #[test]
+    fn test_list_templates_detailed() {
+        let dir = tempfile::tempdir().unwrap();
+        for file in [
+            "build/system.md",
+            "build/user.md",
+            "build/config.yml",
+            "review/system.md",
+        ] {
+            let path = dir.path().join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, "{{ feature_slug }}").unwrap();
+        }
+        let mut pm = PromptManager::new();
+        pm.load_templates(dir.path()).unwrap();
+
+        let info = |name: &str, has_system, has_user, has_config| TemplateInfo {
+            name: name.to_string(),
+            has_system,
+            has_user,
+            has_config,
+            source: Some(dir.path().to_path_buf()),
+            description: None,
+            requires: Vec::new(),
+            version: None,
+        };
+        assert_eq!(
+            pm.list_templates_detailed().unwrap(),
+            vec![
+                info("build", true, true, true),
+                info("review", true, false, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_frontmatter_metadata_and_strict_rendering() {
+        let mut pm = PromptManager::new();
+        pm.add_template(PromptTemplate {
+            name: "build/user.md".to_string(),
+            content: "---\ndescription: Implement the feature\nrequires: [specs, phase]\nversion: 2\n---\nBuild {{ specs }}".to_string(),
+            variables: vec!["specs".to_string()],
+        })
+        .unwrap();
+        pm.add_template(PromptTemplate {
+            name: "build/system.md".to_string(),
+            content: "---\ndescription: Builder\nrequires: [coding_standards]\n---\nYou build."
+                .to_string(),
+            variables: Vec::new(),
+        })
+        .unwrap();
+        pm.add_template(PromptTemplate {
+            name: "review/user.md".to_string(),
+            content: "Review {{ feature_slug }}".to_string(),
+            variables: vec!["feature_slug".to_string()],
+        })
+        .unwrap();
+
+        let info = pm.template_info("build");
+        assert_eq!(info.description.as_deref(), Some("Implement the feature"));
+        assert_eq!(info.requires, vec!["coding_standards", "phase", "specs"]);
+        assert_eq!(info.version.as_deref(), Some("2"));
+        let review = pm.template_info("review");
+        assert!(review.has_user && review.description.is_none() && review.requires.is_empty());
+        assert_eq!(
+            pm.template_metadata("build/user.md").unwrap().requires,
+            vec!["specs", "phase"]
+        );
+
+        let context = PromptContext::new("/repo", "auth", "0001");
+        assert_eq!(pm.render("build/user.md", &context).unwrap(), "Build ");
+        pm.set_strict(true);
+        let err = pm.load_phase_prompts("build", &context).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<PromptError>(),
+            Some(&PromptError::MissingVariables {
+                template: "build/system.md".to_string(),
+                missing: vec!["coding_standards".to_string()],
+            })
+        );
+        let err = pm.render("build/user.md", &context).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Template build/user.md requires missing variables: specs, phase")
+        );
+        let context = context
+            .with_phase("build")
+            .with_extra("specs", "Add login")
+            .with_extra("coding_standards", "");
+        let (system, user) = pm.load_phase_prompts("build", &context).unwrap();
+        assert_eq!(system.as_deref(), Some("You build."));
+        assert_eq!(user, "Build Add login");
+        assert_eq!(
+            pm.render("review/user.md", &context).unwrap(),
+            "Review auth"
+        );
+
+        let err = pm
+            .add_template(PromptTemplate {
+                name: "plan/user.md".to_string(),
+                content: "---\nrequires: specs\n---\nPlan".to_string(),
+                variables: Vec::new(),
+            })
+            .unwrap_err();
+        assert!(format!("{err:#}").contains("Invalid frontmatter"));
+    }
+
+    #[test]
+    fn test_search_path_shadowing_and_fallthrough() {
+        let repo = tempfile::tempdir().unwrap();
+        let global = tempfile::tempdir().unwrap();
+        let write = |root: &Path, file: &str, content: &str| {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        };
+        write(repo.path(), "build/user.md", "repo build");
+        write(global.path(), "build/user.md", "global build");
+        write(global.path(), "review/user.md", "global review");
+        let missing = repo.path().join("missing");
+        let roots = vec![
+            repo.path().to_path_buf(),
+            missing,
+            global.path().to_path_buf(),
+        ];
+        let pm = PromptManager::with_search_path(roots.clone()).unwrap();
+        let context = PromptContext::new("/repo", "auth", "0001");
+
+        assert_eq!(pm.render("build/user.md", &context).unwrap(), "repo build");
+        assert_eq!(
+            pm.render("review/user.md", &context).unwrap(),
+            "global review"
+        );
+        assert!(pm.render("plan/user.md", &context).is_ok());
+        assert_eq!(pm.template_source("build/user.md"), Some(repo.path()));
+        assert_eq!(pm.template_source("review/user.md"), Some(global.path()));
+        assert_eq!(pm.template_source("plan/user.md"), None);
+        let sources: HashMap<String, Option<PathBuf>> = pm
+            .list_templates_detailed()
+            .unwrap()
+            .into_iter()
+            .map(|info| (info.name, info.source))
+            .collect();
+        assert_eq!(sources["build"].as_deref(), Some(repo.path()));
+        assert_eq!(sources["review"].as_deref(), Some(global.path()));
+        assert_eq!(sources["plan"], None);
+
+        // A repo override added later shadows the global template, and
+        // removing it falls through again.
+        write(repo.path(), "review/user.md", "repo review");
+        let pm = PromptManager::with_search_path(roots.clone()).unwrap();
+        assert_eq!(
+            pm.render("review/user.md", &context).unwrap(),
+            "repo review"
+        );
+        assert_eq!(pm.template_source("review/user.md"), Some(repo.path()));
+        std::fs::remove_file(repo.path().join("review/user.md")).unwrap();
+        let pm = PromptManager::with_search_path(roots).unwrap();
+        assert_eq!(
+            pm.render("review/user.md", &context).unwrap(),
+            "global review"
+        );
+    }
+
+    #[test]
+    fn test_render_string() {
+        let pm = PromptManager::new();
+        let context =
+            PromptContext::new("/repo", "auth", "0001").with_extra("title", "User Auth: OAuth2!");
+
+        assert_eq!(
+            pm.render_string("feature/{{ feature_id }}-{{ title | slugify }}", &context)
+                .unwrap(),
+            "feature/0001-user-auth-oauth2"
+        );
+        assert_eq!(pm.list_templates().len(), 0);
+        assert!(pm.render_string("{{ unclosed", &context).is_err());
+        assert!(
+            pm.render_string("{{ x }}", &context.with_extra("phase", "build"))
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_add_and_render_template() {
         let mut pm = PromptManager::new();
         let template = PromptTemplate {
             name: "test".to_string(),
-            content: "Hello {{ data.name }}!".to_string(),
+            content: "Hello {{ extra.name }}!".to_string(),
             variables: vec!["name".to_string()],
         };
 
         pm.add_template(template).unwrap();
 
-        let mut context = HashMap::new();
-        context.insert("name".to_string(), "World".to_string());
+        let context = PromptContext::default().with_extra("name", "World");
 
-        let result = pm.render("test", context).unwrap();
+        let result = pm.render("test", &context).unwrap();
         assert_eq!(result, "Hello World!");
     }
+
+    #[test]
+    fn test_extra_keys_must_not_shadow_builtins() {
+        let mut pm = PromptManager::new();
+        pm.add_template(PromptTemplate {
+            name: "test".to_string(),
+            content: "{{ feature_slug }}: {{ extra.observations }}".to_string(),
+            variables: vec!["feature_slug".to_string(), "extra".to_string()],
+        })
+        .unwrap();
+        let context = PromptContext::new("/repo", "auth", "0001");
+
+        let shadowing = context
+            .clone()
+            .with_extra("feature_slug", "other")
+            .with_extra("extra", "nested");
+        let err = pm.render("test", &shadowing).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Extra prompt variables use reserved names: extra, feature_slug")
+        );
+
+        let context = context.with_extra("observations", "uses tokio");
+        assert_eq!(pm.render("test", &context).unwrap(), "auth: uses tokio");
+    }
+
+    #[test]
+    fn test_extra_variables_are_top_level() {
+        let mut pm = PromptManager::new();
+        pm.add_template(PromptTemplate {
+            name: "test".to_string(),
+            content: "{{ mykey }} {{ extra.mykey }} {{ count + 1 }}".to_string(),
+            variables: vec!["mykey".to_string(), "count".to_string()],
+        })
+        .unwrap();
+        pm.add_template(PromptTemplate {
+            name: "builtins".to_string(),
+            content: "{{ repo_path }} {{ feature_id }}_{{ feature_slug }} {{ phase }}".to_string(),
+            variables: Vec::new(),
+        })
+        .unwrap();
+        let context = PromptContext::new("/repo", "auth", "0001")
+            .with_phase("build")
+            .with_extra("mykey", "value")
+            .with_extra("count", 2);
+
+        assert_eq!(pm.render("test", &context).unwrap(), "value value 3");
+        assert_eq!(
+            pm.render("builtins", &context).unwrap(),
+            "/repo 0001_auth build"
+        );
+    }
+    #[test]
+    fn test_read_file_is_relative_to_repo_path() {
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::create_dir(repo.path().join("src")).unwrap();
+        std::fs::write(repo.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+        let mut pm = PromptManager::new();
+        pm.add_template(PromptTemplate {
+            name: "test".to_string(),
+            content: "```rust\n{{ read_file(\"src/main.rs\") }}```".to_string(),
+            variables: vec!["read_file".to_string()],
+        })
+        .unwrap();
+        let context = PromptContext::new(repo.path().to_string_lossy(), "auth", "0001");
+
+        let rendered = pm.render("test", &context).unwrap();
+
+        assert_eq!(rendered, "```rust\nfn main() {}\n```");
+        let missing = PromptContext::new("/no/such/repo", "auth", "0001");
+        let err = pm.render("test", &missing).unwrap_err();
+        assert!(format!("{err:#}").contains("/no/such/repo/src/main.rs"));
+    }
+
+    #[test]
+    fn test_read_file_stays_within_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir_all(repo.join("docs")).unwrap();
+        std::fs::write(repo.join("docs/notes.md"), "notes").unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "secret").unwrap();
+        let mut pm = PromptManager::new();
+        pm.add_template(PromptTemplate {
+            name: "test".to_string(),
+            content: "{{ read_file(file) }}".to_string(),
+            variables: vec!["file".to_string()],
+        })
+        .unwrap();
+        let render = |pm: &PromptManager, file: &str| {
+            let context =
+                PromptContext::new(repo.to_string_lossy(), "auth", "0001").with_extra("file", file);
+            pm.render("test", &context).map_err(|e| format!("{e:#}"))
+        };
+
+        assert_eq!(render(&pm, "docs/../docs/notes.md").unwrap(), "notes");
+        let err = render(&pm, "../secret.txt").unwrap_err();
+        assert!(err.contains("read_file denied: ../secret.txt is outside of"));
+        let absolute = dir.path().join("secret.txt");
+        assert!(
+            render(&pm, &absolute.to_string_lossy())
+                .unwrap_err()
+                .contains("read_file denied")
+        );
+
+        pm.set_root(repo.join("docs"));
+        assert_eq!(render(&pm, "docs/notes.md").unwrap(), "notes");
+        pm.set_root(dir.path().join("elsewhere"));
+        assert!(
+            render(&pm, "docs/notes.md")
+                .unwrap_err()
+                .contains("Invalid read_file root")
+        );
+    }
+
+    #[test]
+    fn test_approx_tokens_filter() {
+        #[derive(Debug)]
+        struct Words;
+        impl Tokenizer for Words {
+            fn count(&self, text: &str) -> usize {
+                text.split_whitespace().count()
+            }
+        }
+
+        let mut pm = PromptManager::new();
+        pm.add_template(PromptTemplate {
+            name: "test".to_string(),
+            content: "{{ text | approx_tokens }} {% if text | approx_tokens > 4 %}long{% else %}short{% endif %}"
+                .to_string(),
+            variables: vec!["text".to_string()],
+        })
+        .unwrap();
+        let render = |pm: &PromptManager, text: &str| {
+            let context = PromptContext::new("/repo", "auth", "0001").with_extra("text", text);
+            pm.render("test", &context).unwrap()
+        };
+
+        assert_eq!(render(&pm, "one two three"), "4 short");
+        assert_eq!(render(&pm, "one two three four five"), "6 long");
+        pm.set_tokenizer(Arc::new(Words));
+        assert_eq!(render(&pm, "one two three four five"), "5 long");
+    }
+
+    #[test]
+    fn test_read_file_size_limit() {
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::write(repo.path().join("small.txt"), "hello").unwrap();
+        std::fs::write(repo.path().join("big.txt"), "aé".repeat(10)).unwrap();
+        let mut pm = PromptManager::new();
+        pm.add_template(PromptTemplate {
+            name: "test".to_string(),
+            content: "{{ read_file(file) }}".to_string(),
+            variables: vec!["file".to_string()],
+        })
+        .unwrap();
+        let render = |pm: &PromptManager, file: &str| {
+            let context = PromptContext::new(repo.path().to_string_lossy(), "auth", "0001")
+                .with_extra("file", file);
+            pm.render("test", &context).map_err(|e| format!("{e:#}"))
+        };
+
+        // 5 bytes: within the limit, read in full.
+        pm.set_read_file_limit(5, OversizedFile::Truncate);
+        assert_eq!(render(&pm, "small.txt").unwrap(), "hello");
+        // 30 bytes: the limit falls inside the second `é`, which is dropped.
+        assert_eq!(
+            render(&pm, "big.txt").unwrap(),
+            "aéa\n[... big.txt truncated, 26 more bytes ...]\n"
+        );
+
+        pm.set_read_file_limit(5, OversizedFile::Error);
+        assert_eq!(render(&pm, "small.txt").unwrap(), "hello");
+        let err = render(&pm, "big.txt").unwrap_err();
+        assert!(err.contains("30 bytes exceed the read_file limit of 5 bytes"));
+    }
+
+    #[test]
+    fn test_load_templates_from_task_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("build")).unwrap();
+        std::fs::write(
+            dir.path().join("build/user.md"),
+            "Build {{ feature_slug }} in {{ repo_path }}",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("README.md"), "{% not a template").unwrap();
+
+        let mut pm = PromptManager::new();
+        pm.load_templates(dir.path()).unwrap();
+
+        assert_eq!(pm.list_templates(), vec!["build/user.md"]);
+        assert_eq!(
+            pm.templates["build/user.md"].variables,
+            vec!["feature_slug", "repo_path"]
+        );
+        assert!(pm.validate_all().is_empty());
+    }
+
+    #[test]
+    fn test_load_phase_prompts() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("build")).unwrap();
+        std::fs::create_dir(dir.path().join("pr")).unwrap();
+        std::fs::write(dir.path().join("build/system.md"), "You build {{ phase }}.").unwrap();
+        std::fs::write(
+            dir.path().join("build/user.md"),
+            "Feature {{ feature_id }}_{{ feature_slug }} at {{ repo_path }}",
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("pr/user.md"), "Open a PR").unwrap();
+        let mut pm = PromptManager::new();
+        pm.load_templates(dir.path()).unwrap();
+        let context = PromptContext::new("/repo", "auth", "0001").with_phase("build");
+
+        let (system, user) = pm.load_phase_prompts("build", &context).unwrap();
+
+        assert_eq!(system.as_deref(), Some("You build build."));
+        assert_eq!(user, "Feature 0001_auth at /repo");
+        let (system, _) = pm.load_phase_prompts("pr", &context).unwrap();
+        assert!(system.is_none());
+    }
+
+    #[test]
+    fn test_git_functions() {
+        let dir = tempfile::tempdir().unwrap();
+        let repo = dir.path().join("repo");
+        std::fs::create_dir(&repo).unwrap();
+        let git = |args: &[&str]| {
+            let status = std::process::Command::new("git")
+                .args(["-c", "user.name=gba", "-c", "user.email=gba@example.com"])
+                .args(args)
+                .current_dir(&repo)
+                .output()
+                .unwrap()
+                .status;
+            assert!(status.success(), "git {args:?} failed");
+        };
+        git(&["init", "--quiet", "-b", "feature/login"]);
+        std::fs::write(repo.join("login.rs"), "fn login() {}\n").unwrap();
+        git(&["add", "login.rs"]);
+        git(&["commit", "--quiet", "-m", "Add login"]);
+        std::fs::write(repo.join("login.rs"), "fn login() -> bool { true }\n").unwrap();
+        git(&["commit", "--quiet", "-am", "Return whether login worked"]);
+        std::fs::write(repo.join("login.rs"), "fn login() -> bool { false }\n").unwrap();
+
+        let mut pm = PromptManager::new();
+        let context = PromptContext::new(repo.to_string_lossy(), "auth", "0001");
+        let render = |pm: &PromptManager, src: &str| pm.render_string(src, &context);
+
+        assert_eq!(
+            render(&pm, "{{ current_branch('.') }}").unwrap(),
+            "feature/login"
+        );
+        assert_eq!(
+            render(&pm, "{{ git_log('.', 5) }}").unwrap(),
+            "Return whether login worked\nAdd login"
+        );
+        assert_eq!(
+            render(&pm, "{{ git_log(repo_path, 1) }}").unwrap(),
+            "Return whether login worked"
+        );
+        let diff = render(&pm, "{{ git_diff('.', 'HEAD~1') }}").unwrap();
+        assert!(
+            diff.contains("-fn login() {}\n+fn login() -> bool { false }"),
+            "{diff}"
+        );
+        pm.set_git_diff_limit(10);
+        let diff = render(&pm, "{{ git_diff('.', 'HEAD~1') }}").unwrap();
+        assert!(
+            diff.starts_with("diff --git\n[... diff truncated, "),
+            "{diff}"
+        );
+        let unknown = render(&pm, "{{ git_diff('.', 'nope') }}").unwrap();
+        assert!(unknown.starts_with("[git_diff unavailable: "), "{unknown}");
+        let err = render(&pm, "{{ git_diff('.', '--output=x') }}").unwrap_err();
+        assert!(format!("{err:#}").contains("git_diff: invalid ref `--output=x`"));
+
+        // Not a repository: a placeholder instead of an error.
+        std::fs::create_dir(dir.path().join("plain")).unwrap();
+        pm.set_root(dir.path());
+        let branch = render(&pm, "{{ current_branch('../plain') }}").unwrap();
+        assert!(
+            branch.starts_with("[current_branch unavailable: not a git repository"),
+            "{branch}"
+        );
+        // Outside of the root: denied like read_file.
+        pm.set_root(&repo);
+        let err = render(&pm, "{{ git_log('../plain') }}").unwrap_err();
+        assert!(format!("{err:#}").contains("git_log denied: ../plain is outside of"));
+    }
+
+    #[test]
+    fn test_now_uuid_and_env_functions() {
+        #[derive(Debug)]
+        struct Fixed;
+        impl Clock for Fixed {
+            fn now(&self) -> chrono::DateTime<chrono::Utc> {
+                "2026-03-01T09:30:00Z".parse().unwrap()
+            }
+        }
+
+        let mut pm = PromptManager::new();
+        pm.set_clock(Arc::new(Fixed));
+        pm.set_env_allowlist(["PATH", "GBA_TEST_UNSET_VARIABLE"]);
+        let context = PromptContext::new("/repo", "auth", "0001");
+        let render = |src: &str| pm.render_string(src, &context);
+
+        assert_eq!(render("{{ now() }}").unwrap(), "2026-03-01T09:30:00Z");
+        assert_eq!(render("{{ now('%Y-%m-%d') }}").unwrap(), "2026-03-01");
+        let err = render("{{ now('%Q') }}").unwrap_err();
+        assert!(format!("{err:#}").contains("now: invalid format `%Q`"));
+
+        let uuid = render("{{ uuid() }}").unwrap();
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(uuid.chars().nth(14), Some('4'));
+        assert_ne!(uuid, render("{{ uuid() }}").unwrap());
+
+        assert_eq!(
+            render("{{ env('PATH') }}").unwrap(),
+            std::env::var("PATH").unwrap()
+        );
+        assert_eq!(
+            render("{% if env('GBA_TEST_UNSET_VARIABLE') %}set{% else %}unset{% endif %}").unwrap(),
+            "unset"
+        );
+        let err = render("{{ env('HOME') }}").unwrap_err();
+        assert!(format!("{err:#}").contains("env: HOME is not in templateEnvAllowlist"));
+        pm.set_env_allowlist(Vec::<String>::new());
+        assert!(pm.render_string("{{ env('PATH') }}", &context).is_err());
+    }
+
+    #[test]
+    fn test_formatting_filters() {
+        let pm = PromptManager::new();
+        let context = PromptContext::new("/repo", "auth", "0001")
+            .with_extra(
+                "data",
+                serde_json::json!({"name": "Zoë", "tags": ["a", "b"]}),
+            )
+            .with_extra("file", "/repo/src/main.rs");
+        let render = |src: &str| pm.render_string(src, &context).unwrap();
+
+        assert_eq!(
+            render("{{ data | tojson }}"),
+            "{\n  \"name\": \"Zoë\",\n  \"tags\": [\n    \"a\",\n    \"b\"\n  ]\n}"
+        );
+        assert_eq!(render("{{ data | toyaml }}"), "name: Zoë\ntags:\n- a\n- b");
+        assert_eq!(render("{{ '' | tojson }}"), "\"\"");
+        assert_eq!(render("{{ feature_slug | truncate_chars(2) }}"), "au…");
+        assert_eq!(render("{{ 'one two three' | wrap(7) }}"), "one two\nthree");
+        assert_eq!(render("{{ file | relpath }}"), "src/main.rs");
+        assert_eq!(
+            render("{{ file | relpath('/repo/docs') }}"),
+            "../src/main.rs"
+        );
+
+        for src in [
+            "{{ range | tojson }}",
+            "{{ missing | toyaml }}",
+            "{{ [1, range] | toyaml }}",
+            "{{ {'run': range} | tojson }}",
+        ] {
+            let err = pm.render_string(src, &context).unwrap_err();
+            let filter = if src.contains("tojson") {
+                "tojson: "
+            } else {
+                "toyaml: "
+            };
+            assert!(format!("{err:#}").contains(filter), "{src}: {err:#}");
+        }
+    }
+
+    #[test]
+    fn test_max_prompt_tokens() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("build")).unwrap();
+        std::fs::write(dir.path().join("build/system.md"), "a".repeat(40)).unwrap();
+        std::fs::write(
+            dir.path().join("build/user.md"),
+            "{{ text | truncate_tokens(5) }}",
+        )
+        .unwrap();
+        let mut pm = PromptManager::new();
+        pm.load_templates(dir.path()).unwrap();
+        let context =
+            PromptContext::new("/repo", "auth", "0001").with_extra("text", "b".repeat(400));
+        assert_eq!(pm.estimate_tokens(&"c".repeat(40)), 10);
+        assert!(
+            pm.render("build/user.md", &context)
+                .unwrap()
+                .starts_with("bbbbbbbbbbbbbbbbbbbb\n[... truncated")
+        );
+
+        pm.set_max_prompt_tokens(Some(20), OversizedPrompt::Error);
+        // Each template fits, but not both together.
+        assert!(pm.render("build/system.md", &context).is_ok());
+        assert!(pm.render("build/user.md", &context).is_ok());
+        let err = pm.load_phase_prompts("build", &context).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<PromptError>(),
+            Some(&PromptError::TooLarge {
+                template: "build".to_string(),
+                estimated: 24,
+                limit: 20,
+            })
+        );
+        assert!(pm.render_string(&"d".repeat(84), &context).is_err());
+
+        pm.set_max_prompt_tokens(Some(20), OversizedPrompt::Warn);
+        assert!(pm.load_phase_prompts("build", &context).is_ok());
+        pm.set_max_prompt_tokens(None, OversizedPrompt::Error);
+        assert!(pm.render_string(&"d".repeat(84), &context).is_ok());
+    }
+
+    #[test]
+    fn test_invalid_templates_are_named() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("test")).unwrap();
+        std::fs::write(dir.path().join("test/user.md"), "{% if x %}unclosed").unwrap();
+        std::fs::write(dir.path().join("test/system.md"), "{{ x | nosuchfilter }}").unwrap();
+
+        let mut pm = PromptManager::new();
+        let err = pm.load_templates(dir.path()).unwrap_err();
+        assert!(err.to_string().contains("test/user.md"));
+
+        let issues = pm.validate_all();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].template, "test/system.md");
+    }
 }